@@ -0,0 +1,79 @@
+//! Benchmarks for the hot paths in presenterm: parsing markdown, building presentations out of it,
+//! and rendering every slide in them.
+//!
+//! `cargo bench` runs every benchmark and compares the results against the previous run. Running
+//! them via `cargo test --benches` runs every benchmark once so CI can check they still work
+//! without spending time on it.
+
+use comrak::Arena;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use presenterm::{
+    builder::PresentationBuilder,
+    markdown::{elements::MarkdownElement, parse::MarkdownParser},
+    presentation::Presentation,
+    render::{draw::TerminalDrawer, highlighting::CodeHighlighter, properties::WindowSize},
+    resource::Resources,
+    theme::PresentationTheme,
+};
+use std::{io, path::Path};
+
+const DECKS: &[(&str, &str)] = &[
+    ("text", include_str!("decks/text.md")),
+    ("code", include_str!("decks/code.md")),
+    ("demo", include_str!("../examples/demo.md")),
+];
+
+const WINDOW: WindowSize = WindowSize { rows: 40, columns: 120, width: 0, height: 0 };
+
+fn parse(contents: &str) -> Vec<MarkdownElement> {
+    let arena = Arena::new();
+    let parser = MarkdownParser::new(&arena);
+    parser.parse(contents).expect("parsing failed")
+}
+
+fn build(elements: Vec<MarkdownElement>, theme: &PresentationTheme) -> Presentation {
+    let highlighter = CodeHighlighter::new("base16-ocean.dark").expect("no highlighting theme");
+    let mut resources = Resources::new(Path::new("examples"));
+    PresentationBuilder::new(highlighter, theme, &mut resources).build(elements).expect("building failed")
+}
+
+fn render(presentation: &mut Presentation) {
+    let mut drawer = TerminalDrawer::headless(io::sink());
+    for index in 0..presentation.iter_slides().count() {
+        presentation.jump_slide(index);
+        presentation.show_chunks(usize::MAX);
+        drawer.render_slide_sized(presentation, WINDOW.clone()).expect("rendering failed");
+    }
+}
+
+fn presentations(criterion: &mut Criterion) {
+    let theme = PresentationTheme::default();
+    let mut group = criterion.benchmark_group("parse");
+    for (name, contents) in DECKS {
+        group.bench_function(*name, |bencher| bencher.iter(|| parse(contents)));
+    }
+    group.finish();
+
+    let mut group = criterion.benchmark_group("build");
+    for (name, contents) in DECKS {
+        group.bench_function(*name, |bencher| {
+            bencher.iter_batched(|| parse(contents), |elements| build(elements, &theme), BatchSize::SmallInput)
+        });
+    }
+    group.finish();
+
+    let mut group = criterion.benchmark_group("render");
+    for (name, contents) in DECKS {
+        group.bench_function(*name, |bencher| {
+            bencher.iter_batched(
+                || build(parse(contents), &theme),
+                |mut presentation| render(&mut presentation),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, presentations);
+criterion_main!(benches);
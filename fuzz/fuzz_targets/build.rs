@@ -0,0 +1,21 @@
+#![no_main]
+
+use comrak::Arena;
+use libfuzzer_sys::fuzz_target;
+use presenterm::{
+    builder::PresentationBuilder, markdown::parse::MarkdownParser, render::highlighting::CodeHighlighter,
+    resource::Resources, theme::PresentationTheme,
+};
+
+fuzz_target!(|contents: &str| {
+    let arena = Arena::new();
+    let Ok(elements) = MarkdownParser::new(&arena).parse(contents) else {
+        return;
+    };
+    let theme = PresentationTheme::default();
+    let highlighter = CodeHighlighter::new("base16-ocean.dark").expect("no highlighting theme");
+    // Point resources at an empty directory so images and snippets can't be loaded from anywhere.
+    let directory = std::env::temp_dir().join("presenterm-fuzz");
+    let mut resources = Resources::new(directory);
+    let _ = PresentationBuilder::new(highlighter, &theme, &mut resources).build(elements);
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use comrak::Arena;
+use libfuzzer_sys::fuzz_target;
+use presenterm::markdown::parse::MarkdownParser;
+
+fuzz_target!(|contents: &str| {
+    let arena = Arena::new();
+    let _ = MarkdownParser::new(&arena).parse(contents);
+});
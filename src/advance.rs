@@ -0,0 +1,187 @@
+use crate::input::source::Command;
+use std::{
+    sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Moves to the next slide, or the next chunk in it, on its own after a slide has been displayed
+/// for a while.
+pub struct AutoAdvancer {
+    default_duration: Duration,
+    timer: Sender<TimerRequest>,
+    generation: u64,
+    current_slide: Option<(usize, usize)>,
+}
+
+impl AutoAdvancer {
+    /// Construct a new auto advancer that moves to the next slide after the given duration.
+    ///
+    /// A [Command::AutoAdvance] is sent via the given sender when it's time to move on.
+    pub fn new(default_duration: Duration, sender: Sender<Command>) -> Self {
+        // A single timer thread is used for every slide. It's done once this advancer is dropped.
+        let (timer, requests) = channel();
+        thread::spawn(move || Self::run_timer(requests, sender));
+        Self { default_duration, timer, generation: 0, current_slide: None }
+    }
+
+    /// Let this advancer know the slide that's currently being displayed, along with the number of
+    /// its chunks that are visible.
+    ///
+    /// If either of these changed since the last time this was called, the timer is restarted using
+    /// the slide's own duration, if any, or the default one otherwise.
+    pub fn slide_displayed(&mut self, index: usize, visible_chunks: usize, duration: Option<Duration>) {
+        let current = (index, visible_chunks);
+        if self.current_slide == Some(current) {
+            return;
+        }
+        self.current_slide = Some(current);
+
+        self.generation += 1;
+        let duration = duration.unwrap_or(self.default_duration);
+        let _ = self.timer.send(TimerRequest::Start { generation: self.generation, duration });
+    }
+
+    /// Stop the timer for the current slide.
+    ///
+    /// The timer starts over the next time a slide is displayed, including the current one.
+    pub fn pause(&mut self) {
+        self.generation += 1;
+        self.current_slide = None;
+        let _ = self.timer.send(TimerRequest::Stop);
+    }
+
+    /// Check whether an auto advance command for the given generation is still valid.
+    ///
+    /// Commands become stale once the slide that scheduled them is no longer being displayed.
+    pub fn is_current(&self, generation: u64) -> bool {
+        self.generation == generation
+    }
+
+    fn run_timer(requests: Receiver<TimerRequest>, sender: Sender<Command>) {
+        // The generation of the running timer along with the time it goes off at.
+        let mut running: Option<(u64, Instant)> = None;
+        loop {
+            let request = match running {
+                Some((generation, deadline)) => {
+                    match requests.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                        Ok(request) => request,
+                        Err(RecvTimeoutError::Timeout) => {
+                            running = None;
+                            if sender.send(Command::AutoAdvance { generation }).is_err() {
+                                return;
+                            }
+                            continue;
+                        }
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+                None => match requests.recv() {
+                    Ok(request) => request,
+                    Err(_) => return,
+                },
+            };
+            running = match request {
+                // Durations too long to be represented never go off.
+                TimerRequest::Start { generation, duration } => {
+                    Instant::now().checked_add(duration).map(|deadline| (generation, deadline))
+                }
+                TimerRequest::Stop => None,
+            };
+        }
+    }
+}
+
+enum TimerRequest {
+    Start { generation: u64, duration: Duration },
+    Stop,
+}
+
+/// Parse a duration in the form `45s`, `2m`, `500ms`, or any combination of them like `1m30s`.
+pub fn parse_duration(input: &str) -> Result<Duration, InvalidDuration> {
+    let mut total = Duration::ZERO;
+    let mut remaining = input.trim();
+    if remaining.is_empty() {
+        return Err(InvalidDuration(input.into()));
+    }
+    while !remaining.is_empty() {
+        let digits = remaining.find(|c: char| !c.is_ascii_digit()).unwrap_or(remaining.len());
+        let value: u64 = remaining[..digits].parse().map_err(|_| InvalidDuration(input.into()))?;
+        remaining = &remaining[digits..];
+        let units = remaining.find(|c: char| c.is_ascii_digit()).unwrap_or(remaining.len());
+        let duration = match &remaining[..units] {
+            "ms" => Duration::from_millis(value),
+            "s" => Duration::from_secs(value),
+            "m" => Duration::from_secs(value.checked_mul(60).ok_or_else(|| InvalidDuration(input.into()))?),
+            _ => return Err(InvalidDuration(input.into())),
+        };
+        total = total.checked_add(duration).ok_or_else(|| InvalidDuration(input.into()))?;
+        remaining = &remaining[units..];
+    }
+    Ok(total)
+}
+
+/// An invalid duration.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[error("invalid duration '{0}': expected something like 45s, 2m, or 500ms")]
+pub struct InvalidDuration(String);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::seconds("45s", Duration::from_secs(45))]
+    #[case::minutes("2m", Duration::from_secs(120))]
+    #[case::millis("500ms", Duration::from_millis(500))]
+    #[case::combined("1m30s", Duration::from_secs(90))]
+    #[case::spaces(" 3s ", Duration::from_secs(3))]
+    fn valid_durations(#[case] input: &str, #[case] expected: Duration) {
+        assert_eq!(parse_duration(input), Ok(expected));
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::no_units("45")]
+    #[case::no_value("s")]
+    #[case::unknown_units("3h")]
+    #[case::minutes_overflow("307445734561825861m")]
+    #[case::total_overflow("18446744073709551615s1s")]
+    fn invalid_durations(#[case] input: &str) {
+        assert!(parse_duration(input).is_err());
+    }
+
+    #[test]
+    fn advance() {
+        let (sender, receiver) = channel();
+        let mut advancer = AutoAdvancer::new(Duration::from_secs(60), sender);
+        advancer.slide_displayed(0, 1, Some(Duration::from_millis(10)));
+        // Displaying the same slide again doesn't restart the timer.
+        advancer.slide_displayed(0, 1, Some(Duration::from_millis(10)));
+
+        let Command::AutoAdvance { generation } = receiver.recv_timeout(Duration::from_secs(5)).expect("no command")
+        else {
+            panic!("not an auto advance");
+        };
+        assert!(advancer.is_current(generation));
+
+        // Revealing another chunk in the same slide does.
+        advancer.slide_displayed(0, 2, Some(Duration::from_secs(60)));
+        assert!(!advancer.is_current(generation));
+    }
+
+    #[test]
+    fn pause() {
+        let (sender, receiver) = channel();
+        let mut advancer = AutoAdvancer::new(Duration::from_secs(60), sender);
+        advancer.slide_displayed(0, 1, Some(Duration::from_millis(10)));
+        advancer.pause();
+        assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+
+        // The slide starts over once it's displayed again.
+        advancer.slide_displayed(0, 1, Some(Duration::from_millis(10)));
+        let command = receiver.recv_timeout(Duration::from_secs(5)).expect("no command");
+        assert!(matches!(command, Command::AutoAdvance { generation } if advancer.is_current(generation)));
+    }
+}
@@ -1,28 +1,55 @@
 use crate::{
+    advance::{parse_duration, InvalidDuration},
+    compat::parse_front_matter,
+    execute::{CodeExecuter, ExecutionHandle, ExecutionState, ProcessStatus},
+    hooks::SlideHooks,
+    input::source::Command,
     markdown::{
         elements::{
-            Code, ListItem, ListItemType, MarkdownElement, ParagraphElement, StyledText, Table, TableRow, Text,
+            Code, HighlightGroup, ImageAttributes, ListItem, ListItemType, MarkdownElement, ParagraphElement,
+            ProgrammingLanguage, SourcePosition, StyledText, Table, TableRow, Text,
         },
         text::{WeightedLine, WeightedText},
+        width::{char_widths, truncate_with_ellipsis, DisplayWidth},
     },
     presentation::{
-        AsRenderOperations, MarginProperties, PreformattedLine, Presentation, PresentationMetadata,
-        PresentationThemeMetadata, RenderOperation, Slide,
+        AsRenderOperations, BlockLine, ChunkMutator, ImageProperties, MarginProperties, PreformattedLine, Presentation,
+        PresentationAuthor, PresentationMetadata, PresentationOptions, PresentationPart, PresentationThemeMetadata,
+        RenderOnDemand, RenderOperation, Slide, SlideChunk, SlideKind, SlideMargins, SocialHandles, Watermark,
     },
+    profile::{self, Phase},
     render::{
         highlighting::{CodeHighlighter, CodeLine},
         properties::WindowSize,
     },
     resource::{LoadImageError, Resources},
-    style::{Colors, TextStyle},
-    theme::{Alignment, AuthorPositioning, ElementType, FooterStyle, LoadThemeError, Margin, PresentationTheme},
+    stopwatch::{Stopwatch, StopwatchTime},
+    style::{Color, Colors, TextStyle},
+    theme::{
+        Alignment, AuthorDetailStyle, AuthorPositioning, ElementType, ExecutionStatusStyle, FooterStyle,
+        IntroSlideLayout, LineSize, LoadThemeError, Margin, PresentationTheme, SocialsStyle, TableRowStyle,
+    },
+    yaml::YamlError,
 };
+use log::debug;
 use serde::Deserialize;
-use std::{borrow::Cow, cell::RefCell, iter, mem, path::PathBuf, rc::Rc, str::FromStr};
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    cmp::Reverse,
+    collections::HashMap,
+    iter, mem,
+    ops::Range,
+    path::PathBuf,
+    rc::Rc,
+    str::FromStr,
+    sync::mpsc::Sender,
+    time::{Duration, Instant},
+    vec,
+};
 use unicode_width::UnicodeWidthStr;
 
-// TODO: move to a theme config.
-static DEFAULT_BOTTOM_SLIDE_MARGIN: u16 = 3;
+static DEFAULT_TAB_WIDTH: u8 = 4;
 
 /// Builds a presentation.
 ///
@@ -30,15 +57,47 @@ static DEFAULT_BOTTOM_SLIDE_MARGIN: u16 = 3;
 /// render operations.
 pub struct PresentationBuilder<'a> {
     slide_operations: Vec<RenderOperation>,
+    slide_chunks: Vec<SlideChunk>,
+    chunk_mutators: Vec<Rc<dyn ChunkMutator>>,
     slides: Vec<Slide>,
     highlighter: CodeHighlighter,
     theme: Cow<'a, PresentationTheme>,
-    resources: &'a mut Resources,
+    resources: Resources,
     ignore_element_line_break: bool,
     needs_enter_column: bool,
     last_element_is_list: bool,
     footer_context: Rc<RefCell<FooterContext>>,
     layout: LayoutState,
+    default_hooks: SlideHooks,
+    slide_hooks: Rc<RefCell<SlideHooks>>,
+    slide_notes: Rc<RefCell<Vec<String>>>,
+    terminal_rows: Option<u16>,
+    slides_compatibility: bool,
+    nerd_fonts: bool,
+    default_watermark: Option<Watermark>,
+    reveal_rows: bool,
+    reveal_columns: bool,
+    incremental_lists: bool,
+    image_grid: Option<ImageGridState>,
+    comparison: Option<ComparisonState>,
+    figures: usize,
+    tables: usize,
+    slide_options: SlideOptions,
+    slide_duration: Option<Duration>,
+    slide_videos: Vec<String>,
+    slide_setup_code: Vec<Rc<RunCodeOperation>>,
+    snippets: HashMap<String, Code>,
+    presentation_options: PresentationOptions,
+    keywords: Vec<(String, TextStyle)>,
+    parts: Vec<PresentationPart>,
+    current_title: String,
+    slide_has_title: bool,
+    slide_starts_section: bool,
+    slide_is_empty: bool,
+    slide_title_only: bool,
+    slide_is_intro: bool,
+    delivered_slides: usize,
+    warnings: Vec<String>,
 }
 
 impl<'a> PresentationBuilder<'a> {
@@ -46,33 +105,134 @@ impl<'a> PresentationBuilder<'a> {
     pub fn new(
         default_highlighter: CodeHighlighter,
         default_theme: &'a PresentationTheme,
-        resources: &'a mut Resources,
+        resources: &mut Resources,
     ) -> Self {
         Self {
             slide_operations: Vec::new(),
+            slide_chunks: Vec::new(),
+            chunk_mutators: Vec::new(),
             slides: Vec::new(),
             highlighter: default_highlighter,
             theme: Cow::Borrowed(default_theme),
-            resources,
+            resources: resources.clone(),
             ignore_element_line_break: false,
             last_element_is_list: false,
             needs_enter_column: false,
             footer_context: Default::default(),
             layout: Default::default(),
+            default_hooks: Default::default(),
+            slide_hooks: Default::default(),
+            slide_notes: Default::default(),
+            terminal_rows: None,
+            slides_compatibility: false,
+            nerd_fonts: false,
+            default_watermark: None,
+            reveal_rows: false,
+            reveal_columns: false,
+            incremental_lists: false,
+            image_grid: None,
+            comparison: None,
+            figures: 0,
+            tables: 0,
+            slide_options: Default::default(),
+            slide_duration: None,
+            slide_videos: Vec::new(),
+            slide_setup_code: Vec::new(),
+            snippets: HashMap::new(),
+            presentation_options: Default::default(),
+            keywords: Vec::new(),
+            parts: Vec::new(),
+            current_title: String::new(),
+            slide_has_title: false,
+            slide_starts_section: false,
+            slide_is_empty: true,
+            slide_title_only: false,
+            slide_is_intro: false,
+            delivered_slides: 0,
+            warnings: Vec::new(),
         }
     }
 
+    /// Set the number of rows in the terminal the presentation will be displayed in.
+    ///
+    /// This is used to split elements that are too tall, like long tables, into multiple slides.
+    pub fn with_terminal_rows(mut self, rows: u16) -> Self {
+        self.terminal_rows = Some(rows);
+        self
+    }
+
+    /// Use the given stopwatch when rendering the `{stopwatch}` footer template variable.
+    pub fn with_stopwatch(self, stopwatch: Stopwatch) -> Self {
+        self.footer_context.borrow_mut().stopwatch = stopwatch;
+        self
+    }
+
+    /// Interpret the presentation the way [slides](https://github.com/maaslalani/slides) does.
+    ///
+    /// This makes every thematic break end the current slide and lets any code block written in
+    /// a supported language be executed.
+    pub fn with_slides_compatibility(mut self, enabled: bool) -> Self {
+        self.slides_compatibility = enabled;
+        self
+    }
+
+    /// Use Nerd Fonts icons rather than plain text labels, like in the author's social handles.
+    pub fn with_nerd_fonts(mut self, enabled: bool) -> Self {
+        self.nerd_fonts = enabled;
+        self
+    }
+
+    /// Draw the given watermark on every slide unless the presentation sets its own.
+    pub fn with_default_watermark(mut self, watermark: Option<Watermark>) -> Self {
+        self.default_watermark = watermark;
+        self
+    }
+
     /// Build a presentation.
-    pub fn build(mut self, elements: Vec<MarkdownElement>) -> Result<Presentation, BuildError> {
+    pub fn build(self, elements: Vec<MarkdownElement>) -> Result<Presentation, BuildError> {
+        let (presentation, _) = self.build_lazily(elements, usize::MAX)?;
+        Ok(presentation)
+    }
+
+    /// Build a presentation, only building its first few slides right away.
+    ///
+    /// At least `eager_slides` slides are built before this returns. If there's anything left to
+    /// build, a [LazyBuild] is returned along with the presentation so the rest of it can be built
+    /// later on.
+    pub fn build_lazily(
+        mut self,
+        elements: Vec<MarkdownElement>,
+        eager_slides: usize,
+    ) -> Result<(Presentation, Option<LazyBuild<'a>>), BuildError> {
+        // Search paths are defined by the presentation itself so they shouldn't survive reloads.
+        self.resources.set_search_paths(Vec::new());
         if let Some(MarkdownElement::FrontMatter(contents)) = elements.first() {
             self.process_front_matter(contents)?;
         }
         self.set_code_theme()?;
+        if self.presentation_options.watermark.is_none() {
+            self.presentation_options.watermark = self.default_watermark.clone();
+        }
 
         if self.slide_operations.is_empty() {
             self.push_slide_prelude();
         }
-        for element in elements {
+        let mut presentation = Presentation::new(Vec::new())
+            .with_options(self.presentation_options.clone())
+            .with_colors(self.theme.default_style.colors.clone());
+        let mut build = LazyBuild { builder: self, elements: elements.into_iter() };
+        let finished = build.build_into(&mut presentation, eager_slides)?;
+        Ok((presentation, (!finished).then_some(build)))
+    }
+
+    fn build_slides(&mut self, elements: &mut vec::IntoIter<MarkdownElement>, count: usize) -> Result<(), BuildError> {
+        let _measurement = profile::measure(Phase::Build);
+        let start = Instant::now();
+        let target = self.slide_count().saturating_add(count);
+        while self.slide_count() < target {
+            let Some(element) = elements.next() else {
+                break;
+            };
             self.ignore_element_line_break = false;
             self.process_element(element)?;
             self.validate_last_operation()?;
@@ -80,13 +240,29 @@ impl<'a> PresentationBuilder<'a> {
                 self.push_line_break();
             }
         }
-        if !self.slide_operations.is_empty() {
-            self.terminate_slide(TerminateMode::ResetState);
+        if elements.len() == 0 {
+            if self.comparison.is_some() {
+                return Err(BuildError::InvalidComparison("expected two code blocks"));
+            }
+            if !self.slide_operations.is_empty() || !self.slide_chunks.is_empty() {
+                self.terminate_slide(TerminateMode::ResetState);
+            }
+        }
+        {
+            let mut context = self.footer_context.borrow_mut();
+            // The total isn't known until every slide has been built.
+            if elements.len() == 0 {
+                context.total_slides = Some(self.slide_count());
+            }
+            context.part_starts = self.parts.iter().map(|part| part.first_slide).collect();
         }
-        self.footer_context.borrow_mut().total_slides = self.slides.len();
+        debug!("built presentation slides={} elapsed={:?}", self.slide_count(), start.elapsed());
+        Ok(())
+    }
 
-        let presentation = Presentation::new(self.slides);
-        Ok(presentation)
+    // The number of slides built so far, including the ones already handed over to the presentation.
+    fn slide_count(&self) -> usize {
+        self.delivered_slides + self.slides.len()
     }
 
     fn validate_last_operation(&mut self) -> Result<(), BuildError> {
@@ -109,48 +285,99 @@ impl<'a> PresentationBuilder<'a> {
     }
 
     fn push_slide_prelude(&mut self) {
-        let colors = self.theme.default_style.colors.clone();
+        let colors = self.default_colors();
         self.slide_operations.extend([
             RenderOperation::SetColors(colors),
             RenderOperation::ClearScreen,
-            RenderOperation::ApplyMargin(MarginProperties {
-                horizontal_margin: self.theme.default_style.margin.clone().unwrap_or_default(),
-                bottom_slide_margin: DEFAULT_BOTTOM_SLIDE_MARGIN,
-            }),
+            RenderOperation::ApplyMargin(self.margin_properties()),
         ]);
         self.push_line_break();
     }
 
     fn process_element(&mut self, element: MarkdownElement) -> Result<(), BuildError> {
         let is_list = matches!(element, MarkdownElement::List(_));
+        if !matches!(element, MarkdownElement::FrontMatter(_) | MarkdownElement::Comment { .. }) {
+            // Slides that start with a level 1 heading or that only have a slide title are sections.
+            self.slide_starts_section |=
+                self.slide_is_empty && matches!(element, MarkdownElement::Heading { level: 1, .. });
+            self.slide_title_only = matches!(element, MarkdownElement::SetexHeading { .. })
+                && (self.slide_is_empty || self.slide_title_only);
+            self.slide_is_empty = false;
+        }
+        if self.image_grid.is_some() {
+            match &element {
+                MarkdownElement::Image { .. } | MarkdownElement::Comment { .. } => (),
+                // Images on consecutive lines are split by line breaks; these are part of the grid.
+                MarkdownElement::Paragraph(elements)
+                    if elements.iter().all(|element| matches!(element, ParagraphElement::LineBreak)) =>
+                {
+                    self.ignore_element_line_break = true;
+                    return Ok(());
+                }
+                _ => self.close_image_grid(),
+            };
+        }
+        if self.comparison.is_some() {
+            let MarkdownElement::Code(code) = element else {
+                return Err(BuildError::InvalidComparison("expected two code blocks"));
+            };
+            return self.push_comparison_code(code);
+        }
+        let reveal_rows = match &element {
+            MarkdownElement::Comment { .. } => false,
+            _ if self.reveal_columns => return Err(BuildError::InvalidRevealColumns),
+            MarkdownElement::Table(_) | MarkdownElement::BlockQuote(_) => mem::take(&mut self.reveal_rows),
+            _ if self.reveal_rows => return Err(BuildError::InvalidRevealRows),
+            _ => false,
+        };
         match element {
             // This one is processed before everything else as it affects how the rest of the
             // elements is rendered.
             MarkdownElement::FrontMatter(_) => self.ignore_element_line_break = true,
             MarkdownElement::SetexHeading { text } => self.push_slide_title(text),
-            MarkdownElement::Heading { level, text } => self.push_heading(level, text),
+            MarkdownElement::Heading { level, text } => self.push_heading(level, text)?,
             MarkdownElement::Paragraph(elements) => self.push_paragraph(elements)?,
-            MarkdownElement::List(elements) => self.push_list(elements),
-            MarkdownElement::Code(code) => self.push_code(code),
-            MarkdownElement::Table(table) => self.push_table(table),
-            MarkdownElement::ThematicBreak => self.push_separator(),
-            MarkdownElement::Comment(comment) => self.process_comment(comment)?,
-            MarkdownElement::BlockQuote(lines) => self.push_block_quote(lines),
-            MarkdownElement::Image(path) => self.push_image(path)?,
+            MarkdownElement::List(elements) => self.push_list(elements)?,
+            MarkdownElement::Code(code) => self.push_code(code)?,
+            MarkdownElement::Table(table) => self.push_table(table, reveal_rows),
+            MarkdownElement::ThematicBreak => self.process_thematic_break(),
+            MarkdownElement::Comment { comment, source_position } => self.process_comment(comment, source_position)?,
+            MarkdownElement::BlockQuote(lines) => self.push_block_quote(lines, reveal_rows),
+            MarkdownElement::Image { path, title, alt_text, attributes } => {
+                let caption = if alt_text.is_empty() { title } else { alt_text };
+                self.push_image(path, caption, attributes)?
+            }
         };
         self.last_element_is_list = is_list;
         Ok(())
     }
 
     fn process_front_matter(&mut self, contents: &str) -> Result<(), BuildError> {
-        let metadata: PresentationMetadata =
-            serde_yaml::from_str(contents).map_err(|e| BuildError::InvalidMetadata(e.to_string()))?;
-
-        self.footer_context.borrow_mut().author = metadata.author.clone().unwrap_or_default();
+        // The front matter's contents start right after the `---` line at the top of the file.
+        let (metadata, warnings) =
+            parse_front_matter(contents).map_err(|e| BuildError::InvalidFrontMatter(e.with_line_offset(1)))?;
+        self.warnings.extend(warnings);
+
+        self.footer_context.borrow_mut().author =
+            metadata.author.as_ref().map(|author| author.name().to_string()).unwrap_or_default();
+        self.resources.set_search_paths(metadata.resource_paths.clone());
         self.set_theme(&metadata.theme)?;
+        self.default_hooks = metadata.hooks.clone();
+        self.slide_hooks = Rc::new(RefCell::new(self.default_hooks.clone()));
+        self.presentation_options = metadata.options.clone();
+        self.incremental_lists = self.presentation_options.incremental_lists;
+        self.keywords = metadata
+            .keywords
+            .iter()
+            .filter(|(keyword, _)| !keyword.is_empty())
+            .map(|(keyword, style)| (keyword.clone(), style.text_style()))
+            .collect();
+        // Prefer the longest keyword when one of them is a prefix of another one.
+        self.keywords.sort_by_key(|(keyword, _)| Reverse(keyword.len()));
+        self.footer_context.borrow_mut().socials = self.socials_line(&metadata.socials).unwrap_or_default();
         if metadata.title.is_some() || metadata.sub_title.is_some() || metadata.author.is_some() {
             self.push_slide_prelude();
-            self.push_intro_slide(metadata);
+            self.push_intro_slide(metadata)?;
         }
         Ok(())
     }
@@ -185,7 +412,8 @@ impl<'a> PresentationBuilder<'a> {
         Ok(())
     }
 
-    fn push_intro_slide(&mut self, metadata: PresentationMetadata) {
+    fn push_intro_slide(&mut self, metadata: PresentationMetadata) -> Result<(), BuildError> {
+        self.slide_is_intro = true;
         let styles = &self.theme.intro_slide;
         let title = StyledText::new(
             metadata.title.unwrap_or_default().clone(),
@@ -195,18 +423,50 @@ impl<'a> PresentationBuilder<'a> {
             .sub_title
             .as_ref()
             .map(|text| StyledText::new(text.clone(), TextStyle::default().colors(styles.subtitle.colors.clone())));
-        let author = metadata
-            .author
-            .as_ref()
-            .map(|text| StyledText::new(text.clone(), TextStyle::default().colors(styles.author.colors.clone())));
+        let author_style = TextStyle::default().colors(styles.author.colors.clone());
+        let mut author_lines: Vec<_> = match &metadata.author {
+            Some(PresentationAuthor::Name(name)) => vec![StyledText::new(name.clone(), author_style)],
+            Some(PresentationAuthor::Details(details)) => {
+                let detail = |text: &Option<String>, style: &AuthorDetailStyle| {
+                    let mut text_style = TextStyle::default().colors(style.colors.clone());
+                    if style.italics {
+                        text_style = text_style.italics();
+                    }
+                    text.as_ref().map(|text| StyledText::new(text.clone(), text_style))
+                };
+                iter::once(StyledText::new(details.name.clone(), author_style))
+                    .chain(detail(&details.title, &styles.author.title))
+                    .chain(detail(&details.company, &styles.author.company))
+                    .chain(detail(&details.contact, &styles.author.contact))
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+        let socials_style = TextStyle::default().colors(styles.socials.colors.clone());
+        author_lines.extend(self.socials_line(&metadata.socials).map(|line| StyledText::new(line, socials_style)));
+        let layout = styles.layout.clone();
+        let alignment = match layout {
+            IntroSlideLayout::LeftRule => Some(Alignment::Left { margin: Default::default() }),
+            _ => None,
+        };
+        if matches!(layout, IntroSlideLayout::Split { .. }) {
+            self.slide_operations.extend([
+                RenderOperation::InitColumnLayout { columns: vec![1, 1] },
+                RenderOperation::EnterColumn { column: 0 },
+            ]);
+        }
         self.slide_operations.push(RenderOperation::JumpToVerticalCenter);
-        self.push_text(Text::from(title), ElementType::PresentationTitle);
+        self.push_intro_text(title, ElementType::PresentationTitle, &alignment);
         self.push_line_break();
+        if matches!(layout, IntroSlideLayout::LeftRule) {
+            self.slide_operations.push(RenderOperation::RenderSeparator(self.theme.separator.clone()));
+            self.push_line_break();
+        }
         if let Some(text) = sub_title {
-            self.push_text(Text::from(text), ElementType::PresentationSubTitle);
+            self.push_intro_text(text, ElementType::PresentationSubTitle, &alignment);
             self.push_line_break();
         }
-        if let Some(text) = author {
+        if !author_lines.is_empty() {
             match self.theme.intro_slide.author.positioning {
                 AuthorPositioning::BelowTitle => {
                     self.push_line_break();
@@ -214,26 +474,78 @@ impl<'a> PresentationBuilder<'a> {
                     self.push_line_break();
                 }
                 AuthorPositioning::PageBottom => {
-                    self.slide_operations.push(RenderOperation::JumpToBottom);
+                    // Leave enough rows for every line in the author block.
+                    let index = author_lines.len() as u16 - 1;
+                    self.slide_operations.push(RenderOperation::JumpToBottomRow { index });
                 }
             };
-            self.push_text(Text::from(text), ElementType::PresentationAuthor);
+            for (index, line) in author_lines.into_iter().enumerate() {
+                if index > 0 {
+                    self.push_line_break();
+                }
+                self.push_intro_text(line, ElementType::PresentationAuthor, &alignment);
+            }
+        }
+        if let IntroSlideLayout::Split { image } = layout {
+            let image = self.resources.image(&image)?;
+            let properties = ImageProperties { vertical_slots: None, width: None, alignment: Default::default() };
+            self.slide_operations.extend([
+                RenderOperation::EnterColumn { column: 1 },
+                RenderOperation::RenderImage(image, properties),
+                RenderOperation::ExitLayout,
+            ]);
         }
         self.terminate_slide(TerminateMode::ResetState);
+        Ok(())
+    }
+
+    // Put all of the author's social handles in a single line.
+    fn socials_line(&self, socials: &SocialHandles) -> Option<String> {
+        let handles: Vec<_> = socials
+            .iter()
+            .map(|(icon, label, handle)| match self.nerd_fonts {
+                true => format!("{icon} {handle}"),
+                false => format!("{label}: {handle}"),
+            })
+            .collect();
+        if handles.is_empty() {
+            return None;
+        }
+        let separator = self.theme.intro_slide.socials.separator.as_deref().unwrap_or(SocialsStyle::DEFAULT_SEPARATOR);
+        Some(handles.join(separator))
+    }
+
+    // Layouts can force an alignment on the intro slide's lines, otherwise the theme's is used.
+    fn push_intro_text(&mut self, text: StyledText, element_type: ElementType, alignment: &Option<Alignment>) {
+        let alignment = alignment.clone().unwrap_or_else(|| self.alignment(&element_type));
+        self.push_aligned_text(Text::from(text), alignment);
+    }
+
+    fn process_thematic_break(&mut self) {
+        if self.slides_compatibility {
+            self.terminate_slide(TerminateMode::ResetState);
+        } else {
+            self.push_separator();
+        }
     }
 
-    fn process_comment(&mut self, comment: String) -> Result<(), BuildError> {
-        // Ignore any multi line comment; those are assumed to be user comments
-        if comment.contains('\n') {
+    fn process_comment(&mut self, comment: String, source_position: SourcePosition) -> Result<(), BuildError> {
+        // Ignore any multi line comment; those are assumed to be user comments. The only exception
+        // are slide options, which are YAML blocks and therefore usually span multiple lines.
+        if comment.contains('\n') && !comment.trim_start().starts_with("slide:") {
             return Ok(());
         }
-        let comment = comment.parse::<CommentCommand>()?;
+        // Errors point at the comment's lines within the file rather than within the comment itself.
+        let comment = comment.parse::<CommentCommand>().map_err(|CommandParseError(e)| {
+            CommandParseError(e.with_line_offset(source_position.line.saturating_sub(1)))
+        })?;
         match comment {
             CommentCommand::Pause => self.process_pause(),
             CommentCommand::EndSlide => self.terminate_slide(TerminateMode::ResetState),
             CommentCommand::InitColumnLayout(columns) => {
                 Self::validate_column_layout(&columns)?;
-                self.layout = LayoutState::InLayout { columns_count: columns.len() };
+                let reveal = mem::take(&mut self.reveal_columns);
+                self.layout = LayoutState::InLayout { columns_count: columns.len(), reveal };
                 self.slide_operations.push(RenderOperation::InitColumnLayout { columns });
                 self.needs_enter_column = true;
             }
@@ -242,9 +554,9 @@ impl<'a> PresentationBuilder<'a> {
                 self.slide_operations.extend([RenderOperation::ExitLayout, RenderOperation::RenderLineBreak]);
             }
             CommentCommand::Column(column) => {
-                let (current_column, columns_count) = match self.layout {
-                    LayoutState::InColumn { column, columns_count } => (Some(column), columns_count),
-                    LayoutState::InLayout { columns_count } => (None, columns_count),
+                let (current_column, columns_count, reveal) = match self.layout {
+                    LayoutState::InColumn { column, columns_count, reveal } => (Some(column), columns_count, reveal),
+                    LayoutState::InLayout { columns_count, reveal } => (None, columns_count, reveal),
                     LayoutState::Default => return Err(BuildError::NoLayout),
                 };
                 if current_column == Some(column) {
@@ -252,15 +564,128 @@ impl<'a> PresentationBuilder<'a> {
                 } else if column >= columns_count {
                     return Err(BuildError::ColumnIndexTooLarge);
                 }
-                self.layout = LayoutState::InColumn { column, columns_count };
+                // Reveal every column after the first one in a separate chunk.
+                if reveal && current_column.is_some() {
+                    self.push_pause();
+                }
+                self.layout = LayoutState::InColumn { column, columns_count, reveal };
                 self.slide_operations.push(RenderOperation::EnterColumn { column });
             }
+            CommentCommand::OnEnter(command) => self.slide_hooks.borrow_mut().on_enter.push(command),
+            CommentCommand::OnExit(command) => self.slide_hooks.borrow_mut().on_exit.push(command),
+            CommentCommand::SpeakerNote(note) => self.slide_notes.borrow_mut().push(note),
+            CommentCommand::RevealRows => self.reveal_rows = true,
+            CommentCommand::RevealColumns => self.reveal_columns = true,
+            CommentCommand::IncrementalLists(enabled) => self.incremental_lists = enabled,
+            CommentCommand::Slide(options) => self.apply_slide_options(options),
+            CommentCommand::Duration(duration) => self.slide_duration = Some(duration.0),
+            CommentCommand::Video(source) => self.push_video(source),
+            CommentCommand::Part(name) => {
+                let first_slide = self.slide_count();
+                // Two parts starting in the same slide would make the first one unreachable.
+                if self.parts.last().is_some_and(|part| part.first_slide == first_slide) {
+                    self.parts.pop();
+                }
+                self.parts.push(PresentationPart { name, first_slide });
+            }
+            CommentCommand::Compare(titles) => {
+                if !matches!(self.layout, LayoutState::Default) {
+                    return Err(BuildError::InvalidComparison("can't be used inside a column layout"));
+                }
+                self.close_image_grid();
+                self.comparison = Some(ComparisonState { titles, codes: Vec::new() });
+            }
+            CommentCommand::ImageGrid(size) => {
+                if !matches!(self.layout, LayoutState::Default) {
+                    return Err(BuildError::ImageGridInLayout);
+                }
+                self.close_image_grid();
+                self.image_grid = Some(ImageGridState { size, images: 0 });
+            }
         };
         // Don't push line breaks for any comments.
         self.ignore_element_line_break = true;
         Ok(())
     }
 
+    fn apply_slide_options(&mut self, options: SlideOptions) {
+        if let Some(notes) = &options.notes {
+            self.slide_notes.borrow_mut().extend(notes.lines().map(String::from));
+        }
+        self.slide_options = options;
+        if self.slide_options.background.is_some() {
+            // Patch the colors the slide prelude uses so the screen is cleared using this background.
+            let colors = self.default_colors();
+            if let Some(RenderOperation::SetColors(prelude_colors)) =
+                self.iter_slide_operations_mut().find(|op| matches!(op, RenderOperation::SetColors(_)))
+            {
+                *prelude_colors = colors.clone();
+            }
+            self.slide_operations.push(RenderOperation::SetColors(colors));
+        }
+        if self.slide_options.margin.is_some() {
+            // Same as above, the margin is applied by the slide prelude.
+            let properties = self.margin_properties();
+            if let Some(RenderOperation::ApplyMargin(prelude_properties)) =
+                self.iter_slide_operations_mut().find(|op| matches!(op, RenderOperation::ApplyMargin(_)))
+            {
+                *prelude_properties = properties;
+            }
+        }
+    }
+
+    fn slide_margins(&self) -> SlideMargins {
+        let deck_margins = &self.presentation_options.margin;
+        match &self.slide_options.margin {
+            Some(margins) => margins.or(deck_margins),
+            None => deck_margins.clone(),
+        }
+    }
+
+    fn margin_properties(&self) -> MarginProperties {
+        let margins = self.slide_margins();
+        let horizontal_margin =
+            margins.horizontal.or_else(|| self.theme.default_style.margin.clone()).unwrap_or_default();
+        MarginProperties {
+            horizontal_margin,
+            bottom_slide_margin: margins.bottom.unwrap_or(SlideMargins::DEFAULT_BOTTOM),
+            max_columns: self.theme.default_style.max_columns,
+        }
+    }
+
+    fn default_colors(&self) -> Colors {
+        let mut colors = self.theme.default_style.colors.clone();
+        let kind_colors = &self.theme.slide_kinds.get(self.slide_kind()).colors;
+        colors.background = kind_colors.background.or(colors.background);
+        colors.foreground = kind_colors.foreground.or(colors.foreground);
+        if let Some(background) = self.slide_options.background {
+            colors.background = Some(background);
+        }
+        colors
+    }
+
+    fn alignment(&self, element_type: &ElementType) -> Alignment {
+        let kind_alignment = &self.theme.slide_kinds.get(self.slide_kind()).alignment;
+        let slide_alignment = self.slide_options.alignment.as_ref().map(|alignment| &alignment.0);
+        match slide_alignment.or(kind_alignment.as_ref()) {
+            Some(alignment) => alignment.clone(),
+            None => self.theme.alignment(element_type),
+        }
+    }
+
+    // Closing slides can't be inferred as slides are built before we know which one is the last.
+    fn slide_kind(&self) -> SlideKind {
+        if let Some(kind) = self.slide_options.kind {
+            kind
+        } else if self.slide_is_intro {
+            SlideKind::Title
+        } else if self.slide_starts_section {
+            SlideKind::Section
+        } else {
+            SlideKind::Content
+        }
+    }
+
     fn validate_column_layout(columns: &[u8]) -> Result<(), BuildError> {
         if columns.is_empty() {
             Err(BuildError::InvalidLayout("need at least one column"))
@@ -277,33 +702,54 @@ impl<'a> PresentationBuilder<'a> {
         if self.last_element_is_list && matches!(self.slide_operations.last(), Some(RenderOperation::RenderLineBreak)) {
             self.slide_operations.pop();
         }
+        self.push_pause();
+    }
+
+    fn push_pause(&mut self) {
+        let operations = mem::take(&mut self.slide_operations);
+        let mutators = mem::take(&mut self.chunk_mutators);
+        self.slide_chunks.push(SlideChunk::new(operations).with_mutators(mutators));
+    }
 
-        let next_operations = self.slide_operations.clone();
-        self.terminate_slide(TerminateMode::KeepState);
-        self.slide_operations = next_operations;
+    // Iterate the operations in the slide being built, including the ones in previous chunks.
+    fn iter_slide_operations(&self) -> impl Iterator<Item = &RenderOperation> {
+        self.slide_chunks.iter().flat_map(|chunk| &chunk.operations).chain(&self.slide_operations)
+    }
+
+    fn iter_slide_operations_mut(&mut self) -> impl Iterator<Item = &mut RenderOperation> {
+        self.slide_chunks.iter_mut().flat_map(|chunk| &mut chunk.operations).chain(&mut self.slide_operations)
     }
 
     fn push_slide_title(&mut self, mut text: Text) {
+        self.set_current_title(&text);
         let style = self.theme.slide_title.clone();
         text.apply_style(&TextStyle::default().bold().colors(style.colors.clone()));
 
         for _ in 0..style.padding_top.unwrap_or(0) {
             self.push_line_break();
         }
-        self.push_text(text, ElementType::SlideTitle);
+        let line_size = style.line_size.unwrap_or_default();
+        if line_size != LineSize::Normal {
+            self.slide_operations.push(RenderOperation::SetLineSize(line_size));
+            self.push_text(text, ElementType::SlideTitle);
+            self.slide_operations.push(RenderOperation::SetLineSize(LineSize::Normal));
+        } else {
+            self.push_text(text, ElementType::SlideTitle);
+        }
         self.push_line_break();
 
         for _ in 0..style.padding_bottom.unwrap_or(0) {
             self.push_line_break();
         }
         if style.separator {
-            self.slide_operations.push(RenderOperation::RenderSeparator);
+            self.slide_operations.push(RenderOperation::RenderSeparator(self.theme.separator.clone()));
         }
         self.push_line_break();
         self.ignore_element_line_break = true;
     }
 
-    fn push_heading(&mut self, level: u8, mut text: Text) {
+    fn push_heading(&mut self, level: u8, mut text: Text) -> Result<(), BuildError> {
+        self.set_current_title(&text);
         let (element_type, style) = match level {
             1 => (ElementType::Heading1, &self.theme.headings.h1),
             2 => (ElementType::Heading2, &self.theme.headings.h2),
@@ -311,18 +757,20 @@ impl<'a> PresentationBuilder<'a> {
             4 => (ElementType::Heading4, &self.theme.headings.h4),
             5 => (ElementType::Heading5, &self.theme.headings.h5),
             6 => (ElementType::Heading6, &self.theme.headings.h6),
-            other => panic!("unexpected heading level {other}"),
+            other => return Err(BuildError::InvalidHeadingLevel(other)),
         };
         if let Some(prefix) = &style.prefix {
             let mut prefix = prefix.clone();
             prefix.push(' ');
-            text.chunks.insert(0, StyledText::from(prefix));
+            let prefix_style = TextStyle::default().colors(style.prefix_colors.clone());
+            text.chunks.insert(0, StyledText::new(prefix, prefix_style));
         }
         let text_style = TextStyle::default().bold().colors(style.colors.clone());
         text.apply_style(&text_style);
 
         self.push_text(text, element_type);
         self.push_line_break();
+        Ok(())
     }
 
     fn push_paragraph(&mut self, elements: Vec<ParagraphElement>) -> Result<(), BuildError> {
@@ -341,22 +789,89 @@ impl<'a> PresentationBuilder<'a> {
     }
 
     fn push_separator(&mut self) {
-        self.slide_operations.extend([RenderOperation::RenderSeparator, RenderOperation::RenderLineBreak]);
+        let separator = self.theme.separator.clone();
+        self.slide_operations.extend([RenderOperation::RenderSeparator(separator), RenderOperation::RenderLineBreak]);
     }
 
-    fn push_image(&mut self, path: PathBuf) -> Result<(), BuildError> {
+    fn push_image(&mut self, path: PathBuf, caption: String, attributes: ImageAttributes) -> Result<(), BuildError> {
         let image = self.resources.image(&path)?;
-        self.slide_operations.push(RenderOperation::RenderImage(image));
+        let properties = ImageProperties {
+            vertical_slots: None,
+            width: attributes.width,
+            alignment: attributes.alignment.unwrap_or_default(),
+        };
+        let Some(grid) = &mut self.image_grid else {
+            self.slide_operations.push(RenderOperation::RenderImage(image, properties));
+            self.push_caption(caption);
+            return Ok(());
+        };
+        let ImageGridSize { columns, rows } = grid.size;
+        let row = grid.images / columns as usize;
+        let column = grid.images % columns as usize;
+        grid.images += 1;
+        let is_full = grid.images == columns as usize * rows as usize;
+        if column == 0 {
+            self.slide_operations.push(RenderOperation::InitColumnLayout { columns: vec![1; columns as usize] });
+        }
+        // Every row of images takes an equal share of the space that's left below it.
+        let properties = ImageProperties { vertical_slots: Some(u16::from(rows) - row as u16), ..properties };
+        self.slide_operations
+            .extend([RenderOperation::EnterColumn { column }, RenderOperation::RenderImage(image, properties)]);
+        self.push_caption(caption);
+        if column == columns as usize - 1 {
+            self.slide_operations.push(RenderOperation::ExitLayout);
+        }
+        if is_full {
+            self.image_grid = None;
+        } else {
+            self.ignore_element_line_break = true;
+        }
         Ok(())
     }
 
-    fn push_list(&mut self, items: Vec<ListItem>) {
-        for item in items {
-            self.push_list_item(item);
+    fn push_caption(&mut self, caption: String) {
+        if caption.is_empty() {
+            return;
+        }
+        self.figures += 1;
+        self.push_numbered_caption(Text::from(caption), "Figure", self.figures);
+    }
+
+    fn push_numbered_caption(&mut self, mut caption: Text, label: &str, number: usize) {
+        let style = self.theme.caption.clone();
+        if style.numbering {
+            caption.chunks.insert(0, StyledText::from(format!("{label} {number}: ")));
+        }
+        caption.apply_style(&TextStyle::default().italics().colors(style.colors));
+        self.push_text(caption, ElementType::Caption);
+        self.push_line_break();
+    }
+
+    fn close_image_grid(&mut self) {
+        let Some(grid) = self.image_grid.take() else {
+            return;
+        };
+        // Exit the layout for the last row if it wasn't full.
+        if grid.images % grid.size.columns as usize != 0 {
+            self.slide_operations.push(RenderOperation::ExitLayout);
+        }
+        if grid.images > 0 {
+            self.push_line_break();
+        }
+    }
+
+    fn push_list(&mut self, items: Vec<ListItem>) -> Result<(), BuildError> {
+        for (index, item) in items.into_iter().enumerate() {
+            if self.incremental_lists && index > 0 {
+                self.push_pause();
+            }
+            self.push_list_item(item)?;
         }
+        Ok(())
     }
 
-    fn push_list_item(&mut self, item: ListItem) {
+    fn push_list_item(&mut self, item: ListItem) -> Result<(), BuildError> {
+        let color = item.color.as_deref().map(|name| self.palette_color(name)).transpose()?;
         let padding_length = (item.depth as usize + 1) * 3;
         let mut prefix: String = " ".repeat(padding_length);
         match item.item_type {
@@ -367,6 +882,7 @@ impl<'a> PresentationBuilder<'a> {
                     _ => '▪',
                 };
                 prefix.push(delimiter);
+                prefix.push(' ');
             }
             ListItemType::OrderedParens(number) => {
                 prefix.push_str(&number.to_string());
@@ -378,47 +894,136 @@ impl<'a> PresentationBuilder<'a> {
             }
         };
 
-        let prefix_length = prefix.len() as u16;
-        self.push_text(prefix.into(), ElementType::List);
-
-        let text = item.contents;
-        self.push_aligned_text(text, Alignment::Left { margin: Margin::Fixed(prefix_length) });
+        let prefix_length = prefix.display_width();
+        let mut prefix = StyledText::from(prefix);
+        let mut text = item.contents;
+        if let Some(color) = color {
+            for chunk in iter::once(&mut prefix).chain(text.chunks.iter_mut()) {
+                chunk.style.colors.foreground.get_or_insert(color);
+            }
+        }
+        // Wrapped rows are indented so they line up with the text after the bullet.
+        let mut texts = vec![WeightedText::from(prefix)];
+        texts.extend(self.style_text(text));
+        let line = WeightedLine::from(texts).with_hanging_indent(prefix_length);
+        self.slide_operations
+            .push(RenderOperation::RenderTextLine { line, alignment: self.alignment(&ElementType::List) });
         self.push_line_break();
+        if !item.children.is_empty() {
+            self.push_list_item_children(item.children, prefix_length as u16)?;
+        }
+        Ok(())
+    }
+
+    fn push_list_item_children(&mut self, children: Vec<MarkdownElement>, indent: u16) -> Result<(), BuildError> {
+        let margin = RenderOperation::ApplyMargin(MarginProperties {
+            horizontal_margin: Margin::Fixed(indent),
+            bottom_slide_margin: 0,
+            max_columns: None,
+        });
+        self.slide_operations.push(margin.clone());
+        for child in children {
+            match child {
+                // Nested list items are already indented based on their depth.
+                MarkdownElement::List(items) => {
+                    self.slide_operations.push(RenderOperation::PopMargin);
+                    if self.incremental_lists {
+                        self.push_pause();
+                    }
+                    self.push_list(items)?;
+                    self.slide_operations.push(margin.clone());
+                }
+                child => {
+                    self.push_line_break();
+                    self.ignore_element_line_break = false;
+                    self.process_element(child)?;
+                }
+            };
+        }
+        self.close_image_grid();
+        self.ignore_element_line_break = false;
+        self.slide_operations.push(RenderOperation::PopMargin);
+        Ok(())
+    }
+
+    fn palette_color(&self, name: &str) -> Result<Color, BuildError> {
+        if let Some(color) = self.theme.palette.colors.get(name) {
+            return Ok(*color);
+        }
+        name.parse().map_err(|_| BuildError::UnknownColor(name.into()))
     }
 
-    fn push_block_quote(&mut self, lines: Vec<String>) {
+    fn push_block_quote(&mut self, lines: Vec<Text>, reveal_rows: bool) {
         let prefix = self.theme.block_quote.prefix.clone().unwrap_or_default();
-        let block_length = lines.iter().map(|line| line.width() + prefix.width()).max().unwrap_or(0);
+        let tab_width = self.theme.block_quote.tab_width.or(self.theme.code.tab_width);
+        let tab_width = tab_width.unwrap_or(DEFAULT_TAB_WIDTH).max(1);
+        let lines: Vec<_> = lines
+            .into_iter()
+            .map(|mut line| {
+                let mut column = 0;
+                for chunk in &mut line.chunks {
+                    chunk.text = Self::expand_tabs(&chunk.text, tab_width, column);
+                    column += chunk.text.display_width();
+                }
+                WeightedLine::from(self.style_text(line))
+            })
+            .collect();
+        let block_length = lines.iter().map(|line| line.width() + prefix.display_width()).max().unwrap_or(0);
 
         self.slide_operations.push(RenderOperation::SetColors(self.theme.block_quote.colors.clone()));
-        for mut line in lines {
-            line.insert_str(0, &prefix);
+        for (index, line) in lines.into_iter().enumerate() {
+            if reveal_rows && index > 0 {
+                // Restore the default colors so anything drawn after this chunk, like the footer,
+                // isn't affected by the block quote's.
+                self.slide_operations.push(RenderOperation::SetColors(self.default_colors()));
+                self.push_pause();
+                self.slide_operations.push(RenderOperation::SetColors(self.theme.block_quote.colors.clone()));
+            }
+            self.slide_operations.push(RenderOperation::RenderBlockLine(BlockLine {
+                prefix: prefix.clone(),
+                text: line,
+                block_length,
+                alignment: self.alignment(&ElementType::BlockQuote),
+            }));
+            self.push_line_break();
+        }
+        self.slide_operations.push(RenderOperation::SetColors(self.default_colors()));
+    }
+
+    fn push_video(&mut self, source: String) {
+        // URLs are handed to the player as is, anything else is a path relative to the presentation.
+        let source = match source.contains("://") {
+            true => source,
+            false => self.resources.resolve_path(&source).display().to_string(),
+        };
+        let name = source.rsplit('/').next().unwrap_or_default().to_string();
+        self.slide_videos.push(source);
 
-            let line_length = line.width();
-            self.slide_operations.push(RenderOperation::RenderPreformattedLine(PreformattedLine {
+        let lines = [
+            ("▶ ", WeightedLine::from(vec![WeightedText::from(StyledText::new(name, TextStyle::default().bold()))])),
+            ("  ", WeightedLine::from("press v to play".to_string())),
+        ];
+        let block_length = lines.iter().map(|(prefix, line)| line.width() + prefix.display_width()).max().unwrap_or(0);
+        self.slide_operations.push(RenderOperation::SetColors(self.theme.block_quote.colors.clone()));
+        for (prefix, line) in lines {
+            self.slide_operations.push(RenderOperation::RenderBlockLine(BlockLine {
+                prefix: prefix.into(),
                 text: line,
-                unformatted_length: line_length,
                 block_length,
-                alignment: self.theme.alignment(&ElementType::BlockQuote).clone(),
+                alignment: self.alignment(&ElementType::BlockQuote),
             }));
             self.push_line_break();
         }
-        self.slide_operations.push(RenderOperation::SetColors(self.theme.default_style.colors.clone()));
+        self.slide_operations.push(RenderOperation::SetColors(self.default_colors()));
     }
 
     fn push_text(&mut self, text: Text, element_type: ElementType) {
-        let alignment = self.theme.alignment(&element_type);
+        let alignment = self.alignment(&element_type);
         self.push_aligned_text(text, alignment);
     }
 
     fn push_aligned_text(&mut self, text: Text, alignment: Alignment) {
-        let mut texts: Vec<WeightedText> = Vec::new();
-        for mut chunk in text.chunks {
-            if chunk.style.is_code() {
-                chunk.style.colors = self.theme.inline_code.colors.clone();
-            }
-            texts.push(chunk.into());
-        }
+        let texts = self.style_text(text);
         if !texts.is_empty() {
             self.slide_operations.push(RenderOperation::RenderTextLine {
                 line: WeightedLine::from(texts),
@@ -427,12 +1032,198 @@ impl<'a> PresentationBuilder<'a> {
         }
     }
 
+    fn style_text(&self, text: Text) -> Vec<WeightedText> {
+        let mut texts = Vec::new();
+        for mut chunk in text.chunks {
+            if chunk.style.is_code() {
+                self.style_inline_code(&mut chunk);
+                texts.push(chunk.into());
+            } else {
+                texts.extend(self.highlight_keywords(chunk).into_iter().map(WeightedText::from));
+            }
+        }
+        texts
+    }
+
+    fn highlight_keywords(&self, chunk: StyledText) -> Vec<StyledText> {
+        if self.keywords.is_empty() || chunk.style.is_link() {
+            return vec![chunk];
+        }
+        let text = &chunk.text;
+        let is_boundary = |c: Option<char>| !c.is_some_and(char::is_alphanumeric);
+        let mut output = Vec::new();
+        let mut plain_start = 0;
+        let mut index = 0;
+        while index < text.len() {
+            let keyword = self.keywords.iter().find(|(keyword, _)| {
+                text[index..].starts_with(keyword.as_str())
+                    && is_boundary(text[..index].chars().next_back())
+                    && is_boundary(text[index + keyword.len()..].chars().next())
+            });
+            let Some((keyword, style)) = keyword else {
+                index += text[index..].chars().next().map_or(1, char::len_utf8);
+                continue;
+            };
+            if plain_start < index {
+                output.push(StyledText::new(&text[plain_start..index], chunk.style.clone()));
+            }
+            let mut style = style.clone();
+            style.merge(&chunk.style);
+            output.push(StyledText::new(keyword, style));
+            index += keyword.len();
+            plain_start = index;
+        }
+        if plain_start < text.len() {
+            output.push(StyledText::new(&text[plain_start..], chunk.style.clone()));
+        }
+        output
+    }
+
+    fn style_inline_code(&self, chunk: &mut StyledText) {
+        let style = &self.theme.inline_code;
+        chunk.style.colors = style.colors.clone();
+        let padding = " ".repeat(style.padding.unwrap_or(0) as usize);
+        let (start, end) = match &style.delimiters {
+            Some(delimiters) => (delimiters.start.as_str(), delimiters.end.as_str()),
+            None => ("", ""),
+        };
+        if !padding.is_empty() || !start.is_empty() || !end.is_empty() {
+            chunk.text = format!("{start}{padding}{}{padding}{end}", chunk.text);
+        }
+    }
+
     fn push_line_break(&mut self) {
         self.slide_operations.push(RenderOperation::RenderLineBreak);
     }
 
-    fn push_code(&mut self, code: Code) {
-        let Code { contents, language } = code;
+    fn push_code(&mut self, code: Code) -> Result<(), BuildError> {
+        let executable =
+            code.flags.execute || (self.slides_compatibility && CodeExecuter::is_supported(&code.language));
+        if executable && !CodeExecuter::is_supported(&code.language) {
+            return Err(BuildError::UnsupportedExecution(code.language));
+        }
+        let full_code = self.include_snippets(&code)?;
+        let executable_code = executable.then(|| self.execution_code(full_code));
+        let groups = &code.flags.highlight_groups;
+        let lines = self.highlight_code(&code, groups.first());
+        let later_steps: Vec<_> = groups.iter().skip(1).map(|group| self.highlight_code(&code, Some(group))).collect();
+        let block_length = lines.first().map(|line| line.block_length).unwrap_or_default();
+        let alignment = self.alignment(&ElementType::Code);
+        if code.flags.hidden {
+            let code = executable_code.expect("hidden code is always executable");
+            let operation = Rc::new(self.run_code_operation(code, alignment, block_length));
+            self.slide_setup_code.push(operation.clone());
+            self.slide_operations.push(RenderOperation::RenderOnDemand(operation));
+            self.ignore_element_line_break = true;
+            return Ok(());
+        }
+        let run_operation = executable_code.map(|code| {
+            let operation = self.run_code_operation(code, alignment.clone(), block_length);
+            RenderOperation::RenderOnDemand(Rc::new(operation))
+        });
+        let padding_lines = if self.theme.code.padding.vertical.unwrap_or(0) > 0 { 1 } else { 0 };
+        match code.flags.max_height {
+            Some(max_height) if lines.len() - padding_lines * 2 > max_height => {
+                let scroll = CodeScroll { padding_lines, max_height, alignment };
+                self.push_scrolling_code(lines, scroll, run_operation);
+            }
+            _ if !later_steps.is_empty() => {
+                let steps = iter::once(lines).chain(later_steps).collect();
+                self.push_code_steps(steps, run_operation);
+            }
+            _ => {
+                self.push_code_lines(lines.iter());
+                self.slide_operations.extend(run_operation);
+            }
+        };
+        Ok(())
+    }
+
+    fn run_code_operation(&self, code: Code, alignment: Alignment, block_length: usize) -> RunCodeOperation {
+        RunCodeOperation::new(code, alignment, block_length, self.slide_setup_code.clone())
+            .with_status_style(self.theme.execution_status.clone())
+    }
+
+    // Prepends the code blocks the given one includes and keeps track of it if it has an id.
+    fn include_snippets(&mut self, code: &Code) -> Result<Code, BuildError> {
+        let mut full_code = code.clone();
+        let mut contents = String::new();
+        for id in &code.flags.include {
+            let snippet = self.snippets.get(id).ok_or_else(|| BuildError::UnknownSnippet(id.clone()))?;
+            if snippet.language != code.language {
+                return Err(BuildError::SnippetLanguageMismatch(id.clone()));
+            }
+            contents.push_str(&snippet.contents);
+            if !contents.ends_with('\n') {
+                contents.push('\n');
+            }
+        }
+        contents.push_str(&code.contents);
+        full_code.contents = contents;
+        if let Some(id) = &code.flags.id {
+            if self.snippets.insert(id.clone(), full_code.clone()).is_some() {
+                return Err(BuildError::DuplicateSnippet(id.clone()));
+            }
+        }
+        Ok(full_code)
+    }
+
+    // Applies the presentation's execution defaults to a piece of code that's going to be executed.
+    fn execution_code(&self, mut code: Code) -> Code {
+        let defaults = &self.presentation_options.exec;
+        let cwd = code.flags.cwd.take().or_else(|| defaults.cwd.clone());
+        code.flags.cwd = cwd.map(|cwd| self.resources.resolve_path(cwd));
+        // Variables set in the code block itself come last so they take precedence.
+        let env = defaults.env.iter().map(|(name, value)| (name.clone(), value.clone()));
+        code.flags.env = env.chain(mem::take(&mut code.flags.env)).collect();
+        code
+    }
+
+    fn push_comparison_code(&mut self, code: Code) -> Result<(), BuildError> {
+        let Some(comparison) = &mut self.comparison else {
+            return Ok(());
+        };
+        if code.flags.execute || code.flags.max_height.is_some() || code.flags.highlight_groups.len() > 1 {
+            let reason = "code blocks can't be executed, scrolled, nor highlighted in steps";
+            return Err(BuildError::InvalidComparison(reason));
+        }
+        comparison.codes.push(code);
+        if comparison.codes.len() < 2 {
+            self.ignore_element_line_break = true;
+            return Ok(());
+        }
+        let ComparisonState { titles, mut codes } = self.comparison.take().expect("no comparison");
+        // Pad the shortest block with empty lines so both of them have the same height.
+        let height = codes.iter().map(|code| code.contents.lines().count()).max().unwrap_or(0);
+        for code in &mut codes {
+            let missing = height - code.contents.lines().count();
+            if missing > 0 {
+                if !code.contents.ends_with('\n') {
+                    code.contents.push('\n');
+                }
+                code.contents.push_str(&"\n".repeat(missing));
+            }
+        }
+        let alignment = self.alignment(&ElementType::Code);
+        self.slide_operations.push(RenderOperation::InitColumnLayout { columns: vec![1, 1] });
+        for (column, (title, code)) in [titles.left, titles.right].into_iter().zip(codes).enumerate() {
+            self.slide_operations.push(RenderOperation::EnterColumn { column });
+            let title = Text::from(StyledText::new(title, TextStyle::default().bold()));
+            self.push_aligned_text(title, alignment.clone());
+            self.push_line_break();
+            let lines = self.highlight_code(&code, code.flags.highlight_groups.first());
+            self.push_code_lines(lines.iter());
+        }
+        self.slide_operations.push(RenderOperation::ExitLayout);
+        Ok(())
+    }
+
+    // Highlights code, dimming every line that's not in the given group.
+    fn highlight_code(&mut self, code: &Code, group: Option<&HighlightGroup>) -> Vec<PreformattedLine> {
+        let Code { contents, language, flags } = code;
+        let tab_width = self.theme.code.tab_width.unwrap_or(DEFAULT_TAB_WIDTH).max(1);
+        let contents = Self::expand_whitespace(contents, tab_width, flags.show_whitespace);
+        let line_count = contents.lines().count();
         let mut code = String::new();
         let horizontal_padding = self.theme.code.padding.horizontal.unwrap_or(0);
         let vertical_padding = self.theme.code.padding.vertical.unwrap_or(0);
@@ -456,65 +1247,292 @@ impl<'a> PresentationBuilder<'a> {
                 code.push('\n');
             }
         }
-        let block_length = code.lines().map(|line| line.width()).max().unwrap_or(0) + horizontal_padding as usize;
-        for code_line in self.highlighter.highlight(&code, &language) {
-            let CodeLine { formatted, original } = code_line;
-            let trimmed = formatted.trim_end();
-            let original_length = original.width() - (formatted.width() - trimmed.width());
-            self.slide_operations.push(RenderOperation::RenderPreformattedLine(PreformattedLine {
-                text: trimmed.into(),
-                unformatted_length: original_length,
-                block_length,
-                alignment: self.theme.alignment(&ElementType::Code),
-            }));
+        // Line numbers are right aligned and followed by a space.
+        let number_width = line_count.to_string().len();
+        let gutter_width = if flags.line_numbers { number_width + 1 } else { 0 };
+        let first_line = if vertical_padding > 0 { 1 } else { 0 };
+        let number_style = self.line_number_style();
+        let block_length = code.lines().map(|line| line.display_width()).max().unwrap_or(0)
+            + horizontal_padding as usize
+            + gutter_width;
+        let code_lines = {
+            let _measurement = profile::measure_slide(Phase::Highlight, self.slide_count());
+            self.highlighter.highlight(&code, language)
+        };
+        let dimmed_lines = match group {
+            Some(_) => self.highlighter.dim(&code),
+            None => Vec::new(),
+        };
+        let alignment = self.alignment(&ElementType::Code);
+        code_lines
+            .into_iter()
+            .enumerate()
+            .map(|(index, CodeLine { formatted, original })| {
+                // Padding lines don't have a number.
+                let number = index.checked_sub(first_line).filter(|number| *number < line_count).map(|n| n + 1);
+                let formatted = match (group, number) {
+                    (Some(group), Some(number)) if !group.contains(number) => &dimmed_lines[index].formatted,
+                    _ => &formatted,
+                };
+                let trimmed = formatted.trim_end();
+                let original_length = original.display_width() - (formatted.display_width() - trimmed.display_width());
+                let text = match flags.line_numbers {
+                    true => {
+                        let number = number.map(|number| number.to_string()).unwrap_or_default();
+                        format!("{}{trimmed}", number_style.apply(format!("{number:>number_width$} "))).into()
+                    }
+                    false => trimmed.into(),
+                };
+                PreformattedLine {
+                    text,
+                    unformatted_length: original_length + gutter_width,
+                    block_length,
+                    alignment: alignment.clone(),
+                }
+            })
+            .collect()
+    }
+
+    fn line_number_style(&self) -> TextStyle {
+        let defaults = self.highlighter.line_number_colors();
+        let colors = &self.theme.code.line_numbers.colors;
+        TextStyle::default().colors(Colors {
+            foreground: colors.foreground.or(defaults.foreground),
+            background: colors.background.or(defaults.background),
+        })
+    }
+
+    fn push_code_lines<'b>(&mut self, lines: impl Iterator<Item = &'b PreformattedLine>) {
+        for line in lines {
+            self.slide_operations.push(RenderOperation::RenderPreformattedLine(line.clone()));
             self.push_line_break();
         }
     }
 
-    fn terminate_slide(&mut self, mode: TerminateMode) {
-        self.push_footer();
-
-        let elements = mem::take(&mut self.slide_operations);
-        self.slides.push(Slide { render_operations: elements });
-        self.push_slide_prelude();
-        if matches!(mode, TerminateMode::ResetState) {
-            self.ignore_element_line_break = true;
-            self.needs_enter_column = false;
-            self.layout = Default::default();
+    // Pushes a code block that highlights the next group of lines every time the presentation
+    // advances, as if there was a pause before every group.
+    fn push_code_steps(&mut self, steps: Vec<Vec<PreformattedLine>>, run_operation: Option<RenderOperation>) {
+        let current_step = Rc::new(Cell::new(0));
+        let step_count = steps.len();
+        let generator = HighlightedCodeSteps { steps, current_step: current_step.clone() };
+        self.slide_operations.push(RenderOperation::RenderDynamic(Rc::new(generator)));
+        self.slide_operations.extend(run_operation);
+        for step in 1..step_count {
+            self.push_pause();
+            self.chunk_mutators.push(Rc::new(HighlightStepMutator { current_step: current_step.clone(), step }));
         }
     }
 
-    fn push_footer(&mut self) {
-        let generator = FooterGenerator {
-            style: self.theme.footer.clone(),
-            current_slide: self.slides.len(),
-            context: self.footer_context.clone(),
-        };
-        self.slide_operations.extend([
-            // Exit any layout we're in so this gets rendered on a default screen size.
-            RenderOperation::ExitLayout,
-            // Pop the slide margin so we're at the terminal rect.
-            RenderOperation::PopMargin,
-            // Jump to the very bottom of the terminal rect and draw the footer.
-            RenderOperation::JumpToBottom,
-            RenderOperation::RenderDynamic(Rc::new(generator)),
-        ]);
+    // Pushes a code block that only displays `max_height` lines at a time, scrolling down one page
+    // every time the presentation advances.
+    fn push_scrolling_code(
+        &mut self,
+        lines: Vec<PreformattedLine>,
+        scroll: CodeScroll,
+        run_operation: Option<RenderOperation>,
+    ) {
+        let CodeScroll { padding_lines, max_height, alignment } = scroll;
+        let (top_padding, lines) = lines.split_at(padding_lines);
+        let (lines, bottom_padding) = lines.split_at(lines.len() - padding_lines);
+        let total_lines = lines.len();
+        let start = self.slide_operations.len();
+        for (index, page) in lines.chunks(max_height).enumerate() {
+            if index > 0 {
+                // Start over from the operations before this code block, dropping the previous page.
+                // Pages are separate slides so anything in previous chunks is revealed right away.
+                let mut next_operations: Vec<_> =
+                    self.slide_chunks.iter().flat_map(|chunk| chunk.operations.clone()).collect();
+                next_operations.extend_from_slice(&self.slide_operations[..start]);
+                self.terminate_slide(TerminateMode::KeepState);
+                self.slide_operations = next_operations;
+            }
+            self.push_code_lines(top_padding.iter().chain(page).chain(bottom_padding));
+
+            let first_line = index * max_height + 1;
+            let last_line = first_line + page.len() - 1;
+            let more = if last_line < total_lines { " ↓" } else { "" };
+            let indicator = format!("lines {first_line}-{last_line} of {total_lines}{more}");
+            let indicator = Text::from(StyledText::new(indicator, TextStyle::default().italics()));
+            self.push_aligned_text(indicator, alignment.clone());
+            self.push_line_break();
+            self.slide_operations.extend(run_operation.clone());
+        }
     }
 
-    fn push_table(&mut self, table: Table) {
-        let widths: Vec<_> = (0..table.columns())
-            .map(|column| table.iter_column(column).map(|text| text.width()).max().unwrap_or(0))
+    // Expands tabs in a piece of text that starts at the given column.
+    fn expand_tabs(text: &str, tab_width: u8, mut column: usize) -> String {
+        let tab_width = tab_width as usize;
+        let mut output = String::new();
+        for (c, width) in char_widths(text) {
+            if c == '\t' {
+                let width = tab_width - column % tab_width;
+                output.push_str(&" ".repeat(width));
+                column += width;
+            } else {
+                output.push(c);
+                column += width;
+            }
+        }
+        output
+    }
+
+    // Expands tabs into spaces so the block's width is computed correctly, optionally making every
+    // space and tab visible.
+    fn expand_whitespace(contents: &str, tab_width: u8, show_whitespace: bool) -> String {
+        let tab_width = tab_width as usize;
+        let mut output = String::new();
+        for line in contents.lines() {
+            let mut column = 0;
+            for (c, width) in char_widths(line) {
+                match c {
+                    '\t' => {
+                        let width = tab_width - column % tab_width;
+                        if show_whitespace {
+                            output.push('→');
+                            output.push_str(&" ".repeat(width - 1));
+                        } else {
+                            output.push_str(&" ".repeat(width));
+                        }
+                        column += width;
+                    }
+                    ' ' if show_whitespace => {
+                        output.push('·');
+                        column += 1;
+                    }
+                    c => {
+                        output.push(c);
+                        column += width;
+                    }
+                };
+            }
+            output.push('\n');
+        }
+        output
+    }
+
+    fn terminate_slide(&mut self, mode: TerminateMode) {
+        if matches!(mode, TerminateMode::ResetState) {
+            self.close_image_grid();
+        }
+        // Whether a slide only has a title is only known once it's done so the title is aligned again
+        // in case the slide's kind uses a different alignment.
+        if self.slide_title_only && !self.slide_starts_section {
+            self.slide_starts_section = true;
+            let alignment = self.alignment(&ElementType::SlideTitle);
+            for operation in self.iter_slide_operations_mut() {
+                if let RenderOperation::RenderTextLine { alignment: line_alignment, .. } = operation {
+                    *line_alignment = alignment.clone();
+                }
+            }
+        }
+        let footer = self.footer_operations();
+
+        // The kind may only be known after the prelude was pushed so patch the colors it uses.
+        let colors = self.default_colors();
+        if let Some(RenderOperation::SetColors(prelude_colors)) =
+            self.iter_slide_operations_mut().find(|op| matches!(op, RenderOperation::SetColors(_)))
+        {
+            *prelude_colors = colors;
+        }
+        self.push_pause();
+        let mut slide = Slide::from_chunks(mem::take(&mut self.slide_chunks), footer);
+        slide.hooks = self.slide_hooks.clone();
+        slide.notes = self.slide_notes.clone();
+        slide.title = self.current_title.clone();
+        slide.duration = self.slide_duration;
+        slide.videos = self.slide_videos.clone();
+        slide.kind = self.slide_kind();
+        self.slides.push(slide);
+        self.push_slide_prelude();
+        if matches!(mode, TerminateMode::ResetState) {
+            self.ignore_element_line_break = true;
+            self.needs_enter_column = false;
+            self.layout = Default::default();
+            self.slide_hooks = Rc::new(RefCell::new(self.default_hooks.clone()));
+            self.slide_notes = Default::default();
+            self.reveal_rows = false;
+            self.reveal_columns = false;
+            self.comparison = None;
+            self.slide_options = Default::default();
+            self.incremental_lists = self.presentation_options.incremental_lists;
+            self.slide_duration = None;
+            self.slide_setup_code.clear();
+            self.slide_videos.clear();
+            self.slide_has_title = false;
+            self.slide_starts_section = false;
+            self.slide_is_empty = true;
+            self.slide_title_only = false;
+            self.slide_is_intro = false;
+        }
+    }
+
+    fn set_current_title(&mut self, text: &Text) {
+        // Only the first heading in a slide is used as its title. Slides without any headings keep
+        // the previous one's so the footer shows which section we're in.
+        if !self.slide_has_title {
+            self.current_title = Self::plain_text(text);
+            self.slide_has_title = true;
+        }
+    }
+
+    fn plain_text(text: &Text) -> String {
+        text.chunks.iter().map(|chunk| chunk.text.as_str()).collect()
+    }
+
+    fn footer_operations(&self) -> Vec<RenderOperation> {
+        let style = self.theme.slide_kinds.get(self.slide_kind()).footer.as_ref();
+        let generator = FooterGenerator {
+            style: style.unwrap_or(&self.theme.footer).clone(),
+            current_slide: self.slide_count(),
+            current_title: self.current_title.clone(),
+            current_part: self.parts.last().map(|part| part.name.clone()).unwrap_or_default(),
+            context: self.footer_context.clone(),
+        };
+        vec![
+            // Exit any layout we're in so this gets rendered on a default screen size.
+            RenderOperation::ExitLayout,
+            // Pop the slide margin so we're at the terminal rect.
+            RenderOperation::PopMargin,
+            // Jump to the very bottom of the terminal rect and draw the footer.
+            RenderOperation::JumpToBottom,
+            RenderOperation::RenderDynamic(Rc::new(generator)),
+        ]
+    }
+
+    fn push_table(&mut self, table: Table, reveal_rows: bool) {
+        let spans = table.header_spans();
+        let mut widths: Vec<_> = (0..table.columns())
+            .map(|column| {
+                let rows_width = table.rows.iter().map(|row| row.0[column].width()).max().unwrap_or(0);
+                // Header cells that span multiple columns are accounted for below.
+                let header_width = match spans.iter().any(|span| span == &(column..column + 1)) {
+                    true => table.header.0[column].width(),
+                    false => 0,
+                };
+                rows_width.max(header_width)
+            })
             .collect();
-        let flattened_header = Self::prepare_table_row(table.header, &widths);
-        self.push_text(flattened_header, ElementType::Table);
-        self.push_line_break();
+        for span in spans.iter().filter(|span| span.len() > 1) {
+            let available = Self::span_width(&widths, span);
+            let header_width = table.header.0[span.start].width();
+            if header_width > available {
+                widths[span.end - 1] += header_width - available;
+            }
+        }
+        let style = self.theme.table.clone();
+        let header = TableRow(spans.iter().map(|span| table.header.0[span.start].clone()).collect());
+        let header_widths: Vec<_> = spans.iter().map(|span| Self::span_width(&widths, span)).collect();
+        let mut flattened_header = Self::prepare_table_row(header, &header_widths);
+        flattened_header.apply_style(&Self::table_row_text_style(&style.header));
 
         let mut separator = Text { chunks: Vec::new() };
         for (index, width) in widths.iter().enumerate() {
             let mut contents = String::new();
             let mut margin = 1;
             if index > 0 {
-                contents.push('┼');
+                // There's no column separator above this one if it's within a header cell.
+                let within_header = spans.iter().any(|span| span.start < index && index < span.end);
+                contents.push(if within_header { '┬' } else { '┼' });
                 // Append an extra dash to have 1 column margin on both sides
                 if index < widths.len() - 1 {
                     margin += 1;
@@ -524,14 +1542,70 @@ impl<'a> PresentationBuilder<'a> {
             separator.chunks.push(StyledText::from(contents));
         }
 
+        self.push_table_header(flattened_header.clone(), separator.clone());
+        let mut remaining_rows = self.available_table_rows();
+        let caption = table.caption;
+
+        let row_style = Self::table_row_text_style(&style.rows);
+        for (index, row) in table.rows.into_iter().enumerate() {
+            // If this table doesn't fit, continue it in a new slide repeating the header.
+            if remaining_rows == Some(0) {
+                self.terminate_slide(TerminateMode::KeepState);
+                self.push_table_header(flattened_header.clone(), separator.clone());
+                remaining_rows = self.available_table_rows();
+            } else if reveal_rows && index > 0 {
+                self.push_pause();
+            }
+            remaining_rows = remaining_rows.map(|rows| rows.saturating_sub(1));
+
+            let mut flattened_row = Self::prepare_table_row(row, &widths);
+            match style.alternate_row_background {
+                Some(background) if index % 2 == 1 => {
+                    let mut row_style = row_style.clone();
+                    row_style.colors.background = Some(background);
+                    flattened_row.apply_style(&row_style);
+                }
+                _ => flattened_row.apply_style(&row_style),
+            };
+            self.push_text(flattened_row, ElementType::Table);
+            self.push_line_break();
+        }
+        if let Some(caption) = caption {
+            self.tables += 1;
+            self.push_numbered_caption(caption, "Table", self.tables);
+        }
+    }
+
+    fn push_table_header(&mut self, header: Text, separator: Text) {
+        self.push_text(header, ElementType::Table);
+        self.push_line_break();
         self.push_text(separator, ElementType::Table);
         self.push_line_break();
+    }
 
-        for row in table.rows {
-            let flattened_row = Self::prepare_table_row(row, &widths);
-            self.push_text(flattened_row, ElementType::Table);
-            self.push_line_break();
+    fn available_table_rows(&self) -> Option<usize> {
+        // We can't split tables within a layout as we can't break out of it.
+        if !matches!(self.layout, LayoutState::Default) {
+            return None;
         }
+        let total_rows = self.terminal_rows?;
+        // This is an approximation as it doesn't account for wrapped lines or images.
+        let used_rows =
+            self.iter_slide_operations().filter(|op| matches!(op, RenderOperation::RenderLineBreak)).count();
+        let bottom_margin = self.slide_margins().bottom.unwrap_or(SlideMargins::DEFAULT_BOTTOM) as usize;
+        let available = (total_rows as usize).saturating_sub(bottom_margin + used_rows + 1);
+        // Always allow at least one row, otherwise we'd never make any progress.
+        Some(available.max(1))
+    }
+
+    fn table_row_text_style(style: &TableRowStyle) -> TextStyle {
+        let text_style = TextStyle::default().colors(style.colors.clone());
+        if style.bold { text_style.bold() } else { text_style }
+    }
+
+    // The width of a group of columns, including the separators between them.
+    fn span_width(widths: &[usize], span: &Range<usize>) -> usize {
+        widths[span.clone()].iter().sum::<usize>() + (span.len() - 1) * 3
     }
 
     fn prepare_table_row(row: TableRow, widths: &[usize]) -> Text {
@@ -553,6 +1627,43 @@ impl<'a> PresentationBuilder<'a> {
     }
 }
 
+/// A presentation that's still being built.
+///
+/// This is created via [PresentationBuilder::build_lazily] and lets the slides that weren't built
+/// right away be built in steps.
+pub struct LazyBuild<'a> {
+    builder: PresentationBuilder<'a>,
+    elements: vec::IntoIter<MarkdownElement>,
+}
+
+impl<'a> LazyBuild<'a> {
+    /// Build at least the given number of slides, or as many as there are left, and add them to the presentation.
+    ///
+    /// Returns `true` once every slide has been built.
+    pub fn build_into(&mut self, presentation: &mut Presentation, slides: usize) -> Result<bool, BuildError> {
+        let builder = &mut self.builder;
+        builder.build_slides(&mut self.elements, slides)?;
+        let built = mem::take(&mut builder.slides);
+        builder.delivered_slides += built.len();
+        presentation.extend_slides(built);
+        presentation.set_parts(builder.parts.clone());
+        presentation.set_warnings(builder.warnings.clone());
+        Ok(self.elements.len() == 0)
+    }
+
+    /// Build every slide left and add them to the presentation.
+    pub fn finish(mut self, presentation: &mut Presentation) -> Result<(), BuildError> {
+        self.build_into(presentation, usize::MAX)?;
+        Ok(())
+    }
+}
+
+struct CodeScroll {
+    padding_lines: usize,
+    max_height: usize,
+    alignment: Alignment,
+}
+
 enum TerminateMode {
     KeepState,
     ResetState,
@@ -564,40 +1675,123 @@ enum LayoutState {
     Default,
     InLayout {
         columns_count: usize,
+        reveal: bool,
     },
     InColumn {
         column: usize,
         columns_count: usize,
+        reveal: bool,
     },
 }
 
 #[derive(Debug, Default)]
 struct FooterContext {
-    total_slides: usize,
+    total_slides: Option<usize>,
+    part_starts: Vec<usize>,
     author: String,
+    socials: String,
+    stopwatch: Stopwatch,
+}
+
+impl FooterContext {
+    // The position of a slide within the part it belongs to and the number of slides in that part,
+    // if that's known yet.
+    fn part_progress(&self, slide: usize) -> Option<(usize, Option<usize>)> {
+        let index = self.part_starts.iter().rposition(|first_slide| *first_slide <= slide)?;
+        let first_slide = self.part_starts[index];
+        let end = self.part_starts.get(index + 1).copied().or(self.total_slides);
+        Some((slide - first_slide + 1, end.map(|end| end.saturating_sub(first_slide))))
+    }
+}
+
+#[derive(Debug)]
+struct HighlightedCodeSteps {
+    steps: Vec<Vec<PreformattedLine>>,
+    current_step: Rc<Cell<usize>>,
+}
+
+impl AsRenderOperations for HighlightedCodeSteps {
+    fn as_render_operations(&self, _: &WindowSize) -> Vec<RenderOperation> {
+        let Some(lines) = self.steps.get(self.current_step.get()).or(self.steps.last()) else {
+            return Vec::new();
+        };
+        lines
+            .iter()
+            .flat_map(|line| [RenderOperation::RenderPreformattedLine(line.clone()), RenderOperation::RenderLineBreak])
+            .collect()
+    }
+
+    fn diffable_content(&self) -> Option<String> {
+        Some(format!("{:?}", self.steps))
+    }
+}
+
+#[derive(Debug)]
+struct HighlightStepMutator {
+    current_step: Rc<Cell<usize>>,
+    step: usize,
+}
+
+impl ChunkMutator for HighlightStepMutator {
+    fn mutate_next(&self) {
+        self.current_step.set(self.step);
+    }
+
+    fn mutate_previous(&self) {
+        self.current_step.set(self.step - 1);
+    }
 }
 
 #[derive(Debug)]
 struct FooterGenerator {
     current_slide: usize,
+    current_title: String,
+    current_part: String,
     context: Rc<RefCell<FooterContext>>,
     style: FooterStyle,
 }
 
 impl FooterGenerator {
-    fn render_template(
-        template: &str,
-        current_slide: &str,
-        context: &FooterContext,
-        colors: Colors,
-        alignment: Alignment,
-    ) -> RenderOperation {
-        let contents = template
-            .replace("{current_slide}", current_slide)
-            .replace("{total_slides}", &context.total_slides.to_string())
-            .replace("{author}", &context.author);
-        let text = WeightedText::from(StyledText::new(contents, TextStyle::default().colors(colors)));
-        RenderOperation::RenderTextLine { line: vec![text].into(), alignment }
+    fn expand_template(&self, template: &str, context: &FooterContext) -> String {
+        // Totals are displayed as `?` while the presentation is still being built.
+        let total = |total: Option<usize>| total.map(|total| total.to_string()).unwrap_or_else(|| "?".into());
+        let section_progress = match context.part_progress(self.current_slide) {
+            Some((current, part_total)) => format!("{current}/{}", total(part_total)),
+            None => String::new(),
+        };
+        template
+            .replace("{current_slide}", &(self.current_slide + 1).to_string())
+            .replace("{total_slides}", &total(context.total_slides))
+            .replace("{current_title}", &self.current_title)
+            .replace("{part}", &self.current_part)
+            .replace("{section_progress}", &section_progress)
+            .replace("{author}", &context.author)
+            .replace("{socials}", &context.socials)
+            .replace("{stopwatch}", &StopwatchTime(context.stopwatch.elapsed()).to_string())
+    }
+
+    // Get the maximum width of the left, center, and right sections of a footer with the given
+    // widths so they don't overlap. The center one is cut off last.
+    fn fit_sections(columns: u16, widths: [Option<usize>; 3]) -> [usize; 3] {
+        // Leave room for the margin on both sides.
+        let available = (columns as usize).saturating_sub(2);
+        let [left, center, right] = widths;
+        let center = center.filter(|width| *width > 0).map(|width| width.min(available));
+        let (left_max, right_max) = match (left, center, right) {
+            // Leave a space in between the center and each side.
+            (_, Some(center), _) => {
+                let side = (available - center) / 2;
+                (side.saturating_sub(1), side.saturating_sub(1))
+            }
+            (Some(left), None, Some(right)) => {
+                // Sides share what's available, with the shortest one taking what it needs.
+                let available = available.saturating_sub(1);
+                let left = left.min(available - right.min(available / 2));
+                (left, available - left)
+            }
+            _ => (available, available),
+        };
+        [left.unwrap_or(0).min(left_max), center.unwrap_or(0), right.unwrap_or(0).min(right_max)]
     }
 }
 
@@ -606,7 +1800,6 @@ impl AsRenderOperations for FooterGenerator {
         let context = self.context.borrow();
         match &self.style {
             FooterStyle::Template { left, center, right, colors } => {
-                let current_slide = (self.current_slide + 1).to_string();
                 let mut operations = Vec::new();
                 let margin = Margin::Fixed(1);
                 let alignments = [
@@ -614,26 +1807,41 @@ impl AsRenderOperations for FooterGenerator {
                     Alignment::Center { minimum_size: 0, minimum_margin: margin.clone() },
                     Alignment::Right { margin: margin.clone() },
                 ];
-                for (text, alignment) in [left, center, right].iter().zip(alignments) {
+                let contents =
+                    [left, center, right].map(|text| text.as_ref().map(|text| self.expand_template(text, &context)));
+                let width = |text: &Option<String>| text.as_ref().map(|text| text.display_width());
+                let widths = [width(&contents[0]), width(&contents[1]), width(&contents[2])];
+                let max_widths = Self::fit_sections(dimensions.columns, widths);
+                let style = TextStyle::default().colors(colors.clone());
+                for ((text, max_width), alignment) in contents.into_iter().zip(max_widths).zip(alignments) {
                     if let Some(text) = text {
-                        operations.push(Self::render_template(
-                            text,
-                            &current_slide,
-                            &context,
-                            colors.clone(),
-                            alignment,
-                        ));
+                        let text = truncate_with_ellipsis(&text, max_width);
+                        let text = WeightedText::from(StyledText::new(text, style.clone()));
+                        operations.push(RenderOperation::RenderTextLine { line: vec![text].into(), alignment });
                     }
                 }
                 operations
             }
-            FooterStyle::ProgressBar { character, colors } => {
+            FooterStyle::ProgressBar { character, colors, gradient } => {
+                let Some(total_slides) = context.total_slides else {
+                    return Vec::new();
+                };
                 let character = character.unwrap_or('█').to_string();
                 let total_columns = dimensions.columns as usize / character.width();
-                let progress_ratio = (self.current_slide + 1) as f64 / context.total_slides as f64;
-                let columns_ratio = (total_columns as f64 * progress_ratio).ceil();
-                let bar = character.repeat(columns_ratio as usize);
-                let bar = vec![WeightedText::from(StyledText::new(bar, TextStyle::default().colors(colors.clone())))];
+                let progress_ratio = (self.current_slide + 1) as f64 / total_slides as f64;
+                let columns_ratio = (total_columns as f64 * progress_ratio).ceil() as usize;
+                let style = TextStyle::default().colors(colors.clone());
+                let bar = match gradient.is_empty() {
+                    true => vec![WeightedText::from(StyledText::new(character.repeat(columns_ratio), style))],
+                    // Every cell gets its own color based on where it is on the screen.
+                    false => (0..columns_ratio)
+                        .map(|column| {
+                            let position = column as f64 / total_columns.saturating_sub(1).max(1) as f64;
+                            let colors = Colors { foreground: Color::gradient(gradient, position), ..colors.clone() };
+                            StyledText::new(character.clone(), TextStyle::default().colors(colors)).into()
+                        })
+                        .collect(),
+                };
                 vec![RenderOperation::RenderTextLine {
                     line: bar.into(),
                     alignment: Alignment::Left { margin: Margin::Fixed(0) },
@@ -644,6 +1852,159 @@ impl AsRenderOperations for FooterGenerator {
     }
 }
 
+/// Runs a piece of code once requested and renders its output.
+///
+/// Any hidden code that came before it in the same slide is executed first, and the code itself is
+/// only executed once all of it finishes successfully.
+#[derive(Debug)]
+struct RunCodeOperation {
+    code: Code,
+    alignment: Alignment,
+    block_length: usize,
+    status_style: ExecutionStatusStyle,
+    setup: Vec<Rc<RunCodeOperation>>,
+    state: RefCell<RunCodeState>,
+}
+
+#[derive(Debug, Default)]
+enum RunCodeState {
+    #[default]
+    NotStarted,
+    Running(ExecutionHandle),
+    Failed(String),
+}
+
+impl RunCodeOperation {
+    fn new(code: Code, alignment: Alignment, block_length: usize, setup: Vec<Rc<RunCodeOperation>>) -> Self {
+        let status_style = Default::default();
+        Self { code, alignment, block_length, status_style, setup, state: Default::default() }
+    }
+
+    fn with_status_style(mut self, style: ExecutionStatusStyle) -> Self {
+        self.status_style = style;
+        self
+    }
+
+    // The line summarizing how a finished execution went, e.g. `✔ exit code 0 in 1.25s`.
+    fn status_line(&self, state: &ExecutionState) -> Option<RenderOperation> {
+        let duration = state.duration?;
+        let (symbol, colors) = match state.status {
+            ProcessStatus::Running => return None,
+            ProcessStatus::Success => ("✔", &self.status_style.success),
+            ProcessStatus::Failure => ("✘", &self.status_style.failure),
+        };
+        let duration = match duration.as_millis() {
+            millis @ 0..=999 => format!("{millis}ms"),
+            _ => format!("{:.2}s", duration.as_secs_f64()),
+        };
+        let text = match state.exit_code {
+            _ if state.cancelled => format!("{symbol} cancelled after {duration}"),
+            Some(code) => format!("{symbol} exit code {code} in {duration}"),
+            None => format!("{symbol} terminated by a signal in {duration}"),
+        };
+        let style = TextStyle::default().colors(colors.clone());
+        Some(self.render_line(text, style))
+    }
+
+    fn execute(&self, sender: Sender<Command>) -> Result<ExecutionHandle, String> {
+        let mut dependencies = Vec::new();
+        for setup in &self.setup {
+            setup.start_render(sender.clone());
+            match &*setup.state.borrow() {
+                RunCodeState::Running(handle) => dependencies.push(handle.clone()),
+                RunCodeState::Failed(error) => return Err(format!("hidden code failed: {error}")),
+                RunCodeState::NotStarted => unreachable!("hidden code not started"),
+            };
+        }
+        CodeExecuter::execute_after(&self.code, dependencies, sender).map_err(|e| e.to_string())
+    }
+
+    fn render_line(&self, text: String, style: TextStyle) -> RenderOperation {
+        let line = vec![WeightedText::from(StyledText::new(text, style))];
+        RenderOperation::RenderTextLine { line: line.into(), alignment: self.alignment.clone() }
+    }
+}
+
+impl AsRenderOperations for RunCodeOperation {
+    fn as_render_operations(&self, _dimensions: &WindowSize) -> Vec<RenderOperation> {
+        let handle = match &*self.state.borrow() {
+            RunCodeState::NotStarted => return Vec::new(),
+            RunCodeState::Failed(error) => {
+                return vec![RenderOperation::RenderLineBreak, self.render_line(error.clone(), TextStyle::default())];
+            }
+            RunCodeState::Running(handle) => handle.clone(),
+        };
+        let state = handle.state();
+        // Hidden code only shows up if something goes wrong.
+        if self.code.flags.hidden && state.status != ProcessStatus::Failure {
+            return Vec::new();
+        }
+        let status = match state.status {
+            ProcessStatus::Running if state.cancelled => "cancelling",
+            ProcessStatus::Running => "running",
+            ProcessStatus::Failure if state.cancelled => "cancelled",
+            ProcessStatus::Success => "finished",
+            ProcessStatus::Failure if self.code.flags.hidden => "hidden code finished with error",
+            ProcessStatus::Failure => "finished with error",
+        };
+        let mut operations = vec![
+            RenderOperation::RenderLineBreak,
+            self.render_line(format!("—— [{status}] ——"), TextStyle::default().bold()),
+            RenderOperation::RenderLineBreak,
+        ];
+        let block_length =
+            state.output.iter().map(|line| line.display_width()).max().unwrap_or(0).max(self.block_length);
+        let status_line = self.status_line(&state);
+        for line in state.output {
+            operations.extend([
+                RenderOperation::RenderPreformattedLine(PreformattedLine {
+                    unformatted_length: line.display_width(),
+                    text: line.into(),
+                    block_length,
+                    alignment: self.alignment.clone(),
+                }),
+                RenderOperation::RenderLineBreak,
+            ]);
+        }
+        if let Some(line) = status_line {
+            operations.extend([line, RenderOperation::RenderLineBreak]);
+        }
+        operations
+    }
+
+    fn diffable_content(&self) -> Option<String> {
+        // Running this depends on things that aren't displayed, like setup code, included snippets,
+        // and the directory and environment the code runs in.
+        let setup: Vec<_> = self.setup.iter().map(|setup| setup.diffable_content()).collect();
+        Some(format!("{:?} {setup:?}", self.code))
+    }
+}
+
+impl RenderOnDemand for RunCodeOperation {
+    fn start_render(&self, sender: Sender<Command>) -> bool {
+        let mut state = self.state.borrow_mut();
+        if !matches!(*state, RunCodeState::NotStarted) {
+            return false;
+        }
+        *state = match self.execute(sender) {
+            Ok(handle) => RunCodeState::Running(handle),
+            Err(e) => RunCodeState::Failed(e),
+        };
+        true
+    }
+
+    fn stop_render(&self) -> bool {
+        match &*self.state.borrow() {
+            RunCodeState::Running(handle) => handle.kill(),
+            _ => false,
+        }
+    }
+
+    fn starts_automatically(&self) -> bool {
+        self.code.flags.auto_execute
+    }
+}
+
 /// An error when building a presentation.
 #[derive(thiserror::Error, Debug)]
 pub enum BuildError {
@@ -653,6 +2014,9 @@ pub enum BuildError {
     #[error("invalid presentation metadata: {0}")]
     InvalidMetadata(String),
 
+    #[error("invalid presentation metadata: {0}")]
+    InvalidFrontMatter(YamlError),
+
     #[error("invalid theme: {0}")]
     InvalidTheme(#[from] LoadThemeError),
 
@@ -676,6 +2040,46 @@ pub enum BuildError {
 
     #[error(transparent)]
     CommandParse(#[from] CommandParseError),
+
+    #[error("code written in {0:?} can't be executed")]
+    UnsupportedExecution(ProgrammingLanguage),
+
+    #[error("+reveal_rows can only be used right before a table or a block quote")]
+    InvalidRevealRows,
+
+    #[error("+reveal_columns can only be used right before a column layout")]
+    InvalidRevealColumns,
+
+    #[error("image grids can't be used inside a column layout")]
+    ImageGridInLayout,
+
+    #[error("invalid code comparison: {0}")]
+    InvalidComparison(&'static str),
+
+    #[error("unknown color '{0}': it's neither in the theme's palette nor a hex color")]
+    UnknownColor(String),
+
+    #[error("no code block with id '{0}' defined before this one")]
+    UnknownSnippet(String),
+
+    #[error("code block with id '{0}' is written in a different language")]
+    SnippetLanguageMismatch(String),
+
+    #[error("more than one code block uses id '{0}'")]
+    DuplicateSnippet(String),
+
+    #[error("invalid heading level {0}: only levels 1 through 6 are supported")]
+    InvalidHeadingLevel(u8),
+}
+
+impl BuildError {
+    /// Get the YAML error that caused this error, if any.
+    pub fn yaml_error(&self) -> Option<&YamlError> {
+        match self {
+            Self::InvalidFrontMatter(error) | Self::CommandParse(CommandParseError(error)) => Some(error),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize)]
@@ -687,6 +2091,148 @@ enum CommentCommand {
     InitColumnLayout(Vec<u8>),
     Column(usize),
     ResetLayout,
+    OnEnter(String),
+    OnExit(String),
+    SpeakerNote(String),
+    #[serde(rename = "+reveal_rows")]
+    RevealRows,
+    #[serde(rename = "+reveal_columns")]
+    RevealColumns,
+    IncrementalLists(bool),
+    ImageGrid(ImageGridSize),
+    Compare(CompareTitles),
+    Part(String),
+    Duration(SlideDuration),
+    Slide(SlideOptions),
+    Video(String),
+}
+
+/// Options that only apply to the slide they're defined in.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SlideOptions {
+    /// The alignment to use for every element in this slide.
+    #[serde(default)]
+    alignment: Option<SlideAlignment>,
+
+    /// The background color for this slide.
+    #[serde(default)]
+    background: Option<Color>,
+
+    /// Speaker notes for this slide.
+    #[serde(default)]
+    notes: Option<String>,
+
+    /// The margins for this slide.
+    #[serde(default)]
+    margin: Option<SlideMargins>,
+
+    /// The kind of this slide, overriding the one inferred out of its contents.
+    #[serde(default)]
+    kind: Option<SlideKind>,
+}
+
+/// The alignment to use for every element in a slide.
+///
+/// This is either just the alignment's name, like `center`, or the same properties used to define
+/// alignments in themes.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(from = "RawSlideAlignment")]
+struct SlideAlignment(Alignment);
+
+impl From<RawSlideAlignment> for SlideAlignment {
+    fn from(alignment: RawSlideAlignment) -> Self {
+        let alignment = match alignment {
+            RawSlideAlignment::Name(AlignmentName::Left) => Alignment::Left { margin: Default::default() },
+            RawSlideAlignment::Name(AlignmentName::Right) => Alignment::Right { margin: Default::default() },
+            RawSlideAlignment::Name(AlignmentName::Center) => {
+                Alignment::Center { minimum_margin: Default::default(), minimum_size: 0 }
+            }
+            RawSlideAlignment::Explicit(alignment) => alignment,
+        };
+        Self(alignment)
+    }
+}
+
+// The forms a slide's alignment can be written in.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawSlideAlignment {
+    Name(AlignmentName),
+    Explicit(Alignment),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum AlignmentName {
+    Left,
+    Center,
+    Right,
+}
+
+/// The size of an image grid, in the form `<columns>x<rows>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+struct ImageGridSize {
+    columns: u8,
+    rows: u8,
+}
+
+impl TryFrom<String> for ImageGridSize {
+    type Error = &'static str;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let error = "image grid size must be in the form <columns>x<rows>";
+        let (columns, rows) = value.split_once('x').ok_or(error)?;
+        let columns: u8 = columns.trim().parse().map_err(|_| error)?;
+        let rows: u8 = rows.trim().parse().map_err(|_| error)?;
+        if columns == 0 || rows == 0 {
+            return Err("image grid can't have zero columns or rows");
+        }
+        Ok(Self { columns, rows })
+    }
+}
+
+/// The titles of both sides in a code comparison, in the form `<left> | <right>`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+struct CompareTitles {
+    left: String,
+    right: String,
+}
+
+impl TryFrom<String> for CompareTitles {
+    type Error = &'static str;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let (left, right) = value.split_once('|').ok_or("comparison titles must be in the form <left> | <right>")?;
+        Ok(Self { left: left.trim().into(), right: right.trim().into() })
+    }
+}
+
+/// How long a slide should be displayed for when auto advancing, in the form `45s`, `2m`, etc.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+struct SlideDuration(Duration);
+
+impl TryFrom<String> for SlideDuration {
+    type Error = InvalidDuration;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        parse_duration(&value).map(Self)
+    }
+}
+
+#[derive(Debug)]
+struct ComparisonState {
+    titles: CompareTitles,
+    codes: Vec<Code>,
+}
+
+#[derive(Debug)]
+struct ImageGridState {
+    size: ImageGridSize,
+    images: usize,
 }
 
 impl FromStr for CommentCommand {
@@ -696,48 +2242,78 @@ impl FromStr for CommentCommand {
         #[derive(Deserialize)]
         struct CommandWrapper(#[serde(with = "serde_yaml::with::singleton_map")] CommentCommand);
 
-        let wrapper = serde_yaml::from_str::<CommandWrapper>(s)?;
+        let wrapper = serde_yaml::from_str::<CommandWrapper>(s).map_err(|e| YamlError::new(e, s))?;
         Ok(wrapper.0)
     }
 }
 
 #[derive(thiserror::Error, Debug)]
 #[error("invalid command: {0}")]
-pub struct CommandParseError(#[from] serde_yaml::Error);
+pub struct CommandParseError(#[from] YamlError);
 
 #[cfg(test)]
 mod test {
     use rstest::rstest;
 
     use super::*;
-    use crate::{markdown::elements::ProgrammingLanguage, presentation::PreformattedLine};
+    use crate::{
+        diff::PresentationDiffer,
+        markdown::elements::{CodeFlags, ProgrammingLanguage},
+        presentation::PreformattedLine,
+        theme::InlineCodeDelimiters,
+    };
 
     fn build_presentation(elements: Vec<MarkdownElement>) -> Presentation {
-        try_build_presentation(elements).expect("build failed")
+        build_presentation_with_theme(elements, &PresentationTheme::default())
+    }
+
+    fn build_presentation_with_theme(elements: Vec<MarkdownElement>, theme: &PresentationTheme) -> Presentation {
+        with_builder(theme, |builder| builder.build(elements)).expect("build failed")
     }
 
     fn try_build_presentation(elements: Vec<MarkdownElement>) -> Result<Presentation, BuildError> {
+        with_builder(&PresentationTheme::default(), |builder| builder.build(elements))
+    }
+
+    // Calls the given function with a builder that uses the given theme.
+    fn with_builder<T>(theme: &PresentationTheme, f: impl FnOnce(PresentationBuilder) -> T) -> T {
         let highlighter = CodeHighlighter::new("base16-ocean.dark").unwrap();
-        let theme = PresentationTheme::default();
         let mut resources = Resources::new("/tmp");
-        let builder = PresentationBuilder::new(highlighter, &theme, &mut resources);
-        builder.build(elements)
+        f(PresentationBuilder::new(highlighter, theme, &mut resources))
+    }
+
+    fn build_image() -> MarkdownElement {
+        build_captioned_image("")
+    }
+
+    fn build_captioned_image(caption: &str) -> MarkdownElement {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/doge.png").into();
+        MarkdownElement::Image { path, title: String::new(), alt_text: caption.into(), attributes: Default::default() }
+    }
+
+    fn build_code(language: ProgrammingLanguage, execute: bool) -> MarkdownElement {
+        let flags = CodeFlags { execute, ..Default::default() };
+        MarkdownElement::Code(Code { contents: "echo hi".into(), language, flags })
+    }
+
+    fn build_comment<S: Into<String>>(comment: S) -> MarkdownElement {
+        MarkdownElement::Comment { comment: comment.into(), source_position: Default::default() }
     }
 
     fn build_pause() -> MarkdownElement {
-        MarkdownElement::Comment("pause".into())
+        build_comment("pause")
     }
 
     fn build_end_slide() -> MarkdownElement {
-        MarkdownElement::Comment("end_slide".into())
+        build_comment("end_slide")
     }
 
     fn build_column_layout(width: u8) -> MarkdownElement {
-        MarkdownElement::Comment(format!("column_layout: [{width}]"))
+        build_comment(format!("column_layout: [{width}]"))
     }
 
     fn build_column(column: u8) -> MarkdownElement {
-        MarkdownElement::Comment(format!("column: {column}"))
+        build_comment(format!("column: {column}"))
     }
 
     fn is_visible(operation: &RenderOperation) -> bool {
@@ -745,23 +2321,27 @@ mod test {
         match operation {
             ClearScreen
             | SetColors(_)
+            | SetLineSize(_)
             | JumpToVerticalCenter
             | JumpToBottom
+            | JumpToBottomRow { .. }
             | InitColumnLayout { .. }
             | EnterColumn { .. }
             | ExitLayout { .. }
             | ApplyMargin(_)
             | PopMargin => false,
             RenderTextLine { .. }
-            | RenderSeparator
+            | RenderSeparator(_)
             | RenderLineBreak
-            | RenderImage(_)
+            | RenderImage(..)
             | RenderPreformattedLine(_)
-            | RenderDynamic(_) => true,
+            | RenderBlockLine(_)
+            | RenderDynamic(_)
+            | RenderOnDemand(_) => true,
         }
     }
 
-    fn extract_text_lines(operations: &[RenderOperation]) -> Vec<String> {
+    fn extract_text_lines<'a>(operations: impl IntoIterator<Item = &'a RenderOperation>) -> Vec<String> {
         let mut output = Vec::new();
         for operation in operations {
             match operation {
@@ -775,20 +2355,24 @@ mod test {
         output
     }
 
+    fn extract_chunk_text_lines(slide: &Slide, chunks: usize) -> Vec<String> {
+        extract_text_lines(slide.chunks[..chunks].iter().flat_map(|chunk| &chunk.operations))
+    }
+
     #[test]
     fn prelude_appears_once() {
         let elements = vec![
             MarkdownElement::FrontMatter("author: bob".to_string()),
             MarkdownElement::Heading { text: Text::from("hello"), level: 1 },
-            MarkdownElement::Comment("end_slide".to_string()),
+            build_comment("end_slide"),
             MarkdownElement::Heading { text: Text::from("bye"), level: 1 },
         ];
         let presentation = build_presentation(elements);
         for (index, slide) in presentation.iter_slides().into_iter().enumerate() {
             let clear_screen_count =
-                slide.render_operations.iter().filter(|op| matches!(op, RenderOperation::ClearScreen)).count();
+                slide.iter_operations().filter(|op| matches!(op, RenderOperation::ClearScreen)).count();
             let set_colors_count =
-                slide.render_operations.iter().filter(|op| matches!(op, RenderOperation::SetColors(_))).count();
+                slide.iter_operations().filter(|op| matches!(op, RenderOperation::SetColors(_))).count();
             assert_eq!(clear_screen_count, 1, "{clear_screen_count} clear screens in slide {index}");
             assert_eq!(set_colors_count, 1, "{set_colors_count} clear screens in slide {index}");
         }
@@ -799,7 +2383,7 @@ mod test {
         let elements = vec![
             MarkdownElement::FrontMatter("author: bob".to_string()),
             MarkdownElement::Heading { text: Text::from("hello"), level: 1 },
-            MarkdownElement::Comment("end_slide".to_string()),
+            build_comment("end_slide"),
             MarkdownElement::Heading { text: Text::from("bye"), level: 1 },
         ];
         let presentation = build_presentation(elements);
@@ -808,7 +2392,7 @@ mod test {
         // Don't process the intro slide as it's special
         let slides = presentation.into_slides().into_iter().skip(1);
         for slide in slides {
-            let mut ops = slide.render_operations.into_iter().filter(is_visible);
+            let mut ops = slide.iter_operations().filter(|op| is_visible(op));
             // We should start with a newline
             assert!(matches!(ops.next(), Some(RenderOperation::RenderLineBreak)));
             // And the second one should _not_ be a newline
@@ -820,23 +2404,29 @@ mod test {
     fn preformatted_blocks_account_for_unicode_widths() {
         let text = "苹果".to_string();
         let elements = vec![
-            MarkdownElement::BlockQuote(vec![text.clone()]),
-            MarkdownElement::Code(Code { contents: text.clone(), language: ProgrammingLanguage::Unknown }),
+            MarkdownElement::BlockQuote(vec![Text::from(text.clone())]),
+            MarkdownElement::Code(Code {
+                contents: text.clone(),
+                language: ProgrammingLanguage::Unknown,
+                flags: Default::default(),
+            }),
         ];
         let presentation = build_presentation(elements);
         let slides = presentation.into_slides();
         let lengths: Vec<_> = slides[0]
-            .render_operations
-            .iter()
+            .iter_operations()
             .filter_map(|op| match op {
                 RenderOperation::RenderPreformattedLine(PreformattedLine {
                     block_length, unformatted_length, ..
-                }) => Some((block_length, unformatted_length)),
+                }) => Some((*block_length, *unformatted_length)),
+                RenderOperation::RenderBlockLine(BlockLine { block_length, text, .. }) => {
+                    Some((*block_length, text.width()))
+                }
                 _ => None,
             })
             .collect();
         assert_eq!(lengths.len(), 2);
-        let width = &text.width();
+        let width = text.width();
         assert_eq!(lengths[0], (width, width));
         assert_eq!(lengths[1], (width, width));
     }
@@ -846,54 +2436,363 @@ mod test {
         let elements = vec![MarkdownElement::Table(Table {
             header: TableRow(vec![Text::from("key"), Text::from("value"), Text::from("other")]),
             rows: vec![TableRow(vec![Text::from("potato"), Text::from("bar"), Text::from("yes")])],
+            caption: None,
         })];
         let slides = build_presentation(elements).into_slides();
-        let operations: Vec<_> =
-            slides.into_iter().next().unwrap().render_operations.into_iter().filter(|op| is_visible(op)).collect();
+        let operations: Vec<_> = slides[0].iter_operations().filter(|op| is_visible(op)).cloned().collect();
         let lines = extract_text_lines(&operations);
         let expected_lines = &["key    │ value │ other", "───────┼───────┼──────", "potato │ bar   │ yes  "];
         assert_eq!(lines, expected_lines);
     }
 
     #[test]
-    fn layout_without_init() {
-        let elements = vec![MarkdownElement::Comment("column: 0".into())];
-        let result = try_build_presentation(elements);
-        assert!(result.is_err());
+    fn table_header_spans() {
+        let elements = vec![MarkdownElement::Table(Table {
+            header: TableRow(vec![Text::from("item"), Text::from("dimensions"), Text::from("")]),
+            rows: vec![TableRow(vec![Text::from("potato"), Text::from("1"), Text::from("2")])],
+            caption: None,
+        })];
+        let slides = build_presentation(elements).into_slides();
+        let lines = extract_text_lines(slides[0].iter_operations());
+        let expected_lines = &["item   │ dimensions", "───────┼───┬───────", "potato │ 1 │ 2     "];
+        assert_eq!(lines, expected_lines);
     }
 
     #[test]
-    fn already_in_column() {
+    fn table_styles() {
         let elements = vec![
-            MarkdownElement::Comment("column_layout: [1]".into()),
-            MarkdownElement::Comment("column: 0".into()),
-            MarkdownElement::Comment("column: 0".into()),
+            MarkdownElement::FrontMatter(
+                r#"
+theme:
+  override:
+    table:
+      header:
+        bold: true
+      alternate_row_background: "ff0000"
+"#
+                .into(),
+            ),
+            MarkdownElement::Table(Table {
+                header: TableRow(vec![Text::from("key")]),
+                rows: vec![TableRow(vec![Text::from("a")]), TableRow(vec![Text::from("b")])],
+                caption: None,
+            }),
         ];
-        let result = try_build_presentation(elements);
-        assert!(result.is_err());
+        let slides = build_presentation(elements).into_slides();
+        let styles: Vec<_> = slides[0]
+            .iter_operations()
+            .filter_map(|op| match op {
+                RenderOperation::RenderTextLine { line, .. } => line.iter_texts().next().map(|t| t.text.style.clone()),
+                _ => None,
+            })
+            .collect();
+        // header, separator, and 2 rows
+        assert_eq!(styles.len(), 4);
+        assert!(styles[0].is_bold());
+        assert!(!styles[2].is_bold());
+        assert_eq!(styles[2].colors.background, None);
+        assert_eq!(styles[3].colors.background, Some("ff0000".parse().unwrap()));
     }
 
     #[test]
-    fn column_index_overflow() {
-        let elements =
-            vec![MarkdownElement::Comment("column_layout: [1]".into()), MarkdownElement::Comment("column: 1".into())];
-        let result = try_build_presentation(elements);
-        assert!(result.is_err());
+    fn list_item_children() {
+        let item = ListItem {
+            depth: 0,
+            contents: Text::from("item"),
+            item_type: ListItemType::Unordered,
+            color: None,
+            children: vec![MarkdownElement::Paragraph(vec![ParagraphElement::Text("child".into())])],
+        };
+        let slides = build_presentation(vec![MarkdownElement::List(vec![item])]).into_slides();
+        let operations: Vec<_> = slides[0].iter_operations().cloned().collect();
+        let is_indent = |op: &RenderOperation| {
+            matches!(op, RenderOperation::ApplyMargin(MarginProperties { horizontal_margin: Margin::Fixed(5), .. }))
+        };
+        let margin_start = operations.iter().position(is_indent).expect("no margin applied");
+        let is_pop = |op: &RenderOperation| matches!(op, RenderOperation::PopMargin);
+        let margin_end = margin_start + operations[margin_start..].iter().position(is_pop).expect("no pop");
+        assert_eq!(extract_text_lines(&operations[..margin_start]), &["   • item"]);
+        assert_eq!(extract_text_lines(&operations[margin_start..margin_end]), &["child"]);
     }
 
-    #[rstest]
-    #[case::empty("column_layout: []")]
-    #[case::zero("column_layout: [0]")]
-    #[case::one_is_zero("column_layout: [1, 0]")]
-    fn invalid_layouts(#[case] definition: &str) {
-        let elements = vec![MarkdownElement::Comment(definition.into())];
-        let result = try_build_presentation(elements);
-        assert!(result.is_err());
+    #[test]
+    fn list_item_nested_list_child() {
+        let nested = ListItem {
+            depth: 1,
+            contents: Text::from("nested"),
+            item_type: ListItemType::Unordered,
+            color: None,
+            children: Vec::new(),
+        };
+        let item = ListItem {
+            depth: 0,
+            contents: Text::from("item"),
+            item_type: ListItemType::Unordered,
+            color: None,
+            children: vec![
+                MarkdownElement::List(vec![nested]),
+                MarkdownElement::Paragraph(vec![ParagraphElement::Text("after".into())]),
+            ],
+        };
+        let slides = build_presentation(vec![MarkdownElement::List(vec![item])]).into_slides();
+        let lines = extract_text_lines(slides[0].iter_operations());
+        assert_eq!(lines, &["   • item", "      ◦ nested", "after"]);
+    }
+
+    #[test]
+    fn list_item_hanging_indent() {
+        let item = ListItem {
+            depth: 0,
+            contents: Text::from("item"),
+            item_type: ListItemType::OrderedPeriod(1),
+            color: None,
+            children: Vec::new(),
+        };
+        let slides = build_presentation(vec![MarkdownElement::List(vec![item])]).into_slides();
+        let indent = slides[0].iter_operations().find_map(|op| match op {
+            RenderOperation::RenderTextLine { line, .. } => Some(line.hanging_indent()),
+            _ => None,
+        });
+        assert_eq!(indent, Some("   1. ".len()));
+    }
+
+    #[rstest]
+    #[case::comment(vec![build_comment("incremental_lists: true")], 3)]
+    #[case::front_matter(vec![MarkdownElement::FrontMatter("options:\n  incremental_lists: true".into())], 3)]
+    #[case::disabled(
+        vec![
+            MarkdownElement::FrontMatter("options:\n  incremental_lists: true".into()),
+            build_comment("incremental_lists: false"),
+        ],
+        1
+    )]
+    #[case::in_column(
+        vec![build_comment("incremental_lists: true"), build_column_layout(1), build_column(0)],
+        3
+    )]
+    fn incremental_lists(#[case] mut elements: Vec<MarkdownElement>, #[case] expected_chunks: usize) {
+        let item = |text: &str| ListItem {
+            depth: 0,
+            contents: Text::from(text),
+            item_type: ListItemType::Unordered,
+            color: None,
+            children: Vec::new(),
+        };
+        elements.push(MarkdownElement::List(vec![item("a"), item("b"), item("c")]));
+        let slides = build_presentation(elements).into_slides();
+        assert_eq!(slides.len(), 1);
+        assert_eq!(slides[0].chunks.len(), expected_chunks);
+
+        let lines = extract_chunk_text_lines(&slides[0], 1);
+        let all_items = ["   • a", "   • b", "   • c"];
+        let first_items = if expected_chunks == 1 { &all_items[..] } else { &all_items[..1] };
+        assert_eq!(lines, first_items);
+        let lines = extract_chunk_text_lines(&slides[0], expected_chunks);
+        assert_eq!(lines, all_items);
+    }
+
+    #[test]
+    fn incremental_lists_reset() {
+        let list = || {
+            let item = |text: &str| ListItem {
+                depth: 0,
+                contents: Text::from(text),
+                item_type: ListItemType::Unordered,
+                color: None,
+                children: Vec::new(),
+            };
+            MarkdownElement::List(vec![item("a"), item("b")])
+        };
+        let elements = vec![build_comment("incremental_lists: true"), list(), build_end_slide(), list()];
+        let slides = build_presentation(elements).into_slides();
+        let chunks: Vec<_> = slides.iter().map(|slide| slide.chunks.len()).collect();
+        assert_eq!(chunks, &[2, 1]);
+    }
+
+    #[test]
+    fn incremental_nested_lists() {
+        let item = |text: &str, depth, children| ListItem {
+            depth,
+            contents: Text::from(text),
+            item_type: ListItemType::Unordered,
+            color: None,
+            children,
+        };
+        let nested = MarkdownElement::List(vec![item("nested", 1, Vec::new())]);
+        let after = MarkdownElement::Paragraph(vec![ParagraphElement::Text("after".into())]);
+        let elements = vec![
+            build_comment("incremental_lists: true"),
+            MarkdownElement::List(vec![
+                item("a", 0, Vec::new()),
+                item("b", 1, Vec::new()),
+                item("c", 0, vec![nested, after]),
+            ]),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        assert_eq!(slides[0].chunks.len(), 4);
+        assert_eq!(extract_chunk_text_lines(&slides[0], 2), &["   • a", "      ◦ b"]);
+        assert_eq!(
+            extract_chunk_text_lines(&slides[0], 4),
+            &["   • a", "      ◦ b", "   • c", "      ◦ nested", "after"]
+        );
+    }
+
+    #[test]
+    fn list_item_colors() {
+        let item = |text: &str, color: Option<&str>| ListItem {
+            depth: 0,
+            contents: Text::from(text),
+            item_type: ListItemType::Unordered,
+            color: color.map(String::from),
+            children: Vec::new(),
+        };
+        let elements = vec![
+            MarkdownElement::FrontMatter(
+                r#"
+theme:
+  override:
+    palette:
+      colors:
+        done: "00ff00"
+"#
+                .into(),
+            ),
+            MarkdownElement::List(vec![item("a", Some("done")), item("b", Some("ff0000")), item("c", None)]),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        let colors: Vec<_> = slides[0]
+            .iter_operations()
+            .filter_map(|op| match op {
+                RenderOperation::RenderTextLine { line, .. } => {
+                    Some(line.iter_texts().map(|t| t.text.style.colors.foreground).collect::<Vec<_>>())
+                }
+                _ => None,
+            })
+            .collect();
+        let green = Some("00ff00".parse().unwrap());
+        let red = Some("ff0000".parse().unwrap());
+        // Both the prefix and the text are colored.
+        assert_eq!(colors, &[[green, green], [red, red], [None, None]]);
+
+        let elements = vec![MarkdownElement::List(vec![item("a", Some("potato"))])];
+        let result = try_build_presentation(elements);
+        assert!(matches!(result, Err(BuildError::UnknownColor(_))));
+    }
+
+    #[test]
+    fn keyword_highlighting() {
+        let elements = vec![
+            MarkdownElement::FrontMatter(
+                r#"
+keywords:
+  presenterm:
+    bold: true
+    colors:
+      foreground: "ff0000"
+  presenterm pro:
+    italics: true
+"#
+                .into(),
+            ),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text(Text {
+                chunks: vec![
+                    StyledText::from("use presenterm, presenterm pro, or presenterms"),
+                    StyledText::new("presenterm", TextStyle::default().code()),
+                ],
+            })]),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        let texts: Vec<_> = slides[0]
+            .iter_operations()
+            .filter_map(|op| match op {
+                RenderOperation::RenderTextLine { line, .. } => Some(line.iter_texts().map(|t| t.text.clone())),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        let colors = Colors { foreground: Some("ff0000".parse().unwrap()), background: None };
+        let highlighted = TextStyle::default().bold().colors(colors);
+        let expected = &[
+            StyledText::from("use "),
+            StyledText::new("presenterm", highlighted),
+            StyledText::from(", "),
+            StyledText::new("presenterm pro", TextStyle::default().italics()),
+            StyledText::from(", or presenterms"),
+        ];
+        assert_eq!(&texts[..5], expected);
+        // Inline code is left alone.
+        assert!(texts[5].style.is_code());
+    }
+
+    #[test]
+    fn long_table_split() {
+        let rows = (0..10).map(|index| TableRow(vec![Text::from(index.to_string())])).collect();
+        let elements = vec![
+            build_comment("speaker_note: keys"),
+            MarkdownElement::Table(Table { header: TableRow(vec![Text::from("key")]), rows, caption: None }),
+        ];
+        let presentation =
+            with_builder(&PresentationTheme::default(), |builder| builder.with_terminal_rows(12).build(elements))
+                .expect("build failed");
+        let slides = presentation.into_slides();
+        assert_eq!(slides.len(), 2);
+
+        let first = extract_text_lines(slides[0].iter_operations());
+        let second = extract_text_lines(slides[1].iter_operations());
+        // Both slides start with the header
+        assert_eq!(&first[..2], &["key", "────"]);
+        assert_eq!(&second[..2], &["key", "────"]);
+        assert_eq!(first.len() + second.len() - 4, 10);
+        // The continuation is still part of the same slide.
+        assert_eq!(*slides[1].notes.borrow(), &["keys"]);
+        assert!(Rc::ptr_eq(&slides[0].hooks, &slides[1].hooks));
+    }
+
+    #[test]
+    fn layout_without_init() {
+        let elements = vec![build_comment("column: 0")];
+        let result = try_build_presentation(elements);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn already_in_column() {
+        let elements =
+            vec![build_comment("column_layout: [1]"), build_comment("column: 0"), build_comment("column: 0")];
+        let result = try_build_presentation(elements);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn column_index_overflow() {
+        let elements = vec![build_comment("column_layout: [1]"), build_comment("column: 1")];
+        let result = try_build_presentation(elements);
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::empty("column_layout: []")]
+    #[case::zero("column_layout: [0]")]
+    #[case::one_is_zero("column_layout: [1, 0]")]
+    fn invalid_layouts(#[case] definition: &str) {
+        let elements = vec![build_comment(definition)];
+        let result = try_build_presentation(elements);
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::transition("slide:\n  transition: fade")]
+    #[case::typo("slide:\n  notse: hi")]
+    #[case::invalid_alignment("slide:\n  alignment: top")]
+    fn invalid_slide_options(#[case] comment: &str) {
+        let elements = vec![build_comment(comment)];
+        let result = try_build_presentation(elements);
+        assert!(matches!(result, Err(BuildError::CommandParse(_))));
     }
 
     #[test]
     fn operation_without_enter_column() {
-        let elements = vec![MarkdownElement::Comment("column_layout: [1]".into()), MarkdownElement::ThematicBreak];
+        let elements = vec![build_comment("column_layout: [1]"), MarkdownElement::ThematicBreak];
         let result = try_build_presentation(elements);
         assert!(result.is_err());
     }
@@ -905,6 +2804,37 @@ mod test {
     #[case::column_layout("column_layout: [1, 2]", CommentCommand::InitColumnLayout(vec![1, 2]))]
     #[case::column("column: 1", CommentCommand::Column(1))]
     #[case::reset_layout("reset_layout", CommentCommand::ResetLayout)]
+    #[case::on_enter("on_enter: ./start.sh", CommentCommand::OnEnter("./start.sh".into()))]
+    #[case::on_exit("on_exit: \"kill %1\"", CommentCommand::OnExit("kill %1".into()))]
+    #[case::speaker_note("speaker_note: say hi", CommentCommand::SpeakerNote("say hi".into()))]
+    #[case::reveal_rows("+reveal_rows", CommentCommand::RevealRows)]
+    #[case::reveal_columns("+reveal_columns", CommentCommand::RevealColumns)]
+    #[case::incremental_lists("incremental_lists: true", CommentCommand::IncrementalLists(true))]
+    #[case::slide(
+        "slide:\n  alignment: center\n  notes: hi\n  kind: closing",
+        CommentCommand::Slide(SlideOptions {
+            alignment: Some(SlideAlignment(Alignment::Center { minimum_margin: Margin::Fixed(0), minimum_size: 0 })),
+            background: None,
+            notes: Some("hi".into()),
+            margin: None,
+            kind: Some(SlideKind::Closing),
+        })
+    )]
+    #[case::slide_explicit_alignment(
+        "slide:\n  alignment:\n    alignment: right\n    margin: 4",
+        CommentCommand::Slide(SlideOptions {
+            alignment: Some(SlideAlignment(Alignment::Right { margin: Margin::Fixed(4) })),
+            ..Default::default()
+        })
+    )]
+    #[case::image_grid("image_grid: 3x2", CommentCommand::ImageGrid(ImageGridSize { columns: 3, rows: 2 }))]
+    #[case::part("part: Setup", CommentCommand::Part("Setup".into()))]
+    #[case::duration("duration: 45s", CommentCommand::Duration(SlideDuration(Duration::from_secs(45))))]
+    #[case::video("video: demo.mp4", CommentCommand::Video("demo.mp4".into()))]
+    #[case::compare(
+        "compare: Before | After",
+        CommentCommand::Compare(CompareTitles { left: "Before".into(), right: "After".into() })
+    )]
     fn command_formatting(#[case] input: &str, #[case] expected: CommentCommand) {
         let parsed: CommentCommand = input.parse().expect("deserialization failed");
         assert_eq!(parsed, expected);
@@ -924,10 +2854,1097 @@ mod test {
         assert_eq!(presentation.iter_slides().count(), 2);
     }
 
+    #[test]
+    fn hooks_shared_across_pauses() {
+        let elements = vec![
+            MarkdownElement::FrontMatter("hooks:\n  on_enter: [\"echo all\"]".into()),
+            build_pause(),
+            build_comment("on_enter: echo first"),
+            build_end_slide(),
+            build_comment("on_exit: echo second"),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        assert_eq!(slides.len(), 2);
+        assert_eq!(slides[0].chunks.len(), 2);
+        assert!(!Rc::ptr_eq(&slides[0].hooks, &slides[1].hooks));
+
+        let first = slides[0].hooks.borrow();
+        assert_eq!(first.on_enter, &["echo all", "echo first"]);
+        assert!(first.on_exit.is_empty());
+
+        let second = slides[1].hooks.borrow();
+        assert_eq!(second.on_enter, &["echo all"]);
+        assert_eq!(second.on_exit, &["echo second"]);
+    }
+
+    #[test]
+    fn speaker_notes() {
+        let elements = vec![
+            build_comment("speaker_note: first"),
+            build_pause(),
+            build_comment("speaker_note: second"),
+            build_end_slide(),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        assert_eq!(slides.len(), 2);
+        assert_eq!(slides[0].chunks.len(), 2);
+        assert_eq!(*slides[0].notes.borrow(), &["first", "second"]);
+        assert!(slides[1].notes.borrow().is_empty());
+    }
+
     #[test]
     fn pause_inside_layout() {
         let elements = vec![build_column_layout(1), build_pause(), build_column(0)];
+        let slides = build_presentation(elements).into_slides();
+        assert_eq!(slides.len(), 1);
+        assert_eq!(slides[0].chunks.len(), 2);
+    }
+
+    #[rstest]
+    #[case::executable(ProgrammingLanguage::Bash, true, 1)]
+    #[case::not_executable(ProgrammingLanguage::Bash, false, 0)]
+    fn executable_code(#[case] language: ProgrammingLanguage, #[case] execute: bool, #[case] expected: usize) {
+        let presentation = build_presentation(vec![build_code(language, execute)]);
+        let slides = presentation.into_slides();
+        let count = slides[0].iter_operations().filter(|op| matches!(op, RenderOperation::RenderOnDemand(_))).count();
+        assert_eq!(count, expected);
+    }
+
+    #[test]
+    fn unsupported_code_execution() {
+        let result = try_build_presentation(vec![build_code(ProgrammingLanguage::Rust, true)]);
+        assert!(matches!(result, Err(BuildError::UnsupportedExecution(_))));
+    }
+
+    #[test]
+    fn slides_compatibility() {
+        let elements = vec![
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())]),
+            MarkdownElement::ThematicBreak,
+            build_code(ProgrammingLanguage::Shell, false),
+            build_code(ProgrammingLanguage::Rust, false),
+        ];
+        let presentation = with_builder(&PresentationTheme::default(), |builder| {
+            builder.with_slides_compatibility(true).build(elements)
+        })
+        .expect("build failed");
+        let slides = presentation.into_slides();
+        assert_eq!(slides.len(), 2);
+        let on_demand =
+            slides[1].iter_operations().filter(|op| matches!(op, RenderOperation::RenderOnDemand(_))).count();
+        assert_eq!(on_demand, 1);
+    }
+
+    #[test]
+    fn reveal_table_rows() {
+        let rows = (0..3).map(|index| TableRow(vec![Text::from(index.to_string())])).collect();
+        let table = Table { header: TableRow(vec![Text::from("key")]), rows, caption: None };
+        let elements = vec![build_comment("+reveal_rows"), MarkdownElement::Table(table)];
+        let slides = build_presentation(elements).into_slides();
+        assert_eq!(slides.len(), 1);
+        assert_eq!(slides[0].chunks.len(), 3);
+
+        let lines = extract_chunk_text_lines(&slides[0], 1);
+        assert_eq!(lines, &["key", "────", "0  "]);
+        let lines = extract_chunk_text_lines(&slides[0], 3);
+        assert_eq!(lines, &["key", "────", "0  ", "1  ", "2  "]);
+    }
+
+    #[test]
+    fn reveal_block_quote_rows() {
+        let lines = vec!["first".into(), "second".into()];
+        let elements = vec![build_comment("+reveal_rows"), MarkdownElement::BlockQuote(lines)];
+        let slides = build_presentation(elements).into_slides();
+        assert_eq!(slides.len(), 1);
+        assert_eq!(slides[0].chunks.len(), 2);
+    }
+
+    #[test]
+    fn block_quote_tabs() {
+        let first = Text { chunks: vec!["a".into(), StyledText::new("\tb", TextStyle::default().bold())] };
+        let lines = vec![first, "\tlonger".into()];
+        let slides = build_presentation(vec![MarkdownElement::BlockQuote(lines)]).into_slides();
+        let lines: Vec<_> = slides[0]
+            .iter_operations()
+            .filter_map(|op| match op {
+                RenderOperation::RenderBlockLine(line) => Some(line),
+                _ => None,
+            })
+            .collect();
+        let texts: Vec<Vec<_>> =
+            lines.iter().map(|line| line.text.iter_texts().map(|text| text.text.clone()).collect()).collect();
+        let expected = vec![
+            vec![StyledText::from("a"), StyledText::new("   b", TextStyle::default().bold())],
+            vec![StyledText::from("    longer")],
+        ];
+        assert_eq!(texts, expected);
+        let prefix = PresentationTheme::default().block_quote.prefix.unwrap_or_default();
+        assert_eq!(lines[0].block_length, prefix.display_width() + 10);
+    }
+
+    #[test]
+    fn invalid_reveal_rows() {
+        let elements =
+            vec![build_comment("+reveal_rows"), MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())])];
+        let result = try_build_presentation(elements);
+        assert!(matches!(result, Err(BuildError::InvalidRevealRows)));
+    }
+
+    #[test]
+    fn pause_keeps_current_column() {
+        let elements = vec![build_column_layout(1), build_column(0), build_pause()];
+        let slides = build_presentation(elements).into_slides();
+        assert_eq!(slides[0].chunks.len(), 2);
+        let first_chunk = &slides[0].chunks[0].operations;
+        assert!(first_chunk.iter().any(|op| matches!(op, RenderOperation::EnterColumn { column: 0 })));
+        assert!(!first_chunk.iter().any(|op| matches!(op, RenderOperation::ExitLayout)));
+    }
+
+    #[test]
+    fn reveal_columns() {
+        let elements = vec![
+            build_comment("+reveal_columns"),
+            build_comment("column_layout: [1, 1, 1]"),
+            build_column(0),
+            build_column(1),
+            build_column(2),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        assert_eq!(slides.len(), 1);
+        assert_eq!(slides[0].chunks.len(), 3);
+    }
+
+    #[test]
+    fn invalid_reveal_columns() {
+        let elements = vec![build_comment("+reveal_columns"), build_code(ProgrammingLanguage::Rust, false)];
+        let result = try_build_presentation(elements);
+        assert!(matches!(result, Err(BuildError::InvalidRevealColumns)));
+    }
+
+    #[test]
+    fn image_grid() {
+        let elements = vec![
+            build_comment("image_grid: 2x2"),
+            build_image(),
+            MarkdownElement::Paragraph(vec![ParagraphElement::LineBreak]),
+            build_image(),
+            build_image(),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())]),
+            build_image(),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        let operations: Vec<_> = slides[0].iter_operations().cloned().collect();
+        let layouts = operations.iter().filter(|op| matches!(op, RenderOperation::InitColumnLayout { .. })).count();
+        assert_eq!(layouts, 2);
+
+        let slots: Vec<_> = operations
+            .iter()
+            .filter_map(|op| match op {
+                RenderOperation::RenderImage(_, properties) => Some(properties.vertical_slots),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(slots, &[Some(2), Some(2), Some(1), None]);
+    }
+
+    #[test]
+    fn image_grid_inside_layout() {
+        let elements = vec![build_column_layout(1), build_comment("image_grid: 1x1")];
+        let result = try_build_presentation(elements);
+        assert!(matches!(result, Err(BuildError::ImageGridInLayout)));
+    }
+
+    #[rstest]
+    #[case::plain(false, &["doge", "cat"])]
+    #[case::numbered(true, &["Figure 1: doge", "Figure 2: cat"])]
+    fn image_captions(#[case] numbering: bool, #[case] expected: &[&str]) {
+        let elements = vec![build_captioned_image("doge"), build_image(), build_captioned_image("cat")];
+        let mut theme = PresentationTheme::default();
+        theme.caption.numbering = numbering;
+        let presentation = build_presentation_with_theme(elements, &theme);
+        let slides = presentation.into_slides();
+        let lines = extract_text_lines(slides[0].iter_operations());
+        assert_eq!(lines, expected);
+    }
+
+    #[rstest]
+    #[case::plain(false, "cat")]
+    #[case::numbered(true, "Table 1: cat")]
+    fn table_captions(#[case] numbering: bool, #[case] expected: &str) {
+        let table = |caption: Option<&str>| {
+            MarkdownElement::Table(Table {
+                header: TableRow(vec![Text::from("key")]),
+                rows: vec![TableRow(vec![Text::from("a")])],
+                caption: caption.map(Text::from),
+            })
+        };
+        let elements = vec![table(None), build_captioned_image("doge"), table(Some("cat"))];
+        let mut theme = PresentationTheme::default();
+        theme.caption.numbering = numbering;
+        let presentation = build_presentation_with_theme(elements, &theme);
+        let slides = presentation.into_slides();
+        let lines = extract_text_lines(slides[0].iter_operations());
+        assert_eq!(lines.last().map(String::as_str), Some(expected));
+    }
+
+    #[test]
+    fn videos() {
+        let elements = vec![
+            build_comment("video: demo.mp4"),
+            build_comment("pause"),
+            build_comment("video: https://example.com/talk.webm"),
+            build_end_slide(),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("bye".into())]),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        assert_eq!(slides.len(), 2);
+        assert_eq!(slides[0].videos, &["/tmp/demo.mp4", "https://example.com/talk.webm"]);
+        assert!(slides[1].videos.is_empty());
+
+        let lines: Vec<_> = slides[0].chunks[0]
+            .operations
+            .iter()
+            .filter_map(|op| match op {
+                RenderOperation::RenderBlockLine(line) => {
+                    let text: String = line.text.iter_texts().map(|text| text.text.text.as_str()).collect();
+                    Some(format!("{}{text}", line.prefix))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(lines, &["▶ demo.mp4", "  press v to play"]);
+    }
+
+    #[test]
+    fn slide_options() {
+        let elements = vec![
+            build_comment("slide:\n  alignment: right\n  background: \"ff0000\"\n  notes: hi"),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())]),
+            build_end_slide(),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("bye".into())]),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        assert_eq!(slides.len(), 2);
+        assert_eq!(*slides[0].notes.borrow(), &["hi"]);
+
+        let find_alignment = |slide: &Slide| {
+            slide.iter_operations().find_map(|op| match op {
+                RenderOperation::RenderTextLine { alignment, .. } => Some(alignment.clone()),
+                _ => None,
+            })
+        };
+        assert!(matches!(find_alignment(&slides[0]), Some(Alignment::Right { .. })));
+        assert!(matches!(find_alignment(&slides[1]), Some(Alignment::Left { .. })));
+
+        let Some(RenderOperation::SetColors(colors)) = slides[0].iter_operations().next() else {
+            panic!("slide doesn't start with colors");
+        };
+        assert_eq!(colors.background, Some(Color::new(255, 0, 0)));
+    }
+
+    #[test]
+    fn slide_margins() {
+        let elements = vec![
+            MarkdownElement::FrontMatter("options:\n  margin:\n    horizontal: 10\n    bottom: 1".into()),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())]),
+            build_end_slide(),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("bye".into())]),
+            build_comment("slide:\n  margin:\n    horizontal: 2"),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        let find_margin = |slide: &Slide| {
+            slide.iter_operations().find_map(|op| match op {
+                RenderOperation::ApplyMargin(properties) => Some(properties.clone()),
+                _ => None,
+            })
+        };
+        let margin = find_margin(&slides[0]).expect("no margin");
+        assert_eq!(margin.horizontal_margin, Margin::Fixed(10));
+        assert_eq!(margin.bottom_slide_margin, 1);
+
+        // Margins not set in the slide come from the front matter.
+        let margin = find_margin(&slides[1]).expect("no margin");
+        assert_eq!(margin.horizontal_margin, Margin::Fixed(2));
+        assert_eq!(margin.bottom_slide_margin, 1);
+    }
+
+    #[test]
+    fn slide_percent_margin() {
+        let elements = vec![
+            build_comment("slide:\n  margin:\n    horizontal: 20%"),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())]),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        let margin = slides[0].iter_operations().find_map(|op| match op {
+            RenderOperation::ApplyMargin(properties) => Some(properties.horizontal_margin.clone()),
+            _ => None,
+        });
+        assert_eq!(margin, Some(Margin::Percent(20)));
+    }
+
+    #[test]
+    fn heading_prefix_colors() {
+        let mut theme = PresentationTheme::default();
+        let prefix_color = Color::new(255, 0, 0);
+        let heading_color = Color::new(0, 0, 255);
+        theme.headings.h2.prefix = Some("🚀".into());
+        theme.headings.h2.prefix_colors.foreground = Some(prefix_color);
+        theme.headings.h2.colors = Colors { foreground: Some(heading_color), background: Some(heading_color) };
+        let elements = vec![MarkdownElement::Heading { level: 2, text: Text::from("hi") }];
+        let presentation = build_presentation_with_theme(elements, &theme);
+        let slides = presentation.into_slides();
+        let line = slides[0]
+            .iter_operations()
+            .find_map(|op| match op {
+                RenderOperation::RenderTextLine { line, .. } => Some(line.clone()),
+                _ => None,
+            })
+            .expect("no text");
+        let texts: Vec<_> = line.iter_texts().map(|text| text.text.clone()).collect();
+        assert_eq!(texts[0].text, "🚀 ");
+        assert_eq!(texts[0].style.colors, Colors { foreground: Some(prefix_color), background: Some(heading_color) });
+        assert_eq!(texts[1].style.colors.foreground, Some(heading_color));
+    }
+
+    #[rstest]
+    #[case::zero(0)]
+    #[case::too_deep(7)]
+    fn invalid_heading_level(#[case] level: u8) {
+        let elements = vec![MarkdownElement::Heading { level, text: Text::from("hi") }];
+        let result = try_build_presentation(elements);
+        assert!(matches!(result, Err(BuildError::InvalidHeadingLevel(_))));
+    }
+
+    #[test]
+    fn slide_kinds() {
+        let elements = vec![
+            MarkdownElement::FrontMatter("title: hello".into()),
+            MarkdownElement::SetexHeading { text: Text::from("intro") },
+            build_end_slide(),
+            MarkdownElement::Heading { level: 2, text: Text::from("details") },
+            build_end_slide(),
+            build_comment("slide:\n  kind: closing"),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("bye".into())]),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        let kinds: Vec<_> = slides.iter().map(|slide| slide.kind).collect();
+        assert_eq!(kinds, &[SlideKind::Title, SlideKind::Section, SlideKind::Content, SlideKind::Closing]);
+    }
+
+    #[test]
+    fn slide_kind_colors() {
+        let elements = vec![
+            MarkdownElement::SetexHeading { text: Text::from("intro") },
+            build_end_slide(),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())]),
+        ];
+        let (black, white, red) = (Color::new(0, 0, 0), Color::new(255, 255, 255), Color::new(255, 0, 0));
+        let mut theme = PresentationTheme::default();
+        theme.default_style.colors = Colors { background: Some(black), foreground: Some(white) };
+        theme.slide_kinds.section.colors.background = Some(red);
+        let presentation = build_presentation_with_theme(elements, &theme);
+        let backgrounds: Vec<_> = presentation
+            .iter_slides()
+            .map(|slide| {
+                let Some(RenderOperation::SetColors(colors)) = slide.iter_operations().next() else {
+                    panic!("no colors");
+                };
+                assert_eq!(colors.foreground, Some(white));
+                colors.background
+            })
+            .collect();
+        assert_eq!(backgrounds, &[Some(red), Some(black)]);
+    }
+
+    #[test]
+    fn sections() {
+        let elements = vec![
+            MarkdownElement::SetexHeading { text: Text::from("intro") },
+            build_end_slide(),
+            MarkdownElement::Heading { level: 2, text: Text::from("details") },
+            build_end_slide(),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())]),
+            build_pause(),
+            MarkdownElement::Heading { level: 1, text: Text::from("not a section") },
+            build_end_slide(),
+            build_comment("pause"),
+            MarkdownElement::Heading { level: 1, text: Text::from("outro") },
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("bye".into())]),
+            build_end_slide(),
+            MarkdownElement::SetexHeading { text: Text::from("title") },
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("not a section either".into())]),
+        ];
+        let slides = build_presentation(elements).into_slides();
+        let sections: Vec<_> = slides.iter().map(|slide| slide.kind == SlideKind::Section).collect();
+        assert_eq!(sections, &[true, false, false, true, false]);
+    }
+
+    #[test]
+    fn section_title_alignment() {
+        let elements = vec![
+            MarkdownElement::SetexHeading { text: Text::from("section") },
+            build_end_slide(),
+            MarkdownElement::SetexHeading { text: Text::from("content") },
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())]),
+        ];
+        let center = Alignment::Center { minimum_margin: Margin::Fixed(0), minimum_size: 0 };
+        let mut theme = PresentationTheme::default();
+        theme.slide_kinds.section.alignment = Some(center.clone());
+        let presentation = build_presentation_with_theme(elements, &theme);
+        let title_alignments: Vec<_> = presentation
+            .iter_slides()
+            .map(|slide| {
+                let alignment = slide.iter_operations().find_map(|operation| match operation {
+                    RenderOperation::RenderTextLine { alignment, .. } => Some(alignment),
+                    _ => None,
+                });
+                alignment.expect("no title").clone()
+            })
+            .collect();
+        assert_eq!(title_alignments[0], center);
+        assert_ne!(title_alignments[1], center);
+    }
+
+    #[test]
+    fn footer_current_title() {
+        let elements = vec![
+            MarkdownElement::SetexHeading { text: Text::from("intro") },
+            MarkdownElement::Heading { level: 2, text: Text::from("details") },
+            build_end_slide(),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())]),
+            build_end_slide(),
+            MarkdownElement::Heading { level: 1, text: Text::from("outro") },
+        ];
+        let footer = FooterStyle::Template {
+            left: Some("{current_title}".into()),
+            center: None,
+            right: None,
+            colors: Default::default(),
+        };
+        let theme = PresentationTheme { footer, ..Default::default() };
+        let presentation = build_presentation_with_theme(elements, &theme);
+        let dimensions = WindowSize { rows: 10, columns: 10, width: 100, height: 100 };
+        let footers: Vec<_> = presentation
+            .iter_slides()
+            .map(|slide| {
+                let generator = slide
+                    .iter_operations()
+                    .find_map(|op| match op {
+                        RenderOperation::RenderDynamic(generator) => Some(generator.clone()),
+                        _ => None,
+                    })
+                    .expect("no footer");
+                extract_text_lines(&generator.as_render_operations(&dimensions))
+            })
+            .collect();
+        assert_eq!(footers, &[vec!["intro"], vec!["intro"], vec!["outro"]]);
+    }
+
+    #[test]
+    fn footer_section_progress() {
+        let elements = vec![
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("intro".into())]),
+            build_end_slide(),
+            build_comment("part: Setup"),
+            build_end_slide(),
+            build_end_slide(),
+            build_comment("part: Advanced"),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())]),
+        ];
+        let footer = FooterStyle::Template {
+            left: Some("{part}: {section_progress}".into()),
+            center: None,
+            right: None,
+            colors: Default::default(),
+        };
+        let theme = PresentationTheme { footer, ..Default::default() };
+        let presentation = build_presentation_with_theme(elements, &theme);
+        let dimensions = WindowSize { rows: 10, columns: 20, width: 100, height: 100 };
+        let footers: Vec<_> = presentation
+            .iter_slides()
+            .map(|slide| {
+                let generator = slide
+                    .iter_operations()
+                    .find_map(|op| match op {
+                        RenderOperation::RenderDynamic(generator) => Some(generator.clone()),
+                        _ => None,
+                    })
+                    .expect("no footer");
+                extract_text_lines(&generator.as_render_operations(&dimensions))
+            })
+            .collect();
+        assert_eq!(footers, &[vec![": "], vec!["Setup: 1/2"], vec!["Setup: 2/2"], vec!["Advanced: 1/1"]]);
+    }
+
+    #[test]
+    fn footer_per_slide_kind() {
+        let elements = vec![
+            MarkdownElement::FrontMatter("title: hello".into()),
+            MarkdownElement::SetexHeading { text: Text::from("intro") },
+            build_end_slide(),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())]),
+        ];
+        let template = |text: &str| FooterStyle::Template {
+            left: Some(text.into()),
+            center: None,
+            right: None,
+            colors: Default::default(),
+        };
+        let mut theme = PresentationTheme { footer: template("regular"), ..Default::default() };
+        theme.slide_kinds.title.footer = Some(FooterStyle::Empty);
+        theme.slide_kinds.section.footer = Some(template("section"));
+        let presentation = build_presentation_with_theme(elements, &theme);
+        let dimensions = WindowSize { rows: 10, columns: 20, width: 100, height: 100 };
+        let footers: Vec<_> = presentation
+            .iter_slides()
+            .map(|slide| {
+                let generator = slide
+                    .iter_operations()
+                    .find_map(|op| match op {
+                        RenderOperation::RenderDynamic(generator) => Some(generator.clone()),
+                        _ => None,
+                    })
+                    .expect("no footer");
+                extract_text_lines(&generator.as_render_operations(&dimensions))
+            })
+            .collect();
+        assert_eq!(footers, &[vec![], vec!["section"], vec!["regular"]]);
+    }
+
+    #[rstest]
+    #[case::fits(40, [Some(5), Some(6), Some(5)], [5, 6, 5])]
+    #[case::center_first(20, [Some(10), Some(10), Some(10)], [3, 10, 3])]
+    #[case::center_too_long(10, [Some(5), Some(20), None], [0, 8, 0])]
+    #[case::short_side(20, [Some(15), None, Some(3)], [14, 0, 3])]
+    #[case::long_sides(21, [Some(20), None, Some(20)], [9, 0, 9])]
+    #[case::single_side(10, [Some(20), None, None], [8, 0, 0])]
+    fn footer_sections_fit(#[case] columns: u16, #[case] widths: [Option<usize>; 3], #[case] expected: [usize; 3]) {
+        assert_eq!(FooterGenerator::fit_sections(columns, widths), expected);
+    }
+
+    #[rstest]
+    #[case::centered(IntroSlideLayout::Centered)]
+    #[case::left_rule(IntroSlideLayout::LeftRule)]
+    #[case::split(IntroSlideLayout::Split { image: concat!(env!("CARGO_MANIFEST_DIR"), "/examples/doge.png").into() })]
+    fn intro_slide_layouts(#[case] layout: IntroSlideLayout) {
+        let elements = vec![MarkdownElement::FrontMatter("title: hello\nauthor: me".into())];
+        let mut theme = PresentationTheme::default();
+        theme.intro_slide.layout = layout.clone();
+        let presentation = build_presentation_with_theme(elements, &theme);
+        let operations: Vec<_> =
+            presentation.iter_slides().next().expect("no slides").iter_operations().cloned().collect();
+        assert_eq!(extract_text_lines(&operations), &["hello", "me"]);
+
+        let has_separator = operations.iter().any(|op| matches!(op, RenderOperation::RenderSeparator(_)));
+        assert_eq!(has_separator, matches!(layout, IntroSlideLayout::LeftRule));
+        let has_image = operations.iter().any(|op| matches!(op, RenderOperation::RenderImage(..)));
+        assert_eq!(has_image, matches!(layout, IntroSlideLayout::Split { .. }));
+    }
+
+    #[test]
+    fn author_block() {
+        let front_matter = r#"
+title: hello
+author:
+  name: me
+  title: Engineer
+  contact: me@example.com
+"#;
+        let elements = vec![MarkdownElement::FrontMatter(front_matter.into())];
+        let mut theme = PresentationTheme::default();
+        theme.intro_slide.author.title.italics = true;
+        let presentation = build_presentation_with_theme(elements, &theme);
+        let operations: Vec<_> =
+            presentation.iter_slides().next().expect("no slides").iter_operations().cloned().collect();
+        assert_eq!(extract_text_lines(&operations), &["hello", "me", "Engineer", "me@example.com"]);
+
+        let jump = operations.iter().find(|op| matches!(op, RenderOperation::JumpToBottomRow { .. }));
+        assert!(matches!(jump, Some(RenderOperation::JumpToBottomRow { index: 2 })));
+        let italics: Vec<_> = operations
+            .iter()
+            .filter_map(|op| match op {
+                RenderOperation::RenderTextLine { line, .. } => {
+                    line.iter_texts().next().map(|text| text.text.style.is_italics())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(italics, &[false, false, true, false]);
+    }
+
+    #[rstest]
+    #[case::icons(true, "\u{f09b} me   \u{f0e0} me@example.com")]
+    #[case::text(false, "github: me   email: me@example.com")]
+    fn socials(#[case] nerd_fonts: bool, #[case] expected: &str) {
+        let front_matter = "title: hello\nsocials:\n  github: me\n  email: me@example.com";
+        let elements = vec![MarkdownElement::FrontMatter(front_matter.into())];
+        let footer = FooterStyle::Template {
+            left: Some("{socials}".into()),
+            center: None,
+            right: None,
+            colors: Default::default(),
+        };
+        let theme = PresentationTheme { footer, ..Default::default() };
+        let presentation =
+            with_builder(&theme, |builder| builder.with_nerd_fonts(nerd_fonts).build(elements)).expect("build failed");
+        let slide = presentation.iter_slides().next().expect("no slides");
+        let lines = extract_text_lines(slide.chunks.iter().flat_map(|chunk| &chunk.operations));
+        assert_eq!(lines, &["hello", expected]);
+
+        let Some(RenderOperation::RenderDynamic(generator)) = slide.footer.last() else {
+            panic!("no footer");
+        };
+        let dimensions = WindowSize { rows: 10, columns: 100, width: 100, height: 100 };
+        assert_eq!(extract_text_lines(&generator.as_render_operations(&dimensions)), &[expected]);
+    }
+
+    #[test]
+    fn intro_slide_missing_image() {
+        let elements = vec![MarkdownElement::FrontMatter("title: hello".into())];
+        let mut theme = PresentationTheme::default();
+        theme.intro_slide.layout = IntroSlideLayout::Split { image: "/does/not/exist.png".into() };
+        let result = with_builder(&theme, |builder| builder.build(elements));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn progress_bar_gradient() {
+        let context = FooterContext { total_slides: Some(2), ..Default::default() };
+        let generator = FooterGenerator {
+            current_slide: 0,
+            current_title: String::new(),
+            current_part: String::new(),
+            context: Rc::new(RefCell::new(context)),
+            style: FooterStyle::ProgressBar {
+                character: Some('#'),
+                colors: Default::default(),
+                gradient: vec![Color::new(0, 0, 0), Color::new(200, 0, 0)],
+            },
+        };
+        let dimensions = WindowSize { rows: 10, columns: 5, width: 100, height: 100 };
+        let operations = generator.as_render_operations(&dimensions);
+        let Some(RenderOperation::RenderTextLine { line, .. }) = operations.first() else {
+            panic!("no progress bar");
+        };
+        let colors: Vec<_> = line.iter_texts().map(|text| text.text.style.colors.foreground).collect();
+        let expected = [Color::new(0, 0, 0), Color::new(50, 0, 0), Color::new(100, 0, 0)].map(Some);
+        assert_eq!(colors, expected);
+    }
+
+    #[rstest]
+    #[case::no_whitespace("a\tb\n", false, "a   b\n")]
+    #[case::tab_stops("\tab\tc\n", false, "    ab  c\n")]
+    #[case::show_whitespace("\ta b \n", true, "→   a·b·\n")]
+    #[case::joined_emoji("👩\u{200d}💻\tx\n", false, "👩\u{200d}💻  x\n")]
+    #[case::skin_tone("👍🏽\tx\n", false, "👍🏽  x\n")]
+    fn expand_whitespace(#[case] input: &str, #[case] show_whitespace: bool, #[case] expected: &str) {
+        assert_eq!(PresentationBuilder::expand_whitespace(input, 4, show_whitespace), expected);
+    }
+
+    #[test]
+    fn execution_defaults() {
+        let front_matter = "options:\n  exec:\n    cwd: demo\n    env:\n      NAME: bob\n      COLOR: red";
+        let flags = CodeFlags { execute: true, env: vec![("NAME".into(), "alice".into())], ..Default::default() };
+        let code = Code { contents: "echo hi".into(), language: ProgrammingLanguage::Bash, flags };
+        let code = with_builder(&PresentationTheme::default(), |mut builder| {
+            builder.process_front_matter(front_matter).expect("invalid front matter");
+            builder.execution_code(code)
+        });
+        assert_eq!(code.flags.cwd, Some("/tmp/demo".into()));
+        let expected = [("COLOR", "red"), ("NAME", "bob"), ("NAME", "alice")];
+        assert_eq!(code.flags.env, expected.map(|(name, value)| (name.into(), value.into())));
+    }
+
+    #[test]
+    fn included_snippets() {
+        let build_code = |contents: &str, language, id: Option<&str>, include: &[&str]| {
+            let id = id.map(String::from);
+            let include = include.iter().map(|id| id.to_string()).collect();
+            let flags = CodeFlags { id, include, ..Default::default() };
+            Code { contents: contents.into(), language, flags }
+        };
+        with_builder(&PresentationTheme::default(), |mut builder| {
+            let imports = build_code("use std::env;", ProgrammingLanguage::Rust, Some("imports"), &[]);
+            builder.include_snippets(&imports).expect("include failed");
+            let helper = build_code("fn helper() {}\n", ProgrammingLanguage::Rust, Some("helper"), &["imports"]);
+            builder.include_snippets(&helper).expect("include failed");
+            let main = build_code("fn main() {}\n", ProgrammingLanguage::Rust, None, &["helper"]);
+            let code = builder.include_snippets(&main).expect("include failed");
+            assert_eq!(code.contents, "use std::env;\nfn helper() {}\nfn main() {}\n");
+
+            let other = build_code("echo hi", ProgrammingLanguage::Bash, None, &["imports"]);
+            assert!(matches!(builder.include_snippets(&other), Err(BuildError::SnippetLanguageMismatch(_))));
+            let unknown = build_code("", ProgrammingLanguage::Rust, None, &["potato"]);
+            assert!(matches!(builder.include_snippets(&unknown), Err(BuildError::UnknownSnippet(_))));
+            let duplicate = build_code("", ProgrammingLanguage::Rust, Some("helper"), &[]);
+            assert!(matches!(builder.include_snippets(&duplicate), Err(BuildError::DuplicateSnippet(_))));
+        });
+    }
+
+    #[rstest]
+    #[case::success(ProcessStatus::Success, Some(0), 250, Some("✔ exit code 0 in 250ms"))]
+    #[case::failure(ProcessStatus::Failure, Some(2), 1500, Some("✘ exit code 2 in 1.50s"))]
+    #[case::signal(ProcessStatus::Failure, None, 10, Some("✘ terminated by a signal in 10ms"))]
+    #[case::running(ProcessStatus::Running, None, 10, None)]
+    #[case::cancelled(ProcessStatus::Failure, None, 10, Some("✘ cancelled after 10ms"))]
+    fn execution_status_line(
+        #[case] status: ProcessStatus,
+        #[case] exit_code: Option<i32>,
+        #[case] millis: u64,
+        #[case] expected: Option<&str>,
+    ) {
+        let code = Code { contents: "".into(), language: ProgrammingLanguage::Bash, flags: Default::default() };
+        let operation = RunCodeOperation::new(code, Alignment::default(), 0, Vec::new());
+        let duration = Some(Duration::from_millis(millis));
+        let cancelled = expected.is_some_and(|line| line.contains("cancelled"));
+        let state = ExecutionState { status, exit_code, duration, cancelled, ..Default::default() };
+        let line = operation.status_line(&state).map(|line| extract_text_lines(&[line]).join(""));
+        assert_eq!(line.as_deref(), expected);
+    }
+
+    #[test]
+    fn hidden_code() {
+        let directory = tempfile::tempdir().expect("creating temp dir");
+        let build_code = |contents: &str, hidden| {
+            let cwd = Some(directory.path().to_path_buf());
+            let flags = CodeFlags { execute: true, hidden, auto_execute: hidden, cwd, ..Default::default() };
+            MarkdownElement::Code(Code { contents: contents.into(), language: ProgrammingLanguage::Bash, flags })
+        };
+        let elements = vec![build_code("sleep 0.2; echo hi > greeting", true), build_code("cat greeting", false)];
+        let slides = build_presentation(elements).into_slides();
+        let operations: Vec<_> = slides[0]
+            .iter_operations()
+            .filter_map(|op| match op {
+                RenderOperation::RenderOnDemand(operation) => Some(operation.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(operations.len(), 2);
+        assert_eq!(operations.iter().map(|op| op.starts_automatically()).collect::<Vec<_>>(), &[true, false]);
+        // Only the visible code is displayed.
+        let code_lines =
+            slides[0].iter_operations().filter(|op| matches!(op, RenderOperation::RenderPreformattedLine(_)));
+        assert_eq!(code_lines.count(), 1);
+
+        // Running the visible code runs the hidden one first.
+        let (sender, _receiver) = std::sync::mpsc::channel();
+        assert!(operations[1].start_render(sender));
+        let dimensions = WindowSize { rows: 10, columns: 10, height: 0, width: 0 };
+        let output = |operation: &Rc<dyn RenderOnDemand>| {
+            operation
+                .as_render_operations(&dimensions)
+                .into_iter()
+                .filter_map(|op| match op {
+                    RenderOperation::RenderPreformattedLine(line) => Some(line.text.to_string()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        for _ in 0..100 {
+            let lines = extract_text_lines(&operations[1].as_render_operations(&dimensions));
+            if lines.first().is_some_and(|line| line == "—— [finished] ——") {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(output(&operations[1]), &["hi"]);
+        assert!(operations[0].as_render_operations(&dimensions).is_empty());
+    }
+
+    #[rstest]
+    #[case::unchanged("echo setup", "echo helper", "demo", "bob", 2)]
+    #[case::hidden_setup("echo other", "echo helper", "demo", "bob", 1)]
+    #[case::included_snippet("echo setup", "echo other", "demo", "bob", 0)]
+    #[case::cwd("echo setup", "echo helper", "other", "bob", 1)]
+    #[case::env("echo setup", "echo helper", "demo", "alice", 1)]
+    fn reload_code_state(
+        #[case] setup: &str,
+        #[case] snippet: &str,
+        #[case] cwd: &str,
+        #[case] name: &str,
+        #[case] expected: usize,
+    ) {
+        let build = |setup: &str, snippet: &str, cwd: &str, name: &str| {
+            let build_code = |contents: &str, flags| {
+                MarkdownElement::Code(Code { contents: contents.into(), language: ProgrammingLanguage::Bash, flags })
+            };
+            let hidden = CodeFlags { execute: true, hidden: true, ..Default::default() };
+            let env = vec![("NAME".into(), name.into())];
+            let include = vec!["helper".into()];
+            let visible = CodeFlags { execute: true, cwd: Some(cwd.into()), env, include, ..Default::default() };
+            let elements = vec![
+                build_code(snippet, CodeFlags { id: Some("helper".into()), ..Default::default() }),
+                build_end_slide(),
+                build_code(setup, hidden),
+                build_code("echo hi", visible),
+            ];
+            build_presentation(elements)
+        };
+        let original = build("echo setup", "echo helper", "demo", "bob");
+        let mut updated = build(setup, snippet, cwd, name);
+        assert_eq!(PresentationDiffer::carry_over_state(&original, &mut updated), expected);
+    }
+
+    #[test]
+    fn scrolling_code() {
+        let contents = (1..=5).map(|line| format!("line{line}\n")).collect();
+        let flags = CodeFlags { max_height: Some(2), ..Default::default() };
+        let code = Code { contents, language: ProgrammingLanguage::Unknown, flags };
+        let slides = build_presentation(vec![MarkdownElement::Code(code)]).into_slides();
+        assert_eq!(slides.len(), 3);
+
+        let lines: Vec<_> = slides
+            .iter()
+            .map(|slide| {
+                let code: Vec<_> = slide
+                    .iter_operations()
+                    .filter_map(|op| match op {
+                        RenderOperation::RenderPreformattedLine(line) => Some(line.unformatted_length),
+                        _ => None,
+                    })
+                    .collect();
+                (code.len(), extract_text_lines(slide.iter_operations()))
+            })
+            .collect();
+        assert_eq!(lines[0], (2, vec!["lines 1-2 of 5 ↓".to_string()]));
+        assert_eq!(lines[1], (2, vec!["lines 3-4 of 5 ↓".to_string()]));
+        assert_eq!(lines[2], (1, vec!["lines 5-5 of 5".to_string()]));
+    }
+
+    #[test]
+    fn line_numbers() {
+        let contents = (1..=10).map(|line| format!("line{line}\n")).collect();
+        let flags = CodeFlags { line_numbers: true, ..Default::default() };
+        let code = Code { contents, language: ProgrammingLanguage::Unknown, flags };
+        let slides = build_presentation(vec![MarkdownElement::Code(code)]).into_slides();
+        let lines: Vec<_> = slides[0]
+            .iter_operations()
+            .filter_map(|op| match op {
+                RenderOperation::RenderPreformattedLine(line) => Some(line.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(lines.len(), 10);
+        assert!(lines[0].text.contains(" 1 "));
+        assert!(lines[0].text.ends_with("line1"));
+        assert!(lines[9].text.contains("10 "));
+        let lengths: Vec<_> = lines.iter().map(|line| line.unformatted_length).collect();
+        assert_eq!(lengths, &[8, 8, 8, 8, 8, 8, 8, 8, 8, 9]);
+        assert_eq!(lines[0].block_length, 9);
+    }
+
+    #[test]
+    fn highlight_steps() {
+        let highlight_groups = vec![HighlightGroup::Lines(vec![1..=1]), HighlightGroup::All];
+        let flags = CodeFlags { highlight_groups, ..Default::default() };
+        let code = Code { contents: "a\nb\n".into(), language: ProgrammingLanguage::Unknown, flags };
+        let mut presentation = build_presentation(vec![MarkdownElement::Code(code)]);
+        assert_eq!(presentation.current_slide().chunks.len(), 2);
+
+        let code_lines = |presentation: &Presentation| {
+            let generator = presentation.current_slide().chunks[0]
+                .operations
+                .iter()
+                .find_map(|op| match op {
+                    RenderOperation::RenderDynamic(generator) => Some(generator.clone()),
+                    _ => None,
+                })
+                .expect("no code");
+            let dimensions = WindowSize { rows: 10, columns: 10, height: 0, width: 0 };
+            generator
+                .as_render_operations(&dimensions)
+                .into_iter()
+                .filter_map(|op| match op {
+                    RenderOperation::RenderPreformattedLine(line) => Some(line.text.to_string()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        let first = code_lines(&presentation);
+        presentation.jump_next_slide();
+        let second = code_lines(&presentation);
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0], second[0]);
+        assert_ne!(first[1], second[1]);
+
+        presentation.jump_previous_slide();
+        assert_eq!(code_lines(&presentation), first);
+    }
+
+    #[rstest]
+    #[case::unchanged("a\nb\n", None)]
+    #[case::changed("a\nc\n", Some(0))]
+    fn reload_highlight_steps(#[case] contents: &str, #[case] expected: Option<usize>) {
+        let build = |contents: &str| {
+            let highlight_groups = vec![HighlightGroup::Lines(vec![1..=1]), HighlightGroup::All];
+            let flags = CodeFlags { highlight_groups, ..Default::default() };
+            let code = Code { contents: contents.into(), language: ProgrammingLanguage::Unknown, flags };
+            build_presentation(vec![MarkdownElement::Code(code)])
+        };
+        let original = build("a\nb\n");
+        let updated = build(contents);
+        assert_eq!(PresentationDiffer::first_modified_slide(&original, &updated), expected);
+    }
+
+    fn build_plain_code(contents: &str) -> MarkdownElement {
+        let language = ProgrammingLanguage::Unknown;
+        MarkdownElement::Code(Code { contents: contents.into(), language, flags: Default::default() })
+    }
+
+    #[test]
+    fn code_comparison() {
+        let elements =
+            vec![build_comment("compare: Before | After"), build_plain_code("a\nb\nc\n"), build_plain_code("a")];
+        let slides = build_presentation(elements).into_slides();
+        let operations: Vec<_> = slides[0].iter_operations().cloned().collect();
+        let mut columns: Vec<usize> = Vec::new();
+        for operation in &operations {
+            match operation {
+                RenderOperation::EnterColumn { .. } => columns.push(0),
+                RenderOperation::RenderPreformattedLine(_) => *columns.last_mut().expect("not in column") += 1,
+                _ => (),
+            };
+        }
+        assert_eq!(columns, &[3, 3]);
+        assert_eq!(extract_text_lines(&operations), &["Before", "After"]);
+    }
+
+    #[rstest]
+    #[case::not_code(vec![build_plain_code("a"), MarkdownElement::ThematicBreak])]
+    #[case::single_code(vec![build_plain_code("a")])]
+    fn invalid_code_comparison(#[case] mut elements: Vec<MarkdownElement>) {
+        elements.insert(0, build_comment("compare: Before | After"));
+        let result = try_build_presentation(elements);
+        assert!(matches!(result, Err(BuildError::InvalidComparison(_))));
+    }
+
+    #[test]
+    fn inline_code_style() {
+        let mut theme = PresentationTheme::default();
+        theme.inline_code.padding = Some(1);
+        theme.inline_code.delimiters = Some(InlineCodeDelimiters { start: "[".into(), end: "]".into() });
+        let text = Text::from(StyledText::new("potato", TextStyle::default().code()));
+        let elements = vec![MarkdownElement::Paragraph(vec![ParagraphElement::Text(text)])];
+        let presentation = build_presentation_with_theme(elements, &theme);
+        let slides = presentation.into_slides();
+        let lines = extract_text_lines(slides[0].iter_operations());
+        assert_eq!(lines, &["[ potato ]"]);
+    }
+
+    #[test]
+    fn invalid_command_yaml_error() {
+        let comment = "column_layout: [1, potato]".into();
+        let elements = vec![MarkdownElement::Comment { comment, source_position: SourcePosition { line: 7 } }];
+        let error = try_build_presentation(elements).err().expect("build succeeded");
+        let snippet = error.yaml_error().and_then(|error| error.snippet(1)).expect("no snippet");
+        assert_eq!(snippet.lines, &["column_layout: [1, potato]"]);
+        assert_eq!(snippet.first_line, 7);
+        assert_eq!(snippet.column, 20);
+    }
+
+    #[test]
+    fn invalid_front_matter_yaml_error() {
+        let elements = vec![MarkdownElement::FrontMatter("title: hi\nauthor: a: b".into())];
+        let error = try_build_presentation(elements).err().expect("build succeeded");
+        let snippet = error.yaml_error().and_then(|error| error.snippet(0)).expect("no snippet");
+        assert_eq!(snippet.lines, &["author: a: b"]);
+        assert_eq!(snippet.first_line, 3);
+    }
+
+    #[test]
+    fn invalid_front_matter_type_error() {
+        let elements = vec![MarkdownElement::FrontMatter("title: hi\noptions:\n  confirm_exit: nope".into())];
+        let error = try_build_presentation(elements).err().expect("build succeeded");
+        let snippet = error.yaml_error().and_then(|error| error.snippet(0)).expect("no snippet");
+        assert_eq!(snippet.lines, &["  confirm_exit: nope"]);
+        assert_eq!(snippet.first_line, 4);
+    }
+
+    #[test]
+    fn presentation_options() {
+        let elements = vec![MarkdownElement::FrontMatter("options:\n  confirm_exit: false".into())];
         let presentation = build_presentation(elements);
-        assert_eq!(presentation.iter_slides().count(), 2);
+        assert_eq!(presentation.options().confirm_exit, Some(false));
+    }
+
+    #[test]
+    fn parts() {
+        let elements = vec![
+            build_comment("part: Setup"),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())]),
+            build_end_slide(),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("bye".into())]),
+            build_end_slide(),
+            build_comment("part: Advanced"),
+        ];
+        let presentation = build_presentation(elements);
+        let parts = presentation.parts();
+        assert_eq!(
+            parts,
+            &[
+                PresentationPart { name: "Setup".into(), first_slide: 0 },
+                PresentationPart { name: "Advanced".into(), first_slide: 2 }
+            ]
+        );
+    }
+
+    #[test]
+    fn lazy_build() {
+        let mut elements = Vec::new();
+        for index in 0..5 {
+            if index > 0 {
+                elements.push(build_end_slide());
+            }
+            if index == 3 {
+                elements.push(build_comment("part: Later"));
+            }
+            elements.push(MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())]));
+        }
+        let footer = FooterStyle::Template {
+            left: Some("{current_slide}/{total_slides}".into()),
+            center: None,
+            right: None,
+            colors: Default::default(),
+        };
+        let theme = PresentationTheme { footer, ..Default::default() };
+        let footer_lines = |presentation: &Presentation| {
+            let slide = presentation.iter_slides().next().expect("no slides");
+            let Some(RenderOperation::RenderDynamic(generator)) = slide.footer.last() else {
+                panic!("no footer");
+            };
+            let dimensions = WindowSize { rows: 10, columns: 100, width: 100, height: 100 };
+            extract_text_lines(&generator.as_render_operations(&dimensions))
+        };
+        with_builder(&theme, |builder| {
+            let (mut presentation, build) = builder.build_lazily(elements, 2).expect("build failed");
+            assert_eq!(presentation.iter_slides().count(), 2);
+            assert!(presentation.parts().is_empty());
+            assert_eq!(footer_lines(&presentation), &["1/?"]);
+
+            let mut build = build.expect("no lazy build");
+            assert!(!build.build_into(&mut presentation, 1).expect("build failed"));
+            assert_eq!(presentation.iter_slides().count(), 3);
+
+            build.finish(&mut presentation).expect("build failed");
+            assert_eq!(presentation.iter_slides().count(), 5);
+            assert_eq!(presentation.parts(), &[PresentationPart { name: "Later".into(), first_slide: 3 }]);
+            assert_eq!(footer_lines(&presentation), &["1/5"]);
+        });
+    }
+
+    #[test]
+    fn slide_duration() {
+        let elements = vec![
+            build_comment("duration: 2s"),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())]),
+            build_pause(),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("bye".into())]),
+            build_end_slide(),
+            MarkdownElement::Paragraph(vec![ParagraphElement::Text("hi".into())]),
+        ];
+        let presentation = build_presentation(elements);
+        let durations: Vec<_> = presentation.iter_slides().map(|slide| slide.duration).collect();
+        let expected = Some(Duration::from_secs(2));
+        assert_eq!(durations, &[expected, None]);
     }
 }
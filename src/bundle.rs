@@ -0,0 +1,228 @@
+use crate::{
+    compat::parse_front_matter,
+    markdown::{
+        elements::MarkdownElement,
+        parse::{MarkdownParser, ParseError},
+    },
+    resource::{is_data_uri, PdfPage, Resources},
+};
+use comrak::Arena;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use log::debug;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Read, Write},
+    path::{Component, Path, PathBuf},
+};
+
+const MAGIC: &[u8] = b"PRESENTERM-BUNDLE-1\n";
+const PRESENTATION_FILE: &str = "presentation.md";
+
+/// A self-contained presentation bundle.
+///
+/// A bundle contains the presentation itself plus every file it references, like images and
+/// themes, so it can be presented on a different machine without having to copy anything else.
+///
+/// The format is a magic header followed by a gzip stream containing every file as a sequence of
+/// length prefixed path and contents pairs.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Bundle {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl Bundle {
+    /// Create a bundle out of the presentation at the given path.
+    pub fn from_presentation(path: &Path) -> Result<Self, BundleError> {
+        let contents = fs::read_to_string(path).map_err(|e| BundleError::Io(path.into(), e))?;
+        let base_path = path.parent().unwrap_or(Path::new("/"));
+        let mut resources = Resources::new(base_path);
+
+        let arena = Arena::new();
+        let elements = MarkdownParser::new(&arena).parse(&contents)?;
+        let mut referenced_paths = Vec::new();
+        for element in elements {
+            match element {
+                MarkdownElement::FrontMatter(contents) => {
+                    let (metadata, _) =
+                        parse_front_matter(&contents).map_err(|e| BundleError::InvalidMetadata(e.to_string()))?;
+                    resources.set_search_paths(metadata.resource_paths);
+                    referenced_paths.extend(metadata.theme.path.map(PathBuf::from));
+                }
+                // Images in data URIs are already part of the presentation itself.
+                MarkdownElement::Image { path, .. } if !is_data_uri(&path) => {
+                    // For pages within PDFs, the whole file is bundled.
+                    let path = PdfPage::parse(&path).map(|page| page.path).unwrap_or(path);
+                    referenced_paths.push(path);
+                }
+                _ => (),
+            };
+        }
+
+        let mut files = BTreeMap::new();
+        files.insert(PathBuf::from(PRESENTATION_FILE), contents.into_bytes());
+        for path in referenced_paths {
+            Self::validate_path(&path)?;
+            let source = resources.resolve_path(&path);
+            debug!("bundling {} from {}", path.display(), source.display());
+            let contents = fs::read(&source).map_err(|e| BundleError::Io(source, e))?;
+            files.insert(path, contents);
+        }
+        Ok(Self { files })
+    }
+
+    /// Check whether the file at the given path is a bundle.
+    pub fn is_bundle(path: &Path) -> bool {
+        let Ok(mut file) = fs::File::open(path) else {
+            return false;
+        };
+        let mut magic = [0; MAGIC.len()];
+        file.read_exact(&mut magic).is_ok() && magic == MAGIC
+    }
+
+    /// Read a bundle.
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, BundleError> {
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic).map_err(|_| BundleError::NotABundle)?;
+        if magic != MAGIC {
+            return Err(BundleError::NotABundle);
+        }
+        let mut reader = GzDecoder::new(reader);
+        let mut files = BTreeMap::new();
+        while let Some(path) = Self::read_chunk(&mut reader, true)? {
+            let path = PathBuf::from(String::from_utf8(path).map_err(|_| BundleError::Malformed)?);
+            Self::validate_path(&path)?;
+            let contents = Self::read_chunk(&mut reader, false)?.ok_or(BundleError::Malformed)?;
+            files.insert(path, contents);
+        }
+        if !files.contains_key(Path::new(PRESENTATION_FILE)) {
+            return Err(BundleError::Malformed);
+        }
+        Ok(Self { files })
+    }
+
+    /// Write this bundle.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        let mut writer = GzEncoder::new(writer, Compression::default());
+        for (path, contents) in &self.files {
+            let path = path.to_string_lossy();
+            writer.write_all(&(path.len() as u64).to_le_bytes())?;
+            writer.write_all(path.as_bytes())?;
+            writer.write_all(&(contents.len() as u64).to_le_bytes())?;
+            writer.write_all(contents)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Extract all of the files in this bundle into the given directory.
+    ///
+    /// The path to the extracted presentation is returned.
+    pub fn extract(&self, directory: &Path) -> Result<PathBuf, BundleError> {
+        for (path, contents) in &self.files {
+            let path = directory.join(path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| BundleError::Io(parent.into(), e))?;
+            }
+            fs::write(&path, contents).map_err(|e| BundleError::Io(path, e))?;
+        }
+        Ok(directory.join(PRESENTATION_FILE))
+    }
+
+    /// Get the paths of all files in this bundle.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path)
+    }
+
+    fn read_chunk<R: Read>(reader: &mut R, allow_eof: bool) -> Result<Option<Vec<u8>>, BundleError> {
+        let mut length = [0; 8];
+        match reader.read_exact(&mut length) {
+            Ok(()) => (),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof && allow_eof => return Ok(None),
+            Err(_) => return Err(BundleError::Malformed),
+        };
+        let length = u64::from_le_bytes(length);
+        let mut contents = Vec::new();
+        reader.take(length).read_to_end(&mut contents).map_err(|_| BundleError::Malformed)?;
+        if contents.len() as u64 != length {
+            return Err(BundleError::Malformed);
+        }
+        Ok(Some(contents))
+    }
+
+    fn validate_path(path: &Path) -> Result<(), BundleError> {
+        // Files are extracted using these paths so they can't point outside of the bundle.
+        let is_empty = path.components().next().is_none();
+        let is_valid = path.components().all(|component| matches!(component, Component::Normal(_)));
+        if is_valid && !is_empty { Ok(()) } else { Err(BundleError::UnsupportedPath(path.into())) }
+    }
+}
+
+/// An error creating or reading a bundle.
+#[derive(thiserror::Error, Debug)]
+pub enum BundleError {
+    #[error("io error on {0}: {1}")]
+    Io(PathBuf, io::Error),
+
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+
+    #[error("invalid metadata: {0}")]
+    InvalidMetadata(String),
+
+    #[error("path {0} must be relative to the presentation and can't contain '..'")]
+    UnsupportedPath(PathBuf),
+
+    #[error("file is not a presentation bundle")]
+    NotABundle,
+
+    #[error("malformed bundle")]
+    Malformed,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+    use tempfile::tempdir;
+
+    #[test]
+    fn round_trip() {
+        let directory = tempdir().expect("no temp dir");
+        fs::create_dir(directory.path().join("images")).unwrap();
+        fs::write(directory.path().join("images/foo.png"), b"not really an image").unwrap();
+        fs::write(directory.path().join("theme.yaml"), b"default: {}").unwrap();
+        let presentation = directory.path().join("deck.md");
+        fs::write(&presentation, "---\ntheme:\n  path: theme.yaml\n---\n\n![](images/foo.png)\n").unwrap();
+
+        let bundle = Bundle::from_presentation(&presentation).expect("bundling failed");
+        let paths: Vec<_> = bundle.paths().collect();
+        assert_eq!(paths, &[Path::new("images/foo.png"), Path::new("presentation.md"), Path::new("theme.yaml")]);
+
+        let mut serialized = Vec::new();
+        bundle.write(&mut serialized).expect("writing failed");
+        let deserialized = Bundle::read(serialized.as_slice()).expect("reading failed");
+        assert_eq!(deserialized, bundle);
+
+        let output = tempdir().expect("no temp dir");
+        let extracted = deserialized.extract(output.path()).expect("extracting failed");
+        assert_eq!(fs::read(extracted).unwrap(), fs::read(presentation).unwrap());
+        assert_eq!(fs::read(output.path().join("images/foo.png")).unwrap(), b"not really an image");
+    }
+
+    #[rstest]
+    #[case::parent("../foo.png")]
+    #[case::absolute("/tmp/foo.png")]
+    #[case::empty("")]
+    fn unsupported_paths(#[case] path: &str) {
+        let result = Bundle::validate_path(Path::new(path));
+        assert!(matches!(result, Err(BundleError::UnsupportedPath(_))), "{path}: {result:?}");
+    }
+
+    #[test]
+    fn not_a_bundle() {
+        let result = Bundle::read(b"# hello".as_slice());
+        assert!(matches!(result, Err(BundleError::NotABundle)));
+    }
+}
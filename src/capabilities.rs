@@ -0,0 +1,412 @@
+use crate::config::TerminalConfig;
+use crossterm::terminal;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use std::{env, fmt};
+
+static CAPABILITIES: OnceCell<TerminalCapabilities> = OnceCell::new();
+static CONFIG: OnceCell<TerminalConfig> = OnceCell::new();
+
+// The terminal families we know how to deal with. Anything else gets the conservative defaults.
+const KNOWN_TERMINALS: &[&str] = &[
+    "xterm",
+    "screen",
+    "tmux",
+    "kitty",
+    "alacritty",
+    "wezterm",
+    "foot",
+    "rxvt",
+    "vte",
+    "konsole",
+    "ghostty",
+    "st-",
+    "gnome",
+    "iterm",
+    "contour",
+    "mintty",
+];
+
+// The mode terminals use to report whether they support synchronized output.
+#[cfg(unix)]
+const SYNCHRONIZED_OUTPUT_MODE: u16 = 2026;
+
+/// The features supported by the terminal we're running in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TerminalCapabilities {
+    /// Whether the terminal can display 24 bit colors.
+    pub true_color: bool,
+
+    /// The protocol used to draw images.
+    pub image_protocol: ImageProtocol,
+
+    /// Whether the terminal supports synchronized output, which lets it display a whole frame at once.
+    pub synchronized_output: bool,
+
+    /// Whether the terminal supports the kitty keyboard protocol.
+    pub kitty_keyboard: bool,
+
+    /// Whether the terminal can display box drawing characters, like the ones used in table borders.
+    ///
+    /// When it can't, they're replaced with their closest ASCII counterparts.
+    pub box_drawing: bool,
+
+    /// Whether the terminal's font includes the Nerd Fonts icons.
+    ///
+    /// When it doesn't, plain text is used instead of icons.
+    pub nerd_fonts: bool,
+
+    /// The reason conservative defaults are being used, if they are.
+    pub fallback: Option<FallbackReason>,
+}
+
+impl TerminalCapabilities {
+    /// Detect the capabilities of the terminal we're running in.
+    ///
+    /// This looks at the environment and queries the terminal itself, which can take a moment when
+    /// the terminal doesn't answer some of the queries.
+    ///
+    /// Over SSH and in terminals we don't know, conservative defaults are used instead of whatever
+    /// the terminal claims to support: images are replaced with placeholders, colors are limited to
+    /// the 256 color palette, and borders are drawn using ASCII characters. Any of this can be
+    /// overridden via the given configuration.
+    pub fn detect(config: &TerminalConfig) -> Self {
+        let true_color = Self::is_true_color(env::var("COLORTERM").ok().as_deref());
+        let image_protocol = if viuer::get_kitty_support() != viuer::KittySupport::None {
+            ImageProtocol::Kitty
+        } else if viuer::is_iterm_supported() {
+            ImageProtocol::Iterm2
+        } else {
+            ImageProtocol::Blocks
+        };
+        let synchronized_output = Self::query_synchronized_output().unwrap_or(false);
+        let kitty_keyboard = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        let detected = Self {
+            true_color,
+            image_protocol,
+            synchronized_output,
+            kitty_keyboard,
+            box_drawing: true,
+            nerd_fonts: false,
+            fallback: None,
+        };
+        let fallback = Self::fallback_reason(|name| env::var(name).ok());
+        detected.with_config(fallback, config)
+    }
+
+    /// Set the configuration used when detecting capabilities via [TerminalCapabilities::current].
+    ///
+    /// This has no effect once they've been detected.
+    pub fn configure(config: TerminalConfig) {
+        let _ = CONFIG.set(config);
+    }
+
+    /// Get the capabilities of the terminal we're running in.
+    ///
+    /// These are detected the first time this is called and the same ones are used from then on.
+    pub fn current() -> &'static Self {
+        CAPABILITIES.get_or_init(|| Self::detect(CONFIG.get_or_init(Default::default)))
+    }
+
+    fn with_config(mut self, fallback: Option<FallbackReason>, config: &TerminalConfig) -> Self {
+        let fallback = match config.fallback {
+            Some(true) => Some(FallbackReason::Config),
+            Some(false) => None,
+            None => fallback,
+        };
+        if fallback.is_some() {
+            self.true_color = false;
+            self.image_protocol = ImageProtocol::None;
+            self.box_drawing = false;
+            self.fallback = fallback;
+        }
+        self.true_color = config.true_color.unwrap_or(self.true_color);
+        self.image_protocol = config.image_protocol.unwrap_or(self.image_protocol);
+        self.box_drawing = config.box_drawing.unwrap_or(self.box_drawing);
+        self.nerd_fonts = config.nerd_fonts.unwrap_or(self.nerd_fonts);
+        self
+    }
+
+    fn fallback_reason<F: Fn(&str) -> Option<String>>(variable: F) -> Option<FallbackReason> {
+        let is_set = |name| variable(name).is_some_and(|value| !value.is_empty());
+        if is_set("SSH_CONNECTION") || is_set("SSH_CLIENT") || is_set("SSH_TTY") {
+            return Some(FallbackReason::Ssh);
+        }
+        let term = variable("TERM").unwrap_or_default();
+        let term_program = variable("TERM_PROGRAM").unwrap_or_default().to_lowercase();
+        let is_known = |name: &str| KNOWN_TERMINALS.iter().any(|known| name.starts_with(known));
+        match is_known(&term) || is_known(&term_program) {
+            true => None,
+            false => Some(FallbackReason::UnknownTerminal(term)),
+        }
+    }
+
+    fn is_true_color(colorterm: Option<&str>) -> bool {
+        colorterm.is_some_and(|value| value.contains("truecolor") || value.contains("24bit"))
+    }
+
+    #[cfg(unix)]
+    fn query_synchronized_output() -> std::io::Result<bool> {
+        // Every terminal answers the primary device attributes query so it tells us when to stop
+        // waiting for an answer to the first one.
+        let query = format!("\x1b[?{SYNCHRONIZED_OUTPUT_MODE}$p\x1b[c");
+        let response = query::send(query.as_bytes())?;
+        Ok(query::parse_mode_report(&response, SYNCHRONIZED_OUTPUT_MODE).unwrap_or(false))
+    }
+
+    #[cfg(not(unix))]
+    fn query_synchronized_output() -> std::io::Result<bool> {
+        Ok(false)
+    }
+}
+
+impl fmt::Display for TerminalCapabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let yes_no = |value| if value { "yes" } else { "no" };
+        writeln!(f, "true color:          {}", yes_no(self.true_color))?;
+        writeln!(f, "image protocol:      {}", self.image_protocol)?;
+        writeln!(f, "synchronized output: {}", yes_no(self.synchronized_output))?;
+        writeln!(f, "kitty keyboard:      {}", yes_no(self.kitty_keyboard))?;
+        writeln!(f, "box drawing:         {}", yes_no(self.box_drawing))?;
+        writeln!(f, "nerd fonts:          {}", yes_no(self.nerd_fonts))?;
+        match &self.fallback {
+            Some(reason) => writeln!(f, "fallback:            yes, {reason}"),
+            None => writeln!(f, "fallback:            no"),
+        }
+    }
+}
+
+/// The reason conservative defaults are used instead of what the terminal claims to support.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FallbackReason {
+    /// We're running over SSH.
+    Ssh,
+
+    /// The terminal, identified by its `TERM`, isn't one we know.
+    UnknownTerminal(String),
+
+    /// The configuration asks for it.
+    Config,
+}
+
+impl fmt::Display for FallbackReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ssh => write!(f, "running over ssh"),
+            Self::UnknownTerminal(term) if term.is_empty() => write!(f, "TERM is not set"),
+            Self::UnknownTerminal(term) => write!(f, "unknown terminal '{term}'"),
+            Self::Config => write!(f, "enabled in config"),
+        }
+    }
+}
+
+/// The protocol used to draw images.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageProtocol {
+    /// The kitty graphics protocol.
+    Kitty,
+
+    /// The iTerm2 inline images protocol.
+    Iterm2,
+
+    /// Unicode half blocks, which work everywhere but look blurry.
+    Blocks,
+
+    /// Images aren't drawn, a placeholder is drawn in their place instead.
+    None,
+}
+
+impl fmt::Display for ImageProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Kitty => "kitty",
+            Self::Iterm2 => "iterm2",
+            Self::Blocks => "blocks",
+            Self::None => "none",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[cfg(unix)]
+mod query {
+    use crossterm::terminal;
+    use std::{
+        fs::{File, OpenOptions},
+        io::{self, Read, Write},
+        os::fd::AsRawFd,
+        time::{Duration, Instant},
+    };
+
+    const TIMEOUT: Duration = Duration::from_millis(500);
+
+    /// Send a query to the terminal and read its response.
+    ///
+    /// The query must end with a primary device attributes query, as reading stops as soon as the
+    /// response to it arrives.
+    pub(super) fn send(query: &[u8]) -> io::Result<Vec<u8>> {
+        let mut tty = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+        let raw_mode = terminal::is_raw_mode_enabled()?;
+        if !raw_mode {
+            terminal::enable_raw_mode()?;
+        }
+        let response = tty.write_all(query).and_then(|_| tty.flush()).and_then(|_| read_response(&mut tty));
+        if !raw_mode {
+            terminal::disable_raw_mode()?;
+        }
+        response
+    }
+
+    fn read_response(tty: &mut File) -> io::Result<Vec<u8>> {
+        let deadline = Instant::now() + TIMEOUT;
+        let mut response = Vec::new();
+        while !has_device_attributes(&response) {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let mut fd = libc::pollfd { fd: tty.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+            let ready = unsafe { libc::poll(&mut fd, 1, remaining.as_millis() as libc::c_int) };
+            if ready <= 0 {
+                break;
+            }
+            let mut buffer = [0; 64];
+            match tty.read(&mut buffer)? {
+                0 => break,
+                length => response.extend_from_slice(&buffer[..length]),
+            };
+        }
+        Ok(response)
+    }
+
+    // Checks whether a response contains the primary device attributes: `ESC [ ? <values> c`.
+    pub(super) fn has_device_attributes(response: &[u8]) -> bool {
+        find_sequences(response, b"\x1b[?").any(|sequence| {
+            let end = sequence.iter().position(|c| !c.is_ascii_digit() && *c != b';');
+            end.is_some_and(|end| sequence[end] == b'c')
+        })
+    }
+
+    /// Parse the response to a DECRQM query for the given mode: `ESC [ ? <mode> ; <value> $ y`.
+    ///
+    /// Returns `None` if there's no answer for that mode in the response.
+    pub(super) fn parse_mode_report(response: &[u8], mode: u16) -> Option<bool> {
+        let prefix = format!("\x1b[?{mode};");
+        let sequence = find_sequences(response, prefix.as_bytes()).next()?;
+        match sequence {
+            // The mode is recognized and either set or reset.
+            [b'1' | b'2', b'$', b'y', ..] => Some(true),
+            _ => Some(false),
+        }
+    }
+
+    // Finds every sequence that starts with the given prefix, returning what comes after it.
+    fn find_sequences<'a>(response: &'a [u8], prefix: &'a [u8]) -> impl Iterator<Item = &'a [u8]> {
+        (0..response.len())
+            .filter(move |index| response[*index..].starts_with(prefix))
+            .map(move |index| &response[index + prefix.len()..])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::truecolor(Some("truecolor"), true)]
+    #[case::bits(Some("24bit"), true)]
+    #[case::other(Some("yes"), false)]
+    #[case::unset(None, false)]
+    fn true_color(#[case] colorterm: Option<&str>, #[case] expected: bool) {
+        assert_eq!(TerminalCapabilities::is_true_color(colorterm), expected);
+    }
+
+    #[cfg(unix)]
+    #[rstest]
+    #[case::set(b"\x1b[?2026;1$y\x1b[?62;22c", Some(true))]
+    #[case::reset(b"\x1b[?2026;2$y\x1b[?62;22c", Some(true))]
+    #[case::unrecognized(b"\x1b[?2026;0$y\x1b[?62;22c", Some(false))]
+    #[case::permanently_reset(b"\x1b[?2026;4$y\x1b[?62;22c", Some(false))]
+    #[case::no_answer(b"\x1b[?62;22c", None)]
+    fn mode_report(#[case] response: &[u8], #[case] expected: Option<bool>) {
+        assert_eq!(query::parse_mode_report(response, SYNCHRONIZED_OUTPUT_MODE), expected);
+    }
+
+    #[cfg(unix)]
+    #[rstest]
+    #[case::attributes(b"\x1b[?62;22c", true)]
+    #[case::after_report(b"\x1b[?2026;1$y\x1b[?1c", true)]
+    #[case::only_report(b"\x1b[?2026;1$y", false)]
+    #[case::partial(b"\x1b[?62;2", false)]
+    fn device_attributes(#[case] response: &[u8], #[case] expected: bool) {
+        assert_eq!(query::has_device_attributes(response), expected);
+    }
+
+    fn sample_capabilities() -> TerminalCapabilities {
+        TerminalCapabilities {
+            true_color: true,
+            image_protocol: ImageProtocol::Kitty,
+            synchronized_output: false,
+            kitty_keyboard: true,
+            box_drawing: true,
+            nerd_fonts: false,
+            fallback: None,
+        }
+    }
+
+    #[rstest]
+    #[case::known(&[("TERM", "xterm-256color")], None)]
+    #[case::known_program(&[("TERM", "potato"), ("TERM_PROGRAM", "WezTerm")], None)]
+    #[case::ssh(&[("TERM", "xterm-kitty"), ("SSH_CONNECTION", "1.2.3.4 5 6.7.8.9 22")], Some(FallbackReason::Ssh))]
+    #[case::unknown(&[("TERM", "potato")], Some(FallbackReason::UnknownTerminal("potato".into())))]
+    #[case::dumb(&[("TERM", "dumb")], Some(FallbackReason::UnknownTerminal("dumb".into())))]
+    #[case::unset(&[], Some(FallbackReason::UnknownTerminal("".into())))]
+    fn fallback_reason(#[case] variables: &[(&str, &str)], #[case] expected: Option<FallbackReason>) {
+        let variable =
+            |name: &str| variables.iter().find(|(variable, _)| *variable == name).map(|(_, value)| value.to_string());
+        assert_eq!(TerminalCapabilities::fallback_reason(variable), expected);
+    }
+
+    #[test]
+    fn fallback() {
+        let capabilities = sample_capabilities().with_config(Some(FallbackReason::Ssh), &Default::default());
+        assert!(!capabilities.true_color);
+        assert_eq!(capabilities.image_protocol, ImageProtocol::None);
+        assert!(!capabilities.box_drawing);
+        assert!(capabilities.kitty_keyboard);
+        assert_eq!(capabilities.fallback, Some(FallbackReason::Ssh));
+    }
+
+    #[test]
+    fn config_overrides() {
+        let config = TerminalConfig { image_protocol: Some(ImageProtocol::Blocks), ..Default::default() };
+        let capabilities = sample_capabilities().with_config(Some(FallbackReason::Ssh), &config);
+        assert_eq!(capabilities.image_protocol, ImageProtocol::Blocks);
+        assert!(!capabilities.box_drawing);
+
+        let config = TerminalConfig { fallback: Some(false), ..Default::default() };
+        assert_eq!(sample_capabilities().with_config(Some(FallbackReason::Ssh), &config), sample_capabilities());
+
+        let config = TerminalConfig { fallback: Some(true), true_color: Some(true), ..Default::default() };
+        let capabilities = sample_capabilities().with_config(None, &config);
+        assert!(capabilities.true_color);
+        assert_eq!(capabilities.fallback, Some(FallbackReason::Config));
+
+        let config = TerminalConfig { nerd_fonts: Some(true), ..Default::default() };
+        assert!(sample_capabilities().with_config(Some(FallbackReason::Ssh), &config).nerd_fonts);
+    }
+
+    #[test]
+    fn report() {
+        let capabilities = sample_capabilities();
+        let expected = "\
+true color:          yes
+image protocol:      kitty
+synchronized output: no
+kitty keyboard:      yes
+box drawing:         yes
+nerd fonts:          no
+fallback:            no
+";
+        assert_eq!(capabilities.to_string(), expected);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::{presentation::PresentationMetadata, theme::PresentationTheme, yaml::YamlError};
+use serde_yaml::{Mapping, Value};
+
+// Keys used by Marp and reveal-md that have no presenterm equivalent.
+const UNSUPPORTED_KEYS: &[&str] = &[
+    "backgroundColor",
+    "backgroundImage",
+    "class",
+    "color",
+    "css",
+    "header",
+    "headingDivider",
+    "highlightTheme",
+    "math",
+    "preprocessor",
+    "revealOptions",
+    "scripts",
+    "separator",
+    "size",
+    "style",
+    "verticalSeparator",
+];
+
+/// Parse a presentation's front matter.
+///
+/// Front matter keys coming from other presentation tools are translated into their presenterm
+/// equivalent when there is one. A warning is returned for every key that had to be ignored.
+pub fn parse_front_matter(contents: &str) -> Result<(PresentationMetadata, Vec<String>), YamlError> {
+    let mut front_matter: Value = serde_yaml::from_str(contents).map_err(|e| YamlError::new(e, contents))?;
+    let warnings = match front_matter.as_mapping_mut() {
+        Some(mapping) => translate_front_matter(mapping),
+        None => Vec::new(),
+    };
+    let metadata = serde_yaml::from_value(front_matter).map_err(|e| {
+        // Errors coming out of a value don't point anywhere in the document, so parse the document
+        // itself to locate them. That one's only used if it fails the same way, as the keys that
+        // weren't translated could make it fail for some unrelated reason.
+        let message = e.to_string();
+        let located = serde_yaml::from_str::<PresentationMetadata>(contents)
+            .err()
+            .filter(|located| located.location().is_some() && located.to_string().contains(&message));
+        YamlError::new(located.unwrap_or(e), contents)
+    })?;
+    Ok((metadata, warnings))
+}
+
+fn translate_front_matter(mapping: &mut Mapping) -> Vec<String> {
+    let mut warnings = Vec::new();
+    // This only tells marp to process the file, we don't need it.
+    mapping.remove("marp");
+
+    if let Some(Value::String(name)) = mapping.get("theme") {
+        let name = name.clone();
+        mapping.remove("theme");
+        if PresentationTheme::from_name(&name).is_some() {
+            mapping.insert("theme".into(), Value::Mapping(Mapping::from_iter([("name".into(), name.into())])));
+        } else {
+            warnings.push(format!("theme '{name}' is not a presenterm theme, ignoring it"));
+        }
+    }
+
+    let paginate = mapping.remove("paginate");
+    let footer = mapping.remove("footer");
+    match (paginate, footer) {
+        (None, None) => (),
+        (Some(Value::Bool(false)), None) => set_footer_override(mapping, &mut warnings, [("style", "empty")]),
+        (paginate, footer) => {
+            let mut footer_style = vec![("style", "template".into())];
+            if let Some(Value::String(text)) = footer {
+                footer_style.push(("center", text));
+            }
+            if let Some(Value::Bool(true)) = paginate {
+                footer_style.push(("right", "{current_slide} / {total_slides}".into()));
+            }
+            set_footer_override(mapping, &mut warnings, footer_style);
+        }
+    };
+
+    for key in UNSUPPORTED_KEYS {
+        if mapping.remove(*key).is_some() {
+            warnings.push(format!("front matter key '{key}' is not supported, ignoring it"));
+        }
+    }
+    warnings
+}
+
+fn set_footer_override<I, V>(mapping: &mut Mapping, warnings: &mut Vec<String>, footer: I)
+where
+    I: IntoIterator<Item = (&'static str, V)>,
+    V: Into<Value>,
+{
+    let footer = Value::Mapping(footer.into_iter().map(|(key, value)| (key.into(), value.into())).collect());
+    let theme = mapping.entry("theme".into()).or_insert_with(|| Value::Mapping(Mapping::new()));
+    let Some(theme) = theme.as_mapping_mut() else {
+        return;
+    };
+    let overrides = theme.entry("override".into()).or_insert_with(|| Value::Mapping(Mapping::new()));
+    let Some(overrides) = overrides.as_mapping_mut() else {
+        return;
+    };
+    if overrides.contains_key("footer") {
+        warnings.push("footer is already set in the theme override, ignoring 'paginate' and 'footer'".into());
+        return;
+    }
+    overrides.insert("footer".into(), footer);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::theme::FooterStyle;
+    use rstest::rstest;
+
+    #[test]
+    fn presenterm_front_matter() {
+        let (metadata, warnings) = parse_front_matter("title: hi\ntheme:\n  name: dark").expect("parse failed");
+        assert_eq!(metadata.title.as_deref(), Some("hi"));
+        assert_eq!(metadata.theme.name.as_deref(), Some("dark"));
+        assert!(warnings.is_empty());
+    }
+
+    #[rstest]
+    #[case::known("theme: dark", Some("dark"), 0)]
+    #[case::unknown("theme: gaia", None, 1)]
+    fn theme_name(#[case] input: &str, #[case] expected: Option<&str>, #[case] warning_count: usize) {
+        let (metadata, warnings) = parse_front_matter(input).expect("parse failed");
+        assert_eq!(metadata.theme.name.as_deref(), expected);
+        assert_eq!(warnings.len(), warning_count);
+    }
+
+    #[test]
+    fn paginate_and_footer() {
+        let input = "marp: true\npaginate: true\nfooter: my talk\nclass: invert";
+        let (metadata, warnings) = parse_front_matter(input).expect("parse failed");
+        let footer = metadata.theme.overrides.expect("no overrides").footer;
+        let FooterStyle::Template { left, center, right, .. } = footer else { panic!("not a template: {footer:?}") };
+        assert_eq!(left, None);
+        assert_eq!(center.as_deref(), Some("my talk"));
+        assert_eq!(right.as_deref(), Some("{current_slide} / {total_slides}"));
+        assert_eq!(warnings, &["front matter key 'class' is not supported, ignoring it"]);
+    }
+
+    #[test]
+    fn no_pagination() {
+        let (metadata, _) = parse_front_matter("paginate: false").expect("parse failed");
+        let footer = metadata.theme.overrides.expect("no overrides").footer;
+        assert!(matches!(footer, FooterStyle::Empty), "{footer:?}");
+    }
+}
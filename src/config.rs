@@ -0,0 +1,347 @@
+use crate::{
+    capabilities::ImageProtocol, input::bindings::KeyBinding, presentation::Watermark, theme::FooterStyle,
+    yaml::YamlError,
+};
+use serde::Deserialize;
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The user's configuration.
+///
+/// This is loaded from `$XDG_CONFIG_HOME/presenterm/config.yaml`, falling back to
+/// `~/.config/presenterm/config.yaml`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// The defaults used when creating new presentations.
+    #[serde(default)]
+    pub new: NewPresentationConfig,
+
+    /// How videos are played.
+    #[serde(default)]
+    pub video: VideoConfig,
+
+    /// Overrides for the features detected in the terminal.
+    #[serde(default)]
+    pub terminal: TerminalConfig,
+
+    /// The smallest terminal presentations are drawn in.
+    #[serde(default)]
+    pub minimum_size: MinimumSize,
+
+    /// The keys used to navigate presentations.
+    #[serde(default)]
+    pub bindings: KeyBindingsConfig,
+
+    /// The watermark drawn on every presentation that doesn't set its own.
+    #[serde(default)]
+    pub watermark: Option<Watermark>,
+}
+
+impl Config {
+    /// Load the configuration from the given path.
+    ///
+    /// A missing file is not an error: the default configuration is used instead.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        Self::parse(&contents)
+    }
+
+    /// Parse a configuration file's contents.
+    pub fn parse(contents: &str) -> Result<Self, ConfigError> {
+        if contents.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        serde_yaml::from_str(contents).map_err(|e| ConfigError::Invalid(YamlError::new(e, contents)))
+    }
+
+    /// The path the configuration is loaded from by default.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_home = match env::var_os("XDG_CONFIG_HOME") {
+            Some(path) if !path.is_empty() => PathBuf::from(path),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        Some(config_home.join("presenterm").join("config.yaml"))
+    }
+}
+
+/// The defaults used when creating new presentations.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NewPresentationConfig {
+    /// The author of every new presentation.
+    #[serde(default)]
+    pub author: Option<String>,
+
+    /// The name of the theme new presentations use.
+    #[serde(default)]
+    pub theme: Option<String>,
+
+    /// The footer new presentations use.
+    #[serde(default)]
+    pub footer: Option<FooterStyle>,
+}
+
+/// How videos are played.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VideoConfig {
+    /// The command used to play videos, followed by its arguments.
+    ///
+    /// The videos are appended at the end of it. This defaults to starting `mpv` paused and in full screen.
+    #[serde(default)]
+    pub player: Option<Vec<String>>,
+}
+
+impl VideoConfig {
+    /// Get the command used to play videos.
+    pub fn player(&self) -> Vec<String> {
+        match &self.player {
+            Some(player) if !player.is_empty() => player.clone(),
+            _ => ["mpv", "--pause", "--fullscreen"].map(String::from).to_vec(),
+        }
+    }
+}
+
+/// Overrides for the features detected in the terminal.
+///
+/// Anything that's not set is detected automatically.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TerminalConfig {
+    /// Whether to use conservative defaults rather than what the terminal claims to support.
+    ///
+    /// By default these are used over SSH and in terminals that aren't known.
+    #[serde(default)]
+    pub fallback: Option<bool>,
+
+    /// Whether the terminal can display 24 bit colors.
+    #[serde(default)]
+    pub true_color: Option<bool>,
+
+    /// The protocol used to draw images.
+    #[serde(default)]
+    pub image_protocol: Option<ImageProtocol>,
+
+    /// Whether the terminal can display box drawing characters.
+    #[serde(default)]
+    pub box_drawing: Option<bool>,
+
+    /// Whether the terminal's font includes the Nerd Fonts icons.
+    ///
+    /// This can't be detected so icons are only used when this is set.
+    #[serde(default)]
+    pub nerd_fonts: Option<bool>,
+}
+
+/// The smallest terminal presentations are drawn in.
+///
+/// A placeholder explaining why nothing is drawn is displayed in smaller terminals.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct MinimumSize {
+    /// The minimum number of columns.
+    #[serde(default = "default_minimum_columns")]
+    pub columns: u16,
+
+    /// The minimum number of rows.
+    #[serde(default = "default_minimum_rows")]
+    pub rows: u16,
+}
+
+impl Default for MinimumSize {
+    fn default() -> Self {
+        Self { columns: default_minimum_columns(), rows: default_minimum_rows() }
+    }
+}
+
+fn default_minimum_columns() -> u16 {
+    40
+}
+
+fn default_minimum_rows() -> u16 {
+    10
+}
+
+/// The keys used to navigate presentations.
+///
+/// Every command can be bound to any number of key sequences. Any command that's not set keeps its
+/// default bindings.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct KeyBindingsConfig {
+    /// The bindings to jump to the next slide, or the next pause in it.
+    #[serde(default = "default_next_bindings")]
+    pub next: Vec<KeyBinding>,
+
+    /// The bindings to jump to the previous slide, or the previous pause in it.
+    #[serde(default = "default_previous_bindings")]
+    pub previous: Vec<KeyBinding>,
+
+    /// The bindings to jump to the next slide, skipping any pauses left in the current one.
+    #[serde(default = "default_next_full_bindings")]
+    pub next_full: Vec<KeyBinding>,
+
+    /// The bindings to jump to the previous slide with all of its pauses revealed.
+    #[serde(default = "default_previous_full_bindings")]
+    pub previous_full: Vec<KeyBinding>,
+
+    /// The bindings to jump to the first slide.
+    #[serde(default = "default_first_slide_bindings")]
+    pub first_slide: Vec<KeyBinding>,
+
+    /// The bindings to jump to the last slide.
+    #[serde(default = "default_last_slide_bindings")]
+    pub last_slide: Vec<KeyBinding>,
+
+    /// The bindings to jump to a specific slide.
+    ///
+    /// These need to contain `<number>`, which is the number of the slide to jump to.
+    #[serde(default = "default_go_to_slide_bindings")]
+    pub go_to_slide: Vec<KeyBinding>,
+
+    /// The bindings to exit the presentation.
+    #[serde(default = "default_exit_bindings")]
+    pub exit: Vec<KeyBinding>,
+}
+
+impl Default for KeyBindingsConfig {
+    fn default() -> Self {
+        Self {
+            next: default_next_bindings(),
+            previous: default_previous_bindings(),
+            next_full: default_next_full_bindings(),
+            previous_full: default_previous_full_bindings(),
+            first_slide: default_first_slide_bindings(),
+            last_slide: default_last_slide_bindings(),
+            go_to_slide: default_go_to_slide_bindings(),
+            exit: default_exit_bindings(),
+        }
+    }
+}
+
+fn make_bindings<const N: usize>(bindings: [&str; N]) -> Vec<KeyBinding> {
+    bindings.into_iter().map(|binding| binding.parse().expect("invalid default binding")).collect()
+}
+
+fn default_next_bindings() -> Vec<KeyBinding> {
+    make_bindings(["l", "j", "<right>", "<down>", "<space>"])
+}
+
+fn default_previous_bindings() -> Vec<KeyBinding> {
+    make_bindings(["h", "k", "<left>", "<up>"])
+}
+
+fn default_next_full_bindings() -> Vec<KeyBinding> {
+    make_bindings(["<page_down>"])
+}
+
+fn default_previous_full_bindings() -> Vec<KeyBinding> {
+    make_bindings(["<page_up>"])
+}
+
+fn default_first_slide_bindings() -> Vec<KeyBinding> {
+    make_bindings(["gg"])
+}
+
+fn default_last_slide_bindings() -> Vec<KeyBinding> {
+    make_bindings(["G"])
+}
+
+fn default_go_to_slide_bindings() -> Vec<KeyBinding> {
+    make_bindings(["<number>G"])
+}
+
+fn default_exit_bindings() -> Vec<KeyBinding> {
+    make_bindings(["q", "<c-c>"])
+}
+
+/// An error loading the configuration.
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigError {
+    #[error("io: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("invalid config: {0}")]
+    Invalid(YamlError),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{presentation::WatermarkPosition, style::Color};
+
+    #[test]
+    fn parse() {
+        let contents = r#"
+new:
+  author: Jane
+  theme: light
+  footer:
+    style: template
+    right: "{current_slide}"
+"#;
+        let config = Config::parse(contents).expect("parse failed");
+        assert_eq!(config.new.author.as_deref(), Some("Jane"));
+        assert_eq!(config.new.theme.as_deref(), Some("light"));
+        assert!(matches!(config.new.footer, Some(FooterStyle::Template { .. })));
+    }
+
+    #[test]
+    fn video_player() {
+        let config = Config::parse("video:\n  player: [vlc, --fullscreen]").expect("parse failed");
+        assert_eq!(config.video.player(), &["vlc", "--fullscreen"]);
+        assert_eq!(Config::default().video.player(), &["mpv", "--pause", "--fullscreen"]);
+    }
+
+    #[test]
+    fn minimum_size() {
+        let config = Config::parse("minimum_size:\n  columns: 80").expect("parse failed");
+        assert_eq!(config.minimum_size, MinimumSize { columns: 80, rows: 10 });
+        assert_eq!(Config::default().minimum_size, MinimumSize { columns: 40, rows: 10 });
+    }
+
+    #[test]
+    fn terminal() {
+        let config = Config::parse("terminal:\n  fallback: false\n  image_protocol: none").expect("parse failed");
+        assert_eq!(config.terminal.fallback, Some(false));
+        assert_eq!(config.terminal.image_protocol, Some(ImageProtocol::None));
+        assert!(config.terminal.box_drawing.is_none());
+    }
+
+    #[test]
+    fn bindings() {
+        let config = Config::parse("bindings:\n  next: [n, \"<c-n>\"]\n  exit: [ZZ]").expect("parse failed");
+        assert_eq!(config.bindings.next, make_bindings(["n", "<c-n>"]));
+        assert_eq!(config.bindings.exit, make_bindings(["ZZ"]));
+        assert_eq!(config.bindings.previous, default_previous_bindings());
+
+        assert!(Config::parse("bindings:\n  next: [\"<potato>\"]").is_err());
+    }
+
+    #[test]
+    fn watermark() {
+        let config = Config::parse("watermark:\n  text: DRAFT\n  position: top_right").expect("parse failed");
+        let watermark = config.watermark.expect("no watermark");
+        assert_eq!(watermark.text, "DRAFT");
+        assert_eq!(watermark.position, WatermarkPosition::TopRight);
+        assert_eq!(watermark.color(), Color::new(100, 100, 100));
+    }
+
+    #[test]
+    fn empty() {
+        let config = Config::parse("").expect("parse failed");
+        assert!(config.new.author.is_none());
+    }
+
+    #[test]
+    fn unknown_keys() {
+        assert!(Config::parse("potato: 42").is_err());
+    }
+}
@@ -27,6 +27,39 @@ impl PresentationDiffer {
             Ordering::Greater => Some(total_updated.saturating_sub(1)),
         }
     }
+
+    /// Carry over the state of every slide in the original presentation that is unchanged in the
+    /// updated one.
+    ///
+    /// This currently keeps the output of any code that was executed in those slides. Slides are
+    /// matched both from the start and from the end of the presentation so that adding or removing
+    /// a slide doesn't discard the state of the ones after it.
+    ///
+    /// Returns the number of slides whose state was carried over.
+    pub fn carry_over_state(original: &Presentation, updated: &mut Presentation) -> usize {
+        let original: Vec<_> = original.iter_slides().collect();
+        let total_updated = updated.iter_slides().count();
+        let mut carried_over = 0;
+        for (index, slide) in updated.iter_slides_mut().enumerate() {
+            let from_end = original.len().checked_sub(total_updated - index).map(|index| original[index]);
+            let candidates = [original.get(index).copied(), from_end];
+            if let Some(original) = candidates.into_iter().flatten().find(|original| original.is_identical(slide)) {
+                Self::carry_over_slide_state(original, slide);
+                carried_over += 1;
+            }
+        }
+        carried_over
+    }
+
+    // Both slides are expected to be identical so their operations match one to one.
+    fn carry_over_slide_state(original: &Slide, updated: &mut Slide) {
+        let operations = original.iter_operations().zip(updated.iter_operations_mut());
+        for (original, updated) in operations {
+            if let RenderOperation::RenderOnDemand(original) = original {
+                *updated = RenderOperation::RenderOnDemand(original.clone());
+            }
+        }
+    }
 }
 
 trait ContentDiff {
@@ -35,7 +68,10 @@ trait ContentDiff {
 
 impl ContentDiff for Slide {
     fn is_content_different(&self, other: &Self) -> bool {
-        self.render_operations.iter().is_content_different(&other.render_operations.iter())
+        // Moving a pause around doesn't change the contents so chunks are compared as a whole.
+        let lhs: Vec<_> = self.iter_operations().collect();
+        let rhs: Vec<_> = other.iter_operations().collect();
+        lhs.len() != rhs.len() || lhs.into_iter().zip(rhs).any(|(lhs, rhs)| lhs.is_content_different(rhs))
     }
 }
 
@@ -58,10 +94,79 @@ impl ContentDiff for RenderOperation {
             {
                 false
             }
-            (RenderImage(original), RenderImage(updated)) if original != updated => true,
+            (RenderImage(original, original_properties), RenderImage(updated, updated_properties))
+                if original != updated || original_properties != updated_properties =>
+            {
+                true
+            }
             (RenderPreformattedLine(original), RenderPreformattedLine(updated)) if original != updated => true,
-            // This is only used for footers which are global. Ignore for now.
-            (RenderDynamic(_), RenderDynamic(_)) => false,
+            (RenderBlockLine(original), RenderBlockLine(updated)) if original.text != updated.text => true,
+            // Footers are global so only things like highlighted code steps have content to compare.
+            (RenderDynamic(original), RenderDynamic(updated)) => {
+                original.diffable_content() != updated.diffable_content()
+            }
+            // The code these run can change without anything being displayed differently.
+            (RenderOnDemand(original), RenderOnDemand(updated)) => {
+                original.diffable_content() != updated.diffable_content()
+            }
+            _ => false,
+        }
+    }
+}
+
+// Unlike [ContentDiff], this takes every difference into account, including style changes.
+trait Identical {
+    fn is_identical(&self, other: &Self) -> bool;
+}
+
+impl Identical for Slide {
+    fn is_identical(&self, other: &Self) -> bool {
+        let chunk_lengths =
+            |slide: &Slide| -> Vec<_> { slide.chunks.iter().map(|chunk| chunk.operations.len()).collect() };
+        chunk_lengths(self) == chunk_lengths(other)
+            && self.footer.len() == other.footer.len()
+            && self.iter_operations().zip(other.iter_operations()).all(|(lhs, rhs)| lhs.is_identical(rhs))
+    }
+}
+
+impl Identical for RenderOperation {
+    fn is_identical(&self, other: &Self) -> bool {
+        use RenderOperation::*;
+        match (self, other) {
+            (ClearScreen, ClearScreen)
+            | (JumpToVerticalCenter, JumpToVerticalCenter)
+            | (JumpToBottom, JumpToBottom)
+            | (RenderLineBreak, RenderLineBreak)
+            | (ExitLayout, ExitLayout)
+            | (PopMargin, PopMargin) => true,
+            (SetColors(original), SetColors(updated)) => original == updated,
+            (SetLineSize(original), SetLineSize(updated)) => original == updated,
+            (JumpToBottomRow { index: original }, JumpToBottomRow { index: updated }) => original == updated,
+            (
+                RenderTextLine { line: original_line, alignment: original_alignment },
+                RenderTextLine { line: updated_line, alignment: updated_alignment },
+            ) => original_line == updated_line && original_alignment == updated_alignment,
+            (RenderSeparator(original), RenderSeparator(updated)) => original == updated,
+            (RenderImage(original, original_properties), RenderImage(updated, updated_properties)) => {
+                original == updated && original_properties == updated_properties
+            }
+            (RenderPreformattedLine(original), RenderPreformattedLine(updated)) => original == updated,
+            (RenderBlockLine(original), RenderBlockLine(updated)) => original == updated,
+            (RenderDynamic(original), RenderDynamic(updated)) => {
+                original.diffable_content() == updated.diffable_content()
+            }
+            // Keeping the state of code that changed would run the old code, even if the change
+            // isn't visible, like in hidden setup code.
+            (RenderOnDemand(original), RenderOnDemand(updated)) => {
+                original.diffable_content() == updated.diffable_content()
+            }
+            (InitColumnLayout { columns: original }, InitColumnLayout { columns: updated }) => original == updated,
+            (EnterColumn { column: original }, EnterColumn { column: updated }) => original == updated,
+            (ApplyMargin(original), ApplyMargin(updated)) => {
+                original.horizontal_margin == updated.horizontal_margin
+                    && original.bottom_slide_margin == updated.bottom_slide_margin
+                    && original.max_columns == updated.max_columns
+            }
             _ => false,
         }
     }
@@ -89,13 +194,14 @@ where
 mod test {
     use super::*;
     use crate::{
-        presentation::{AsRenderOperations, PreformattedLine},
+        input::source::Command,
+        presentation::{AsRenderOperations, BlockLine, PreformattedLine, RenderOnDemand, SlideChunk},
         render::properties::WindowSize,
         style::{Color, Colors},
         theme::{Alignment, Margin},
     };
     use rstest::rstest;
-    use std::rc::Rc;
+    use std::{rc::Rc, sync::mpsc::Sender};
 
     #[derive(Debug)]
     struct Dynamic;
@@ -110,7 +216,8 @@ mod test {
     #[case(RenderOperation::ClearScreen)]
     #[case(RenderOperation::JumpToVerticalCenter)]
     #[case(RenderOperation::JumpToBottom)]
-    #[case(RenderOperation::RenderSeparator)]
+    #[case(RenderOperation::JumpToBottomRow { index: 1 })]
+    #[case(RenderOperation::RenderSeparator(Default::default()))]
     #[case(RenderOperation::RenderLineBreak)]
     #[case(RenderOperation::SetColors(Colors{background: None, foreground: None}))]
     #[case(RenderOperation::RenderTextLine{line: String::from("asd").into(), alignment: Default::default()})]
@@ -122,6 +229,14 @@ mod test {
             unformatted_length: 1337
         }
     ))]
+    #[case(RenderOperation::RenderBlockLine(
+        BlockLine{
+            prefix: "> ".into(),
+            text: String::from("asd").into(),
+            alignment: Default::default(),
+            block_length: 42,
+        }
+    ))]
     #[case(RenderOperation::RenderDynamic(Rc::new(Dynamic)))]
     fn same_not_modified(#[case] operation: RenderOperation) {
         let diff = operation.is_content_different(&operation);
@@ -158,9 +273,9 @@ mod test {
     #[test]
     fn no_slide_changes() {
         let presentation = Presentation::new(vec![
-            Slide { render_operations: vec![RenderOperation::JumpToBottom] },
-            Slide { render_operations: vec![RenderOperation::JumpToBottom] },
-            Slide { render_operations: vec![RenderOperation::JumpToBottom] },
+            Slide::new(vec![RenderOperation::JumpToBottom]),
+            Slide::new(vec![RenderOperation::JumpToBottom]),
+            Slide::new(vec![RenderOperation::JumpToBottom]),
         ]);
         assert_eq!(PresentationDiffer::first_modified_slide(&presentation, &presentation), None);
     }
@@ -168,20 +283,20 @@ mod test {
     #[test]
     fn slides_truncated() {
         let lhs = Presentation::new(vec![
-            Slide { render_operations: vec![RenderOperation::JumpToBottom] },
-            Slide { render_operations: vec![RenderOperation::JumpToBottom] },
+            Slide::new(vec![RenderOperation::JumpToBottom]),
+            Slide::new(vec![RenderOperation::JumpToBottom]),
         ]);
-        let rhs = Presentation::new(vec![Slide { render_operations: vec![RenderOperation::JumpToBottom] }]);
+        let rhs = Presentation::new(vec![Slide::new(vec![RenderOperation::JumpToBottom])]);
 
         assert_eq!(PresentationDiffer::first_modified_slide(&lhs, &rhs), Some(0));
     }
 
     #[test]
     fn slides_added() {
-        let lhs = Presentation::new(vec![Slide { render_operations: vec![RenderOperation::JumpToBottom] }]);
+        let lhs = Presentation::new(vec![Slide::new(vec![RenderOperation::JumpToBottom])]);
         let rhs = Presentation::new(vec![
-            Slide { render_operations: vec![RenderOperation::JumpToBottom] },
-            Slide { render_operations: vec![RenderOperation::JumpToBottom] },
+            Slide::new(vec![RenderOperation::JumpToBottom]),
+            Slide::new(vec![RenderOperation::JumpToBottom]),
         ]);
 
         assert_eq!(PresentationDiffer::first_modified_slide(&lhs, &rhs), Some(1));
@@ -190,34 +305,111 @@ mod test {
     #[test]
     fn second_slide_content_changed() {
         let lhs = Presentation::new(vec![
-            Slide { render_operations: vec![RenderOperation::JumpToBottom] },
-            Slide { render_operations: vec![RenderOperation::JumpToBottom] },
-            Slide { render_operations: vec![RenderOperation::JumpToBottom] },
+            Slide::new(vec![RenderOperation::JumpToBottom]),
+            Slide::new(vec![RenderOperation::JumpToBottom]),
+            Slide::new(vec![RenderOperation::JumpToBottom]),
         ]);
         let rhs = Presentation::new(vec![
-            Slide { render_operations: vec![RenderOperation::JumpToBottom] },
-            Slide { render_operations: vec![RenderOperation::JumpToVerticalCenter] },
-            Slide { render_operations: vec![RenderOperation::JumpToBottom] },
+            Slide::new(vec![RenderOperation::JumpToBottom]),
+            Slide::new(vec![RenderOperation::JumpToVerticalCenter]),
+            Slide::new(vec![RenderOperation::JumpToBottom]),
         ]);
 
         assert_eq!(PresentationDiffer::first_modified_slide(&lhs, &rhs), Some(1));
     }
 
+    #[test]
+    fn pause_added() {
+        // Pauses only split slides into chunks so they don't shift the slides after them.
+        let operations = vec![RenderOperation::JumpToBottom, RenderOperation::RenderLineBreak];
+        let lhs = Presentation::new(vec![Slide::new(vec![RenderOperation::JumpToBottom]), Slide::new(operations)]);
+        let chunks = vec![
+            SlideChunk::new(vec![RenderOperation::JumpToBottom]),
+            SlideChunk::new(vec![RenderOperation::RenderLineBreak]),
+        ];
+        let rhs = Presentation::new(vec![
+            Slide::new(vec![RenderOperation::JumpToBottom]),
+            Slide::from_chunks(chunks, Vec::new()),
+        ]);
+
+        assert_eq!(PresentationDiffer::first_modified_slide(&lhs, &rhs), None);
+    }
+
     #[test]
     fn presentation_changed_style() {
-        let lhs = Presentation::new(vec![Slide {
-            render_operations: vec![RenderOperation::SetColors(Colors {
-                background: None,
-                foreground: Some(Color::new(255, 0, 0)),
-            })],
-        }]);
-        let rhs = Presentation::new(vec![Slide {
-            render_operations: vec![RenderOperation::SetColors(Colors {
-                background: None,
-                foreground: Some(Color::new(0, 0, 0)),
-            })],
-        }]);
+        let lhs = Presentation::new(vec![Slide::new(vec![RenderOperation::SetColors(Colors {
+            background: None,
+            foreground: Some(Color::new(255, 0, 0)),
+        })])]);
+        let rhs = Presentation::new(vec![Slide::new(vec![RenderOperation::SetColors(Colors {
+            background: None,
+            foreground: Some(Color::new(0, 0, 0)),
+        })])]);
 
         assert_eq!(PresentationDiffer::first_modified_slide(&lhs, &rhs), None);
     }
+
+    #[derive(Debug)]
+    struct OnDemand;
+
+    impl AsRenderOperations for OnDemand {
+        fn as_render_operations(&self, _dimensions: &WindowSize) -> Vec<RenderOperation> {
+            Vec::new()
+        }
+    }
+
+    impl RenderOnDemand for OnDemand {
+        fn start_render(&self, _sender: Sender<Command>) -> bool {
+            false
+        }
+    }
+
+    fn on_demand_slide(text: &str) -> (Slide, Rc<OnDemand>) {
+        let operation = Rc::new(OnDemand);
+        let slide = Slide::new(vec![
+            RenderOperation::RenderTextLine { line: String::from(text).into(), alignment: Default::default() },
+            RenderOperation::RenderOnDemand(operation.clone()),
+        ]);
+        (slide, operation)
+    }
+
+    fn on_demand_operation(presentation: &Presentation, index: usize) -> Rc<dyn RenderOnDemand> {
+        match presentation.iter_slides().nth(index).unwrap().iter_operations().nth(1).unwrap() {
+            RenderOperation::RenderOnDemand(operation) => operation.clone(),
+            _ => panic!("not an on demand operation"),
+        }
+    }
+
+    #[test]
+    fn carry_over_state() {
+        let (first, first_operation) = on_demand_slide("first");
+        let (last, last_operation) = on_demand_slide("last");
+        let original = Presentation::new(vec![first, on_demand_slide("second").0, last]);
+        let mut updated = Presentation::new(vec![
+            on_demand_slide("first").0,
+            on_demand_slide("new").0,
+            on_demand_slide("second!").0,
+            on_demand_slide("last").0,
+        ]);
+
+        assert_eq!(PresentationDiffer::carry_over_state(&original, &mut updated), 2);
+        let first_operation: Rc<dyn RenderOnDemand> = first_operation;
+        let last_operation: Rc<dyn RenderOnDemand> = last_operation;
+        assert!(Rc::ptr_eq(&on_demand_operation(&updated, 0), &first_operation));
+        assert!(!Rc::ptr_eq(&on_demand_operation(&updated, 2), &first_operation));
+        assert!(Rc::ptr_eq(&on_demand_operation(&updated, 3), &last_operation));
+    }
+
+    #[test]
+    fn style_changes_discard_state() {
+        let original = Presentation::new(vec![Slide::new(vec![RenderOperation::SetColors(Colors {
+            background: None,
+            foreground: Some(Color::new(255, 0, 0)),
+        })])]);
+        let mut updated = Presentation::new(vec![Slide::new(vec![RenderOperation::SetColors(Colors {
+            background: None,
+            foreground: Some(Color::new(0, 0, 0)),
+        })])]);
+        assert_eq!(PresentationDiffer::carry_over_state(&original, &mut updated), 0);
+    }
 }
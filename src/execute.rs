@@ -0,0 +1,393 @@
+use crate::{
+    input::source::Command,
+    markdown::elements::{Code, ProgrammingLanguage},
+};
+use log::debug;
+use std::{
+    io::{self, BufRead, BufReader, Read},
+    process::{self, ExitStatus, Stdio},
+    sync::{mpsc::Sender, Arc, Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+// How long processes get to terminate once they're asked to before they're killed.
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// Allows executing code.
+pub struct CodeExecuter;
+
+impl CodeExecuter {
+    /// Check whether code in the given language can be executed.
+    pub fn is_supported(language: &ProgrammingLanguage) -> bool {
+        Self::interpreter(language).is_some()
+    }
+
+    /// Execute a piece of code in the background.
+    ///
+    /// Every time there's new output or the process finishes, a [Command::Redraw] is sent via the
+    /// given sender.
+    pub fn execute(code: &Code, sender: Sender<Command>) -> Result<ExecutionHandle, CodeExecuteError> {
+        Self::execute_after(code, Vec::new(), sender)
+    }
+
+    /// Execute a piece of code in the background once every one of the given executions finishes.
+    ///
+    /// If any of them fails, the code isn't executed and this execution fails as well.
+    pub fn execute_after(
+        code: &Code,
+        dependencies: Vec<ExecutionHandle>,
+        sender: Sender<Command>,
+    ) -> Result<ExecutionHandle, CodeExecuteError> {
+        let (program, args) = Self::interpreter(&code.language).ok_or(CodeExecuteError::UnsupportedExecution)?;
+        debug!("executing code using {program}");
+        let mut command = process::Command::new(program);
+        if let Some(cwd) = &code.flags.cwd {
+            command.current_dir(cwd);
+        }
+        command
+            .args(args)
+            .arg(&code.contents)
+            .envs(code.flags.env.iter().map(|(name, value)| (name, value)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        // Run the code in its own process group so anything it spawns can be killed along with it.
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+        let shared: Arc<SharedState> = Default::default();
+        let handle = ExecutionHandle { shared: shared.clone() };
+        if dependencies.is_empty() {
+            Self::spawn(command, shared, sender).map_err(CodeExecuteError::SpawnProcess)?;
+            return Ok(handle);
+        }
+        thread::spawn(move || {
+            if !dependencies.iter().all(|dependency| dependency.wait() == ProcessStatus::Success) {
+                shared.finish(vec!["a previous piece of code failed".into()], ProcessStatus::Failure);
+                let _ = sender.send(Command::Redraw);
+                return;
+            }
+            if let Err(e) = Self::spawn(command, shared.clone(), sender.clone()) {
+                shared.finish(vec![CodeExecuteError::SpawnProcess(e).to_string()], ProcessStatus::Failure);
+                let _ = sender.send(Command::Redraw);
+            }
+        });
+        Ok(handle)
+    }
+
+    fn spawn(mut command: process::Command, shared: Arc<SharedState>, sender: Sender<Command>) -> io::Result<()> {
+        // The lock is held until the process id is set so the execution can't be killed in between.
+        let mut state = shared.state.lock().expect("poisoned lock");
+        if state.cancelled {
+            return Ok(());
+        }
+        let child = command.spawn()?;
+        state.process_id = Some(child.id());
+        drop(state);
+        Self::spawn_waiter(child, shared, sender);
+        Ok(())
+    }
+
+    fn spawn_waiter(mut child: process::Child, shared: Arc<SharedState>, sender: Sender<Command>) {
+        debug!("executing code in process {}", child.id());
+        let start = Instant::now();
+        let stdout = child.stdout.take().expect("no stdout");
+        let stderr = child.stderr.take().expect("no stderr");
+        let readers = [Self::spawn_reader(stdout, &shared, &sender), Self::spawn_reader(stderr, &shared, &sender)];
+        thread::spawn(move || {
+            for reader in readers {
+                let _ = reader.join();
+            }
+            let exit_status = child.wait();
+            {
+                let mut state = shared.state.lock().expect("poisoned lock");
+                state.exit_code = exit_status.as_ref().ok().and_then(ExitStatus::code);
+                state.duration = Some(start.elapsed());
+            }
+            let status = match exit_status {
+                Ok(status) if status.success() => ProcessStatus::Success,
+                _ => ProcessStatus::Failure,
+            };
+            shared.finish(Vec::new(), status);
+            let _ = sender.send(Command::Redraw);
+        });
+    }
+
+    fn spawn_reader<R: Read + Send + 'static>(
+        reader: R,
+        shared: &Arc<SharedState>,
+        sender: &Sender<Command>,
+    ) -> thread::JoinHandle<()> {
+        let shared = shared.clone();
+        let sender = sender.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                let Ok(line) = line else {
+                    return;
+                };
+                shared.state.lock().expect("poisoned lock").output.push(line);
+                let _ = sender.send(Command::Redraw);
+            }
+        })
+    }
+
+    fn interpreter(language: &ProgrammingLanguage) -> Option<(&'static str, &'static [&'static str])> {
+        use ProgrammingLanguage::*;
+        let interpreter: (&str, &[&str]) = match language {
+            Bash => ("bash", &["-c"]),
+            Shell => ("sh", &["-c"]),
+            Python => ("python3", &["-c"]),
+            JavaScript => ("node", &["-e"]),
+            Lua => ("lua", &["-e"]),
+            Perl => ("perl", &["-e"]),
+            _ => return None,
+        };
+        Some(interpreter)
+    }
+}
+
+/// A handle to a piece of code that's being executed.
+#[derive(Clone, Debug)]
+pub struct ExecutionHandle {
+    shared: Arc<SharedState>,
+}
+
+impl ExecutionHandle {
+    /// Get the current state of the execution.
+    pub fn state(&self) -> ExecutionState {
+        self.shared.state.lock().expect("poisoned lock").clone()
+    }
+
+    /// Block until the execution finishes, returning its final status.
+    pub fn wait(&self) -> ProcessStatus {
+        let state = self.shared.state.lock().expect("poisoned lock");
+        let state = self
+            .shared
+            .finished
+            .wait_while(state, |state| state.status == ProcessStatus::Running)
+            .expect("poisoned lock");
+        state.status.clone()
+    }
+
+    /// Kill the process executing the code.
+    ///
+    /// The process is asked to terminate first and it's only killed forcefully if it's still running
+    /// after a grace period. Returns `false` if the execution had already finished or been cancelled.
+    pub fn kill(&self) -> bool {
+        let mut state = self.shared.state.lock().expect("poisoned lock");
+        if state.status != ProcessStatus::Running || state.cancelled {
+            return false;
+        }
+        state.cancelled = true;
+        let Some(process_id) = state.process_id else {
+            // The process hasn't been started yet and now it never will be.
+            drop(state);
+            self.shared.finish(vec!["cancelled before starting".into()], ProcessStatus::Failure);
+            return true;
+        };
+        drop(state);
+        debug!("terminating process {process_id}");
+        Signal::Terminate.send(process_id);
+        let shared = self.shared.clone();
+        thread::spawn(move || {
+            let state = shared.state.lock().expect("poisoned lock");
+            let (_state, result) = shared
+                .finished
+                .wait_timeout_while(state, KILL_GRACE_PERIOD, |state| state.status == ProcessStatus::Running)
+                .expect("poisoned lock");
+            if result.timed_out() {
+                debug!("killing process {process_id}");
+                Signal::Kill.send(process_id);
+            }
+        });
+        true
+    }
+}
+
+enum Signal {
+    Terminate,
+    Kill,
+}
+
+impl Signal {
+    #[cfg(unix)]
+    fn send(&self, process_id: u32) {
+        let signal = match self {
+            Self::Terminate => libc::SIGTERM,
+            Self::Kill => libc::SIGKILL,
+        };
+        // A negative id sends the signal to the whole process group.
+        let result = unsafe { libc::kill(-(process_id as libc::pid_t), signal) };
+        if result != 0 {
+            debug!("failed to signal process {process_id}: {}", io::Error::last_os_error());
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn send(&self, process_id: u32) {
+        let mut command = process::Command::new("taskkill");
+        command.args(["/T", "/PID", &process_id.to_string()]);
+        if matches!(self, Self::Kill) {
+            command.arg("/F");
+        }
+        if let Err(e) = command.stdout(Stdio::null()).stderr(Stdio::null()).status() {
+            debug!("failed to signal process {process_id}: {e}");
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct SharedState {
+    state: Mutex<ExecutionState>,
+    finished: Condvar,
+}
+
+impl SharedState {
+    fn finish(&self, output: Vec<String>, status: ProcessStatus) {
+        let mut state = self.state.lock().expect("poisoned lock");
+        state.output.extend(output);
+        state.status = status;
+        self.finished.notify_all();
+    }
+}
+
+/// The state of a piece of code being executed.
+#[derive(Clone, Debug, Default)]
+pub struct ExecutionState {
+    /// The lines written to stdout and stderr so far.
+    pub output: Vec<String>,
+
+    /// The status of the process.
+    pub status: ProcessStatus,
+
+    /// The process' exit code, once it finishes.
+    ///
+    /// This is `None` if the process was terminated by a signal.
+    pub exit_code: Option<i32>,
+
+    /// How long the process took to run, once it finishes.
+    pub duration: Option<Duration>,
+
+    /// Whether the execution was cancelled via [ExecutionHandle::kill].
+    pub cancelled: bool,
+
+    /// The id of the process, once it's started.
+    pub process_id: Option<u32>,
+}
+
+/// The status of a process.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ProcessStatus {
+    #[default]
+    Running,
+    Success,
+    Failure,
+}
+
+/// An error executing code.
+#[derive(thiserror::Error, Debug)]
+pub enum CodeExecuteError {
+    #[error("code language is not supported for execution")]
+    UnsupportedExecution,
+
+    #[error("error spawning process: {0}")]
+    SpawnProcess(io::Error),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::markdown::elements::CodeFlags;
+    use std::{sync::mpsc::channel, time::Duration};
+
+    #[test]
+    fn shell_code_execution() {
+        let contents = "echo hello\necho bye >&2\nexit 1".into();
+        let flags = CodeFlags { execute: true, ..Default::default() };
+        let code = Code { contents, language: ProgrammingLanguage::Shell, flags };
+        let (sender, receiver) = channel();
+        let handle = CodeExecuter::execute(&code, sender).expect("execution failed");
+        while handle.state().status == ProcessStatus::Running {
+            receiver.recv_timeout(Duration::from_secs(5)).expect("no updates");
+        }
+        let mut state = handle.state();
+        state.output.sort();
+        assert_eq!(state.output, &["bye", "hello"]);
+        assert_eq!(state.status, ProcessStatus::Failure);
+    }
+
+    #[test]
+    fn working_directory_and_environment() {
+        let directory = tempfile::tempdir().expect("creating temp dir");
+        let contents = "echo $GREETING $(basename $(pwd))".into();
+        let flags = CodeFlags {
+            execute: true,
+            cwd: Some(directory.path().into()),
+            env: vec![("GREETING".into(), "hi".into())],
+            ..Default::default()
+        };
+        let code = Code { contents, language: ProgrammingLanguage::Shell, flags };
+        let (sender, receiver) = channel();
+        let handle = CodeExecuter::execute(&code, sender).expect("execution failed");
+        while handle.state().status == ProcessStatus::Running {
+            receiver.recv_timeout(Duration::from_secs(5)).expect("no updates");
+        }
+        let name = directory.path().file_name().unwrap().to_string_lossy();
+        assert_eq!(handle.state().output, &[format!("hi {name}")]);
+    }
+
+    #[test]
+    fn exit_code_and_duration() {
+        let flags = CodeFlags { execute: true, ..Default::default() };
+        let code = Code { contents: "sleep 0.1; exit 3".into(), language: ProgrammingLanguage::Shell, flags };
+        let (sender, _receiver) = channel();
+        let handle = CodeExecuter::execute(&code, sender).expect("execution failed");
+        assert_eq!(handle.wait(), ProcessStatus::Failure);
+        let state = handle.state();
+        assert_eq!(state.exit_code, Some(3));
+        assert!(state.duration.expect("no duration") >= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn kill() {
+        let flags = CodeFlags { execute: true, ..Default::default() };
+        let code = Code { contents: "sleep 10".into(), language: ProgrammingLanguage::Shell, flags };
+        let (sender, _receiver) = channel();
+        let handle = CodeExecuter::execute(&code, sender).expect("execution failed");
+        assert!(handle.kill());
+        assert!(!handle.kill());
+        assert_eq!(handle.wait(), ProcessStatus::Failure);
+        let state = handle.state();
+        assert!(state.cancelled);
+        assert!(state.duration.expect("no duration") < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn kill_before_starting() {
+        let flags = CodeFlags { execute: true, ..Default::default() };
+        let setup = Code { contents: "sleep 0.2".into(), language: ProgrammingLanguage::Shell, flags };
+        let code = Code { contents: "echo hi".into(), ..setup.clone() };
+        let (sender, _receiver) = channel();
+        let setup = CodeExecuter::execute(&setup, sender.clone()).expect("execution failed");
+        let handle = CodeExecuter::execute_after(&code, vec![setup.clone()], sender).expect("execution failed");
+        assert!(handle.kill());
+        assert_eq!(handle.wait(), ProcessStatus::Failure);
+        assert_eq!(setup.wait(), ProcessStatus::Success);
+        // Give it a chance to run, which it shouldn't.
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(handle.state().output, &["cancelled before starting"]);
+    }
+
+    #[test]
+    fn execute_after() {
+        let flags = CodeFlags { execute: true, ..Default::default() };
+        let setup = Code { contents: "sleep 0.2; exit 1".into(), language: ProgrammingLanguage::Shell, flags };
+        let code = Code { contents: "echo hi".into(), ..setup.clone() };
+        let (sender, _receiver) = channel();
+        let setup = CodeExecuter::execute(&setup, sender.clone()).expect("execution failed");
+        let handle = CodeExecuter::execute_after(&code, vec![setup], sender).expect("execution failed");
+        assert_eq!(handle.wait(), ProcessStatus::Failure);
+        assert_eq!(handle.state().output, &["a previous piece of code failed"]);
+    }
+}
@@ -0,0 +1,434 @@
+use crate::{
+    builder::{BuildError, PresentationBuilder},
+    input::fs::PresentationFileWatcher,
+    markdown::parse::{MarkdownParser, ParseError},
+    presentation::{Presentation, RenderOperation, Slide, Watermark},
+    render::{
+        draw::{RenderError, TerminalDrawer},
+        highlighting::CodeHighlighter,
+        html::HtmlWriter,
+        media::PlacedImage,
+        pdf::PdfWriter,
+        properties::WindowSize,
+        raster::Rasterizer,
+        virtual_terminal::VirtualTerminal,
+    },
+    resource::Resources,
+    style::Color,
+    theme::PresentationTheme,
+};
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, Frame, ImageError,
+};
+use log::{debug, info};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    rc::Rc,
+    thread,
+    time::Duration,
+};
+
+// The size of the screen presentations are rendered in when exporting them as images.
+const EXPORT_ROWS: u16 = 30;
+const EXPORT_COLUMNS: u16 = 100;
+
+// How long every slide is displayed for in animated exports.
+const FRAME_DURATION: Duration = Duration::from_secs(2);
+
+/// Exports presentations into files without presenting them.
+pub struct Exporter<'a> {
+    parser: MarkdownParser<'a>,
+    default_theme: &'a PresentationTheme,
+    default_highlighter: CodeHighlighter,
+    resources: Resources,
+    default_watermark: Option<Watermark>,
+}
+
+impl<'a> Exporter<'a> {
+    /// Construct a new exporter.
+    pub fn new(
+        parser: MarkdownParser<'a>,
+        default_theme: &'a PresentationTheme,
+        default_highlighter: CodeHighlighter,
+        resources: Resources,
+    ) -> Self {
+        Self { parser, default_theme, default_highlighter, resources, default_watermark: None }
+    }
+
+    /// Draw the given watermark on every slide of presentations that don't set their own.
+    pub fn with_default_watermark(mut self, watermark: Option<Watermark>) -> Self {
+        self.default_watermark = watermark;
+        self
+    }
+
+    /// Export the presentation in the given path into the given output file.
+    ///
+    /// The format to use is determined based on the output file's extension.
+    pub fn export(&mut self, path: &Path, output: &Path) -> Result<(), ExportError> {
+        let format = ExportFormat::from_path(output)?;
+        let content = fs::read_to_string(path).map_err(|e| ExportError::Io(path.into(), e))?;
+        let elements = self.parser.parse(&content)?;
+        let mut presentation =
+            PresentationBuilder::new(self.default_highlighter.clone(), self.default_theme, &mut self.resources)
+                .with_default_watermark(self.default_watermark.clone())
+                .build(elements)?;
+        let contents = match format {
+            ExportFormat::Text => Self::export_text(&presentation).into_bytes(),
+            ExportFormat::Gif => Self::export_gif(&mut presentation)?,
+            ExportFormat::Pdf => Self::export_pdf(&mut presentation)?,
+            ExportFormat::Html => Self::export_html(&mut presentation)?.into_bytes(),
+        };
+        fs::write(output, contents).map_err(|e| ExportError::Io(output.into(), e))?;
+        debug!("exported presentation into {}", output.display());
+        Ok(())
+    }
+
+    /// Export the presentation every time it changes.
+    ///
+    /// Errors exporting are handed to `on_error` rather than stopping this, as they're likely
+    /// caused by the presentation being in the middle of being edited. This never returns unless
+    /// the presentation can't be read anymore.
+    pub fn watch<F>(&mut self, path: &Path, output: &Path, mut on_error: F) -> Result<(), ExportError>
+    where
+        F: FnMut(ExportError),
+    {
+        let mut watcher = PresentationFileWatcher::new(path);
+        loop {
+            match self.export(path, output) {
+                Ok(()) => info!("exported presentation into {}", output.display()),
+                Err(e @ ExportError::UnsupportedFormat(_)) => return Err(e),
+                Err(e) => on_error(e),
+            };
+            while !watcher.has_modifications().map_err(|e| ExportError::Io(path.into(), e))? {
+                thread::sleep(Duration::from_millis(250));
+            }
+        }
+    }
+
+    fn export_text(presentation: &Presentation) -> String {
+        let slides: Vec<_> = presentation.iter_slides().collect();
+        let mut output = String::new();
+        for (number, index) in Self::complete_slides(presentation).into_iter().enumerate() {
+            let slide = slides[index];
+            if number > 0 {
+                output.push('\n');
+            }
+            output.push_str(&format!("--- slide {} ---\n", number + 1));
+            for line in Self::slide_text_lines(slide) {
+                output.push_str(line.trim_end());
+                output.push('\n');
+            }
+        }
+        output
+    }
+
+    fn export_gif(presentation: &mut Presentation) -> Result<Vec<u8>, ExportError> {
+        let (foreground, background) = Self::colors(presentation);
+        let rasterizer = Rasterizer::new(foreground, background);
+        let (cell_width, cell_height) = rasterizer.cell_size();
+        let dimensions = WindowSize {
+            rows: EXPORT_ROWS,
+            columns: EXPORT_COLUMNS,
+            width: EXPORT_COLUMNS * cell_width as u16,
+            height: EXPORT_ROWS * cell_height as u16,
+        };
+
+        let mut output = Vec::new();
+        let mut encoder = GifEncoder::new_with_speed(&mut output, 10);
+        encoder.set_repeat(Repeat::Infinite)?;
+        // Every step in the presentation, including revealing every chunk in a slide, is a frame.
+        presentation.jump_first_slide();
+        loop {
+            let mut terminal = VirtualTerminal::new(dimensions.rows, dimensions.columns);
+            TerminalDrawer::headless(&mut terminal).render_slide_sized(presentation, dimensions.clone())?;
+            let image = rasterizer.rasterize(&terminal);
+            encoder.encode_frame(Frame::from_parts(image, 0, 0, Delay::from_saturating_duration(FRAME_DURATION)))?;
+            if !presentation.jump_next_slide() {
+                break;
+            }
+        }
+        drop(encoder);
+        Ok(output)
+    }
+
+    fn export_pdf(presentation: &mut Presentation) -> Result<Vec<u8>, ExportError> {
+        let (foreground, background) = Self::colors(presentation);
+        let mut writer = PdfWriter::new(foreground, background);
+        let dimensions = PdfWriter::page_size(EXPORT_ROWS, EXPORT_COLUMNS);
+        Self::render_complete_slides(presentation, dimensions, |terminal, images| writer.add_page(terminal, images))?;
+        Ok(writer.finish())
+    }
+
+    fn export_html(presentation: &mut Presentation) -> Result<String, ExportError> {
+        let (foreground, background) = Self::colors(presentation);
+        let mut writer = HtmlWriter::new(foreground, background);
+        let dimensions = HtmlWriter::slide_size(EXPORT_ROWS, EXPORT_COLUMNS);
+        Self::render_complete_slides(presentation, dimensions, |terminal, images| writer.add_slide(terminal, images))?;
+        Ok(writer.finish())
+    }
+
+    // Get the foreground and background colors of the theme the presentation was built with.
+    fn colors(presentation: &Presentation) -> (Color, Color) {
+        let colors = presentation.colors();
+        (colors.foreground.unwrap_or(Color::new(229, 229, 229)), colors.background.unwrap_or(Color::new(0, 0, 0)))
+    }
+
+    // Render every complete slide, with all of its chunks shown, into a virtual terminal.
+    fn render_complete_slides<F>(
+        presentation: &mut Presentation,
+        dimensions: WindowSize,
+        mut on_slide: F,
+    ) -> Result<(), ExportError>
+    where
+        F: FnMut(&VirtualTerminal, &[PlacedImage]),
+    {
+        for index in Self::complete_slides(presentation) {
+            presentation.jump_slide(index);
+            presentation.show_chunks(usize::MAX);
+            let mut terminal = VirtualTerminal::new(dimensions.rows, dimensions.columns);
+            let images = {
+                let mut drawer = TerminalDrawer::headless(&mut terminal);
+                drawer.render_slide_sized(presentation, dimensions.clone())?;
+                drawer.take_placed_images()
+            };
+            on_slide(&terminal, &images);
+        }
+        Ok(())
+    }
+
+    // Get the indexes of the slides that contain everything in a markdown slide.
+    //
+    // Slides generated out of the same markdown slide, like the pages in a scrolling code block,
+    // share their hooks. Only the last one is needed as it's the one the slide ends with.
+    fn complete_slides(presentation: &Presentation) -> Vec<usize> {
+        let slides: Vec<_> = presentation.iter_slides().collect();
+        (0..slides.len())
+            .filter(|index| !slides.get(index + 1).is_some_and(|next| Rc::ptr_eq(&slides[*index].hooks, &next.hooks)))
+            .collect()
+    }
+
+    fn slide_text_lines(slide: &Slide) -> Vec<String> {
+        let operations = slide.chunks.iter().flat_map(|chunk| &chunk.operations);
+        Self::text_lines(operations)
+    }
+
+    fn text_lines<'b>(operations: impl IntoIterator<Item = &'b RenderOperation>) -> Vec<String> {
+        let mut lines = Vec::new();
+        for operation in operations {
+            match operation {
+                RenderOperation::RenderTextLine { line, .. } => {
+                    lines.push(line.iter_texts().map(|text| text.text.text.as_str()).collect())
+                }
+                RenderOperation::RenderPreformattedLine(line) => lines.push(Self::strip_escape_codes(&line.text)),
+                RenderOperation::RenderBlockLine(line) => {
+                    let text: String = line.text.iter_texts().map(|text| text.text.text.as_str()).collect();
+                    lines.push(format!("{}{text}", line.prefix));
+                }
+                // Things like code blocks that highlight lines in steps are generated on the fly.
+                RenderOperation::RenderDynamic(generator) => {
+                    let dimensions = WindowSize { rows: EXPORT_ROWS, columns: EXPORT_COLUMNS, width: 0, height: 0 };
+                    lines.extend(Self::text_lines(&generator.as_render_operations(&dimensions)));
+                }
+                _ => (),
+            };
+        }
+        lines
+    }
+
+    fn strip_escape_codes(text: &str) -> String {
+        let mut output = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c != '\x1b' {
+                output.push(c);
+                continue;
+            }
+            // Skip everything up until the byte that terminates the escape sequence.
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+        }
+        output
+    }
+}
+
+/// A format a presentation can be exported into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ExportFormat {
+    /// Plain text, containing the text in every slide.
+    Text,
+
+    /// An animated GIF that goes through every slide.
+    Gif,
+
+    /// A PDF document that contains a page for every slide.
+    Pdf,
+
+    /// A standalone HTML document that contains a section for every slide.
+    Html,
+}
+
+impl ExportFormat {
+    fn from_path(path: &Path) -> Result<Self, ExportError> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("txt") => Ok(Self::Text),
+            Some("gif") => Ok(Self::Gif),
+            Some("pdf") => Ok(Self::Pdf),
+            Some("html") => Ok(Self::Html),
+            _ => Err(ExportError::UnsupportedFormat(path.into())),
+        }
+    }
+}
+
+/// An error during an export.
+#[derive(thiserror::Error, Debug)]
+pub enum ExportError {
+    #[error("unsupported export format for {0}")]
+    UnsupportedFormat(PathBuf),
+
+    #[error("io error on {0}: {1}")]
+    Io(PathBuf, io::Error),
+
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+
+    #[error(transparent)]
+    Build(#[from] BuildError),
+
+    #[error("rendering: {0}")]
+    Render(#[from] RenderError),
+
+    #[error("encoding: {0}")]
+    Encode(#[from] ImageError),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use comrak::Arena;
+    use flate2::read::ZlibDecoder;
+    use image::{codecs::gif::GifDecoder, AnimationDecoder};
+    use std::io::Read;
+
+    fn export(input: &str) -> String {
+        String::from_utf8(export_bytes(input, "txt")).expect("invalid utf8")
+    }
+
+    fn export_bytes(input: &str, extension: &str) -> Vec<u8> {
+        let directory = tempfile::tempdir().expect("creating temp dir");
+        let path = directory.path().join("presentation.md");
+        let output = directory.path().join(format!("presentation.{extension}"));
+        fs::write(&path, input).expect("writing presentation");
+        let image = image::RgbaImage::from_pixel(100, 100, image::Rgba([255, 0, 0, 255]));
+        image.save(directory.path().join("image.png")).expect("writing image");
+
+        let arena = Arena::new();
+        let theme = PresentationTheme::default();
+        let highlighter = CodeHighlighter::new("base16-ocean.dark").unwrap();
+        let mut exporter =
+            Exporter::new(MarkdownParser::new(&arena), &theme, highlighter, Resources::new(directory.path()));
+        exporter.export(&path, &output).expect("export failed");
+        fs::read(output).expect("reading output")
+    }
+
+    #[test]
+    fn text_export() {
+        let input = "hello\n\n<!-- pause -->\n\nworld\n\n<!-- end_slide -->\n\n```rust\nlet q = 42;\n```";
+        let output = export(input);
+        let lines: Vec<_> = output.lines().filter(|line| !line.is_empty()).collect();
+        assert_eq!(lines, &["--- slide 1 ---", "hello", "world", "--- slide 2 ---", "let q = 42;"]);
+    }
+
+    #[test]
+    fn text_export_highlight_steps() {
+        let input = "```rust {1|2}\nlet q = 42;\nlet w = 1;\n```";
+        let output = export(input);
+        let lines: Vec<_> = output.lines().filter(|line| !line.is_empty()).collect();
+        assert_eq!(lines, &["--- slide 1 ---", "let q = 42;", "let w = 1;"]);
+    }
+
+    #[test]
+    fn gif_export() {
+        let input = "# hello\n\n<!-- pause -->\n\nworld\n\n<!-- end_slide -->\n\nbye";
+        let output = export_bytes(input, "gif");
+        let decoder = GifDecoder::new(output.as_slice()).expect("invalid gif");
+        let frames = decoder.into_frames().collect_frames().expect("invalid frames");
+        assert_eq!(frames.len(), 3);
+    }
+
+    #[test]
+    fn gif_export_theme_colors() {
+        let input =
+            "---\ntheme:\n  override:\n    default:\n      colors:\n        background: \"101010\"\n---\n\nhello";
+        let output = export_bytes(input, "gif");
+        let decoder = GifDecoder::new(output.as_slice()).expect("invalid gif");
+        let frames = decoder.into_frames().collect_frames().expect("invalid frames");
+        assert_eq!(frames[0].buffer().get_pixel(0, 0), &image::Rgba([16, 16, 16, 255]));
+    }
+
+    #[test]
+    fn pdf_export() {
+        let input = "# hello\n\n<!-- pause -->\n\n![](image.png)\n\n<!-- end_slide -->\n\nbye";
+        let output = export_bytes(input, "pdf");
+        let output = String::from_utf8_lossy(&output);
+        assert!(output.starts_with("%PDF-"));
+        assert!(output.contains("/Count 2"));
+        assert_eq!(output.matches("/Subtype /Image").count(), 1);
+    }
+
+    #[test]
+    fn pdf_export_theme_colors() {
+        let input = "---\ntheme:\n  override:\n    default:\n      colors:\n        foreground: \"beeeff\"\n        \
+                     background: \"101010\"\n---\n\nhello";
+        let output = export_bytes(input, "pdf");
+        let start = output.windows(7).position(|window| window == b"stream\n").expect("no stream") + 7;
+        let mut contents = String::new();
+        ZlibDecoder::new(&output[start..]).read_to_string(&mut contents).expect("invalid stream");
+        assert!(contents.starts_with("0.063 0.063 0.063 rg 0 0 "), "{contents}");
+        assert!(contents.contains("0.745 0.933 1.000 rg"), "{contents}");
+    }
+
+    #[test]
+    fn html_export() {
+        let input = "# hello\n\n<!-- pause -->\n\n![](image.png)\n\n<!-- end_slide -->\n\nbye <3";
+        let output = String::from_utf8(export_bytes(input, "html")).expect("invalid utf8");
+        assert!(output.starts_with("<!DOCTYPE html>"));
+        assert_eq!(output.matches("<section>").count(), 2);
+        assert_eq!(output.matches("data:image/png;base64,").count(), 1);
+        assert!(output.contains("hello"));
+        assert!(output.contains("bye &lt;3"));
+    }
+
+    #[test]
+    fn html_export_theme_colors() {
+        let input = "---\ntheme:\n  override:\n    default:\n      colors:\n        foreground: \"beeeff\"\n        \
+                     background: \"101010\"\n---\n\nhello";
+        let output = String::from_utf8(export_bytes(input, "html")).expect("invalid utf8");
+        assert!(output.contains("body { margin: 0; background: #101010; color: #beeeff; }"), "{output}");
+    }
+
+    #[test]
+    fn watermark() {
+        let input = "---\noptions:\n  watermark:\n    text: DRAFT\n    position: top_right\n---\n\nhello";
+        let output = String::from_utf8(export_bytes(input, "html")).expect("invalid utf8");
+        assert!(output.contains("DRAFT"));
+    }
+
+    #[test]
+    fn unsupported_format() {
+        let error = ExportFormat::from_path(Path::new("presentation.docx")).expect_err("format accepted");
+        assert!(matches!(error, ExportError::UnsupportedFormat(_)));
+    }
+
+    #[test]
+    fn strip_escape_codes() {
+        let text = "\x1b[38;2;1;2;3mlet\x1b[0m q";
+        assert_eq!(Exporter::strip_escape_codes(text), "let q");
+    }
+}
@@ -0,0 +1,85 @@
+use crate::input::source::Command;
+use log::debug;
+use serde::Deserialize;
+use std::{
+    process::{self, Stdio},
+    sync::mpsc::Sender,
+    thread,
+};
+
+/// The shell commands to be run when a slide is entered or left.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+pub struct SlideHooks {
+    /// The commands to run when the slide is entered.
+    #[serde(default)]
+    pub on_enter: Vec<String>,
+
+    /// The commands to run when the slide is left.
+    #[serde(default)]
+    pub on_exit: Vec<String>,
+}
+
+impl SlideHooks {
+    /// Check whether there's no hooks at all.
+    pub fn is_empty(&self) -> bool {
+        self.on_enter.is_empty() && self.on_exit.is_empty()
+    }
+}
+
+/// Runs slide hooks.
+///
+/// Every hook is run asynchronously in the background so a slow command doesn't block the
+/// presentation. If a hook fails, a [Command::HookFailed] is sent so the failure can be displayed.
+pub struct HookRunner {
+    sender: Sender<Command>,
+}
+
+impl HookRunner {
+    /// Construct a new hook runner that notifies failures via the given sender.
+    pub fn new(sender: Sender<Command>) -> Self {
+        Self { sender }
+    }
+
+    /// Run the hooks for a slide that was just entered.
+    pub fn run_enter(&self, hooks: &SlideHooks) {
+        for command in &hooks.on_enter {
+            self.spawn(command.clone());
+        }
+    }
+
+    /// Run the hooks for a slide that was just left.
+    pub fn run_exit(&self, hooks: &SlideHooks) {
+        for command in &hooks.on_exit {
+            self.spawn(command.clone());
+        }
+    }
+
+    fn spawn(&self, command: String) {
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            debug!("running hook command={command:?}");
+            if let Err(error) = Self::run(&command) {
+                let _ = sender.send(Command::HookFailed { error: format!("hook '{command}' failed: {error}") });
+            }
+        });
+    }
+
+    fn run(command: &str) -> Result<(), String> {
+        let output = process::Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output()
+            .map_err(|e| e.to_string())?;
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match stderr.lines().last() {
+            Some(line) => Err(line.to_string()),
+            None => Err(output.status.to_string()),
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::str::FromStr;
+
+/// A key press, as it's matched against key bindings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    fn digit(&self) -> Option<u32> {
+        match self.code {
+            KeyCode::Char(c) if self.modifiers.is_empty() => c.to_digit(10),
+            _ => None,
+        }
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Self {
+        let mut modifiers = event.modifiers;
+        // Shift is already implied by characters like `G` and terminals don't agree on whether to report it.
+        if matches!(event.code, KeyCode::Char(_)) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self { code: event.code, modifiers }
+    }
+}
+
+/// A sequence of keys that triggers a command.
+///
+/// Keys are written as they're typed, like `gg`, other than special ones which go between angle
+/// brackets, like `<page_down>` or `<c-c>` for control + c. `<number>` matches any number, which is
+/// used as the command's argument.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct KeyBinding(Vec<KeyMatcher>);
+
+impl KeyBinding {
+    /// Match the keys pressed so far against this binding.
+    pub(crate) fn match_keys(&self, keys: &[Key]) -> BindingMatch {
+        let mut keys = keys.iter().peekable();
+        let mut number = None;
+        for matcher in &self.0 {
+            match matcher {
+                KeyMatcher::Key(expected) => match keys.next() {
+                    Some(key) if key == expected => (),
+                    Some(_) => return BindingMatch::None,
+                    None => return BindingMatch::Partial,
+                },
+                KeyMatcher::Number => {
+                    let mut value: Option<u32> = None;
+                    while let Some(digit) = keys.peek().and_then(|key| key.digit()) {
+                        keys.next();
+                        // Numbers that are too large saturate so 123123123G points past the last
+                        // slide rather than being an alias for G.
+                        value = Some(value.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+                    }
+                    match (value, keys.peek()) {
+                        (Some(value), _) => number = Some(value),
+                        (None, Some(_)) => return BindingMatch::None,
+                        (None, None) => return BindingMatch::Partial,
+                    }
+                }
+            }
+        }
+        match keys.next() {
+            Some(_) => BindingMatch::None,
+            None => BindingMatch::Full(number),
+        }
+    }
+
+    fn parse_special(name: &str) -> Option<KeyMatcher> {
+        if name == "number" {
+            return Some(KeyMatcher::Number);
+        }
+        if let Some(key) = name.strip_prefix("c-") {
+            let mut chars = key.chars();
+            let (Some(c), None) = (chars.next(), chars.next()) else {
+                return None;
+            };
+            return Some(KeyMatcher::Key(Key { code: KeyCode::Char(c), modifiers: KeyModifiers::CONTROL }));
+        }
+        let code = match name {
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "page_up" => KeyCode::PageUp,
+            "page_down" => KeyCode::PageDown,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "enter" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "esc" => KeyCode::Esc,
+            "space" => KeyCode::Char(' '),
+            _ => return None,
+        };
+        Some(KeyMatcher::Key(Key { code, modifiers: KeyModifiers::NONE }))
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = InvalidKeyBinding;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidKeyBinding(input.into());
+        let mut matchers = Vec::new();
+        let mut remaining = input;
+        while let Some(c) = remaining.chars().next() {
+            if c == '<' && remaining.len() > 1 {
+                let end = remaining.find('>').ok_or_else(invalid)?;
+                matchers.push(Self::parse_special(&remaining[1..end]).ok_or_else(invalid)?);
+                remaining = &remaining[end + 1..];
+            } else {
+                matchers.push(KeyMatcher::Key(Key { code: KeyCode::Char(c), modifiers: KeyModifiers::NONE }));
+                remaining = &remaining[c.len_utf8()..];
+            }
+        }
+        // A number at the very end would swallow every digit typed after it.
+        if matchers.is_empty() || matches!(matchers.last(), Some(KeyMatcher::Number)) {
+            return Err(invalid());
+        }
+        Ok(Self(matchers))
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = InvalidKeyBinding;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        input.parse()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum KeyMatcher {
+    Key(Key),
+    Number,
+}
+
+/// The result of matching keys against a binding.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum BindingMatch {
+    /// The keys match the entire binding, along with the number in it, if any.
+    Full(Option<u32>),
+
+    /// The keys match the start of the binding.
+    Partial,
+
+    /// The keys don't match the binding.
+    None,
+}
+
+/// An invalid key binding.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[error("invalid key binding '{0}'")]
+pub struct InvalidKeyBinding(String);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    fn keys(input: &str) -> Vec<Key> {
+        input.chars().map(|c| KeyEvent::from(KeyCode::Char(c)).into()).collect()
+    }
+
+    #[rstest]
+    #[case::single("q", "q", BindingMatch::Full(None))]
+    #[case::sequence("gg", "gg", BindingMatch::Full(None))]
+    #[case::sequence_start("gg", "g", BindingMatch::Partial)]
+    #[case::different("gg", "gq", BindingMatch::None)]
+    #[case::too_long("g", "gg", BindingMatch::None)]
+    #[case::number("<number>G", "42G", BindingMatch::Full(Some(42)))]
+    #[case::number_start("<number>G", "42", BindingMatch::Partial)]
+    #[case::no_number("<number>G", "G", BindingMatch::None)]
+    #[case::huge_number("<number>G", "123123123123G", BindingMatch::Full(Some(u32::MAX)))]
+    fn match_keys(#[case] binding: &str, #[case] input: &str, #[case] expected: BindingMatch) {
+        let binding: KeyBinding = binding.parse().expect("invalid binding");
+        assert_eq!(binding.match_keys(&keys(input)), expected);
+    }
+
+    #[test]
+    fn special_keys() {
+        let binding: KeyBinding = "<c-c>".parse().expect("invalid binding");
+        let key = Key::from(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        assert_eq!(binding.match_keys(&[key]), BindingMatch::Full(None));
+        assert_eq!(binding.match_keys(&keys("c")), BindingMatch::None);
+
+        let binding: KeyBinding = "<page_down>".parse().expect("invalid binding");
+        assert_eq!(binding.match_keys(&[KeyEvent::from(KeyCode::PageDown).into()]), BindingMatch::Full(None));
+    }
+
+    #[test]
+    fn shift_ignored() {
+        let binding: KeyBinding = "G".parse().expect("invalid binding");
+        let key = Key::from(KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT));
+        assert_eq!(binding.match_keys(&[key]), BindingMatch::Full(None));
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::unknown_key("<potato>")]
+    #[case::unterminated("<left")]
+    #[case::trailing_number("G<number>")]
+    #[case::long_control("<c-cc>")]
+    fn invalid(#[case] input: &str) {
+        assert!(input.parse::<KeyBinding>().is_err());
+    }
+}
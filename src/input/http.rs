@@ -0,0 +1,291 @@
+use super::{source::Command, user::UserCommand};
+use serde::Serialize;
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    sync::{mpsc::Sender, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+// Requests are tiny so anything bigger than this is bogus.
+const MAX_BODY_LENGTH: usize = 4096;
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// An HTTP server that allows controlling a presentation remotely.
+///
+/// The server only listens on the loopback interface and understands the following requests:
+///
+/// * `POST /next` and `POST /previous` to move to the next/previous slide.
+/// * `POST /first` and `POST /last` to jump to the first/last slide.
+/// * `POST /slide/<number>` to jump to a specific slide.
+/// * `GET /slide` to get the current slide's number along with the total number of slides.
+///
+/// Requests that carry an `Origin` header are rejected. Browsers add it to requests made by web
+/// pages, so this prevents any page the user visits from controlling the presentation.
+///
+/// Commands are handed to the [Sender] the server is built with, which is expected to feed them
+/// into the presentation's [super::source::CommandSource].
+pub struct HttpControlServer {
+    address: SocketAddr,
+    position: Arc<Mutex<SlidePosition>>,
+}
+
+impl HttpControlServer {
+    /// Start a server that listens on the given port.
+    pub fn bind(port: u16, sender: Sender<Command>) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        let address = listener.local_addr()?;
+        let position = Arc::new(Mutex::new(SlidePosition::default()));
+        let handler = RequestHandler { sender, position: position.clone() };
+        thread::spawn(move || handler.accept_connections(listener));
+        Ok(Self { address, position })
+    }
+
+    /// Get the address this server is listening on.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Set the position in the presentation that's reported to clients.
+    ///
+    /// The slide index is 0 based. The total number of slides should be `None` if it's not known yet.
+    pub fn set_position(&self, slide_index: usize, total_slides: Option<usize>) {
+        let mut position = self.position.lock().expect("lock poisoned");
+        *position = SlidePosition { current_slide: slide_index + 1, total_slides };
+    }
+}
+
+/// The position in the presentation, as reported to clients.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+struct SlidePosition {
+    current_slide: usize,
+    total_slides: Option<usize>,
+}
+
+struct RequestHandler {
+    sender: Sender<Command>,
+    position: Arc<Mutex<SlidePosition>>,
+}
+
+impl RequestHandler {
+    fn accept_connections(self, listener: TcpListener) {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            // Requests are handled one at a time as they take no time at all. The read timeout makes
+            // sure a client that never finishes its request can't stall the rest.
+            let _ = self.handle_connection(stream);
+        }
+    }
+
+    fn handle_connection(&self, stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        let mut writer = stream.try_clone()?;
+        let mut reader = BufReader::new(stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut content_length = 0;
+        let mut has_origin = false;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                let name = name.trim();
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                } else if name.eq_ignore_ascii_case("origin") {
+                    has_origin = true;
+                }
+            }
+        }
+        // The body isn't used but it's read anyway so the client doesn't see the connection being reset.
+        io::copy(&mut reader.take(content_length.min(MAX_BODY_LENGTH) as u64), &mut io::sink())?;
+
+        if has_origin {
+            return Response::error(403, "Forbidden", "requests from web pages aren't allowed").write(&mut writer);
+        }
+        let response = match Route::parse(&request_line) {
+            Ok(Route::Position) => {
+                let position = self.position.lock().expect("lock poisoned").clone();
+                Response::ok(serde_json::to_string(&position).expect("serializing position failed"))
+            }
+            Ok(Route::Command(command)) => match self.sender.send(Command::User(command)) {
+                Ok(()) => Response::ok(r#"{"status":"ok"}"#.into()),
+                Err(_) => Response::error(503, "Service Unavailable", "presentation is over"),
+            },
+            Err(e) => Response::error(e.status(), e.reason(), &e.to_string()),
+        };
+        response.write(&mut writer)
+    }
+}
+
+/// A request the server understands.
+#[derive(Debug, PartialEq, Eq)]
+enum Route {
+    Command(UserCommand),
+    Position,
+}
+
+impl Route {
+    fn parse(request_line: &str) -> Result<Self, RouteError> {
+        let mut parts = request_line.split_whitespace();
+        let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+            return Err(RouteError::Malformed);
+        };
+        // Query strings aren't used for anything.
+        let path = path.split_once('?').map(|(path, _)| path).unwrap_or(path);
+        let path = path.trim_end_matches('/');
+        let (route, expected_method) = match path {
+            "/next" => (Self::Command(UserCommand::JumpNextSlide), "POST"),
+            "/previous" => (Self::Command(UserCommand::JumpPreviousSlide), "POST"),
+            "/first" => (Self::Command(UserCommand::JumpFirstSlide), "POST"),
+            "/last" => (Self::Command(UserCommand::JumpLastSlide), "POST"),
+            "/slide" => (Self::Position, "GET"),
+            _ => match path.strip_prefix("/slide/") {
+                Some(number) => {
+                    let number = number.parse().map_err(|_| RouteError::InvalidSlide(number.into()))?;
+                    (Self::Command(UserCommand::JumpSlide(number)), "POST")
+                }
+                None => return Err(RouteError::NotFound(path.into())),
+            },
+        };
+        match method == expected_method {
+            true => Ok(route),
+            false => Err(RouteError::MethodNotAllowed(method.into())),
+        }
+    }
+}
+
+/// An error routing a request.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+enum RouteError {
+    #[error("malformed request")]
+    Malformed,
+
+    #[error("unknown path '{0}'")]
+    NotFound(String),
+
+    #[error("method '{0}' not allowed")]
+    MethodNotAllowed(String),
+
+    #[error("invalid slide number '{0}'")]
+    InvalidSlide(String),
+}
+
+impl RouteError {
+    fn status(&self) -> u16 {
+        match self {
+            Self::Malformed | Self::InvalidSlide(_) => 400,
+            Self::NotFound(_) => 404,
+            Self::MethodNotAllowed(_) => 405,
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self {
+            Self::Malformed | Self::InvalidSlide(_) => "Bad Request",
+            Self::NotFound(_) => "Not Found",
+            Self::MethodNotAllowed(_) => "Method Not Allowed",
+        }
+    }
+}
+
+struct Response {
+    status: u16,
+    reason: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn ok(body: String) -> Self {
+        Self { status: 200, reason: "OK", body }
+    }
+
+    fn error(status: u16, reason: &'static str, message: &str) -> Self {
+        let body = serde_json::json!({ "error": message }).to_string();
+        Self { status, reason, body }
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let Self { status, reason, body } = self;
+        write!(
+            writer,
+            "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{body}",
+            body.len()
+        )?;
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+    use std::sync::mpsc::channel;
+
+    #[rstest]
+    #[case::next("POST /next HTTP/1.1", Route::Command(UserCommand::JumpNextSlide))]
+    #[case::previous("POST /previous HTTP/1.1", Route::Command(UserCommand::JumpPreviousSlide))]
+    #[case::first("POST /first HTTP/1.1", Route::Command(UserCommand::JumpFirstSlide))]
+    #[case::last("POST /last/ HTTP/1.1", Route::Command(UserCommand::JumpLastSlide))]
+    #[case::jump("POST /slide/12 HTTP/1.1", Route::Command(UserCommand::JumpSlide(12)))]
+    #[case::position("GET /slide?x=1 HTTP/1.1", Route::Position)]
+    fn parse_route(#[case] request_line: &str, #[case] expected: Route) {
+        assert_eq!(Route::parse(request_line), Ok(expected));
+    }
+
+    #[rstest]
+    #[case::empty("", 400)]
+    #[case::unknown("POST /potato HTTP/1.1", 404)]
+    #[case::wrong_method("GET /next HTTP/1.1", 405)]
+    #[case::invalid_slide("POST /slide/potato HTTP/1.1", 400)]
+    fn parse_invalid_route(#[case] request_line: &str, #[case] status: u16) {
+        let error = Route::parse(request_line).expect_err("parse succeeded");
+        assert_eq!(error.status(), status);
+    }
+
+    fn request(server: &HttpControlServer, request: &str) -> String {
+        let mut stream = TcpStream::connect(server.address()).expect("connecting failed");
+        stream.write_all(request.as_bytes()).expect("writing failed");
+        let mut response = String::new();
+        stream.read_to_string(&mut response).expect("reading failed");
+        response
+    }
+
+    #[test]
+    fn serve() {
+        let (sender, receiver) = channel();
+        let server = HttpControlServer::bind(0, sender).expect("bind failed");
+        server.set_position(2, Some(10));
+
+        let response = request(&server, "GET /slide HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+        assert!(response.ends_with(r#"{"current_slide":3,"total_slides":10}"#), "{response}");
+
+        let response = request(&server, "POST /slide/4 HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+        let command = receiver.try_recv().expect("no command");
+        assert!(matches!(command, Command::User(UserCommand::JumpSlide(4))));
+
+        let response = request(&server, "POST /potato HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"), "{response}");
+
+        server.set_position(0, None);
+        let response = request(&server, "GET /slide HTTP/1.1\r\n\r\n");
+        assert!(response.ends_with(r#"{"current_slide":1,"total_slides":null}"#), "{response}");
+    }
+
+    #[test]
+    fn reject_origin() {
+        let (sender, receiver) = channel();
+        let server = HttpControlServer::bind(0, sender).expect("bind failed");
+        let response = request(&server, "POST /next HTTP/1.1\r\nOrigin: https://example.com\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 403 Forbidden\r\n"), "{response}");
+        assert!(receiver.try_recv().is_err());
+    }
+}
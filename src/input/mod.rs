@@ -1,3 +1,7 @@
+pub mod bindings;
 pub mod fs;
+pub mod http;
+#[cfg(unix)]
+pub mod socket;
 pub mod source;
 pub mod user;
@@ -0,0 +1,248 @@
+use super::{source::Command, user::UserCommand};
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    os::unix::{
+        fs::FileTypeExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Mutex,
+    },
+    thread,
+};
+
+// The paths of the sockets currently bound, so they can be removed in places where destructors don't run.
+static BOUND_SOCKETS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Listens for commands sent over a unix socket.
+///
+/// Every connection can send any number of newline delimited commands, like `next` or `goto 12`.
+/// Each of them will be acknowledged with a line containing either `ok` or an error message. This
+/// allows external tools like window managers or scripts to drive the presentation.
+pub struct SocketCommandListener {
+    path: PathBuf,
+    receiver: Receiver<Command>,
+}
+
+impl SocketCommandListener {
+    /// Bind a listener to the given socket path.
+    ///
+    /// Any stale socket file in this path will be removed first. Anything other than a socket in it
+    /// is left alone and makes this fail instead.
+    pub fn bind<P: Into<PathBuf>>(path: P) -> io::Result<Self> {
+        let path = path.into();
+        if let Ok(metadata) = fs::symlink_metadata(&path) {
+            if !metadata.file_type().is_socket() {
+                return Err(io::Error::new(io::ErrorKind::AlreadyExists, "path exists and is not a socket"));
+            }
+            fs::remove_file(&path)?;
+        }
+        let listener = UnixListener::bind(&path)?;
+        if let Ok(mut paths) = BOUND_SOCKETS.lock() {
+            paths.push(path.clone());
+        }
+        let (sender, receiver) = channel();
+        thread::spawn(move || Self::accept_connections(listener, sender));
+        Ok(Self { path, receiver })
+    }
+
+    /// Get the path this listener is bound to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the next command, if any, without blocking.
+    pub fn try_next_command(&self) -> Option<Command> {
+        self.receiver.try_recv().ok()
+    }
+
+    fn accept_connections(listener: UnixListener, sender: Sender<Command>) {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let sender = sender.clone();
+            thread::spawn(move || Self::handle_connection(stream, sender));
+        }
+    }
+
+    fn handle_connection(stream: UnixStream, sender: Sender<Command>) -> io::Result<()> {
+        let mut writer = stream.try_clone()?;
+        for line in BufReader::new(stream).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match line.parse::<SocketCommand>() {
+                Ok(command) => {
+                    if sender.send(command.into()).is_err() {
+                        // The presentation is gone, there's nothing left to do.
+                        return Ok(());
+                    }
+                    writeln!(writer, "ok")?;
+                }
+                Err(e) => writeln!(writer, "error: {e}")?,
+            };
+        }
+        Ok(())
+    }
+}
+
+impl Drop for SocketCommandListener {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+        if let Ok(mut paths) = BOUND_SOCKETS.lock() {
+            paths.retain(|path| path != &self.path);
+        }
+    }
+}
+
+/// Remove the files of every socket that's currently bound.
+///
+/// This is meant to be used right before exiting the process without running destructors.
+pub(crate) fn remove_bound_sockets() {
+    if let Ok(paths) = BOUND_SOCKETS.lock() {
+        for path in paths.iter() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// A command received over the socket.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum SocketCommand {
+    Next,
+    Previous,
+    First,
+    Last,
+    Goto(u32),
+    Reload,
+    Exit,
+}
+
+impl FromStr for SocketCommand {
+    type Err = SocketCommandParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let name = parts.next().ok_or(SocketCommandParseError::Empty)?;
+        let command = match name {
+            "next" => Self::Next,
+            "prev" | "previous" => Self::Previous,
+            "first" => Self::First,
+            "last" => Self::Last,
+            "goto" => {
+                let number = parts.next().ok_or(SocketCommandParseError::MissingArgument("goto"))?;
+                let number = number.parse().map_err(|_| SocketCommandParseError::InvalidSlide(number.into()))?;
+                Self::Goto(number)
+            }
+            "reload" => Self::Reload,
+            "exit" | "quit" => Self::Exit,
+            other => return Err(SocketCommandParseError::UnknownCommand(other.into())),
+        };
+        match parts.next() {
+            Some(_) => Err(SocketCommandParseError::TrailingArguments(name.into())),
+            None => Ok(command),
+        }
+    }
+}
+
+impl From<SocketCommand> for Command {
+    fn from(command: SocketCommand) -> Self {
+        let command = match command {
+            SocketCommand::Next => UserCommand::JumpNextSlide,
+            SocketCommand::Previous => UserCommand::JumpPreviousSlide,
+            SocketCommand::First => UserCommand::JumpFirstSlide,
+            SocketCommand::Last => UserCommand::JumpLastSlide,
+            SocketCommand::Goto(number) => UserCommand::JumpSlide(number),
+            SocketCommand::Exit => UserCommand::Exit,
+            SocketCommand::Reload => return Command::ReloadPresentation,
+        };
+        Command::User(command)
+    }
+}
+
+/// An error parsing a socket command.
+#[derive(thiserror::Error, Debug)]
+enum SocketCommandParseError {
+    #[error("empty command")]
+    Empty,
+
+    #[error("unknown command '{0}'")]
+    UnknownCommand(String),
+
+    #[error("missing argument for '{0}'")]
+    MissingArgument(&'static str),
+
+    #[error("invalid slide number '{0}'")]
+    InvalidSlide(String),
+
+    #[error("too many arguments for '{0}'")]
+    TrailingArguments(String),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::next("next", SocketCommand::Next)]
+    #[case::prev("prev", SocketCommand::Previous)]
+    #[case::previous("previous", SocketCommand::Previous)]
+    #[case::first("first", SocketCommand::First)]
+    #[case::last("last", SocketCommand::Last)]
+    #[case::goto("goto 12", SocketCommand::Goto(12))]
+    #[case::goto_spaces("  goto   3 ", SocketCommand::Goto(3))]
+    #[case::reload("reload", SocketCommand::Reload)]
+    #[case::exit("exit", SocketCommand::Exit)]
+    fn parse_command(#[case] input: &str, #[case] expected: SocketCommand) {
+        let command: SocketCommand = input.parse().expect("parse failed");
+        assert_eq!(command, expected);
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::unknown("potato")]
+    #[case::goto_without_number("goto")]
+    #[case::goto_invalid_number("goto potato")]
+    #[case::trailing("next 1")]
+    fn parse_invalid_command(#[case] input: &str) {
+        let result = input.parse::<SocketCommand>();
+        assert!(result.is_err(), "{input} parsed: {result:?}");
+    }
+
+    #[test]
+    fn bind_replaces_stale_socket() {
+        let directory = tempfile::tempdir().expect("creating temp dir");
+        let path = directory.path().join("presenterm.sock");
+        UnixListener::bind(&path).expect("bind failed");
+
+        let listener = SocketCommandListener::bind(&path).expect("bind failed");
+        UnixStream::connect(listener.path()).expect("connect failed");
+    }
+
+    #[test]
+    fn bound_sockets_tracked() {
+        let directory = tempfile::tempdir().expect("creating temp dir");
+        let path = directory.path().join("presenterm.sock");
+        let listener = SocketCommandListener::bind(&path).expect("bind failed");
+        assert!(BOUND_SOCKETS.lock().unwrap().contains(&path));
+
+        drop(listener);
+        assert!(!BOUND_SOCKETS.lock().unwrap().contains(&path));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn bind_keeps_other_files() {
+        let file = tempfile::NamedTempFile::new().expect("creating temp file");
+        let result = SocketCommandListener::bind(file.path());
+        assert!(result.is_err());
+        assert!(file.path().exists());
+    }
+}
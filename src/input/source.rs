@@ -1,41 +1,122 @@
+#[cfg(unix)]
+use super::socket::SocketCommandListener;
 use super::{
     fs::PresentationFileWatcher,
     user::{UserCommand, UserInput},
 };
-use std::{io, path::PathBuf, time::Duration};
+use crate::config::KeyBindingsConfig;
+#[cfg(unix)]
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::{
+    io,
+    path::PathBuf,
+    sync::mpsc::{channel, Receiver, Sender},
+    time::Duration,
+};
 
 /// The source of commands.
 ///
 /// This expects user commands as well as watches over the presentation file to reload if it that
-/// happens.
+/// happens. Optionally, commands can also be received over a unix socket.
 pub struct CommandSource {
     watcher: PresentationFileWatcher,
     user_input: UserInput,
+    #[cfg(unix)]
+    socket_listener: Option<SocketCommandListener>,
+    internal_sender: Sender<Command>,
+    internal_receiver: Receiver<Command>,
+    #[cfg(unix)]
+    suspend_requested: Arc<AtomicBool>,
 }
 
 impl CommandSource {
     /// Create a new command source over the given presentation path.
     pub fn new<P: Into<PathBuf>>(presentation_path: P) -> Self {
         let watcher = PresentationFileWatcher::new(presentation_path);
-        Self { watcher, user_input: UserInput::default() }
+        let (internal_sender, internal_receiver) = channel();
+        // Rather than letting SIGTSTP stop us right away, turn it into a command so the terminal
+        // can be restored before suspending.
+        #[cfg(unix)]
+        let suspend_requested = Arc::new(AtomicBool::new(false));
+        #[cfg(unix)]
+        let _ = signal_hook::flag::register(signal_hook::consts::SIGTSTP, suspend_requested.clone());
+        Self {
+            watcher,
+            user_input: UserInput::default(),
+            #[cfg(unix)]
+            socket_listener: None,
+            internal_sender,
+            internal_receiver,
+            #[cfg(unix)]
+            suspend_requested,
+        }
+    }
+
+    /// Watch the presentation in the given path rather than the current one.
+    pub fn watch_presentation<P: Into<PathBuf>>(&mut self, path: P) {
+        self.watcher = PresentationFileWatcher::new(path);
+    }
+
+    /// Get a sender that allows other components to inject commands into this source.
+    pub fn sender(&self) -> Sender<Command> {
+        self.internal_sender.clone()
+    }
+
+    /// Use the given key bindings to turn key presses into commands.
+    pub fn with_key_bindings(mut self, bindings: &KeyBindingsConfig) -> Self {
+        self.user_input = UserInput::new(bindings);
+        self
+    }
+
+    /// Also accept commands coming from the given socket listener.
+    #[cfg(unix)]
+    pub fn with_socket_listener(mut self, listener: SocketCommandListener) -> Self {
+        self.socket_listener = Some(listener);
+        self
     }
 
     /// Block until the next command arrives.
     pub fn next_command(&mut self) -> io::Result<Command> {
         loop {
-            match self.user_input.poll_next_command(Duration::from_millis(250)) {
-                Ok(Some(command)) => {
-                    return Ok(Command::User(command));
-                }
-                Ok(None) => (),
-                Err(e) => {
-                    return Ok(Command::Abort { error: e.to_string() });
-                }
-            };
-            if self.watcher.has_modifications()? {
-                return Ok(Command::ReloadPresentation);
+            if let Some(command) = self.poll_next_command(Duration::from_millis(250))? {
+                return Ok(command);
+            }
+        }
+    }
+
+    /// Get the next command if there's one available right away.
+    pub fn try_next_command(&mut self) -> io::Result<Option<Command>> {
+        self.poll_next_command(Duration::ZERO)
+    }
+
+    fn poll_next_command(&mut self, timeout: Duration) -> io::Result<Option<Command>> {
+        match self.user_input.poll_next_command(timeout) {
+            Ok(Some(command)) => {
+                return Ok(Some(Command::User(command)));
+            }
+            Ok(None) => (),
+            Err(e) => {
+                return Ok(Some(Command::Abort { error: e.to_string() }));
             }
+        };
+        #[cfg(unix)]
+        if self.suspend_requested.swap(false, Ordering::Relaxed) {
+            return Ok(Some(Command::User(UserCommand::Suspend)));
         }
+        if let Ok(command) = self.internal_receiver.try_recv() {
+            return Ok(Some(command));
+        }
+        #[cfg(unix)]
+        if let Some(command) = self.socket_listener.as_ref().and_then(SocketCommandListener::try_next_command) {
+            return Ok(Some(command));
+        }
+        if self.watcher.has_modifications()? {
+            return Ok(Some(Command::ReloadPresentation));
+        }
+        Ok(None)
     }
 }
 
@@ -50,4 +131,14 @@ pub enum Command {
 
     /// Something bad has happened and we need to abort.
     Abort { error: String },
+
+    /// Something changed in the background and the current slide needs to be drawn again.
+    Redraw,
+
+    /// The slide that was being displayed when this was scheduled has been on screen for long
+    /// enough and it's time to move to the next one.
+    AutoAdvance { generation: u64 },
+
+    /// A slide hook failed to run.
+    HookFailed { error: String },
 }
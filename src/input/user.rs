@@ -1,13 +1,34 @@
-use crossterm::event::{poll, read, Event, KeyCode, KeyEvent, KeyModifiers};
+use super::bindings::{BindingMatch, Key, KeyBinding};
+use crate::{config::KeyBindingsConfig, style::Color};
+use crossterm::event::{poll, read, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use std::{io, mem, time::Duration};
 
 /// A user input handler.
-#[derive(Default)]
 pub struct UserInput {
     state: InputState,
+    bindings: Vec<(KeyBinding, BoundCommand)>,
 }
 
 impl UserInput {
+    /// Construct a new user input handler that uses the given key bindings.
+    pub fn new(config: &KeyBindingsConfig) -> Self {
+        let commands = [
+            (&config.next, BoundCommand::JumpNextSlide),
+            (&config.previous, BoundCommand::JumpPreviousSlide),
+            (&config.next_full, BoundCommand::JumpNextFullSlide),
+            (&config.previous_full, BoundCommand::JumpPreviousFullSlide),
+            (&config.first_slide, BoundCommand::JumpFirstSlide),
+            (&config.last_slide, BoundCommand::JumpLastSlide),
+            (&config.go_to_slide, BoundCommand::JumpSlide),
+            (&config.exit, BoundCommand::Exit),
+        ];
+        let bindings = commands
+            .into_iter()
+            .flat_map(|(bindings, command)| bindings.iter().map(move |binding| (binding.clone(), command)))
+            .collect();
+        Self { state: Default::default(), bindings }
+    }
+
     /// Polls for the next input command coming from the keyboard.
     pub fn poll_next_command(&mut self, timeout: Duration) -> io::Result<Option<UserCommand>> {
         if poll(timeout)? { self.next_command() } else { Ok(None) }
@@ -17,67 +38,116 @@ impl UserInput {
     pub fn next_command(&mut self) -> io::Result<Option<UserCommand>> {
         let current_state = mem::take(&mut self.state);
         let (command, next_state) = match read()? {
-            Event::Key(event) => Self::apply_key_event(event, current_state),
+            Event::Key(event) => self.apply_key_event(event, current_state),
             Event::Resize(..) => (Some(UserCommand::Redraw), current_state),
+            // Pasted text is ignored altogether, otherwise it would be interpreted as navigation.
+            Event::Paste(..) => (None, InputState::Empty),
             _ => (None, current_state),
         };
         self.state = next_state;
         Ok(command)
     }
 
-    fn apply_key_event(event: KeyEvent, state: InputState) -> (Option<UserCommand>, InputState) {
+    fn apply_key_event(&self, event: KeyEvent, state: InputState) -> (Option<UserCommand>, InputState) {
+        // Terminals using the kitty keyboard protocol report releases too. Only presses and
+        // repeats should be acted upon, otherwise every key would trigger twice.
+        if event.kind == KeyEventKind::Release {
+            return (None, state);
+        }
+        if let InputState::Prompt(text) = state {
+            return Self::apply_prompt_key_event(event, text);
+        }
+        if matches!(state, InputState::PendingMark | InputState::PendingJumpToMark) {
+            return (Self::apply_mark(event, state), InputState::Empty);
+        }
+        let key = Key::from(event);
+        if let InputState::PendingKeys(mut keys) = state {
+            keys.push(key);
+            if let Some(output) = self.apply_bindings(keys) {
+                return output;
+            }
+        }
+        // Keys that don't continue the pending sequence start a new one.
+        if let Some(output) = self.apply_bindings(vec![key]) {
+            return output;
+        }
         match event.code {
-            KeyCode::Char('h') | KeyCode::Char('k') | KeyCode::Left | KeyCode::PageUp | KeyCode::Up => {
-                (Some(UserCommand::JumpPreviousSlide), InputState::Empty)
+            KeyCode::Char('k') if event.modifiers == KeyModifiers::CONTROL => {
+                (Some(UserCommand::KillCode), InputState::Empty)
             }
-            KeyCode::Char('l')
-            | KeyCode::Char('j')
-            | KeyCode::Right
-            | KeyCode::PageDown
-            | KeyCode::Down
-            | KeyCode::Char(' ') => (Some(UserCommand::JumpNextSlide), InputState::Empty),
-            KeyCode::Char('c') if event.modifiers == KeyModifiers::CONTROL => {
-                (Some(UserCommand::Exit), InputState::Empty)
+            KeyCode::Char('z') if event.modifiers == KeyModifiers::CONTROL => {
+                (Some(UserCommand::Suspend), InputState::Empty)
+            }
+            KeyCode::Char('e') if event.modifiers == KeyModifiers::CONTROL => {
+                (Some(UserCommand::ExecuteCode), InputState::Empty)
             }
-            KeyCode::Char('G') => Self::apply_uppercase_g(state),
-            KeyCode::Char('g') => Self::apply_lowercase_g(state),
-            KeyCode::Char(number) if number.is_ascii_digit() => {
-                let number = number.to_digit(10).expect("not a digit");
-                (None, Self::apply_number(number, state))
+            KeyCode::Char('n') => (Some(UserCommand::ToggleNotes), InputState::Empty),
+            KeyCode::Char('p') => (Some(UserCommand::ToggleNextSlidePreview), InputState::Empty),
+            KeyCode::Char(']') => (Some(UserCommand::JumpNextPart), InputState::Empty),
+            KeyCode::Char('[') => (Some(UserCommand::JumpPreviousPart), InputState::Empty),
+            KeyCode::Char('}') => (Some(UserCommand::JumpNextSection), InputState::Empty),
+            KeyCode::Char('{') => (Some(UserCommand::JumpPreviousSection), InputState::Empty),
+            KeyCode::Char('N') => (Some(UserCommand::NextDeck), InputState::Empty),
+            KeyCode::Char('P') => (Some(UserCommand::PreviousDeck), InputState::Empty),
+            KeyCode::Char('s') => (Some(UserCommand::ToggleStopwatch), InputState::Empty),
+            KeyCode::Char('r') => (Some(UserCommand::ResetStopwatch), InputState::Empty),
+            KeyCode::Char('L') => (Some(UserCommand::StopwatchLap), InputState::Empty),
+            KeyCode::Char('f') => (Some(UserCommand::ToggleHotReload), InputState::Empty),
+            KeyCode::Char('v') => (Some(UserCommand::PlayVideo), InputState::Empty),
+            KeyCode::Char('b') => (Some(UserCommand::BlankScreen(Color::new(0, 0, 0))), InputState::Empty),
+            KeyCode::Char('w') => (Some(UserCommand::BlankScreen(Color::new(255, 255, 255))), InputState::Empty),
+            KeyCode::Char('m') => (None, InputState::PendingMark),
+            KeyCode::Char('\'') => (None, InputState::PendingJumpToMark),
+            KeyCode::Char(':') => {
+                (Some(UserCommand::UpdatePrompt(Some(String::new()))), InputState::Prompt(String::new()))
             }
             _ => (None, InputState::Empty),
         }
     }
 
-    fn apply_lowercase_g(state: InputState) -> (Option<UserCommand>, InputState) {
-        match state {
-            InputState::PendingG => (Some(UserCommand::JumpFirstSlide), InputState::Empty),
-            InputState::Empty => (None, InputState::PendingG),
-            _ => (None, InputState::Empty),
+    // Match the given keys against the key bindings.
+    //
+    // Returns `None` if they don't match any of them, not even partially.
+    fn apply_bindings(&self, keys: Vec<Key>) -> Option<(Option<UserCommand>, InputState)> {
+        let mut partial = false;
+        for (binding, command) in &self.bindings {
+            match binding.match_keys(&keys) {
+                BindingMatch::Full(number) => return Some((command.as_user_command(number), InputState::Empty)),
+                BindingMatch::Partial => partial = true,
+                BindingMatch::None => (),
+            }
         }
+        partial.then_some((None, InputState::PendingKeys(keys)))
     }
 
-    fn apply_uppercase_g(state: InputState) -> (Option<UserCommand>, InputState) {
-        match state {
-            InputState::Empty => (Some(UserCommand::JumpLastSlide), InputState::Empty),
-            InputState::PendingNumber(number) => (Some(UserCommand::JumpSlide(number)), InputState::Empty),
-            _ => (None, InputState::Empty),
+    fn apply_prompt_key_event(event: KeyEvent, mut text: String) -> (Option<UserCommand>, InputState) {
+        match event.code {
+            KeyCode::Enter => (Some(UserCommand::GoTo(text)), InputState::Empty),
+            KeyCode::Esc => (Some(UserCommand::UpdatePrompt(None)), InputState::Empty),
+            KeyCode::Char('c') if event.modifiers == KeyModifiers::CONTROL => {
+                (Some(UserCommand::Exit), InputState::Empty)
+            }
+            KeyCode::Backspace => {
+                text.pop();
+                (Some(UserCommand::UpdatePrompt(Some(text.clone()))), InputState::Prompt(text))
+            }
+            KeyCode::Char(c) => {
+                text.push(c);
+                (Some(UserCommand::UpdatePrompt(Some(text.clone()))), InputState::Prompt(text))
+            }
+            _ => (None, InputState::Prompt(text)),
         }
     }
 
-    fn apply_number(number: u32, state: InputState) -> InputState {
-        let maybe_next = match state {
-            InputState::PendingNumber(current) => current.checked_mul(10).and_then(|n| n.checked_add(number)),
-            InputState::Empty => Some(number),
-            _ => {
-                return InputState::Empty;
+    fn apply_mark(event: KeyEvent, state: InputState) -> Option<UserCommand> {
+        match (event.code, state) {
+            (KeyCode::Char(mark), InputState::PendingMark) if mark.is_ascii_alphabetic() => {
+                Some(UserCommand::SetMark(mark))
             }
-        };
-        // If we overflowed, jump to a terminal state that indicates so. This way 123123123G is not
-        // an alias for G
-        match maybe_next {
-            Some(number) => InputState::PendingNumber(number),
-            None => InputState::OverflowedNumber,
+            (KeyCode::Char(mark), InputState::PendingJumpToMark) if mark.is_ascii_alphabetic() || mark == '\'' => {
+                Some(UserCommand::JumpToMark(mark))
+            }
+            _ => None,
         }
     }
 }
@@ -96,64 +166,346 @@ pub enum UserCommand {
     /// Jump to the previous slide.
     JumpPreviousSlide,
 
+    /// Jump to the next slide, skipping any pauses left in the current one.
+    JumpNextFullSlide,
+
+    /// Jump to the previous slide with all of its pauses revealed.
+    JumpPreviousFullSlide,
+
     /// Jump to the first slide.
     JumpFirstSlide,
 
     /// Jump to the last slide.
     JumpLastSlide,
 
+    /// Jump to the first slide in the next part.
+    JumpNextPart,
+
+    /// Jump to the first slide in the current part, or the previous one if already there.
+    JumpPreviousPart,
+
+    /// Jump to the next slide that begins a section.
+    JumpNextSection,
+
+    /// Jump to the slide that begins the current section, or the previous one if already there.
+    JumpPreviousSection,
+
+    /// Mark the current slide using the given letter.
+    SetMark(char),
+
+    /// Jump to the slide marked using the given letter.
+    ///
+    /// The `'` mark points to the slide we were at before the last jump.
+    JumpToMark(char),
+
+    /// Switch to the next presentation, when presenting more than one.
+    NextDeck,
+
+    /// Switch to the previous presentation, when presenting more than one.
+    PreviousDeck,
+
+    /// Start or stop the stopwatch.
+    ToggleStopwatch,
+
+    /// Stop the stopwatch and set it back to zero.
+    ResetStopwatch,
+
+    /// Record a lap in the stopwatch.
+    StopwatchLap,
+
     /// Jump to one particular slide.
     JumpSlide(u32),
 
+    /// Suspend the presentation, going back to the shell.
+    Suspend,
+
+    /// Execute the code in the current slide.
+    ExecuteCode,
+
+    /// Kill any code that's running in the current slide.
+    KillCode,
+
+    /// Play the videos in the current slide using an external player.
+    PlayVideo,
+
+    /// Show or hide the speaker notes pane.
+    ToggleNotes,
+
+    /// Show or hide the next slide preview pane.
+    ToggleNextSlidePreview,
+
+    /// Pause or resume reloading the presentation when it changes.
+    ToggleHotReload,
+
+    /// Blank the screen using the given color until the next key is pressed.
+    BlankScreen(Color),
+
+    /// Display the go to prompt with the given text in it, or hide it if there's none.
+    UpdatePrompt(Option<String>),
+
+    /// Go to the slide identified by the given text.
+    ///
+    /// This can either be a slide number or a piece of a slide's title.
+    GoTo(String),
+
     /// Exit the presentation.
     Exit,
 }
 
+impl Default for UserInput {
+    fn default() -> Self {
+        Self::new(&KeyBindingsConfig::default())
+    }
+}
+
+// The commands that can be bound to keys.
+#[derive(Clone, Copy, Debug)]
+enum BoundCommand {
+    JumpNextSlide,
+    JumpPreviousSlide,
+    JumpNextFullSlide,
+    JumpPreviousFullSlide,
+    JumpFirstSlide,
+    JumpLastSlide,
+    JumpSlide,
+    Exit,
+}
+
+impl BoundCommand {
+    fn as_user_command(self, number: Option<u32>) -> Option<UserCommand> {
+        let command = match self {
+            Self::JumpNextSlide => UserCommand::JumpNextSlide,
+            Self::JumpPreviousSlide => UserCommand::JumpPreviousSlide,
+            Self::JumpNextFullSlide => UserCommand::JumpNextFullSlide,
+            Self::JumpPreviousFullSlide => UserCommand::JumpPreviousFullSlide,
+            Self::JumpFirstSlide => UserCommand::JumpFirstSlide,
+            Self::JumpLastSlide => UserCommand::JumpLastSlide,
+            // Bindings that don't contain a number don't point to any slide.
+            Self::JumpSlide => UserCommand::JumpSlide(number?),
+            Self::Exit => UserCommand::Exit,
+        };
+        Some(command)
+    }
+}
+
 #[derive(Default, Debug, PartialEq, Eq)]
 enum InputState {
     #[default]
     Empty,
-    PendingG,
-    PendingNumber(u32),
-    OverflowedNumber,
+    PendingKeys(Vec<Key>),
+    PendingMark,
+    PendingJumpToMark,
+    Prompt(String),
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    fn pending(keys: &str) -> InputState {
+        InputState::PendingKeys(keys.chars().map(|c| KeyEvent::from(KeyCode::Char(c)).into()).collect())
+    }
+
     #[test]
     fn lowercase_g() {
+        let input = UserInput::default();
         let state = InputState::Empty;
-        let (command, state) = UserInput::apply_key_event(KeyCode::Char('g').into(), state);
+        let (command, state) = input.apply_key_event(KeyCode::Char('g').into(), state);
         assert!(command.is_none());
 
-        let (command, state) = UserInput::apply_key_event(KeyCode::Char('g').into(), state);
+        let (command, state) = input.apply_key_event(KeyCode::Char('g').into(), state);
         assert_eq!(command, Some(UserCommand::JumpFirstSlide));
         assert_eq!(state, InputState::Empty);
     }
 
     #[test]
     fn uppercase_g() {
+        let input = UserInput::default();
         let state = InputState::Empty;
-        let (command, state) = UserInput::apply_key_event(KeyCode::Char('G').into(), state);
+        let (command, state) = input.apply_key_event(KeyCode::Char('G').into(), state);
         assert_eq!(command, Some(UserCommand::JumpLastSlide));
         assert_eq!(state, InputState::Empty);
     }
 
+    #[test]
+    fn full_slide_navigation() {
+        let input = UserInput::default();
+        let (command, _) = input.apply_key_event(KeyCode::PageDown.into(), InputState::Empty);
+        assert_eq!(command, Some(UserCommand::JumpNextFullSlide));
+
+        let (command, _) = input.apply_key_event(KeyCode::PageUp.into(), InputState::Empty);
+        assert_eq!(command, Some(UserCommand::JumpPreviousFullSlide));
+
+        let (command, _) = input.apply_key_event(KeyCode::Char(' ').into(), InputState::Empty);
+        assert_eq!(command, Some(UserCommand::JumpNextSlide));
+    }
+
+    #[test]
+    fn marks() {
+        let input = UserInput::default();
+        let (command, state) = input.apply_key_event(KeyCode::Char('m').into(), InputState::Empty);
+        assert!(command.is_none());
+        let (command, state) = input.apply_key_event(KeyCode::Char('a').into(), state);
+        assert_eq!(command, Some(UserCommand::SetMark('a')));
+        assert_eq!(state, InputState::Empty);
+
+        let (command, state) = input.apply_key_event(KeyCode::Char('\'').into(), InputState::Empty);
+        assert!(command.is_none());
+        let (command, state) = input.apply_key_event(KeyCode::Char('\'').into(), state);
+        assert_eq!(command, Some(UserCommand::JumpToMark('\'')));
+        assert_eq!(state, InputState::Empty);
+
+        // Anything other than a letter cancels it.
+        let (_, state) = input.apply_key_event(KeyCode::Char('m').into(), InputState::Empty);
+        let (command, state) = input.apply_key_event(KeyCode::Char('1').into(), state);
+        assert!(command.is_none());
+        assert_eq!(state, InputState::Empty);
+    }
+
     #[test]
     fn jump_number() {
+        let input = UserInput::default();
         let state = InputState::Empty;
-        let (command, state) = UserInput::apply_key_event(KeyCode::Char('1').into(), state);
+        let (command, state) = input.apply_key_event(KeyCode::Char('1').into(), state);
         assert!(command.is_none());
-        assert_eq!(state, InputState::PendingNumber(1));
+        assert_eq!(state, pending("1"));
 
-        let (command, state) = UserInput::apply_key_event(KeyCode::Char('2').into(), state);
+        let (command, state) = input.apply_key_event(KeyCode::Char('2').into(), state);
         assert!(command.is_none());
-        assert_eq!(state, InputState::PendingNumber(12));
+        assert_eq!(state, pending("12"));
 
-        let (command, state) = UserInput::apply_key_event(KeyCode::Char('G').into(), state);
+        let (command, state) = input.apply_key_event(KeyCode::Char('G').into(), state);
         assert_eq!(command, Some(UserCommand::JumpSlide(12)));
         assert_eq!(state, InputState::Empty);
     }
+
+    #[test]
+    fn key_release_ignored() {
+        let input = UserInput::default();
+        let mut event = KeyEvent::from(KeyCode::Right);
+        event.kind = KeyEventKind::Release;
+        let (command, state) = input.apply_key_event(event, pending("3"));
+        assert!(command.is_none());
+        assert_eq!(state, pending("3"));
+    }
+
+    #[test]
+    fn kill_code() {
+        let input = UserInput::default();
+        let event = KeyEvent::new(KeyCode::Char('k'), KeyModifiers::CONTROL);
+        let (command, state) = input.apply_key_event(event, pending("g"));
+        assert_eq!(command, Some(UserCommand::KillCode));
+        assert_eq!(state, InputState::Empty);
+
+        let (command, _) = input.apply_key_event(KeyCode::Char('k').into(), InputState::Empty);
+        assert_eq!(command, Some(UserCommand::JumpPreviousSlide));
+    }
+
+    #[test]
+    fn suspend() {
+        let input = UserInput::default();
+        let event = KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL);
+        let (command, state) = input.apply_key_event(event, pending("g"));
+        assert_eq!(command, Some(UserCommand::Suspend));
+        assert_eq!(state, InputState::Empty);
+    }
+
+    #[test]
+    fn toggle_next_slide_preview() {
+        let input = UserInput::default();
+        let (command, state) = input.apply_key_event(KeyCode::Char('p').into(), pending("2"));
+        assert_eq!(command, Some(UserCommand::ToggleNextSlidePreview));
+        assert_eq!(state, InputState::Empty);
+    }
+
+    #[test]
+    fn go_to_prompt() {
+        let input = UserInput::default();
+        let (command, state) = input.apply_key_event(KeyCode::Char(':').into(), InputState::Empty);
+        assert_eq!(command, Some(UserCommand::UpdatePrompt(Some("".into()))));
+
+        let (command, state) = input.apply_key_event(KeyCode::Char('1').into(), state);
+        assert_eq!(command, Some(UserCommand::UpdatePrompt(Some("1".into()))));
+
+        let (command, state) = input.apply_key_event(KeyCode::Char('2').into(), state);
+        let (command2, state) = input.apply_key_event(KeyCode::Backspace.into(), state);
+        assert_eq!(command, Some(UserCommand::UpdatePrompt(Some("12".into()))));
+        assert_eq!(command2, Some(UserCommand::UpdatePrompt(Some("1".into()))));
+
+        // Navigation keys are just text while in the prompt.
+        let (command, state) = input.apply_key_event(KeyCode::Char('G').into(), state);
+        assert_eq!(command, Some(UserCommand::UpdatePrompt(Some("1G".into()))));
+
+        let (command, state) = input.apply_key_event(KeyCode::Enter.into(), state);
+        assert_eq!(command, Some(UserCommand::GoTo("1G".into())));
+        assert_eq!(state, InputState::Empty);
+    }
+
+    #[test]
+    fn cancel_prompt() {
+        let input = UserInput::default();
+        let (_, state) = input.apply_key_event(KeyCode::Char(':').into(), InputState::Empty);
+        let (command, state) = input.apply_key_event(KeyCode::Esc.into(), state);
+        assert_eq!(command, Some(UserCommand::UpdatePrompt(None)));
+        assert_eq!(state, InputState::Empty);
+    }
+
+    #[test]
+    fn blank_screen() {
+        let input = UserInput::default();
+        let (command, state) = input.apply_key_event(KeyCode::Char('w').into(), InputState::Empty);
+        assert_eq!(command, Some(UserCommand::BlankScreen(Color::new(255, 255, 255))));
+        assert_eq!(state, InputState::Empty);
+    }
+
+    #[test]
+    fn toggle_hot_reload() {
+        let input = UserInput::default();
+        let (command, state) = input.apply_key_event(KeyCode::Char('f').into(), pending("g"));
+        assert_eq!(command, Some(UserCommand::ToggleHotReload));
+        assert_eq!(state, InputState::Empty);
+    }
+
+    #[test]
+    fn switch_decks() {
+        let input = UserInput::default();
+        let (command, state) = input.apply_key_event(KeyCode::Char('N').into(), pending("g"));
+        assert_eq!(command, Some(UserCommand::NextDeck));
+        assert_eq!(state, InputState::Empty);
+
+        let (command, _) = input.apply_key_event(KeyCode::Char('P').into(), InputState::Empty);
+        assert_eq!(command, Some(UserCommand::PreviousDeck));
+    }
+
+    #[test]
+    fn exit() {
+        let input = UserInput::default();
+        let (command, state) = input.apply_key_event(KeyCode::Char('q').into(), pending("1"));
+        assert_eq!(command, Some(UserCommand::Exit));
+        assert_eq!(state, InputState::Empty);
+    }
+
+    #[test]
+    fn custom_bindings() {
+        let config = KeyBindingsConfig {
+            next: vec!["<c-n>".parse().unwrap()],
+            exit: vec!["ZZ".parse().unwrap()],
+            ..Default::default()
+        };
+        let input = UserInput::new(&config);
+        let event = KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL);
+        let (command, _) = input.apply_key_event(event, InputState::Empty);
+        assert_eq!(command, Some(UserCommand::JumpNextSlide));
+
+        // The default bindings are gone.
+        let (command, state) = input.apply_key_event(KeyCode::Char('l').into(), InputState::Empty);
+        assert!(command.is_none());
+        assert_eq!(state, InputState::Empty);
+
+        let (command, state) = input.apply_key_event(KeyCode::Char('Z').into(), InputState::Empty);
+        assert!(command.is_none());
+        let (command, state) = input.apply_key_event(KeyCode::Char('Z').into(), state);
+        assert_eq!(command, Some(UserCommand::Exit));
+        assert_eq!(state, InputState::Empty);
+    }
 }
@@ -2,14 +2,35 @@
 //!
 //! This is not meant to be used as a crate!
 
+pub mod advance;
 pub mod builder;
+pub mod bundle;
+pub mod capabilities;
+pub mod compat;
+pub mod config;
 pub mod diff;
+pub mod execute;
+pub mod export;
+pub mod hooks;
 pub mod input;
+pub mod logging;
 pub mod markdown;
+pub mod marks;
+pub mod picker;
 pub mod presentation;
 pub mod presenter;
+pub mod preview;
+pub mod profile;
+pub mod record;
 pub mod render;
+pub mod replay;
 pub mod resource;
+pub mod resume;
+pub mod scaffold;
+pub mod stats;
+pub mod status;
+pub mod stopwatch;
 pub mod style;
 pub mod theme;
+pub mod yaml;
 pub mod splash;
@@ -0,0 +1,61 @@
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::Path,
+    sync::Mutex,
+    time::Instant,
+};
+
+/// A logger that writes every log line into a file.
+///
+/// We can't log into stdout/stderr as that would mess up the presentation, so instead logs are
+/// written into a file that can be attached when reporting issues.
+pub struct FileLogger {
+    file: Mutex<File>,
+    start: Instant,
+}
+
+impl FileLogger {
+    /// Initialize a file logger as the global logger.
+    pub fn init<P: AsRef<Path>>(path: P, level: LevelFilter) -> Result<(), InitLoggerError> {
+        let file = File::create(path)?;
+        let logger = Self { file: Mutex::new(file), start: Instant::now() };
+        log::set_boxed_logger(Box::new(logger))?;
+        log::set_max_level(level);
+        Ok(())
+    }
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let Ok(mut file) = self.file.lock() else {
+            return;
+        };
+        let _ = writeln!(file, "{elapsed:.6} {:<5} {}: {}", record.level(), record.target(), record.args());
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// An error initializing the logger.
+#[derive(thiserror::Error, Debug)]
+pub enum InitLoggerError {
+    #[error("io: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("logger already set: {0}")]
+    AlreadySet(#[from] SetLoggerError),
+}
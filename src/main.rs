@@ -1,23 +1,52 @@
-use clap::{error::ErrorKind, CommandFactory, Parser};
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
 use comrak::Arena;
+use log::LevelFilter;
+#[cfg(unix)]
+use presenterm::input::socket::SocketCommandListener;
 use presenterm::{
-    input::source::CommandSource,
+    advance::parse_duration,
+    bundle::Bundle,
+    capabilities::TerminalCapabilities,
+    config::Config,
+    export::Exporter,
+    input::{http::HttpControlServer, source::CommandSource},
+    logging::FileLogger,
     markdown::parse::MarkdownParser,
+    picker::{find_decks, DeckPicker},
     presenter::{PresentMode, Presenter},
-    render::highlighting::CodeHighlighter,
+    preview::theme_preview,
+    profile,
+    record::AsciicastRecorder,
+    render::{highlighting::CodeHighlighter, properties::WindowSize},
+    replay::{Player, Recording},
     resource::Resources,
+    resume::ResumeFile,
+    scaffold::PresentationScaffold,
     splash::show_splashes,
     theme::PresentationTheme,
 };
-use std::path::{Path, PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    slice,
+    time::Duration,
+};
+use tempfile::TempDir;
 
 /// Run slideshows from your terminal.
 #[derive(Parser)]
 #[command()]
 #[command(author, version, about = show_splashes(), long_about = show_splashes(), arg_required_else_help = true)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Cli {
-    /// The path to the markdown file that contains the presentation.
-    path: PathBuf,
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+
+    /// The paths to the markdown files that contain the presentations, or presentation bundles.
+    ///
+    /// When more than one is provided, they are presented one after the other. When a directory is
+    /// provided, a picker for the presentations in it is displayed instead.
+    paths: Vec<PathBuf>,
 
     /// Whether to use presentation mode.
     #[clap(short, long, default_value_t = false)]
@@ -26,15 +55,268 @@ struct Cli {
     /// The theme to use.
     #[clap(short, long, default_value = "dark")]
     theme: String,
+
+    /// Interpret the presentation the way maaslalani/slides does: every `---` ends a slide and code
+    /// blocks can be executed using ctrl+e.
+    #[clap(long, default_value_t = false)]
+    slides_compat: bool,
+
+    /// Write debug logs into this file.
+    #[clap(long)]
+    log_file: Option<PathBuf>,
+
+    /// Print timing statistics once the presentation is over.
+    #[clap(long, default_value_t = false)]
+    stats: bool,
+
+    /// Write timing statistics to this file once the presentation is over.
+    #[clap(long)]
+    stats_file: Option<PathBuf>,
+
+    /// Measure the time spent parsing, building and drawing, and print a summary once the
+    /// presentation is over.
+    #[clap(long, default_value_t = false)]
+    profile: bool,
+
+    /// Start the presentation at the slide it was left at last time it was presented with this
+    /// flag, and remember where it's left at this time.
+    #[clap(long, default_value_t = false)]
+    resume: bool,
+
+    /// Move to the next slide on its own after each slide has been displayed for this long, e.g. `30s`.
+    ///
+    /// Slides can override this via the `duration` comment command.
+    #[clap(long, value_parser = parse_duration)]
+    auto_advance: Option<Duration>,
+
+    /// Record the presentation into this file in asciicast v2 format so it can be replayed later on.
+    #[clap(long)]
+    record: Option<PathBuf>,
+
+    /// Export the presentation into this file and exit, without presenting it.
+    ///
+    /// The export format is determined by the file's extension: either plain text (`.txt`), an
+    /// animated GIF (`.gif`), a PDF document (`.pdf`), or an HTML document (`.html`).
+    #[clap(long)]
+    export: Option<PathBuf>,
+
+    /// Export the presentation into a PDF document next to it and exit, without presenting it.
+    #[clap(long, default_value_t = false, conflicts_with = "export")]
+    export_pdf: bool,
+
+    /// Export the presentation into this file every time it changes, without presenting it.
+    ///
+    /// The export format is determined the same way as in `--export`.
+    #[clap(long)]
+    watch_export: Option<PathBuf>,
+
+    /// Print the features the terminal supports, like image protocols, and exit.
+    #[clap(long, default_value_t = false)]
+    capabilities: bool,
+
+    /// The path to a unix socket to listen on for navigation commands.
+    #[cfg(unix)]
+    #[clap(long)]
+    socket: Option<PathBuf>,
+
+    /// The port to listen on for HTTP navigation commands.
+    #[clap(long)]
+    control_port: Option<u16>,
+}
+
+#[derive(Subcommand)]
+enum CliCommand {
+    /// Pack a presentation and every file it references into a single bundle.
+    Bundle {
+        /// The path to the markdown file that contains the presentation.
+        path: PathBuf,
+
+        /// The path to write the bundle to.
+        output: PathBuf,
+    },
+
+    /// Create a new presentation out of a template.
+    ///
+    /// The presentation's author, theme, and footer are taken from the `new` section in the config
+    /// file, if any.
+    New {
+        /// The path to write the presentation to.
+        path: PathBuf,
+
+        /// Also create a custom theme for the presentation, based on the configured one, so it can
+        /// be tweaked.
+        #[clap(long, default_value_t = false)]
+        theme_stub: bool,
+    },
+
+    /// Display a presentation that showcases what every element looks like using a built-in theme.
+    ThemePreview {
+        /// The name of the theme.
+        name: String,
+    },
+
+    /// Replay a presentation recorded using `--record`.
+    Replay {
+        /// The path to the recording.
+        path: PathBuf,
+    },
+}
+
+/// A bundle extracted into a temporary directory, which is removed when this is dropped.
+struct ExtractedBundle {
+    // Removes the extracted files when dropped.
+    _directory: TempDir,
+    presentation_path: PathBuf,
+}
+
+impl ExtractedBundle {
+    fn new(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let file = fs::File::open(path)?;
+        let bundle = Bundle::read(file)?;
+        let directory = tempfile::Builder::new().prefix("presenterm-bundle-").tempdir()?;
+        let presentation_path = bundle.extract(directory.path())?;
+        Ok(Self { _directory: directory, presentation_path })
+    }
+}
+
+fn create_bundle(path: &Path, output: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let bundle = Bundle::from_presentation(path)?;
+    let file = fs::File::create(output).map_err(|e| format!("creating {}: {e}", output.display()))?;
+    bundle.write(file)?;
+    for path in bundle.paths() {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
+    let config = match Config::default_path() {
+        Some(config_path) => {
+            Config::load(&config_path).map_err(|e| format!("loading config {}: {e}", config_path.display()))?
+        }
+        None => Config::default(),
+    };
+    Ok(config)
 }
 
-fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
-    let Some(default_theme) = PresentationTheme::from_name(&cli.theme) else {
+fn create_presentation(path: &Path, theme_stub: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let config = load_config()?;
+    let files = PresentationScaffold::new(&config.new).with_theme_stub(theme_stub).create(path)?;
+    for path in files {
+        println!("created {}", path.display());
+    }
+    Ok(())
+}
+
+fn load_theme(name: &str) -> PresentationTheme {
+    let Some(theme) = PresentationTheme::from_name(name) else {
         let mut cmd = Cli::command();
         let valid_themes = PresentationTheme::theme_names().collect::<Vec<_>>().join(", ");
         let error_message = format!("invalid theme name, valid themes are: {valid_themes}");
         cmd.error(ErrorKind::InvalidValue, error_message).exit();
     };
+    theme
+}
+
+fn preview_theme(cli: &Cli, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let theme = load_theme(name);
+    let directory = tempfile::Builder::new().prefix("presenterm-preview-").tempdir()?;
+    let path = directory.path().join("preview.md");
+    fs::write(&path, theme_preview(name))?;
+    present(cli, &[path], &theme)
+}
+
+fn run(mut cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(path) = &cli.log_file {
+        FileLogger::init(path, LevelFilter::Debug).map_err(|e| format!("initializing logger: {e}"))?;
+    }
+    if cli.profile {
+        profile::enable();
+    }
+    let config = load_config()?;
+    TerminalCapabilities::configure(config.terminal.clone());
+    match &cli.command {
+        Some(CliCommand::Bundle { path, output }) => return create_bundle(path, output),
+        Some(CliCommand::New { path, theme_stub }) => return create_presentation(path, *theme_stub),
+        Some(CliCommand::ThemePreview { name }) => return preview_theme(&cli, name),
+        Some(CliCommand::Replay { path }) => {
+            let recording = Recording::load(path).map_err(|e| format!("loading {}: {e}", path.display()))?;
+            Player::new(recording).play()?;
+            return Ok(());
+        }
+        None => (),
+    };
+    if cli.capabilities {
+        print!("{}", TerminalCapabilities::detect(&config.terminal));
+        return Ok(());
+    }
+    if cli.paths.is_empty() {
+        Cli::command().error(ErrorKind::MissingRequiredArgument, "no presentation path provided").exit();
+    }
+    if cli.paths.len() > 1 && (cli.resume || cli.export.is_some() || cli.export_pdf || cli.watch_export.is_some()) {
+        let message = "--resume, --export, --export-pdf, and --watch-export only work with a single presentation";
+        Cli::command().error(ErrorKind::ArgumentConflict, message).exit();
+    }
+    if cli.export_pdf {
+        cli.export = Some(cli.paths[0].with_extension("pdf"));
+    }
+    let default_theme = load_theme(&cli.theme);
+    match cli.paths.as_slice() {
+        [directory] if directory.is_dir() => pick_presentations(&cli, directory, &default_theme),
+        paths => present(&cli, paths, &default_theme),
+    }
+}
+
+/// Show a picker for the presentations in a directory, presenting every one of them that's picked.
+fn pick_presentations(cli: &Cli, path: &Path, theme: &PresentationTheme) -> Result<(), Box<dyn std::error::Error>> {
+    if cli.export.is_some() || cli.watch_export.is_some() {
+        Cli::command().error(ErrorKind::ArgumentConflict, "a directory can't be exported").exit();
+    }
+    let decks = find_decks(path).map_err(|e| format!("reading {}: {e}", path.display()))?;
+    if decks.is_empty() {
+        return Err(format!("no presentations found in {}", path.display()).into());
+    }
+    let mut picker = DeckPicker::new(decks);
+    while let Some(deck_path) = picker.pick()? {
+        // Errors are displayed in the picker so another presentation can be picked.
+        if let Err(e) = present(cli, slice::from_ref(&deck_path), theme) {
+            picker.set_message(format!("failed to run {}: {e}", deck_path.display()));
+        }
+    }
+    Ok(())
+}
+
+fn present(cli: &Cli, paths: &[PathBuf], theme: &PresentationTheme) -> Result<(), Box<dyn std::error::Error>> {
+    let mut extracted_bundles = Vec::new();
+    let mut presentation_paths = Vec::new();
+    for path in paths {
+        if Bundle::is_bundle(path) {
+            let bundle = ExtractedBundle::new(path).map_err(|e| format!("reading bundle: {e}"))?;
+            presentation_paths.push(bundle.presentation_path.clone());
+            extracted_bundles.push(bundle);
+        } else {
+            presentation_paths.push(path.clone());
+        }
+    }
+    let path = presentation_paths[0].clone();
+    let config = load_config()?;
+
+    if cli.export.is_some() || cli.watch_export.is_some() {
+        let arena = Arena::new();
+        let parser = MarkdownParser::new(&arena);
+        let default_highlighter = CodeHighlighter::new("base16-ocean.dark")?;
+        let resources = Resources::new(path.parent().unwrap_or(Path::new("/")));
+        let mut exporter = Exporter::new(parser, theme, default_highlighter, resources)
+            .with_default_watermark(config.watermark.clone());
+        if let Some(output) = &cli.export {
+            exporter.export(&path, output)?;
+        }
+        if let Some(output) = &cli.watch_export {
+            println!("exporting presentation into {} every time it changes", output.display());
+            exporter.watch(&path, output, |e| eprintln!("failed to export presentation: {e}"))?;
+        }
+        return Ok(());
+    }
 
     let mode = match cli.present {
         true => PresentMode::Presentation,
@@ -43,12 +325,51 @@ fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
     let arena = Arena::new();
     let parser = MarkdownParser::new(&arena);
     let default_highlighter = CodeHighlighter::new("base16-ocean.dark")?;
-    let resources_path = cli.path.parent().unwrap_or(Path::new("/"));
-    let resources = Resources::new(resources_path);
-    let commands = CommandSource::new(&cli.path);
+    #[allow(unused_mut)]
+    let mut commands = CommandSource::new(&path).with_key_bindings(&config.bindings);
+    let resources_path = path.parent().unwrap_or(Path::new("/"));
+    let resources = Resources::new(resources_path).with_async_images(commands.sender());
+    #[cfg(unix)]
+    if let Some(socket_path) = &cli.socket {
+        let listener = SocketCommandListener::bind(socket_path)
+            .map_err(|e| format!("binding socket {}: {e}", socket_path.display()))?;
+        commands = commands.with_socket_listener(listener);
+    }
+    let control_server = cli
+        .control_port
+        .map(|port| HttpControlServer::bind(port, commands.sender()).map_err(|e| format!("binding port {port}: {e}")))
+        .transpose()?;
 
-    let presenter = Presenter::new(&default_theme, default_highlighter, commands, parser, resources, mode);
-    presenter.present(&cli.path)?;
+    let mut presenter = Presenter::new(theme, default_highlighter, commands, parser, resources, mode)
+        .with_slides_compatibility(cli.slides_compat)
+        .with_video_player(config.video.player())
+        .with_minimum_size(config.minimum_size)
+        .with_default_watermark(config.watermark);
+    if let Some(server) = control_server {
+        presenter = presenter.with_control_server(server);
+    }
+    if cli.resume {
+        // Bundles are extracted into a temporary directory so use the bundle's path instead.
+        presenter = presenter.with_resume_file(ResumeFile::for_presentation(&paths[0]));
+    }
+    if let Some(record_path) = &cli.record {
+        let dimensions = WindowSize::current()?;
+        let recorder = AsciicastRecorder::create(record_path, &dimensions)
+            .map_err(|e| format!("creating recording {}: {e}", record_path.display()))?;
+        presenter = presenter.with_recorder(recorder);
+    }
+    if let Some(duration) = cli.auto_advance {
+        presenter = presenter.with_auto_advance(duration);
+    }
+    let stats = presenter.present_all(&presentation_paths)?;
+    if let Some(path) = &cli.stats_file {
+        fs::write(path, stats.to_string()).map_err(|e| format!("writing stats to {}: {e}", path.display()))?;
+    } else if cli.stats {
+        print!("{stats}");
+    }
+    if let Some(profile) = profile::report() {
+        print!("{profile}");
+    }
     Ok(())
 }
 
@@ -1,13 +1,17 @@
+use super::width::DisplayWidth;
 use crate::style::TextStyle;
-use std::{iter, path::PathBuf};
+use std::{
+    iter,
+    ops::{Range, RangeInclusive},
+    path::PathBuf,
+};
 use strum::EnumIter;
-use unicode_width::UnicodeWidthStr;
 
 /// A markdown element.
 ///
 /// This represents each of the supported markdown elements. The structure here differs a bit from
 /// the spec, mostly in how inlines are handled, to simplify its processing.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum MarkdownElement {
     /// The front matter that optionally shows up at the beginning of the file.
     FrontMatter(String),
@@ -22,7 +26,19 @@ pub enum MarkdownElement {
     Paragraph(Vec<ParagraphElement>),
 
     /// An image.
-    Image(PathBuf),
+    Image {
+        /// The path to the image.
+        path: PathBuf,
+
+        /// The image's title, if any.
+        title: String,
+
+        /// The image's alt text, if any.
+        alt_text: String,
+
+        /// The attributes defined in the image's title, if any.
+        attributes: ImageAttributes,
+    },
 
     /// A list.
     ///
@@ -39,10 +55,19 @@ pub enum MarkdownElement {
     ThematicBreak,
 
     /// An HTML comment.
-    Comment(String),
+    Comment { comment: String, source_position: SourcePosition },
 
     /// A quote.
-    BlockQuote(Vec<String>),
+    ///
+    /// Every element is a line in the quote, which keeps any inline formatting within it.
+    BlockQuote(Vec<Text>),
+}
+
+/// The position of an element in the source file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SourcePosition {
+    /// The 1-based line the element starts at.
+    pub line: usize,
 }
 
 /// The components that make up a paragraph.
@@ -62,7 +87,7 @@ pub enum ParagraphElement {
 /// A piece of styled text.
 ///
 /// Text is represented as a series of chunks, each with their own formatting.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Text {
     /// The chunks that make up this text.
     pub chunks: Vec<StyledText>,
@@ -71,7 +96,7 @@ pub struct Text {
 impl Text {
     /// Get the total width for this text.
     pub fn width(&self) -> usize {
-        self.chunks.iter().map(|text| text.text.width()).sum()
+        self.chunks.iter().map(|text| text.text.display_width()).sum()
     }
 
     /// Applies the given style to this text.
@@ -129,6 +154,15 @@ pub struct ListItem {
 
     /// The type of list item.
     pub item_type: ListItemType,
+
+    /// The color this item is displayed in, as set via a trailing `{color=<name>}` attribute.
+    pub color: Option<String>,
+
+    /// Any block elements within this item after its first paragraph, like code blocks or images.
+    ///
+    /// These are displayed below the item, indented to match its contents. Nested lists are only
+    /// part of these when they're followed by some other block element.
+    pub children: Vec<MarkdownElement>,
 }
 
 /// The type of a list item.
@@ -152,6 +186,95 @@ pub struct Code {
 
     /// The programming language this code is written in.
     pub language: ProgrammingLanguage,
+
+    /// The flags that modify how this code is handled.
+    pub flags: CodeFlags,
+}
+
+/// The flags that can be attached to a piece of code.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CodeFlags {
+    /// Whether this code can be executed.
+    pub execute: bool,
+
+    /// Whether whitespace should be made visible when rendering this code.
+    pub show_whitespace: bool,
+
+    /// The maximum number of lines to display at once.
+    ///
+    /// Longer code is clipped and scrolled through as the presentation advances.
+    pub max_height: Option<usize>,
+
+    /// The directory to run this code in when executing it.
+    pub cwd: Option<PathBuf>,
+
+    /// The environment variables to set when executing this code.
+    pub env: Vec<(String, String)>,
+
+    /// Whether this code is hidden.
+    ///
+    /// Hidden code is never displayed but it's executed before any other code in the same slide, which
+    /// allows using it to set up whatever the rest of the code in the slide needs.
+    pub hidden: bool,
+
+    /// Whether this code is executed automatically as soon as its slide is displayed.
+    pub auto_execute: bool,
+
+    /// The name this code can be referenced by in code blocks that come after it.
+    pub id: Option<String>,
+
+    /// The names of the code blocks to prepend to this code when executing it.
+    pub include: Vec<String>,
+
+    /// Whether to display the number of every line next to it.
+    pub line_numbers: bool,
+
+    /// The groups of lines to highlight, one after the other, while dimming the rest.
+    ///
+    /// These are written like `{1,3-5|7}` and every group after the first one is revealed as if
+    /// there was a pause before it.
+    pub highlight_groups: Vec<HighlightGroup>,
+}
+
+/// A group of lines in a piece of code that are highlighted together.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HighlightGroup {
+    /// Every line.
+    All,
+
+    /// The lines in these 1 based ranges.
+    Lines(Vec<RangeInclusive<usize>>),
+}
+
+impl HighlightGroup {
+    /// Check whether the given 1 based line number is part of this group.
+    pub fn contains(&self, line: usize) -> bool {
+        match self {
+            Self::All => true,
+            Self::Lines(ranges) => ranges.iter().any(|range| range.contains(&line)),
+        }
+    }
+}
+
+/// The attributes that can be attached to an image via its title.
+///
+/// These are defined as a comma separated list of `key=value` pairs, like `width=50%,align=left`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImageAttributes {
+    /// The width of the image, as a percentage of the available width.
+    pub width: Option<u8>,
+
+    /// The horizontal alignment of the image.
+    pub alignment: Option<ImageAlignment>,
+}
+
+/// The horizontal alignment of an image.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImageAlignment {
+    Left,
+    #[default]
+    Center,
+    Right,
 }
 
 /// A programming language.
@@ -200,6 +323,9 @@ pub struct Table {
 
     /// All of the rows in this table, excluding the header.
     pub rows: Vec<TableRow>,
+
+    /// The table's caption, taken from an emphasized line right after it.
+    pub caption: Option<Text>,
 }
 
 impl Table {
@@ -216,6 +342,21 @@ impl Table {
         let row_elements = self.rows.iter().map(move |row| &row.0[column]);
         iter::once(header_element).chain(row_elements)
     }
+
+    /// Get the range of columns each header cell spans.
+    ///
+    /// Empty header cells are merged into the one before them, which lets a single header cell
+    /// group several columns.
+    pub fn header_spans(&self) -> Vec<Range<usize>> {
+        let mut spans: Vec<Range<usize>> = Vec::new();
+        for (column, text) in self.header.0.iter().enumerate() {
+            match spans.last_mut() {
+                Some(span) if text.width() == 0 => span.end = column + 1,
+                _ => spans.push(column..column + 1),
+            };
+        }
+        spans
+    }
 }
 
 /// A table row.
@@ -1,3 +1,4 @@
 pub mod elements;
 pub mod parse;
 pub mod text;
+pub mod width;
@@ -1,8 +1,9 @@
 use crate::{
     markdown::elements::{
-        Code, ListItem, ListItemType, MarkdownElement, ParagraphElement, ProgrammingLanguage, StyledText, Table,
-        TableRow, Text,
+        Code, CodeFlags, HighlightGroup, ImageAlignment, ImageAttributes, ListItem, ListItemType, MarkdownElement,
+        ParagraphElement, ProgrammingLanguage, SourcePosition, StyledText, Table, TableRow, Text,
     },
+    profile::{self, Phase},
     style::TextStyle,
 };
 use comrak::{
@@ -12,10 +13,12 @@ use comrak::{
     },
     parse_document, Arena, ComrakOptions, ListStyleType,
 };
+use log::debug;
 use std::{
     fmt::{self, Debug, Display},
     io::BufWriter,
     mem,
+    time::Instant,
 };
 
 /// The result of parsing a markdown file.
@@ -49,12 +52,27 @@ impl<'a> MarkdownParser<'a> {
 
     /// Parse the contents of a markdown file.
     pub fn parse(&self, contents: &str) -> ParseResult<Vec<MarkdownElement>> {
+        let _measurement = profile::measure(Phase::Parse);
+        let start = Instant::now();
         let node = parse_document(self.arena, contents, &self.options);
         let mut elements = Vec::new();
         for node in node.children() {
-            let element = Self::parse_node(node)?;
-            elements.extend(element);
+            for element in Self::parse_node(node)? {
+                // An emphasized line right after a table is its caption.
+                if let (Some(MarkdownElement::Table(table)), MarkdownElement::Paragraph(paragraph)) =
+                    (elements.last_mut(), &element)
+                {
+                    if table.caption.is_none() {
+                        if let Some(caption) = Self::table_caption(paragraph) {
+                            table.caption = Some(caption);
+                            continue;
+                        }
+                    }
+                }
+                elements.push(element);
+            }
         }
+        debug!("parsed markdown bytes={} elements={} elapsed={:?}", contents.len(), elements.len(), start.elapsed());
         Ok(elements)
     }
 
@@ -97,10 +115,44 @@ impl<'a> MarkdownParser<'a> {
         }
         let block = &block[start_tag.len()..];
         let block = &block[0..block.len() - end_tag.len()];
-        Ok(MarkdownElement::Comment(block.into()))
+        let source_position = SourcePosition { line: sourcepos.start.line };
+        Ok(MarkdownElement::Comment { comment: block.into(), source_position })
     }
 
     fn parse_block_quote(node: &'a AstNode<'a>) -> ParseResult<MarkdownElement> {
+        let mut lines = Vec::new();
+        for (index, child) in node.children().enumerate() {
+            if index > 0 {
+                lines.push(Text::default());
+            }
+            if matches!(child.data.borrow().value, NodeValue::Paragraph) {
+                lines.extend(Self::parse_block_quote_paragraph(child)?);
+            } else {
+                // Anything other than paragraphs, like lists or nested quotes, is displayed as is.
+                lines.extend(Self::format_commonmark(child)?.into_iter().map(Text::from));
+            }
+        }
+        Ok(MarkdownElement::BlockQuote(lines))
+    }
+
+    fn parse_block_quote_paragraph(node: &'a AstNode<'a>) -> ParseResult<Vec<Text>> {
+        let inlines = InlinesParser::default().with_soft_breaks(true).parse(node)?;
+        let mut lines = vec![Text::default()];
+        for inline in inlines {
+            match inline {
+                Inline::Text(text) => lines.last_mut().expect("no lines").chunks.extend(text.chunks),
+                Inline::LineBreak => lines.push(Text::default()),
+                // Images can't be displayed inside a quote so their alt text is used instead.
+                Inline::Image { alt_text, .. } if !alt_text.is_empty() => {
+                    lines.last_mut().expect("no lines").chunks.push(StyledText::from(alt_text))
+                }
+                Inline::Image { .. } => (),
+            };
+        }
+        Ok(lines)
+    }
+
+    fn format_commonmark(node: &'a AstNode<'a>) -> ParseResult<Vec<String>> {
         let mut buffer = BufWriter::new(Vec::new());
         let mut options = ParserOptions::default().0;
         options.render.list_style = ListStyleType::Star;
@@ -108,23 +160,34 @@ impl<'a> MarkdownParser<'a> {
             .map_err(|e| ParseErrorKind::Internal(e.to_string()).with_sourcepos(node.data.borrow().sourcepos))?;
 
         let buffer = buffer.into_inner().expect("unwrapping writer failed");
-        let mut lines = Vec::new();
-        for line in String::from_utf8_lossy(&buffer).lines() {
-            let line = match line.find('>') {
-                Some(index) => line[index + 1..].trim(),
-                None => line,
-            };
-            lines.push(line.to_string());
+        let contents = String::from_utf8_lossy(&buffer);
+        let mut lines: Vec<_> = contents.lines().map(|line| line.trim_end().to_string()).collect();
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
         }
-        Ok(MarkdownElement::BlockQuote(lines))
+        Ok(lines)
     }
 
     fn parse_code_block(block: &NodeCodeBlock, sourcepos: Sourcepos) -> ParseResult<MarkdownElement> {
         if !block.fenced {
             return Err(ParseErrorKind::UnfencedCodeBlock.with_sourcepos(sourcepos));
         }
+        let mut tokens = block.info.split_whitespace();
+        let language = tokens.next().unwrap_or_default();
+        if tokens.clone().any(|token| token == "+table") {
+            let delimiter = match language {
+                "csv" => ',',
+                "tsv" => '\t',
+                _ => return Err(ParseErrorKind::InvalidCodeAttribute("+table".into()).with_sourcepos(sourcepos)),
+            };
+            if let Some(attribute) = tokens.find(|token| token.starts_with('+') && *token != "+table") {
+                return Err(ParseErrorKind::InvalidCodeAttribute(attribute.into()).with_sourcepos(sourcepos));
+            }
+            return Self::parse_delimited_table(&block.literal, delimiter)
+                .map_err(|e| ParseErrorKind::InvalidTableData(e).with_sourcepos(sourcepos));
+        }
         use ProgrammingLanguage::*;
-        let language = match block.info.as_str() {
+        let language = match language {
             "asp" => Asp,
             "bash" => Bash,
             "c" => C,
@@ -158,10 +221,134 @@ impl<'a> MarkdownParser<'a> {
             "yaml" => Yaml,
             _ => Unknown,
         };
-        let code = Code { contents: block.literal.clone(), language };
+        let mut flags = CodeFlags::default();
+        if let Some(id) = tokens.clone().find_map(|token| token.strip_prefix("id=")) {
+            if id.is_empty() {
+                return Err(ParseErrorKind::InvalidCodeAttribute("id=".into()).with_sourcepos(sourcepos));
+            }
+            flags.id = Some(id.into());
+        }
+        if let Some(groups) = tokens.clone().find(|token| token.starts_with('{')) {
+            flags.highlight_groups = Self::parse_highlight_groups(groups)
+                .ok_or_else(|| ParseErrorKind::InvalidCodeAttribute(groups.into()).with_sourcepos(sourcepos))?;
+        }
+        for attribute in tokens.filter(|token| token.starts_with('+')) {
+            match attribute {
+                "+exec" => flags.execute = true,
+                "+show_whitespace" => flags.show_whitespace = true,
+                "+hidden" => flags.hidden = true,
+                "+auto" => flags.auto_execute = true,
+                "+line_numbers" => flags.line_numbers = true,
+                _ if attribute.starts_with("+max_height=") => {
+                    let height = attribute["+max_height=".len()..].parse().ok().filter(|height| *height > 0);
+                    let height = height.ok_or_else(|| {
+                        ParseErrorKind::InvalidCodeAttribute(attribute.into()).with_sourcepos(sourcepos)
+                    })?;
+                    flags.max_height = Some(height);
+                }
+                _ if attribute.starts_with("+cwd=") => {
+                    let cwd = &attribute["+cwd=".len()..];
+                    if cwd.is_empty() {
+                        return Err(ParseErrorKind::InvalidCodeAttribute(attribute.into()).with_sourcepos(sourcepos));
+                    }
+                    flags.cwd = Some(cwd.into());
+                }
+                _ if attribute.starts_with("+include=") => {
+                    let id = &attribute["+include=".len()..];
+                    if id.is_empty() {
+                        return Err(ParseErrorKind::InvalidCodeAttribute(attribute.into()).with_sourcepos(sourcepos));
+                    }
+                    flags.include.push(id.into());
+                }
+                _ if attribute.starts_with("+env=") => {
+                    let variable = attribute["+env=".len()..].split_once('=').filter(|(name, _)| !name.is_empty());
+                    let (name, value) = variable.ok_or_else(|| {
+                        ParseErrorKind::InvalidCodeAttribute(attribute.into()).with_sourcepos(sourcepos)
+                    })?;
+                    flags.env.push((name.into(), value.into()));
+                }
+                _ => return Err(ParseErrorKind::InvalidCodeAttribute(attribute.into()).with_sourcepos(sourcepos)),
+            };
+        }
+        if (flags.hidden || flags.auto_execute) && !flags.execute {
+            let attribute = if flags.hidden { "+hidden" } else { "+auto" };
+            let error = ParseErrorKind::InvalidCodeAttribute(format!("{attribute} requires +exec"));
+            return Err(error.with_sourcepos(sourcepos));
+        }
+        if flags.max_height.is_some() && flags.highlight_groups.len() > 1 {
+            let error = ParseErrorKind::InvalidCodeAttribute("+max_height can't be used with highlight steps".into());
+            return Err(error.with_sourcepos(sourcepos));
+        }
+        let code = Code { contents: block.literal.clone(), language, flags };
         Ok(MarkdownElement::Code(code))
     }
 
+    // Parses groups of lines like `{1,3-5|7|all}`.
+    fn parse_highlight_groups(input: &str) -> Option<Vec<HighlightGroup>> {
+        let input = input.strip_prefix('{')?.strip_suffix('}')?;
+        let mut groups = Vec::new();
+        for group in input.split('|') {
+            if group == "all" {
+                groups.push(HighlightGroup::All);
+                continue;
+            }
+            let mut ranges = Vec::new();
+            for range in group.split(',') {
+                let (start, end) = range.split_once('-').unwrap_or((range, range));
+                let (start, end): (usize, usize) = (start.parse().ok()?, end.parse().ok()?);
+                if start == 0 || start > end {
+                    return None;
+                }
+                ranges.push(start..=end);
+            }
+            groups.push(HighlightGroup::Lines(ranges));
+        }
+        Some(groups)
+    }
+
+    fn parse_delimited_table(contents: &str, delimiter: char) -> Result<MarkdownElement, String> {
+        let mut rows = contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| TableRow(Self::split_delimited_line(line, delimiter).into_iter().map(Text::from).collect()));
+        let header = rows.next().ok_or("no header row")?;
+        let mut table = Table { header, rows: Vec::new(), caption: None };
+        for (index, mut row) in rows.enumerate() {
+            if row.0.len() > table.columns() {
+                return Err(format!("row {} has more columns than the header", index + 1));
+            }
+            // Missing trailing cells are treated as empty.
+            row.0.resize_with(table.columns(), || Text::from(""));
+            table.rows.push(row);
+        }
+        Ok(MarkdownElement::Table(table))
+    }
+
+    // Splits a line of delimited data into its fields.
+    //
+    // Fields can be quoted so they can contain the delimiter, and quotes within a quoted field are
+    // escaped by doubling them, as in `"say ""hi"""`.
+    fn split_delimited_line(line: &str, delimiter: char) -> Vec<String> {
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted && chars.next_if_eq(&'"').is_some() => field.push('"'),
+                '"' if quoted => quoted = false,
+                '"' if field.trim().is_empty() => {
+                    field.clear();
+                    quoted = true;
+                }
+                c if c == delimiter && !quoted => fields.push(mem::take(&mut field).trim().to_string()),
+                c => field.push(c),
+            };
+        }
+        fields.push(field.trim().to_string());
+        fields
+    }
+
     fn parse_heading(heading: &NodeHeading, node: &'a AstNode<'a>) -> ParseResult<MarkdownElement> {
         let text = Self::parse_text(node)?;
         if heading.setext {
@@ -179,11 +366,11 @@ impl<'a> MarkdownParser<'a> {
             match inline {
                 Inline::Text(text) => paragraph_elements.push(ParagraphElement::Text(text)),
                 Inline::LineBreak => paragraph_elements.push(ParagraphElement::LineBreak),
-                Inline::Image(path) => {
+                Inline::Image { path, title, alt_text, attributes } => {
                     if !paragraph_elements.is_empty() {
                         elements.push(MarkdownElement::Paragraph(mem::take(&mut paragraph_elements)));
                     }
-                    elements.push(MarkdownElement::Image(path.into()));
+                    elements.push(MarkdownElement::Image { path: path.into(), title, alt_text, attributes });
                 }
             }
         }
@@ -235,27 +422,91 @@ impl<'a> MarkdownParser<'a> {
             (ListType::Ordered, ListDelimType::Paren) => ListItemType::OrderedParens(number),
             (ListType::Ordered, ListDelimType::Period) => ListItemType::OrderedPeriod(number),
         };
-        let mut elements = Vec::new();
+        let mut item: Option<ListItem> = None;
+        let mut leading_items = Vec::new();
         for node in root.children() {
             let data = node.data.borrow();
             match &data.value {
-                NodeValue::Paragraph => {
-                    let contents = Self::parse_text(node)?;
-                    elements.push(ListItem { contents, depth, item_type: item_type.clone() });
+                NodeValue::Paragraph if item.is_none() => {
+                    let (mut contents, children) = Self::split_list_item_paragraph(Self::parse_paragraph(node)?);
+                    let color = Self::take_list_item_color(&mut contents);
+                    item = Some(ListItem { contents, depth, item_type: item_type.clone(), color, children });
                 }
                 NodeValue::List(_) => {
-                    elements.extend(Self::parse_list(node, depth + 1)?);
+                    let nested_items = Self::parse_list(node, depth + 1)?;
+                    match &mut item {
+                        Some(item) => item.children.push(MarkdownElement::List(nested_items)),
+                        None => leading_items.extend(nested_items),
+                    };
                 }
-                other => {
-                    return Err(ParseErrorKind::UnsupportedStructure {
-                        container: "list",
-                        element: other.identifier(),
-                    }
-                    .with_sourcepos(data.sourcepos));
+                _ => {
+                    let item = item.get_or_insert_with(|| ListItem {
+                        contents: Text::default(),
+                        depth,
+                        item_type: item_type.clone(),
+                        color: None,
+                        children: Vec::new(),
+                    });
+                    item.children.extend(Self::parse_node(node)?);
                 }
             }
         }
-        Ok(elements)
+        // Nested lists at the end of an item are merged into the list the item belongs to. Any
+        // other nested list stays among the item's children so everything keeps its original order.
+        let nested_items = match &mut item {
+            Some(item) => {
+                let trailing_lists = item
+                    .children
+                    .iter()
+                    .rposition(|child| !matches!(child, MarkdownElement::List(_)))
+                    .map(|index| index + 1)
+                    .unwrap_or(0);
+                item.children.split_off(trailing_lists)
+            }
+            None => Vec::new(),
+        };
+        let nested_items = nested_items.into_iter().flat_map(|child| match child {
+            MarkdownElement::List(items) => items,
+            _ => Vec::new(),
+        });
+        Ok(leading_items.into_iter().chain(item).chain(nested_items).collect())
+    }
+
+    // Splits the first paragraph in a list item into the item's text and anything after it that
+    // can't be part of it, like images.
+    fn split_list_item_paragraph(elements: Vec<MarkdownElement>) -> (Text, Vec<MarkdownElement>) {
+        let mut elements = elements.into_iter();
+        match elements.next() {
+            Some(MarkdownElement::Paragraph(paragraph)) => {
+                let mut paragraph = paragraph.into_iter().peekable();
+                let contents = match paragraph.next_if(|element| matches!(element, ParagraphElement::Text(_))) {
+                    Some(ParagraphElement::Text(text)) => text,
+                    _ => Text::default(),
+                };
+                paragraph.next_if(|element| matches!(element, ParagraphElement::LineBreak));
+                let rest: Vec<_> = paragraph.collect();
+                let rest = (!rest.is_empty()).then_some(MarkdownElement::Paragraph(rest));
+                (contents, rest.into_iter().chain(elements).collect())
+            }
+            other => (Text::default(), other.into_iter().chain(elements).collect()),
+        }
+    }
+
+    // Strips a `{color=<name>}` attribute at the end of a list item, if any.
+    fn take_list_item_color(contents: &mut Text) -> Option<String> {
+        let last = contents.chunks.last_mut()?;
+        let trimmed = last.text.trim_end();
+        let start = trimmed.rfind("{color=")?;
+        let color = trimmed[start..].strip_prefix("{color=")?.strip_suffix('}')?.trim().to_string();
+        if color.is_empty() || color.contains(char::is_whitespace) {
+            return None;
+        }
+        last.text.truncate(start);
+        last.text.truncate(last.text.trim_end().len());
+        if last.text.is_empty() {
+            contents.chunks.pop();
+        }
+        Some(color)
     }
 
     fn parse_table(node: &'a AstNode<'a>) -> ParseResult<MarkdownElement> {
@@ -277,7 +528,15 @@ impl<'a> MarkdownParser<'a> {
                 rows.push(row)
             }
         }
-        Ok(MarkdownElement::Table(Table { header, rows }))
+        Ok(MarkdownElement::Table(Table { header, rows, caption: None }))
+    }
+
+    fn table_caption(paragraph: &[ParagraphElement]) -> Option<Text> {
+        let [ParagraphElement::Text(text)] = paragraph else {
+            return None;
+        };
+        let is_caption = text.chunks.iter().all(|chunk| chunk.style.is_italics() || chunk.text.trim().is_empty());
+        is_caption.then(|| text.clone())
     }
 
     fn parse_table_row(node: &'a AstNode<'a>) -> ParseResult<TableRow> {
@@ -302,9 +561,16 @@ impl<'a> MarkdownParser<'a> {
 struct InlinesParser {
     inlines: Vec<Inline>,
     pending_text: Vec<StyledText>,
+    soft_breaks: bool,
 }
 
 impl InlinesParser {
+    // Treat soft breaks as line breaks rather than as spaces.
+    fn with_soft_breaks(mut self, value: bool) -> Self {
+        self.soft_breaks = value;
+        self
+    }
+
     fn parse<'a>(mut self, node: &'a AstNode<'a>) -> ParseResult<Vec<Inline>> {
         self.process_children(node, TextStyle::default())?;
         self.store_pending_text();
@@ -330,6 +596,10 @@ impl InlinesParser {
             NodeValue::Strong => self.process_children(node, style.clone().bold())?,
             NodeValue::Emph => self.process_children(node, style.clone().italics())?,
             NodeValue::Strikethrough => self.process_children(node, style.clone().strikethrough())?,
+            NodeValue::SoftBreak if self.soft_breaks => {
+                self.store_pending_text();
+                self.inlines.push(Inline::LineBreak);
+            }
             NodeValue::SoftBreak => self.pending_text.push(StyledText::from(" ")),
             NodeValue::Link(link) => {
                 self.pending_text.push(StyledText::new(link.url.clone(), TextStyle::default().link()))
@@ -340,7 +610,10 @@ impl InlinesParser {
             }
             NodeValue::Image(link) => {
                 self.store_pending_text();
-                self.inlines.push(Inline::Image(link.url.clone()));
+                let alt_text = Self::collect_plain_text(node);
+                let (title, attributes) =
+                    Self::parse_image_title(&link.title).map_err(|e| e.with_sourcepos(data.sourcepos))?;
+                self.inlines.push(Inline::Image { path: link.url.clone(), title, alt_text, attributes });
             }
             other => {
                 return Err(ParseErrorKind::UnsupportedStructure { container: "text", element: other.identifier() }
@@ -356,11 +629,57 @@ impl InlinesParser {
         }
         Ok(())
     }
+
+    fn parse_image_title(title: &str) -> Result<(String, ImageAttributes), ParseErrorKind> {
+        let mut attributes = ImageAttributes::default();
+        // Only treat the title as attributes if it's a list of known ones, otherwise it's just a
+        // regular title that happens to contain an `=`.
+        let is_attribute =
+            |pair: &str| pair.split_once('=').is_some_and(|(key, _)| matches!(key.trim(), "width" | "align"));
+        if title.is_empty() || !title.split(',').all(is_attribute) {
+            return Ok((title.into(), attributes));
+        }
+        for pair in title.split(',') {
+            let (key, value) = pair.split_once('=').expect("no separator");
+            let invalid = || ParseErrorKind::InvalidImageAttribute(pair.trim().into());
+            match key.trim() {
+                "width" => {
+                    let width = value.trim().strip_suffix('%').ok_or_else(invalid)?;
+                    let width = width.parse::<u8>().ok().filter(|width| (1..=100).contains(width));
+                    attributes.width = Some(width.ok_or_else(invalid)?);
+                }
+                "align" => {
+                    let alignment = match value.trim() {
+                        "left" => ImageAlignment::Left,
+                        "center" => ImageAlignment::Center,
+                        "right" => ImageAlignment::Right,
+                        _ => return Err(invalid()),
+                    };
+                    attributes.alignment = Some(alignment);
+                }
+                _ => return Err(invalid()),
+            };
+        }
+        Ok((String::new(), attributes))
+    }
+
+    fn collect_plain_text<'a>(node: &'a AstNode<'a>) -> String {
+        let mut text = String::new();
+        for node in node.descendants().skip(1) {
+            match &node.data.borrow().value {
+                NodeValue::Text(contents) => text.push_str(contents),
+                NodeValue::Code(code) => text.push_str(&code.literal),
+                NodeValue::SoftBreak | NodeValue::LineBreak => text.push(' '),
+                _ => (),
+            };
+        }
+        text
+    }
 }
 
 enum Inline {
     Text(Text),
-    Image(String),
+    Image { path: String, title: String, alt_text: String, attributes: ImageAttributes },
     LineBreak,
 }
 
@@ -368,7 +687,7 @@ impl Inline {
     fn kind(&self) -> &'static str {
         match self {
             Self::Text(_) => "text",
-            Self::Image(_) => "image",
+            Self::Image { .. } => "image",
             Self::LineBreak => "line break",
         }
     }
@@ -408,6 +727,15 @@ pub enum ParseErrorKind {
     /// We don't support unfenced code blocks.
     UnfencedCodeBlock,
 
+    /// A code block contains an attribute we don't know about.
+    InvalidCodeAttribute(String),
+
+    /// An image contains an invalid attribute in its title.
+    InvalidImageAttribute(String),
+
+    /// A block of delimited data can't be turned into a table.
+    InvalidTableData(String),
+
     /// An internal parsing error.
     Internal(String),
 }
@@ -420,6 +748,9 @@ impl Display for ParseErrorKind {
                 write!(f, "unsupported structure in {container}: {element}")
             }
             Self::UnfencedCodeBlock => write!(f, "only fenced code blocks are supported"),
+            Self::InvalidCodeAttribute(attribute) => write!(f, "invalid code attribute: {attribute}"),
+            Self::InvalidImageAttribute(attribute) => write!(f, "invalid image attribute: {attribute}"),
+            Self::InvalidTableData(error) => write!(f, "invalid table data: {error}"),
             Self::Internal(message) => write!(f, "internal error: {message}"),
         }
     }
@@ -478,6 +809,7 @@ mod test {
     use std::path::Path;
 
     use super::*;
+    use rstest::rstest;
 
     fn parse_single(input: &str) -> MarkdownElement {
         let arena = Arena::new();
@@ -541,10 +873,60 @@ boop
     #[test]
     fn image() {
         let parsed = parse_single("![](potato.png)");
-        let MarkdownElement::Image(path) = parsed else { panic!("not an image: {parsed:?}") };
+        let MarkdownElement::Image { path, .. } = parsed else { panic!("not an image: {parsed:?}") };
         assert_eq!(path, Path::new("potato.png"));
     }
 
+    #[test]
+    fn data_uri_image() {
+        let uri = "data:image/png;base64,iVBORw0KGgo+/A==";
+        let parsed = parse_single(&format!("![]({uri})"));
+        let MarkdownElement::Image { path, .. } = parsed else { panic!("not an image: {parsed:?}") };
+        assert_eq!(path, Path::new(uri));
+    }
+
+    #[rstest]
+    #[case::width("width=60%", ImageAttributes { width: Some(60), alignment: None })]
+    #[case::align(" align = right ", ImageAttributes { width: None, alignment: Some(ImageAlignment::Right) })]
+    #[case::both("width=5%,align=left", ImageAttributes { width: Some(5), alignment: Some(ImageAlignment::Left) })]
+    fn image_attributes(#[case] title: &str, #[case] expected: ImageAttributes) {
+        let parsed = parse_single(&format!(r#"![](potato.png "{title}")"#));
+        let MarkdownElement::Image { title, attributes, .. } = parsed else { panic!("not an image: {parsed:?}") };
+        assert_eq!(title, "");
+        assert_eq!(attributes, expected);
+    }
+
+    #[rstest]
+    #[case::equation("E=mc2")]
+    #[case::unknown_keys("a=b, c=d")]
+    #[case::partially_known("width=50%, size=3")]
+    fn image_title_with_equals(#[case] title: &str) {
+        let parsed = parse_single(&format!(r#"![](potato.png "{title}")"#));
+        let MarkdownElement::Image { title: parsed_title, attributes, .. } = parsed else {
+            panic!("not an image: {parsed:?}")
+        };
+        assert_eq!(parsed_title, title);
+        assert_eq!(attributes, ImageAttributes::default());
+    }
+
+    #[rstest]
+    #[case::no_percent("width=50")]
+    #[case::too_wide("width=101%")]
+    #[case::unknown_alignment("align=top")]
+    fn invalid_image_attributes(#[case] title: &str) {
+        let arena = Arena::new();
+        let result = MarkdownParser::new(&arena).parse(&format!(r#"![](potato.png "{title}")"#));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn image_alt_text_and_title() {
+        let parsed = parse_single(r#"![a *big* potato](potato.png "my title")"#);
+        let MarkdownElement::Image { title, alt_text, .. } = parsed else { panic!("not an image: {parsed:?}") };
+        assert_eq!(title, "my title");
+        assert_eq!(alt_text, "a big potato");
+    }
+
     #[test]
     fn image_within_text() {
         let parsed = parse_all(
@@ -599,6 +981,67 @@ Title
         assert_eq!(next().depth, 0);
     }
 
+    #[test]
+    fn list_item_children() {
+        let parsed = parse_single(
+            r"
+* one
+
+  more text
+
+  ```rust
+  let q = 42;
+  ```
+  * nested
+* ![image](foo.png)",
+        );
+        let MarkdownElement::List(items) = parsed else { panic!("not a list: {parsed:?}") };
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].contents, Text::from("one"));
+        assert!(matches!(
+            items[0].children.as_slice(),
+            [MarkdownElement::Paragraph(_), MarkdownElement::Code(Code { language: ProgrammingLanguage::Rust, .. })]
+        ));
+        assert_eq!(items[1].contents, Text::from("nested"));
+        assert_eq!(items[1].depth, 1);
+        assert_eq!(items[2].contents, Text::default());
+        assert!(matches!(items[2].children.as_slice(), [MarkdownElement::Image { .. }]));
+    }
+
+    #[test]
+    fn list_item_children_order() {
+        let parsed = parse_single(
+            r"
+* one
+  * nested
+
+  after
+* two",
+        );
+        let MarkdownElement::List(items) = parsed else { panic!("not a list: {parsed:?}") };
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].contents, Text::from("one"));
+        let [MarkdownElement::List(nested), MarkdownElement::Paragraph(_)] = items[0].children.as_slice() else {
+            panic!("unexpected children: {:?}", items[0].children);
+        };
+        assert_eq!(nested.len(), 1);
+        assert_eq!(nested[0].contents, Text::from("nested"));
+        assert_eq!(nested[0].depth, 1);
+        assert_eq!(items[1].contents, Text::from("two"));
+    }
+
+    #[rstest]
+    #[case::plain("* done {color=green}", &[StyledText::from("done")], Some("green"))]
+    #[case::styled("* **done** {color=green}", &[StyledText::new("done", TextStyle::default().bold())], Some("green"))]
+    #[case::not_at_end("* {color=green} done", &[StyledText::from("{color=green} done")], None)]
+    #[case::other_braces("* a {b}", &[StyledText::from("a {b}")], None)]
+    fn list_item_color(#[case] input: &str, #[case] chunks: &[StyledText], #[case] color: Option<&str>) {
+        let parsed = parse_single(input);
+        let MarkdownElement::List(items) = parsed else { panic!("not a list: {parsed:?}") };
+        assert_eq!(items[0].contents.chunks, chunks);
+        assert_eq!(items[0].color.as_deref(), color);
+    }
+
     #[test]
     fn line_breaks() {
         let parsed = parse_all(
@@ -635,6 +1078,143 @@ let q = 42;
         let MarkdownElement::Code(code) = parsed else { panic!("not a code block: {parsed:?}") };
         assert_eq!(code.language, ProgrammingLanguage::Rust);
         assert_eq!(code.contents, "let q = 42;\n");
+        assert!(!code.flags.execute);
+    }
+
+    #[test]
+    fn executable_code_block() {
+        let parsed = parse_single(
+            r"
+```bash +exec
+echo hi
+```
+",
+        );
+        let MarkdownElement::Code(code) = parsed else { panic!("not a code block: {parsed:?}") };
+        assert_eq!(code.language, ProgrammingLanguage::Bash);
+        assert!(code.flags.execute);
+    }
+
+    #[test]
+    fn show_whitespace_code_block() {
+        let parsed = parse_single(
+            r"
+```python +show_whitespace
+print('hi')
+```
+",
+        );
+        let MarkdownElement::Code(code) = parsed else { panic!("not a code block: {parsed:?}") };
+        assert!(code.flags.show_whitespace);
+        assert!(!code.flags.execute);
+    }
+
+    #[rstest]
+    #[case::single("{3}", Some(vec![HighlightGroup::Lines(vec![3..=3])]))]
+    #[case::ranges("{1,3-5}", Some(vec![HighlightGroup::Lines(vec![1..=1, 3..=5])]))]
+    #[case::steps("{1|all}", Some(vec![HighlightGroup::Lines(vec![1..=1]), HighlightGroup::All]))]
+    #[case::zero("{0}", None)]
+    #[case::reversed("{5-3}", None)]
+    #[case::empty_group("{1||2}", None)]
+    #[case::unterminated("{1", None)]
+    fn highlight_groups_code_block(#[case] groups: &str, #[case] expected: Option<Vec<HighlightGroup>>) {
+        let arena = Arena::new();
+        let result = MarkdownParser::new(&arena).parse(&format!("```rust {groups}\nlet q = 42;\n```"));
+        match expected {
+            Some(expected) => {
+                let parsed = result.expect("parse failed").into_iter().next().unwrap();
+                let MarkdownElement::Code(code) = parsed else { panic!("not a code block: {parsed:?}") };
+                assert_eq!(code.flags.highlight_groups, expected);
+            }
+            None => assert!(result.is_err()),
+        };
+    }
+
+    #[test]
+    fn line_numbers_code_block() {
+        let parsed = parse_single(
+            "```rust +line_numbers
+let q = 42;
+```",
+        );
+        let MarkdownElement::Code(code) = parsed else { panic!("not a code block: {parsed:?}") };
+        assert!(code.flags.line_numbers);
+    }
+
+    #[rstest]
+    #[case::valid("+max_height=10", Some(10))]
+    #[case::zero("+max_height=0", None)]
+    #[case::not_a_number("+max_height=potato", None)]
+    fn max_height_code_block(#[case] attribute: &str, #[case] expected: Option<usize>) {
+        let arena = Arena::new();
+        let input = format!("```rust {attribute}\nlet q = 42;\n```");
+        let result = MarkdownParser::new(&arena).parse(&input);
+        match expected {
+            Some(height) => {
+                let parsed = result.expect("parse failed").into_iter().next().unwrap();
+                let MarkdownElement::Code(code) = parsed else { panic!("not a code block: {parsed:?}") };
+                assert_eq!(code.flags.max_height, Some(height));
+            }
+            None => assert!(result.is_err()),
+        };
+    }
+
+    #[test]
+    fn execution_environment_code_block() {
+        let parsed = parse_single("```bash +exec +cwd=demo +env=NAME=bob +env=GREETING=a=b\necho hi\n```");
+        let MarkdownElement::Code(code) = parsed else { panic!("not a code block: {parsed:?}") };
+        assert_eq!(code.flags.cwd, Some("demo".into()));
+        let expected = [("NAME", "bob"), ("GREETING", "a=b")].map(|(name, value)| (name.into(), value.into()));
+        assert_eq!(code.flags.env, expected);
+    }
+
+    #[rstest]
+    #[case::empty_cwd("+cwd=")]
+    #[case::no_value("+env=NAME")]
+    #[case::no_name("+env==bob")]
+    #[case::empty_id("id=")]
+    #[case::empty_include("+include=")]
+    fn invalid_execution_environment(#[case] attribute: &str) {
+        let arena = Arena::new();
+        let result = MarkdownParser::new(&arena).parse(&format!("```bash +exec {attribute}\necho hi\n```"));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn hidden_code_block() {
+        let parsed = parse_single("```bash +exec +hidden +auto\necho hi\n```");
+        let MarkdownElement::Code(code) = parsed else { panic!("not a code block: {parsed:?}") };
+        assert!(code.flags.hidden);
+        assert!(code.flags.auto_execute);
+    }
+
+    #[test]
+    fn snippet_references() {
+        let parsed = parse_single("```rust id=main +exec +include=imports +include=types\nfn main() {}\n```");
+        let MarkdownElement::Code(code) = parsed else { panic!("not a code block: {parsed:?}") };
+        assert_eq!(code.flags.id.as_deref(), Some("main"));
+        assert_eq!(code.flags.include, &["imports", "types"]);
+    }
+
+    #[rstest]
+    #[case::hidden("+hidden")]
+    #[case::auto("+auto")]
+    fn non_executable_hidden_code_block(#[case] attribute: &str) {
+        let arena = Arena::new();
+        let result = MarkdownParser::new(&arena).parse(&format!("```bash {attribute}\necho hi\n```"));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn invalid_code_attribute() {
+        let arena = Arena::new();
+        let parser = MarkdownParser::new(&arena);
+        let result = parser.parse(
+            "```bash +potato
+echo hi
+```",
+        );
+        assert!(result.is_err());
     }
 
     #[test]
@@ -658,13 +1238,56 @@ let q = 42;
 | Carrot | Yuck |
 ",
         );
-        let MarkdownElement::Table(Table { header, rows }) = parsed else { panic!("not a table: {parsed:?}") };
+        let MarkdownElement::Table(Table { header, rows, .. }) = parsed else { panic!("not a table: {parsed:?}") };
         assert_eq!(header.0.len(), 2);
         assert_eq!(rows.len(), 2);
         assert_eq!(rows[0].0.len(), 2);
         assert_eq!(rows[1].0.len(), 2);
     }
 
+    #[rstest]
+    #[case::csv("csv", "name,taste\npotato,great\n\"carrot, raw\",\"so \"\"so\"\"\"\n")]
+    #[case::tsv("tsv", "name\ttaste\npotato\tgreat\ncarrot, raw\tso \"so\"\n")]
+    fn delimited_table(#[case] language: &str, #[case] contents: &str) {
+        let parsed = parse_single(&format!("```{language} +table\n{contents}```"));
+        let MarkdownElement::Table(table) = parsed else { panic!("not a table: {parsed:?}") };
+        let cells = |row: &TableRow| row.0.iter().map(|text| text.chunks[0].text.clone()).collect::<Vec<_>>();
+        assert_eq!(cells(&table.header), &["name", "taste"]);
+        let rows: Vec<_> = table.rows.iter().map(cells).collect();
+        assert_eq!(rows, &[vec!["potato", "great"], vec!["carrot, raw", "so \"so\""]]);
+    }
+
+    #[test]
+    fn delimited_table_short_rows() {
+        let parsed = parse_single("```csv +table\na,b\nc\n```");
+        let MarkdownElement::Table(table) = parsed else { panic!("not a table: {parsed:?}") };
+        assert_eq!(table.rows[0].0, &[Text::from("c"), Text::from("")]);
+    }
+
+    #[rstest]
+    #[case::long_row("```csv +table\na,b\nc,d,e\n```")]
+    #[case::empty("```csv +table\n```")]
+    #[case::not_delimited("```rust +table\nfn main() {}\n```")]
+    #[case::other_attributes("```csv +table +exec\na,b\n```")]
+    fn invalid_delimited_table(#[case] input: &str) {
+        let arena = Arena::new();
+        assert!(MarkdownParser::new(&arena).parse(input).is_err());
+    }
+
+    #[rstest]
+    #[case::emphasized("_Vegetables I like_", Some("Vegetables I like"))]
+    #[case::plain("Vegetables I like", None)]
+    #[case::partially_emphasized("_Vegetables_ I like", None)]
+    fn table_caption(#[case] line: &str, #[case] caption: Option<&str>) {
+        let input = format!("| Name |\n| ---- |\n| Potato |\n\n{line}");
+        let parsed = parse_all(&input);
+        let MarkdownElement::Table(table) = &parsed[0] else { panic!("not a table: {parsed:?}") };
+        let caption = caption.map(|caption| Text::from(StyledText::new(caption, TextStyle::default().italics())));
+        assert_eq!(table.caption, caption);
+        let expected_elements = if table.caption.is_some() { 1 } else { 2 };
+        assert_eq!(parsed.len(), expected_elements);
+    }
+
     #[test]
     fn comment() {
         let parsed = parse_single(
@@ -672,8 +1295,9 @@ let q = 42;
 <!-- foo -->
 ",
         );
-        let MarkdownElement::Comment(text) = parsed else { panic!("not a comment: {parsed:?}") };
-        assert_eq!(text, " foo ");
+        let MarkdownElement::Comment { comment, source_position } = parsed else { panic!("not a comment: {parsed:?}") };
+        assert_eq!(comment, " foo ");
+        assert_eq!(source_position.line, 2);
     }
 
     #[test]
@@ -702,12 +1326,50 @@ let q = 42;
 ",
         );
         let MarkdownElement::BlockQuote(lines) = parsed else { panic!("not a block quote: {parsed:?}") };
-        assert_eq!(lines.len(), 5);
-        assert_eq!(lines[0], "bar");
-        assert_eq!(lines[1], "foo");
-        assert_eq!(lines[2], "");
-        assert_eq!(lines[3], "* a");
-        assert_eq!(lines[4], "* b");
+        let expected = [Text::from("bar"), Text::from("foo"), Text::default(), Text::from("* a"), Text::from("* b")];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn styled_block_quote() {
+        let parsed = parse_single(
+            r"
+> **hello** _there_
+> `code` and [link](https://example.com)
+",
+        );
+        let MarkdownElement::BlockQuote(lines) = parsed else { panic!("not a block quote: {parsed:?}") };
+        let expected = [
+            Text {
+                chunks: vec![
+                    StyledText::new("hello", TextStyle::default().bold()),
+                    StyledText::from(" "),
+                    StyledText::new("there", TextStyle::default().italics()),
+                ],
+            },
+            Text {
+                chunks: vec![
+                    StyledText::new("code", TextStyle::default().code()),
+                    StyledText::from(" and "),
+                    StyledText::new("https://example.com", TextStyle::default().link()),
+                ],
+            },
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn block_quote_image() {
+        let parsed = parse_single(
+            r"
+> look at ![a potato](potato.png)
+> ![](empty.png)
+",
+        );
+        let MarkdownElement::BlockQuote(lines) = parsed else { panic!("not a block quote: {parsed:?}") };
+        let expected =
+            [Text { chunks: vec![StyledText::from("look at "), StyledText::from("a potato")] }, Text::default()];
+        assert_eq!(lines, expected);
     }
 
     #[test]
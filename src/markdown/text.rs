@@ -1,40 +1,62 @@
-use super::elements::StyledText;
+use super::{elements::StyledText, width::char_widths};
 use crate::style::TextStyle;
-use unicode_width::UnicodeWidthChar;
+use std::{mem, rc::Rc};
 
 /// A weighted line of text.
 ///
-/// The weight of a character is its given by its width in unicode.
+/// The weight of a character is given by its display width in the terminal.
+///
+/// Clones share the same texts so slides generated via pauses, which repeat every line in the slide
+/// before them, don't take up much memory.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
-pub struct WeightedLine(Vec<WeightedText>);
+pub struct WeightedLine {
+    texts: Rc<[WeightedText]>,
+    hanging_indent: usize,
+}
 
 impl WeightedLine {
+    /// Indent every row after the first one when this line is wrapped.
+    ///
+    /// This lets wrapped rows line up with something other than the beginning of the line, like
+    /// the text that follows a list item's bullet.
+    pub fn with_hanging_indent(mut self, indent: usize) -> Self {
+        self.hanging_indent = indent;
+        self
+    }
+
+    /// The number of columns every row after the first one is indented by when wrapped.
+    pub fn hanging_indent(&self) -> usize {
+        self.hanging_indent
+    }
+
     /// Split this line into chunks of at most `max_length` width.
+    ///
+    /// Every chunk after the first one is at most `max_length` minus the hanging indent wide.
     pub fn split(&self, max_length: usize) -> SplitTextIter {
-        SplitTextIter::new(&self.0, max_length)
+        SplitTextIter::new(&self.texts, max_length, self.hanging_indent)
     }
 
     /// The total width of this line.
     pub fn width(&self) -> usize {
-        self.0.iter().map(|text| text.width()).sum()
+        self.texts.iter().map(|text| text.width()).sum()
     }
 
     /// Get an iterator to the underlying text chunks.
     pub fn iter_texts(&self) -> impl Iterator<Item = &WeightedText> {
-        self.0.iter()
+        self.texts.iter()
     }
 }
 
 impl From<Vec<WeightedText>> for WeightedLine {
     fn from(texts: Vec<WeightedText>) -> Self {
-        Self(texts)
+        Self { texts: texts.into(), hanging_indent: 0 }
     }
 }
 
 impl From<String> for WeightedLine {
     fn from(text: String) -> Self {
         let texts = vec![WeightedText::from(StyledText::from(text))];
-        Self(texts)
+        Self::from(texts)
     }
 }
 
@@ -66,9 +88,9 @@ impl From<StyledText> for WeightedText {
         let mut accumulators = Vec::new();
         let mut width = 0;
         let mut bytes = 0;
-        for c in text.text.chars() {
+        for (c, char_width) in char_widths(&text.text) {
             accumulators.push(CharAccumulator { width, bytes });
-            width += c.width().unwrap_or(0);
+            width += char_width;
             bytes += c.len_utf8();
         }
         accumulators.push(CharAccumulator { width, bytes });
@@ -80,12 +102,13 @@ impl From<StyledText> for WeightedText {
 pub struct SplitTextIter<'a> {
     texts: &'a [WeightedText],
     max_length: usize,
+    hanging_indent: usize,
     current: Option<WeightedTextRef<'a>>,
 }
 
 impl<'a> SplitTextIter<'a> {
-    fn new(texts: &'a [WeightedText], max_length: usize) -> Self {
-        Self { texts, max_length, current: texts.get(0).map(WeightedText::to_ref) }
+    fn new(texts: &'a [WeightedText], max_length: usize, hanging_indent: usize) -> Self {
+        Self { texts, max_length, hanging_indent, current: texts.get(0).map(WeightedText::to_ref) }
     }
 }
 
@@ -97,6 +120,8 @@ impl<'a> Iterator for SplitTextIter<'a> {
 
         let mut elements = Vec::new();
         let mut remaining = self.max_length as i64;
+        // Only the first row uses the full length, the rest are indented.
+        self.max_length = self.max_length.saturating_sub(mem::take(&mut self.hanging_indent)).max(1);
         while let Some(current) = self.current.take() {
             let (head, rest) = current.word_split_at_length(remaining as usize);
             // Prevent splitting a word partially. We do allow this on the first chunk as otherwise
@@ -174,7 +199,8 @@ impl<'a> WeightedTextRef<'a> {
         Self { text, accumulators, style: self.style }
     }
 
-    fn width(&self) -> usize {
+    /// The display width of this text.
+    pub fn width(&self) -> usize {
         let last_width = self.accumulators.last().map(|a| a.width).unwrap_or(0);
         let first_width = self.accumulators.get(0).map(|a| a.width).unwrap_or(0);
         last_width - first_width
@@ -196,6 +222,14 @@ mod test {
         lines.map(|l| l.iter().map(|weighted| weighted.text).collect::<Vec<_>>().join(" ")).collect()
     }
 
+    #[test]
+    fn clones_share_texts() {
+        let line = WeightedLine::from("hello world".to_string()).with_hanging_indent(2);
+        let clone = line.clone();
+        assert!(Rc::ptr_eq(&line.texts, &clone.texts));
+        assert_eq!(clone.hanging_indent(), 2);
+    }
+
     #[test]
     fn text_creation() {
         let text = WeightedText::from(StyledText::from("hello world"));
@@ -204,6 +238,12 @@ mod test {
         assert_eq!(text_ref.width(), 11);
     }
 
+    #[test]
+    fn text_creation_emoji_sequences() {
+        let line = WeightedLine::from("a 👨\u{200d}👩\u{200d}👧 👍🏽".to_string());
+        assert_eq!(line.width(), 7);
+    }
+
     #[test]
     fn text_creation_utf8() {
         let text = WeightedText::from(StyledText::from("█████"));
@@ -270,7 +310,7 @@ mod test {
 
     #[test]
     fn split_at_full_length() {
-        let text = WeightedLine(vec![WeightedText::from(StyledText::from("hello world"))]);
+        let text = WeightedLine::from(vec![WeightedText::from(StyledText::from("hello world"))]);
         let lines = join_lines(text.split(11));
         let expected = vec!["hello world"];
         assert_eq!(lines, expected);
@@ -278,7 +318,7 @@ mod test {
 
     #[test]
     fn no_split_necessary() {
-        let text = WeightedLine(vec![
+        let text = WeightedLine::from(vec![
             WeightedText::from(StyledText::from("short")),
             WeightedText::from(StyledText::from("text")),
         ]);
@@ -289,7 +329,7 @@ mod test {
 
     #[test]
     fn split_lines_single() {
-        let text = WeightedLine(vec![WeightedText::from(StyledText::from("this is a slightly long line"))]);
+        let text = WeightedLine::from(vec![WeightedText::from(StyledText::from("this is a slightly long line"))]);
         let lines = join_lines(text.split(6));
         let expected = vec!["this", "is a", "slight", "ly", "long", "line"];
         assert_eq!(lines, expected);
@@ -297,7 +337,7 @@ mod test {
 
     #[test]
     fn split_lines_multi() {
-        let text = WeightedLine(vec![
+        let text = WeightedLine::from(vec![
             WeightedText::from(StyledText::from("this is a slightly long line")),
             WeightedText::from(StyledText::from("another chunk")),
             WeightedText::from(StyledText::from("yet some other piece")),
@@ -309,7 +349,7 @@ mod test {
 
     #[test]
     fn long_splits() {
-        let text = WeightedLine(vec![
+        let text = WeightedLine::from(vec![
             WeightedText::from(StyledText::from("this is a slightly long line")),
             WeightedText::from(StyledText::from("another chunk")),
             WeightedText::from(StyledText::from("yet some other piece")),
@@ -321,15 +361,27 @@ mod test {
 
     #[test]
     fn prefixed_by_whitespace() {
-        let text = WeightedLine(vec![WeightedText::from(StyledText::from("   * bullet"))]);
+        let text = WeightedLine::from(vec![WeightedText::from(StyledText::from("   * bullet"))]);
         let lines = join_lines(text.split(50));
         let expected = vec!["   * bullet"];
         assert_eq!(lines, expected);
     }
 
+    #[test]
+    fn split_with_hanging_indent() {
+        let text = WeightedLine::from(vec![
+            WeightedText::from(StyledText::from("1. ")),
+            WeightedText::from(StyledText::from("one two three four")),
+        ])
+        .with_hanging_indent(3);
+        let lines = join_lines(text.split(11));
+        let expected = vec!["1.  one two", "three", "four"];
+        assert_eq!(lines, expected);
+    }
+
     #[test]
     fn utf8_character() {
-        let text = WeightedLine(vec![WeightedText::from(StyledText::from("• A"))]);
+        let text = WeightedLine::from(vec![WeightedText::from(StyledText::from("• A"))]);
         let lines = join_lines(text.split(50));
         let expected = vec!["• A"];
         assert_eq!(lines, expected);
@@ -338,7 +390,7 @@ mod test {
     #[test]
     fn many_utf8_characters() {
         let content = "█████ ██";
-        let text = WeightedLine(vec![WeightedText::from(StyledText::from(content))]);
+        let text = WeightedLine::from(vec![WeightedText::from(StyledText::from(content))]);
         let lines = join_lines(text.split(3));
         let expected = vec!["███", "██", "██"];
         assert_eq!(lines, expected);
@@ -347,7 +399,7 @@ mod test {
     #[test]
     fn no_whitespaces_ascii() {
         let content = "X".repeat(10);
-        let text = WeightedLine(vec![WeightedText::from(StyledText::from(content))]);
+        let text = WeightedLine::from(vec![WeightedText::from(StyledText::from(content))]);
         let lines = join_lines(text.split(3));
         let expected = vec!["XXX", "XXX", "XXX", "X"];
         assert_eq!(lines, expected);
@@ -356,7 +408,7 @@ mod test {
     #[test]
     fn no_whitespaces_utf8() {
         let content = "─".repeat(10);
-        let text = WeightedLine(vec![WeightedText::from(StyledText::from(content))]);
+        let text = WeightedLine::from(vec![WeightedText::from(StyledText::from(content))]);
         let lines = join_lines(text.split(3));
         let expected = vec!["───", "───", "───", "─"];
         assert_eq!(lines, expected);
@@ -365,7 +417,7 @@ mod test {
     #[test]
     fn wide_characters() {
         let content = "Ｈｅｌｌｏ ｗｏｒｌｄ";
-        let text = WeightedLine(vec![WeightedText::from(StyledText::from(content))]);
+        let text = WeightedLine::from(vec![WeightedText::from(StyledText::from(content))]);
         let lines = join_lines(text.split(10));
         // Each word is 10 characters long
         let expected = vec!["Ｈｅｌｌｏ", "ｗｏｒｌｄ"];
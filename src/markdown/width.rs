@@ -0,0 +1,124 @@
+use unicode_width::UnicodeWidthChar;
+
+const ZERO_WIDTH_JOINER: char = '\u{200d}';
+const EMOJI_PRESENTATION_SELECTOR: char = '\u{fe0f}';
+const TEXT_PRESENTATION_SELECTOR: char = '\u{fe0e}';
+const EMOJI_MODIFIERS: std::ops::RangeInclusive<char> = '\u{1f3fb}'..='\u{1f3ff}';
+const TAGS: std::ops::RangeInclusive<char> = '\u{e0020}'..='\u{e007f}';
+
+/// The number of columns a piece of text takes when displayed in a terminal.
+///
+/// Unlike a plain sum of the width of every character, this treats sequences of characters that
+/// are displayed as a single glyph, like emoji joined via zero width joiners, emoji with skin tone
+/// modifiers, or characters followed by an emoji presentation selector, as a single unit.
+pub trait DisplayWidth {
+    /// Get the display width of this text.
+    fn display_width(&self) -> usize;
+}
+
+impl DisplayWidth for str {
+    fn display_width(&self) -> usize {
+        char_widths(self).map(|(_, width)| width).sum()
+    }
+}
+
+impl DisplayWidth for String {
+    fn display_width(&self) -> usize {
+        self.as_str().display_width()
+    }
+}
+
+/// Get the width of every character in a piece of text.
+///
+/// The width of a sequence of characters that's displayed as a single glyph is assigned to the
+/// first character in it, so that adding up the widths of every character in the text gives its
+/// [DisplayWidth::display_width].
+pub fn char_widths(text: &str) -> impl Iterator<Item = (char, usize)> + '_ {
+    let mut cluster_width = 0;
+    let mut previous: Option<char> = None;
+    text.chars().map(move |c| {
+        let width = match c {
+            ZERO_WIDTH_JOINER | TEXT_PRESENTATION_SELECTOR => 0,
+            // This turns the character before it into an emoji, which always takes 2 columns.
+            EMOJI_PRESENTATION_SELECTOR if previous.is_some() => 2_usize.saturating_sub(cluster_width),
+            c if TAGS.contains(&c) => 0,
+            c if EMOJI_MODIFIERS.contains(&c) && previous.is_some_and(|c| c.width().unwrap_or(0) > 0) => 0,
+            // Anything right after a zero width joiner is part of the same glyph.
+            _ if previous == Some(ZERO_WIDTH_JOINER) => 0,
+            c => {
+                cluster_width = 0;
+                c.width().unwrap_or(0)
+            }
+        };
+        cluster_width += width;
+        previous = Some(c);
+        (c, width)
+    })
+}
+
+/// Truncate a piece of text so it takes at most `max_width` columns, ending it with an ellipsis
+/// if anything was cut off.
+///
+/// Sequences of characters that are displayed as a single glyph are never split.
+pub fn truncate_with_ellipsis(text: &str, max_width: usize) -> String {
+    if text.display_width() <= max_width {
+        return text.to_string();
+    }
+    let Some(limit) = max_width.checked_sub(1) else {
+        return String::new();
+    };
+    let mut output = String::new();
+    let mut width = 0;
+    for (c, char_width) in char_widths(text) {
+        // Characters that don't take up any width belong to the glyph before them.
+        if char_width > 0 && width + char_width > limit {
+            break;
+        }
+        width += char_width;
+        output.push(c);
+    }
+    output.push('…');
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::ascii("hello", 5)]
+    #[case::cjk("日本語", 6)]
+    #[case::combining("e\u{301}", 1)]
+    #[case::emoji("🦀", 2)]
+    #[case::zwj_family("👨\u{200d}👩\u{200d}👧", 2)]
+    #[case::zwj_and_text("a👩\u{200d}💻b", 4)]
+    #[case::skin_tone("👍🏽", 2)]
+    #[case::emoji_presentation("❤\u{fe0f}", 2)]
+    #[case::emoji_presentation_wide("🦀\u{fe0f}", 2)]
+    #[case::keycap("1\u{fe0f}\u{20e3}", 2)]
+    #[case::flag("🇦🇷", 2)]
+    #[case::rainbow_flag("🏳\u{fe0f}\u{200d}🌈", 2)]
+    fn widths(#[case] text: &str, #[case] expected: usize) {
+        assert_eq!(text.display_width(), expected);
+    }
+
+    #[rstest]
+    #[case::fits("hello", 5, "hello")]
+    #[case::ascii("hello", 4, "hel…")]
+    #[case::wide("日本語", 4, "日…")]
+    #[case::zwj_family("a👨\u{200d}👩\u{200d}👧bc", 4, "a👨\u{200d}👩\u{200d}👧…")]
+    #[case::zwj_family_cut("a👨\u{200d}👩\u{200d}👧bc", 3, "a…")]
+    #[case::zero("hello", 0, "")]
+    fn truncate(#[case] text: &str, #[case] max_width: usize, #[case] expected: &str) {
+        let truncated = truncate_with_ellipsis(text, max_width);
+        assert_eq!(truncated, expected);
+        assert!(truncated.display_width() <= max_width);
+    }
+
+    #[test]
+    fn width_assigned_to_first_char() {
+        let widths: Vec<_> = char_widths("👨\u{200d}👩x").map(|(_, width)| width).collect();
+        assert_eq!(widths, &[2, 0, 0, 1]);
+    }
+}
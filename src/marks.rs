@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+/// The mark that points to where we were before the last jump.
+pub const LAST_JUMP_MARK: char = '\'';
+
+/// Slides marked by the presenter so they can jump back to them, just like vim's marks.
+///
+/// Besides the ones set explicitly, [LAST_JUMP_MARK] always points to the slide we were at before
+/// the last jump, which allows hopping back and forth between two slides.
+#[derive(Debug, Default)]
+pub struct Marks {
+    marks: HashMap<char, usize>,
+    last_jump_origin: Option<usize>,
+}
+
+impl Marks {
+    /// Mark a slide.
+    pub fn set(&mut self, mark: char, slide_index: usize) {
+        self.marks.insert(mark, slide_index);
+    }
+
+    /// Get the slide a mark points to.
+    pub fn get(&self, mark: char) -> Option<usize> {
+        match mark {
+            LAST_JUMP_MARK => self.last_jump_origin,
+            mark => self.marks.get(&mark).copied(),
+        }
+    }
+
+    /// Keep track of a jump that started at the given slide.
+    pub fn record_jump(&mut self, origin_slide_index: usize) {
+        self.last_jump_origin = Some(origin_slide_index);
+    }
+
+    /// Forget about every mark.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn set_and_get() {
+        let mut marks = Marks::default();
+        assert_eq!(marks.get('a'), None);
+
+        marks.set('a', 3);
+        marks.set('b', 5);
+        marks.set('a', 7);
+        assert_eq!(marks.get('a'), Some(7));
+        assert_eq!(marks.get('b'), Some(5));
+    }
+
+    #[test]
+    fn last_jump() {
+        let mut marks = Marks::default();
+        assert_eq!(marks.get(LAST_JUMP_MARK), None);
+
+        marks.record_jump(4);
+        marks.record_jump(2);
+        assert_eq!(marks.get(LAST_JUMP_MARK), Some(2));
+
+        marks.clear();
+        assert_eq!(marks.get(LAST_JUMP_MARK), None);
+    }
+}
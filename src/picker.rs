@@ -0,0 +1,304 @@
+use crate::{
+    compat::parse_front_matter,
+    render::{properties::WindowSize, terminal::Terminal},
+};
+use crossterm::{
+    event::{read, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    style::Stylize,
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+// The rows used by the query line and the blank line below it.
+const HEADER_ROWS: u16 = 2;
+
+/// A presentation that can be picked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeckEntry {
+    /// The path to the presentation.
+    pub path: PathBuf,
+
+    /// The presentation's title, or its file name if it has none.
+    pub title: String,
+
+    /// The path to the presentation relative to the directory it was found in.
+    pub relative_path: String,
+}
+
+impl DeckEntry {
+    fn load(path: PathBuf, directory: &Path) -> Self {
+        let relative_path = path.strip_prefix(directory).unwrap_or(&path).display().to_string();
+        let title = fs::read_to_string(&path).ok().and_then(|contents| read_title(&contents));
+        let title = title.unwrap_or_else(|| relative_path.clone());
+        Self { path, title, relative_path }
+    }
+}
+
+/// Find every markdown file within a directory and its subdirectories.
+///
+/// Hidden files and directories are ignored.
+pub fn find_decks(directory: &Path) -> io::Result<Vec<DeckEntry>> {
+    let mut paths = Vec::new();
+    collect_markdown_files(directory, &mut paths)?;
+    paths.sort();
+    Ok(paths.into_iter().map(|path| DeckEntry::load(path, directory)).collect())
+}
+
+fn collect_markdown_files(directory: &Path, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect_markdown_files(&path, paths)?;
+        } else if path.extension().is_some_and(|extension| extension == "md") {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn read_title(contents: &str) -> Option<String> {
+    let contents = contents.strip_prefix("---\n")?;
+    let end = contents.lines().position(|line| line.trim_end() == "---")?;
+    let front_matter = contents.lines().take(end).collect::<Vec<_>>().join("\n");
+    let (metadata, _) = parse_front_matter(&front_matter).ok()?;
+    metadata.title
+}
+
+/// Score how well a query matches a piece of text.
+///
+/// Every character in the query must appear in the text in the same order, ignoring case, for it to
+/// match. Matches where characters are next to each other or at the beginning of words score higher.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let mut score = 0;
+    let mut previous_match: Option<usize> = None;
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let mut position = 0;
+    for query_char in query.chars().flat_map(char::to_lowercase) {
+        let offset = text[position..].iter().position(|c| *c == query_char)?;
+        let index = position + offset;
+        score += 1;
+        if index > 0 && previous_match == Some(index - 1) {
+            score += 2;
+        }
+        if index == 0 || !text[index - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous_match = Some(index);
+        position = index + 1;
+    }
+    Some(score)
+}
+
+/// Lets the user pick one presentation out of a list by typing a piece of its title or path.
+pub struct DeckPicker {
+    entries: Vec<DeckEntry>,
+    query: String,
+    matches: Vec<usize>,
+    selected: usize,
+    message: Option<String>,
+}
+
+impl DeckPicker {
+    /// Construct a new picker for the given presentations.
+    pub fn new(entries: Vec<DeckEntry>) -> Self {
+        let matches = (0..entries.len()).collect();
+        Self { entries, query: String::new(), matches, selected: 0, message: None }
+    }
+
+    /// Display a message to the user the next time the picker is shown, e.g. an error.
+    pub fn set_message<S: Into<String>>(&mut self, message: S) {
+        self.message = Some(message.into());
+    }
+
+    /// Show the picker until the user either picks a presentation or exits.
+    ///
+    /// The query and selection are kept between calls so users can pick up where they left off.
+    pub fn pick(&mut self) -> io::Result<Option<PathBuf>> {
+        let mut terminal = Terminal::new(io::stdout())?;
+        loop {
+            self.draw(&mut terminal)?;
+            let Event::Key(event) = read()? else {
+                continue;
+            };
+            match self.apply_key_event(event) {
+                PickerAction::None => (),
+                PickerAction::Select => {
+                    if let Some(entry) = self.selected() {
+                        return Ok(Some(entry.path.clone()));
+                    }
+                }
+                PickerAction::Exit => return Ok(None),
+            };
+        }
+    }
+
+    /// Get the currently selected presentation, if any.
+    pub fn selected(&self) -> Option<&DeckEntry> {
+        self.matches.get(self.selected).map(|index| &self.entries[*index])
+    }
+
+    fn apply_key_event(&mut self, event: KeyEvent) -> PickerAction {
+        if event.kind == KeyEventKind::Release {
+            return PickerAction::None;
+        }
+        let control = event.modifiers == KeyModifiers::CONTROL;
+        match event.code {
+            KeyCode::Enter => return PickerAction::Select,
+            KeyCode::Esc => return PickerAction::Exit,
+            KeyCode::Char('c') if control => return PickerAction::Exit,
+            KeyCode::Up => self.move_selection(-1),
+            KeyCode::Char('p') if control => self.move_selection(-1),
+            KeyCode::Down => self.move_selection(1),
+            KeyCode::Char('n') if control => self.move_selection(1),
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.update_matches();
+            }
+            KeyCode::Char(c) if !control => {
+                self.query.push(c);
+                self.update_matches();
+            }
+            _ => (),
+        };
+        PickerAction::None
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let last = self.matches.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    fn update_matches(&mut self) {
+        let mut scored: Vec<_> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                let title_score = fuzzy_score(&self.query, &entry.title);
+                let path_score = fuzzy_score(&self.query, &entry.relative_path);
+                title_score.max(path_score).map(|score| (index, score))
+            })
+            .collect();
+        // This is a stable sort so entries that score the same stay sorted by path.
+        scored.sort_by(|(_, left), (_, right)| right.cmp(left));
+        self.matches = scored.into_iter().map(|(index, _)| index).collect();
+        self.selected = 0;
+    }
+
+    fn draw<W: io::Write>(&mut self, terminal: &mut Terminal<W>) -> io::Result<()> {
+        let dimensions = WindowSize::current()?;
+        let visible_rows = dimensions.rows.saturating_sub(HEADER_ROWS + 1).max(1) as usize;
+        let first_visible = self.selected.saturating_sub(visible_rows - 1);
+        let max_width = dimensions.columns as usize;
+
+        terminal.clear_screen()?;
+        terminal.move_to(0, 0)?;
+        terminal.print_styled_line(format!("presentation: {}", self.query).bold())?;
+        for (row, index) in self.matches.iter().enumerate().skip(first_visible).take(visible_rows) {
+            let entry = &self.entries[*index];
+            let text = format!("{} ({})", entry.title, entry.relative_path);
+            let text: String = text.chars().take(max_width).collect();
+            terminal.move_to(0, (row - first_visible) as u16 + HEADER_ROWS)?;
+            if row == self.selected {
+                terminal.print_styled_line(text.reverse())?;
+            } else {
+                terminal.print_line(&text)?;
+            }
+        }
+        let status = match (&self.message, self.matches.is_empty()) {
+            (Some(message), _) => message.clone(),
+            (None, true) => "no presentations match".into(),
+            (None, false) => format!("{}/{}", self.matches.len(), self.entries.len()),
+        };
+        terminal.move_to(0, dimensions.rows.saturating_sub(1))?;
+        terminal.print_styled_line(status.dim())?;
+        terminal.flush()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum PickerAction {
+    None,
+    Select,
+    Exit,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+    use tempfile::tempdir;
+
+    fn entry(title: &str, relative_path: &str) -> DeckEntry {
+        DeckEntry { path: relative_path.into(), title: title.into(), relative_path: relative_path.into() }
+    }
+
+    fn type_query(picker: &mut DeckPicker, query: &str) {
+        for c in query.chars() {
+            picker.apply_key_event(KeyCode::Char(c).into());
+        }
+    }
+
+    #[rstest]
+    #[case::exact("rust", "rust", true)]
+    #[case::subsequence("rst", "rust", true)]
+    #[case::case_insensitive("RuSt", "Intro to rust", true)]
+    #[case::wrong_order("tsur", "rust", false)]
+    #[case::missing("go", "rust", false)]
+    fn fuzzy_matches(#[case] query: &str, #[case] text: &str, #[case] matches: bool) {
+        assert_eq!(fuzzy_score(query, text).is_some(), matches);
+    }
+
+    #[test]
+    fn fuzzy_ranking() {
+        let consecutive = fuzzy_score("talk", "my talk").unwrap();
+        let scattered = fuzzy_score("talk", "tall stack").unwrap();
+        assert!(consecutive > scattered);
+    }
+
+    #[test]
+    fn titles() {
+        let directory = tempdir().expect("creating temp dir");
+        fs::write(directory.path().join("first.md"), "---\ntitle: My talk\n---\n\nhi").unwrap();
+        fs::write(directory.path().join("second.md"), "# no front matter").unwrap();
+        fs::write(directory.path().join("notes.txt"), "not markdown").unwrap();
+        fs::create_dir(directory.path().join("nested")).unwrap();
+        fs::write(directory.path().join("nested/third.md"), "---\nauthor: me\n---\n").unwrap();
+        fs::create_dir(directory.path().join(".hidden")).unwrap();
+        fs::write(directory.path().join(".hidden/fourth.md"), "").unwrap();
+
+        let decks = find_decks(directory.path()).expect("finding decks");
+        let titles: Vec<_> = decks.iter().map(|deck| deck.title.as_str()).collect();
+        assert_eq!(titles, &["My talk", "nested/third.md", "second.md"]);
+    }
+
+    #[test]
+    fn filter_and_select() {
+        let mut picker = DeckPicker::new(vec![
+            entry("Intro", "intro.md"),
+            entry("Rust in production", "alice.md"),
+            entry("Closing", "outro.md"),
+        ]);
+        assert_eq!(picker.selected(), Some(&entry("Intro", "intro.md")));
+
+        picker.apply_key_event(KeyCode::Down.into());
+        assert_eq!(picker.selected(), Some(&entry("Rust in production", "alice.md")));
+
+        type_query(&mut picker, "o");
+        assert_eq!(picker.matches.len(), 3);
+        type_query(&mut picker, "ut");
+        assert_eq!(picker.selected(), Some(&entry("Closing", "outro.md")));
+
+        type_query(&mut picker, "x");
+        assert_eq!(picker.selected(), None);
+        picker.apply_key_event(KeyCode::Backspace.into());
+        assert_eq!(picker.apply_key_event(KeyCode::Enter.into()), PickerAction::Select);
+        assert_eq!(picker.apply_key_event(KeyCode::Esc.into()), PickerAction::Exit);
+    }
+}
@@ -1,22 +1,96 @@
 use crate::{
-    markdown::text::WeightedLine,
+    hooks::SlideHooks,
+    input::source::Command,
+    markdown::{elements::ImageAlignment, text::WeightedLine},
     render::{media::Image, properties::WindowSize},
-    style::Colors,
-    theme::{Alignment, Margin, PresentationTheme},
+    style::{Color, Colors, TextStyle},
+    theme::{Alignment, LineSize, Margin, PresentationTheme, SeparatorStyle},
 };
 use serde::Deserialize;
-use std::rc::Rc;
+use std::{cell::RefCell, collections::BTreeMap, path::PathBuf, rc::Rc, sync::mpsc::Sender, time::Duration};
 
 /// A presentation.
 pub struct Presentation {
     slides: Vec<Slide>,
     current_slide_index: usize,
+    warnings: Vec<String>,
+    options: PresentationOptions,
+    parts: Vec<PresentationPart>,
+    colors: Colors,
 }
 
 impl Presentation {
     /// Construct a new presentation.
     pub fn new(slides: Vec<Slide>) -> Self {
-        Self { slides, current_slide_index: 0 }
+        Self {
+            slides,
+            current_slide_index: 0,
+            warnings: Vec::new(),
+            options: Default::default(),
+            parts: Vec::new(),
+            colors: Default::default(),
+        }
+    }
+
+    /// Set the parts this presentation is split into.
+    pub fn with_parts(mut self, parts: Vec<PresentationPart>) -> Self {
+        self.parts = parts;
+        self
+    }
+
+    /// Replace the parts this presentation is split into.
+    pub fn set_parts(&mut self, parts: Vec<PresentationPart>) {
+        self.parts = parts;
+    }
+
+    /// Get the parts this presentation is split into.
+    pub fn parts(&self) -> &[PresentationPart] {
+        &self.parts
+    }
+
+    /// Set the options for this presentation.
+    pub fn with_options(mut self, options: PresentationOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Get the options for this presentation.
+    pub fn options(&self) -> &PresentationOptions {
+        &self.options
+    }
+
+    /// Set the default colors of the theme this presentation was built with.
+    pub fn with_colors(mut self, colors: Colors) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    /// Get the default colors of the theme this presentation was built with.
+    pub fn colors(&self) -> &Colors {
+        &self.colors
+    }
+
+    /// Attach the warnings produced while building this presentation.
+    pub fn with_warnings(mut self, warnings: Vec<String>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    /// Replace the warnings produced while building this presentation.
+    pub fn set_warnings(&mut self, warnings: Vec<String>) {
+        self.warnings = warnings;
+    }
+
+    /// Get the warnings produced while building this presentation.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Add slides at the end of this presentation.
+    ///
+    /// This is used to complete presentations that are built lazily.
+    pub fn extend_slides<I: IntoIterator<Item = Slide>>(&mut self, slides: I) {
+        self.slides.extend(slides);
     }
 
     /// Iterate the slides in this presentation.
@@ -24,6 +98,11 @@ impl Presentation {
         self.slides.iter()
     }
 
+    /// Iterate the slides in this presentation mutably.
+    pub fn iter_slides_mut(&mut self) -> impl Iterator<Item = &mut Slide> {
+        self.slides.iter_mut()
+    }
+
     /// Consume this presentation and return its slides.
     pub fn into_slides(self) -> Vec<Slide> {
         self.slides
@@ -34,56 +113,217 @@ impl Presentation {
         &self.slides[self.current_slide_index]
     }
 
+    fn current_slide_mut(&mut self) -> &mut Slide {
+        &mut self.slides[self.current_slide_index]
+    }
+
+    /// Get the slide after the current one, if any.
+    pub fn next_slide(&self) -> Option<&Slide> {
+        self.slides.get(self.current_slide_index + 1)
+    }
+
     /// Get the current slide index.
     pub fn current_slide_index(&self) -> usize {
         self.current_slide_index
     }
 
-    /// Jump to the next slide.
+    /// Reveal the next chunk in the current slide or, if they're all visible, jump to the next slide.
     pub fn jump_next_slide(&mut self) -> bool {
-        if self.current_slide_index < self.slides.len() - 1 {
-            self.current_slide_index += 1;
+        let visible = self.current_slide().visible_chunks();
+        if self.show_chunks(visible + 1) {
             true
+        } else if self.current_slide_index < self.slides.len() - 1 {
+            self.jump_slide(self.current_slide_index + 1)
         } else {
             false
         }
     }
 
-    /// Jump to the previous slide.
+    /// Hide the last visible chunk in the current slide or, if only the first one is visible, jump to
+    /// the previous slide with every one of its chunks revealed.
     pub fn jump_previous_slide(&mut self) -> bool {
-        if self.current_slide_index > 0 {
-            self.current_slide_index -= 1;
+        let visible = self.current_slide().visible_chunks();
+        if visible > 1 {
+            self.show_chunks(visible - 1)
+        } else if self.current_slide_index > 0 {
+            self.jump_slide(self.current_slide_index - 1);
+            self.show_chunks(usize::MAX);
             true
         } else {
             false
         }
     }
 
+    /// Jump to the next slide, skipping any chunks left in the current one.
+    pub fn jump_next_full_slide(&mut self) -> bool {
+        let current = self.current_slide_index;
+        let next_slide = (current..self.slides.len()).find(|index| !self.in_same_slide(current, *index));
+        match next_slide {
+            Some(index) => self.jump_slide(index),
+            // Reveal everything that's left in the last slide.
+            None => self.jump_last_slide(),
+        }
+    }
+
+    /// Jump to the previous slide with every one of its chunks revealed.
+    pub fn jump_previous_full_slide(&mut self) -> bool {
+        let current = self.current_slide_index;
+        match (0..current).rev().find(|index| !self.in_same_slide(current, *index)) {
+            Some(index) => {
+                self.jump_slide(index);
+                self.show_chunks(usize::MAX);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Slides generated out of the same markdown slide, like the pages in scrolling code blocks, share
+    // their hooks.
+    fn in_same_slide(&self, first: usize, second: usize) -> bool {
+        Rc::ptr_eq(&self.slides[first].hooks, &self.slides[second].hooks)
+    }
+
     /// Jump to the first slide.
     pub fn jump_first_slide(&mut self) -> bool {
-        if self.current_slide_index != 0 {
-            self.current_slide_index = 0;
-            true
-        } else {
-            false
-        }
+        let changed = self.current_slide_index != 0 || self.current_slide().visible_chunks() != 1;
+        self.jump_slide(0);
+        changed
     }
 
-    /// Jump to the last slide.
+    /// Jump to the last slide, with every one of its chunks revealed.
     pub fn jump_last_slide(&mut self) -> bool {
         let last_slide_index = self.slides.len().saturating_sub(1);
-        if self.current_slide_index != last_slide_index {
-            self.current_slide_index = last_slide_index;
-            true
-        } else {
-            false
+        let changed = self.current_slide_index != last_slide_index;
+        self.current_slide_index = last_slide_index;
+        self.show_chunks(usize::MAX) || changed
+    }
+
+    /// Reveal the given number of chunks in the current slide, hiding any others.
+    ///
+    /// The count is clamped so at least the first chunk is visible. Returns `true` if the number of
+    /// visible chunks changed.
+    pub fn show_chunks(&mut self, count: usize) -> bool {
+        let slide = self.current_slide_mut();
+        let count = count.clamp(1, slide.chunks.len().max(1));
+        let previous = slide.visible_chunks;
+        for chunk in slide.chunks.iter().take(count).skip(previous) {
+            chunk.mutators.iter().for_each(|mutator| mutator.mutate_next());
+        }
+        for chunk in slide.chunks.iter().take(previous).skip(count).rev() {
+            chunk.mutators.iter().rev().for_each(|mutator| mutator.mutate_previous());
+        }
+        let changed = previous != count;
+        slide.visible_chunks = count;
+        changed
+    }
+
+    /// Start every on demand render operation in the visible chunks of the current slide.
+    ///
+    /// Returns `true` if any of them was started.
+    pub fn start_on_demand_renders(&self, sender: &Sender<Command>) -> bool {
+        let mut started = false;
+        for operation in self.current_slide().iter_visible_operations() {
+            if let RenderOperation::RenderOnDemand(operation) = operation {
+                started |= operation.start_render(sender.clone());
+            }
+        }
+        started
+    }
+
+    /// Stop every on demand render operation in the current slide that's running.
+    ///
+    /// Returns `true` if any of them was stopped.
+    pub fn stop_on_demand_renders(&self) -> bool {
+        let mut stopped = false;
+        for operation in self.current_slide().iter_operations() {
+            if let RenderOperation::RenderOnDemand(operation) = operation {
+                stopped |= operation.stop_render();
+            }
+        }
+        stopped
+    }
+
+    /// Start every on demand render operation in the visible chunks of the current slide that starts
+    /// automatically.
+    ///
+    /// Returns `true` if any of them was started.
+    pub fn start_automatic_renders(&self, sender: &Sender<Command>) -> bool {
+        let mut started = false;
+        for operation in self.current_slide().iter_visible_operations() {
+            if let RenderOperation::RenderOnDemand(operation) = operation {
+                if operation.starts_automatically() {
+                    started |= operation.start_render(sender.clone());
+                }
+            }
+        }
+        started
+    }
+
+    /// Jump to the first slide in the next part.
+    pub fn jump_next_part(&mut self) -> bool {
+        let current = self.current_slide_index;
+        match self.parts.iter().find(|part| part.first_slide > current) {
+            Some(part) => self.jump_slide(part.first_slide),
+            None => false,
+        }
+    }
+
+    /// Jump to the first slide in the current part or, if already there, the previous one.
+    pub fn jump_previous_part(&mut self) -> bool {
+        let current = self.current_slide_index;
+        match self.parts.iter().rev().find(|part| part.first_slide < current) {
+            Some(part) => self.jump_slide(part.first_slide),
+            None => false,
+        }
+    }
+
+    /// Jump to the next slide that begins a section.
+    pub fn jump_next_section(&mut self) -> bool {
+        let current = self.current_slide_index;
+        match (current + 1..self.slides.len()).find(|index| self.starts_section(*index)) {
+            Some(index) => self.jump_slide(index),
+            None => false,
+        }
+    }
+
+    /// Jump to the slide that begins the current section or, if already there, the previous one.
+    pub fn jump_previous_section(&mut self) -> bool {
+        let current = self.current_slide_index;
+        match (0..current).rev().find(|index| self.starts_section(*index)) {
+            Some(index) => self.jump_slide(index),
+            None => false,
         }
     }
 
-    /// Jump to a specific slide.
+    // Only the first of the slides generated out of a section's markdown slide begins it.
+    fn starts_section(&self, index: usize) -> bool {
+        let is_section = |index: usize| self.slides[index].kind == SlideKind::Section;
+        let is_continuation = index > 0 && self.in_same_slide(index - 1, index) && is_section(index - 1);
+        is_section(index) && !is_continuation
+    }
+
+    /// Find the index of the slide identified by the given target.
+    ///
+    /// The target can either be a 1-based slide number or a piece of text that's contained in a
+    /// slide's title, in which case the first matching slide is returned.
+    pub fn find_slide(&self, target: &str) -> Option<usize> {
+        let target = target.trim();
+        if let Ok(number) = target.parse::<usize>() {
+            return number.checked_sub(1).filter(|index| *index < self.slides.len());
+        }
+        let target = target.trim_start_matches('#').trim().to_lowercase();
+        if target.is_empty() {
+            return None;
+        }
+        self.slides.iter().position(|slide| slide.title.to_lowercase().contains(&target))
+    }
+
+    /// Jump to a specific slide, with only its first chunk visible.
     pub fn jump_slide(&mut self, slide_index: usize) -> bool {
         if slide_index < self.slides.len() {
             self.current_slide_index = slide_index;
+            self.show_chunks(1);
             true
         } else {
             false
@@ -94,10 +334,162 @@ impl Presentation {
 /// A slide.
 ///
 /// Slides are composed of render operations that can be carried out to materialize this slide into
-/// the terminal's screen.
+/// the terminal's screen. These are split into chunks by pauses, which are revealed one at a time.
 #[derive(Clone, Debug)]
 pub struct Slide {
-    pub render_operations: Vec<RenderOperation>,
+    /// The chunks this slide is split into.
+    pub chunks: Vec<SlideChunk>,
+
+    /// The operations that render this slide's footer, which are carried out after the visible
+    /// chunks.
+    pub footer: Vec<RenderOperation>,
+
+    /// The hooks to run when entering and leaving this slide.
+    ///
+    /// These are shared between all the slides generated out of the same markdown slide, like the
+    /// pages in a scrolling code block, so moving within them doesn't trigger any hooks.
+    pub hooks: Rc<RefCell<SlideHooks>>,
+
+    /// The speaker notes for this slide.
+    ///
+    /// Just like hooks, these are shared between all the slides generated out of the same
+    /// markdown slide.
+    pub notes: Rc<RefCell<Vec<String>>>,
+
+    /// The title of this slide.
+    ///
+    /// Slides that don't have a title of their own inherit the one in the slide before them.
+    pub title: String,
+
+    /// How long this slide should be displayed for when auto advancing.
+    ///
+    /// When this isn't set, the default duration is used.
+    pub duration: Option<Duration>,
+
+    /// The videos referenced in this slide, which can be played using an external player.
+    pub videos: Vec<String>,
+
+    /// The kind of slide this is.
+    pub kind: SlideKind,
+
+    visible_chunks: usize,
+}
+
+/// The kind of a slide.
+///
+/// This is inferred from the slide's contents unless it's set explicitly, and allows themes to
+/// style every kind of slide differently.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlideKind {
+    /// The introduction slide, generated out of the presentation's metadata.
+    Title,
+
+    /// A slide that begins a section, meaning it has a slide title or a level 1 heading.
+    Section,
+
+    /// Any other slide.
+    #[default]
+    Content,
+
+    /// A slide that wraps up the presentation.
+    ///
+    /// This one is never inferred and needs to be set explicitly.
+    Closing,
+}
+
+impl Slide {
+    /// Construct a new content slide with a single chunk and no footer, hooks, notes, title,
+    /// duration, nor videos.
+    pub fn new(render_operations: Vec<RenderOperation>) -> Self {
+        Self::from_chunks(vec![SlideChunk::new(render_operations)], Vec::new())
+    }
+
+    /// Construct a new content slide out of the given chunks and footer.
+    pub fn from_chunks(chunks: Vec<SlideChunk>, footer: Vec<RenderOperation>) -> Self {
+        Self {
+            chunks,
+            footer,
+            hooks: Default::default(),
+            notes: Default::default(),
+            title: String::new(),
+            duration: None,
+            videos: Vec::new(),
+            kind: SlideKind::Content,
+            visible_chunks: 1,
+        }
+    }
+
+    /// Get the number of chunks that are currently visible.
+    pub fn visible_chunks(&self) -> usize {
+        self.visible_chunks
+    }
+
+    /// Iterate every operation in this slide, including the ones in chunks that aren't visible.
+    pub fn iter_operations(&self) -> impl Iterator<Item = &RenderOperation> {
+        self.chunks.iter().flat_map(|chunk| &chunk.operations).chain(&self.footer)
+    }
+
+    /// Iterate every operation in this slide mutably.
+    pub fn iter_operations_mut(&mut self) -> impl Iterator<Item = &mut RenderOperation> {
+        self.chunks.iter_mut().flat_map(|chunk| &mut chunk.operations).chain(&mut self.footer)
+    }
+
+    /// Iterate the operations that need to be carried out to render this slide's visible chunks.
+    pub fn iter_visible_operations(&self) -> impl Iterator<Item = &RenderOperation> {
+        self.chunks.iter().take(self.visible_chunks).flat_map(|chunk| &chunk.operations).chain(&self.footer)
+    }
+
+    /// Set the title of this slide.
+    pub fn with_title<S: Into<String>>(mut self, title: S) -> Self {
+        self.title = title.into();
+        self
+    }
+}
+
+/// A piece of a slide that's revealed all at once.
+#[derive(Clone, Debug, Default)]
+pub struct SlideChunk {
+    /// The operations in this chunk.
+    pub operations: Vec<RenderOperation>,
+
+    /// The mutators applied when this chunk is revealed, which are undone when it's hidden again.
+    pub mutators: Vec<Rc<dyn ChunkMutator>>,
+}
+
+impl SlideChunk {
+    /// Construct a new chunk.
+    pub fn new(operations: Vec<RenderOperation>) -> Self {
+        Self { operations, mutators: Vec::new() }
+    }
+
+    /// Set the mutators applied when this chunk is revealed.
+    pub fn with_mutators(mut self, mutators: Vec<Rc<dyn ChunkMutator>>) -> Self {
+        self.mutators = mutators;
+        self
+    }
+}
+
+/// Changes something in the chunks before the one it belongs to when that one is revealed.
+///
+/// This allows chunks to modify what's already displayed, like the lines highlighted in a code
+/// block, rather than only adding to it.
+pub trait ChunkMutator: std::fmt::Debug {
+    /// Apply this mutation.
+    fn mutate_next(&self);
+
+    /// Undo this mutation.
+    fn mutate_previous(&self);
+}
+
+/// A part of a presentation, like a section in a workshop.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PresentationPart {
+    /// The name of this part.
+    pub name: String,
+
+    /// The index of the first slide in this part.
+    pub first_slide: usize,
 }
 
 /// The metadata for a presentation.
@@ -112,11 +504,243 @@ pub struct PresentationMetadata {
 
     /// The presentation author.
     #[serde(default)]
-    pub author: Option<String>,
+    pub author: Option<PresentationAuthor>,
 
     /// The presentation's theme metadata.
     #[serde(default)]
     pub theme: PresentationThemeMetadata,
+
+    /// The hooks to run when entering and leaving every slide.
+    #[serde(default)]
+    pub hooks: SlideHooks,
+
+    /// Additional directories to look for resources like images and themes in.
+    #[serde(default)]
+    pub resource_paths: Vec<PathBuf>,
+
+    /// Options that control how the presentation behaves.
+    #[serde(default)]
+    pub options: PresentationOptions,
+
+    /// Keywords that are highlighted every time they appear in the presentation's text.
+    #[serde(default)]
+    pub keywords: BTreeMap<String, KeywordStyle>,
+
+    /// The author's handles in social networks and other ways to reach them.
+    #[serde(default)]
+    pub socials: SocialHandles,
+}
+
+/// The author of a presentation.
+///
+/// This is either just the author's name or a structured entry with details about them.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum PresentationAuthor {
+    /// Just the author's name.
+    Name(String),
+
+    /// The author's name along with details about them.
+    Details(AuthorDetails),
+}
+
+impl PresentationAuthor {
+    /// Get the author's name.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Name(name) => name,
+            Self::Details(details) => &details.name,
+        }
+    }
+}
+
+/// The handles the author of a presentation can be reached at.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SocialHandles {
+    /// The author's GitHub user.
+    #[serde(default)]
+    pub github: Option<String>,
+
+    /// The author's Mastodon handle.
+    #[serde(default)]
+    pub mastodon: Option<String>,
+
+    /// The author's email address.
+    #[serde(default)]
+    pub email: Option<String>,
+}
+
+impl SocialHandles {
+    /// Iterate the handles that are set, along with their Nerd Fonts icon and their plain text label.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &'static str, &str)> {
+        let handles = [
+            ("\u{f09b}", "github", &self.github),
+            ("\u{f4f6}", "mastodon", &self.mastodon),
+            ("\u{f0e0}", "email", &self.email),
+        ];
+        handles.into_iter().filter_map(|(icon, label, handle)| handle.as_deref().map(|handle| (icon, label, handle)))
+    }
+}
+
+/// Details about the author of a presentation.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuthorDetails {
+    /// The author's name.
+    pub name: String,
+
+    /// The author's title, e.g. their role.
+    #[serde(default)]
+    pub title: Option<String>,
+
+    /// The company or organization the author is affiliated with.
+    #[serde(default)]
+    pub company: Option<String>,
+
+    /// A way to contact the author, like an email address or a handle.
+    #[serde(default)]
+    pub contact: Option<String>,
+}
+
+/// The style used to highlight a keyword.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct KeywordStyle {
+    /// The colors to use.
+    #[serde(default)]
+    pub colors: Colors,
+
+    /// Whether to make the keyword bold.
+    #[serde(default)]
+    pub bold: bool,
+
+    /// Whether to make the keyword italics.
+    #[serde(default)]
+    pub italics: bool,
+}
+
+impl KeywordStyle {
+    /// Get the text style for this keyword style.
+    pub fn text_style(&self) -> TextStyle {
+        let mut style = TextStyle::default().colors(self.colors.clone());
+        if self.bold {
+            style = style.bold();
+        }
+        if self.italics {
+            style = style.italics();
+        }
+        style
+    }
+}
+
+/// Options that control how a presentation behaves.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct PresentationOptions {
+    /// Whether to ask for confirmation before exiting when in presentation mode.
+    ///
+    /// This defaults to `true`.
+    #[serde(default)]
+    pub confirm_exit: Option<bool>,
+
+    /// The margins used in every slide.
+    #[serde(default)]
+    pub margin: SlideMargins,
+
+    /// The defaults used when executing code.
+    #[serde(default)]
+    pub exec: ExecutionOptions,
+
+    /// Whether to reveal every list item one at a time, as if there was a pause in between them.
+    #[serde(default)]
+    pub incremental_lists: bool,
+
+    /// The watermark drawn on top of every slide.
+    #[serde(default)]
+    pub watermark: Option<Watermark>,
+}
+
+/// A text, like "DRAFT", that's drawn on top of every slide in a dim color.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Watermark {
+    /// The text to draw.
+    pub text: String,
+
+    /// Where the text is drawn.
+    #[serde(default)]
+    pub position: WatermarkPosition,
+
+    /// The color the text is drawn in.
+    #[serde(default)]
+    pub color: Option<Color>,
+}
+
+impl Watermark {
+    /// Get the color the text is drawn in, which defaults to one that's dim enough to not get in
+    /// the way of the slide's contents.
+    pub fn color(&self) -> Color {
+        self.color.unwrap_or(Color::new(100, 100, 100))
+    }
+}
+
+/// Where a watermark is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatermarkPosition {
+    /// Across the center of the slide, going from its bottom left to its top right.
+    #[default]
+    Diagonal,
+
+    /// The top left corner.
+    TopLeft,
+
+    /// The top right corner.
+    TopRight,
+
+    /// The bottom left corner.
+    BottomLeft,
+
+    /// The bottom right corner.
+    BottomRight,
+}
+
+/// The defaults used when executing code, which individual code blocks can override.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExecutionOptions {
+    /// The directory code is run in, relative to the presentation.
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+
+    /// The environment variables to set when executing code.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+/// The margins around the contents of a slide.
+///
+/// Any margin that isn't set falls back to the theme's, or to the default one.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SlideMargins {
+    /// The margin to the left and right of the slide's contents.
+    #[serde(default)]
+    pub horizontal: Option<Margin>,
+
+    /// The number of rows at the bottom of the slide that are kept empty, e.g. for the footer.
+    #[serde(default)]
+    pub bottom: Option<u16>,
+}
+
+impl SlideMargins {
+    /// The bottom margin used when none is set.
+    pub const DEFAULT_BOTTOM: u16 = 3;
+
+    /// Fill in any margins that aren't set in this one using the ones in `other`.
+    pub fn or(&self, other: &SlideMargins) -> SlideMargins {
+        let horizontal = self.horizontal.clone().or_else(|| other.horizontal.clone());
+        SlideMargins { horizontal, bottom: self.bottom.or(other.bottom) }
+    }
 }
 
 /// A presentation's theme metadata.
@@ -138,12 +762,28 @@ pub struct PresentationThemeMetadata {
 /// A line of preformatted text to be rendered.
 #[derive(Clone, Debug, PartialEq)]
 pub struct PreformattedLine {
-    pub text: String,
+    pub text: Rc<str>,
     pub unformatted_length: usize,
     pub block_length: usize,
     pub alignment: Alignment,
 }
 
+/// A line of text that's part of a block, like a block quote.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockLine {
+    /// The prefix to be drawn at the beginning of every row this line is wrapped into.
+    pub prefix: String,
+
+    /// The text in this line.
+    pub text: WeightedLine,
+
+    /// The length of the longest line in the block, including its prefix.
+    pub block_length: usize,
+
+    /// The alignment of the block.
+    pub alignment: Alignment,
+}
+
 /// A render operation.
 ///
 /// Render operations are primitives that allow the input markdown file to be decoupled with what
@@ -156,23 +796,29 @@ pub enum RenderOperation {
     /// Set the colors to be used for any subsequent operations.
     SetColors(Colors),
 
+    /// Set the size of the lines of text rendered by any subsequent operations.
+    SetLineSize(LineSize),
+
     /// Jump the draw cursor into the vertical center, that is, at `screen_height / 2`.
     JumpToVerticalCenter,
 
     /// Jumps to the last row in the slide.
     JumpToBottom,
 
+    /// Jumps to the row that's `index` rows above the last one in the slide.
+    JumpToBottomRow { index: u16 },
+
     /// Render a line of text.
     RenderTextLine { line: WeightedLine, alignment: Alignment },
 
     /// Render a horizontal separator line.
-    RenderSeparator,
+    RenderSeparator(SeparatorStyle),
 
     /// Render a line break.
     RenderLineBreak,
 
     /// Render an image.
-    RenderImage(Image),
+    RenderImage(Image, ImageProperties),
 
     /// Render a preformatted line.
     ///
@@ -180,6 +826,12 @@ pub enum RenderOperation {
     /// embedded in it.
     RenderPreformattedLine(PreformattedLine),
 
+    /// Render a line that's part of a block.
+    ///
+    /// The line is wrapped at render time to fit the available width, and every row it's wrapped
+    /// into is padded up to the block's length so the block keeps its shape.
+    RenderBlockLine(BlockLine),
+
     /// Render a dynamically generated sequence of render operations.
     ///
     /// This allows drawing something on the screen that requires knowing dynamic properties of the
@@ -187,6 +839,11 @@ pub enum RenderOperation {
     /// [RenderOperation] with the screen itself.
     RenderDynamic(Rc<dyn AsRenderOperations>),
 
+    /// Render a sequence of render operations that are only generated once the user asks for it.
+    ///
+    /// This is used for things like executing code and displaying its output.
+    RenderOnDemand(Rc<dyn RenderOnDemand>),
+
     /// Initialize a column layout.
     ///
     /// The value for each column is the width of the column in column-unit units, where the entire
@@ -216,10 +873,229 @@ pub struct MarginProperties {
 
     /// The margin at the bottom of the slide.
     pub bottom_slide_margin: u16,
+
+    /// The maximum number of columns the content can take.
+    ///
+    /// When the screen is wider than this, the horizontal margin grows so the content is centered.
+    pub max_columns: Option<u16>,
+}
+
+/// The properties of an image to be rendered.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImageProperties {
+    /// Split the rows below the cursor into this many equally sized slots and fit the image in the
+    /// first one.
+    ///
+    /// This is used to fit several rows of images in the remaining space of a slide.
+    pub vertical_slots: Option<u16>,
+
+    /// The width of the image, as a percentage of the available width.
+    ///
+    /// If not set, the image is drawn in its original size as long as it fits.
+    pub width: Option<u8>,
+
+    /// The horizontal alignment of the image.
+    pub alignment: ImageAlignment,
 }
 
 /// A type that can generate render operations.
 pub trait AsRenderOperations: std::fmt::Debug {
     /// Generate render operations.
     fn as_render_operations(&self, dimensions: &WindowSize) -> Vec<RenderOperation>;
+
+    /// Get a representation of everything this generates its operations from.
+    ///
+    /// This is used to tell whether it changed when the presentation is reloaded. Things that
+    /// don't belong to any specific slide, like footers, return `None` and are never considered
+    /// to have changed.
+    fn diffable_content(&self) -> Option<String> {
+        None
+    }
+}
+
+/// A type that generates render operations only after being explicitly started.
+pub trait RenderOnDemand: AsRenderOperations {
+    /// Start rendering.
+    ///
+    /// Any updates that require redrawing must be notified via the given sender. Returns `false`
+    /// if this had already been started.
+    fn start_render(&self, sender: Sender<Command>) -> bool;
+
+    /// Stop rendering.
+    ///
+    /// Returns `false` if this wasn't running.
+    fn stop_render(&self) -> bool {
+        false
+    }
+
+    /// Whether this is started as soon as the slide it belongs to is displayed.
+    fn starts_automatically(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::number("2", Some(1))]
+    #[case::number_out_of_bounds("4", None)]
+    #[case::zero("0", None)]
+    #[case::title("bye", Some(2))]
+    #[case::title_case_insensitive("Hello", Some(0))]
+    #[case::anchor("#goodbye", Some(2))]
+    #[case::inherited_title("hello world", Some(0))]
+    #[case::no_match("potato", None)]
+    #[case::empty("", None)]
+    fn find_slide(#[case] target: &str, #[case] expected: Option<usize>) {
+        let presentation = Presentation::new(vec![
+            Slide::new(vec![]).with_title("hello world"),
+            Slide::new(vec![]).with_title("hello world"),
+            Slide::new(vec![]).with_title("Goodbye"),
+        ]);
+        assert_eq!(presentation.find_slide(target), expected);
+    }
+
+    #[rstest]
+    #[case::next_from_start(0, true, Some(1))]
+    #[case::next_from_middle(2, true, Some(3))]
+    #[case::next_from_last_part(4, true, None)]
+    #[case::previous_from_middle(2, false, Some(1))]
+    #[case::previous_from_part_start(3, false, Some(1))]
+    #[case::previous_from_first_part(1, false, None)]
+    fn jump_parts(#[case] current: usize, #[case] next: bool, #[case] expected: Option<usize>) {
+        let slides = (0..5).map(|_| Slide::new(vec![])).collect();
+        let parts = vec![
+            PresentationPart { name: "one".into(), first_slide: 1 },
+            PresentationPart { name: "two".into(), first_slide: 3 },
+        ];
+        let mut presentation = Presentation::new(slides).with_parts(parts);
+        presentation.jump_slide(current);
+        let jumped = if next { presentation.jump_next_part() } else { presentation.jump_previous_part() };
+        assert_eq!(jumped, expected.is_some());
+        assert_eq!(presentation.current_slide_index(), expected.unwrap_or(current));
+    }
+
+    #[rstest]
+    #[case::next_from_first_chunk(0, true, 3)]
+    #[case::next_from_last_chunk(2, true, 3)]
+    #[case::next_into_last_slide(4, true, 5)]
+    #[case::next_reveals_last_slide(5, true, 6)]
+    #[case::next_from_end(6, true, 6)]
+    #[case::previous_from_middle(4, false, 2)]
+    #[case::previous_from_first_chunk(3, false, 2)]
+    #[case::previous_from_last_slide(5, false, 4)]
+    #[case::previous_from_first_slide(1, false, 1)]
+    fn jump_full_slides(#[case] current: usize, #[case] next: bool, #[case] expected: usize) {
+        // Three markdown slides, each of them split into pages like the ones in scrolling code blocks.
+        let pages = [3, 2, 2];
+        let mut slides = Vec::new();
+        for count in pages {
+            let hooks = Rc::new(RefCell::new(SlideHooks::default()));
+            slides.extend((0..count).map(|_| Slide { hooks: hooks.clone(), ..Slide::new(vec![]) }));
+        }
+        let mut presentation = Presentation::new(slides);
+        presentation.jump_slide(current);
+        let jumped = if next { presentation.jump_next_full_slide() } else { presentation.jump_previous_full_slide() };
+        assert_eq!(jumped, expected != current);
+        assert_eq!(presentation.current_slide_index(), expected);
+    }
+
+    fn chunked_slide(chunks: usize) -> Slide {
+        let chunks = (0..chunks).map(|_| SlideChunk::new(vec![RenderOperation::RenderLineBreak])).collect();
+        Slide::from_chunks(chunks, vec![RenderOperation::JumpToBottom])
+    }
+
+    #[rstest]
+    #[case::next_reveals_chunk((0, 1), true, (0, 2))]
+    #[case::next_from_last_chunk((0, 3), true, (1, 1))]
+    #[case::next_from_end((1, 2), true, (1, 2))]
+    #[case::previous_hides_chunk((0, 3), false, (0, 2))]
+    #[case::previous_from_first_chunk((1, 1), false, (0, 3))]
+    #[case::previous_from_start((0, 1), false, (0, 1))]
+    fn jump_chunks(#[case] current: (usize, usize), #[case] next: bool, #[case] expected: (usize, usize)) {
+        let mut presentation = Presentation::new(vec![chunked_slide(3), chunked_slide(2)]);
+        presentation.jump_slide(current.0);
+        presentation.show_chunks(current.1);
+        let jumped = if next { presentation.jump_next_slide() } else { presentation.jump_previous_slide() };
+        assert_eq!(jumped, expected != current);
+
+        let slide = presentation.current_slide();
+        assert_eq!((presentation.current_slide_index(), slide.visible_chunks()), expected);
+        let visible = slide.iter_visible_operations().count();
+        assert_eq!(visible, expected.1 + 1);
+    }
+
+    #[derive(Debug)]
+    struct CountingMutator(Rc<RefCell<i32>>);
+
+    impl ChunkMutator for CountingMutator {
+        fn mutate_next(&self) {
+            *self.0.borrow_mut() += 1;
+        }
+
+        fn mutate_previous(&self) {
+            *self.0.borrow_mut() -= 1;
+        }
+    }
+
+    #[test]
+    fn chunk_mutators() {
+        let count = Rc::new(RefCell::new(0));
+        let mut slide = chunked_slide(3);
+        for chunk in &mut slide.chunks[1..] {
+            chunk.mutators.push(Rc::new(CountingMutator(count.clone())));
+        }
+        let mut presentation = Presentation::new(vec![slide, chunked_slide(1)]);
+        presentation.jump_next_slide();
+        assert_eq!(*count.borrow(), 1);
+        presentation.jump_next_slide();
+        assert_eq!(*count.borrow(), 2);
+        presentation.jump_previous_slide();
+        assert_eq!(*count.borrow(), 1);
+
+        // Leaving the slide keeps the mutations around but coming back to it undoes them.
+        presentation.jump_slide(1);
+        presentation.jump_slide(0);
+        assert_eq!(*count.borrow(), 0);
+        presentation.show_chunks(usize::MAX);
+        assert_eq!(*count.borrow(), 2);
+    }
+
+    #[test]
+    fn jump_full_slide_reveals_chunks() {
+        let mut presentation = Presentation::new(vec![chunked_slide(3), chunked_slide(2)]);
+        presentation.jump_slide(1);
+        assert!(presentation.jump_previous_full_slide());
+        assert_eq!(presentation.current_slide().visible_chunks(), 3);
+
+        assert!(presentation.jump_next_full_slide());
+        assert_eq!(presentation.current_slide().visible_chunks(), 1);
+        assert!(presentation.jump_last_slide());
+        assert_eq!(presentation.current_slide().visible_chunks(), 2);
+        assert!(!presentation.jump_last_slide());
+    }
+
+    #[rstest]
+    #[case::next_from_start(0, true, Some(2))]
+    #[case::next_skips_pauses(2, true, Some(5))]
+    #[case::next_from_last_section(5, true, None)]
+    #[case::previous_from_middle(4, false, Some(2))]
+    #[case::previous_from_section_start(2, false, Some(0))]
+    #[case::previous_from_first_section(0, false, None)]
+    fn jump_sections(#[case] current: usize, #[case] next: bool, #[case] expected: Option<usize>) {
+        let hooks = Rc::new(RefCell::new(SlideHooks::default()));
+        let slide = |kind| Slide { kind, ..Slide::new(vec![]) };
+        let pause = |kind| Slide { kind, hooks: hooks.clone(), ..Slide::new(vec![]) };
+        let (section, content) = (SlideKind::Section, SlideKind::Content);
+        let slides =
+            vec![slide(section), slide(content), pause(section), pause(section), slide(content), slide(section)];
+        let mut presentation = Presentation::new(slides);
+        presentation.jump_slide(current);
+        let jumped = if next { presentation.jump_next_section() } else { presentation.jump_previous_section() };
+        assert_eq!(jumped, expected.is_some());
+        assert_eq!(presentation.current_slide_index(), expected.unwrap_or(current));
+    }
 }
@@ -1,26 +1,50 @@
 use crate::{
-    builder::{BuildError, PresentationBuilder},
+    advance::AutoAdvancer,
+    builder::{BuildError, LazyBuild, PresentationBuilder},
+    capabilities::TerminalCapabilities,
+    config::{MinimumSize, VideoConfig},
     diff::PresentationDiffer,
+    hooks::HookRunner,
     input::{
+        http::HttpControlServer,
         source::{Command, CommandSource},
         user::UserCommand,
     },
     markdown::parse::{MarkdownParser, ParseError},
-    presentation::Presentation,
+    marks::Marks,
+    presentation::{Presentation, Watermark},
+    record::{AsciicastRecorder, RecordingWriter},
     render::{
-        draw::{RenderError, RenderResult, TerminalDrawer},
+        draw::{ErrorSource, RenderError, SlidePanes, TerminalDrawer},
         highlighting::CodeHighlighter,
+        properties::WindowSize,
     },
     resource::Resources,
+    resume::{ResumeFile, ResumeState},
+    stats::PresentationStats,
+    status::StatusLine,
+    stopwatch::{Stopwatch, StopwatchTime},
+    style::Color,
     theme::PresentationTheme,
+    yaml::YamlError,
 };
+use log::{debug, info, warn};
 use std::{
     fs,
     io::{self, Stdout},
     mem,
-    path::Path,
+    path::{Path, PathBuf},
+    process::{self, Stdio},
+    rc::Rc,
+    time::Duration,
 };
 
+// How many slides past the current one are built right away when building presentations lazily.
+const EAGER_SLIDES: usize = 10;
+
+// How many slides are built at a time in between commands while a presentation is built lazily.
+const LAZY_BUILD_STEP: usize = 5;
+
 /// A slideshow presenter.
 ///
 /// This type puts everything else together.
@@ -32,6 +56,27 @@ pub struct Presenter<'a> {
     resources: Resources,
     mode: PresentMode,
     state: PresenterState,
+    hook_runner: HookRunner,
+    status: StatusLine,
+    stats: PresentationStats,
+    slides_compatibility: bool,
+    panes: SlidePanes,
+    prompt: Option<String>,
+    blank_screen: Option<Color>,
+    reload_paused: bool,
+    exit_requested: bool,
+    resume_file: Option<ResumeFile>,
+    stopwatch: Stopwatch,
+    auto_advancer: Option<AutoAdvancer>,
+    recorder: Option<AsciicastRecorder>,
+    control_server: Option<HttpControlServer>,
+    decks: Vec<PathBuf>,
+    current_deck: usize,
+    video_player: Vec<String>,
+    minimum_size: MinimumSize,
+    default_watermark: Option<Watermark>,
+    marks: Marks,
+    pending_build: Option<LazyBuild<'a>>,
 }
 
 impl<'a> Presenter<'a> {
@@ -44,100 +89,656 @@ impl<'a> Presenter<'a> {
         resources: Resources,
         mode: PresentMode,
     ) -> Self {
-        Self { default_theme, default_highlighter, commands, parser, resources, mode, state: PresenterState::Empty }
+        let hook_runner = HookRunner::new(commands.sender());
+        let status = StatusLine::new(commands.sender());
+        Self {
+            default_theme,
+            default_highlighter,
+            commands,
+            parser,
+            resources,
+            mode,
+            state: PresenterState::Empty,
+            hook_runner,
+            status,
+            stats: PresentationStats::new(),
+            slides_compatibility: false,
+            panes: SlidePanes::default(),
+            prompt: None,
+            blank_screen: None,
+            reload_paused: false,
+            exit_requested: false,
+            resume_file: None,
+            stopwatch: Stopwatch::default(),
+            auto_advancer: None,
+            recorder: None,
+            control_server: None,
+            decks: Vec::new(),
+            current_deck: 0,
+            video_player: VideoConfig::default().player(),
+            minimum_size: MinimumSize::default(),
+            default_watermark: None,
+            marks: Marks::default(),
+            pending_build: None,
+        }
+    }
+
+    /// Interpret presentations the way [slides](https://github.com/maaslalani/slides) does.
+    pub fn with_slides_compatibility(mut self, enabled: bool) -> Self {
+        self.slides_compatibility = enabled;
+        self
+    }
+
+    /// Resume the presentation from where it was left at last time, and keep track of where it's left
+    /// at this time, using the given file.
+    pub fn with_resume_file(mut self, file: ResumeFile) -> Self {
+        self.resume_file = Some(file);
+        self
+    }
+
+    /// Move to the next slide on its own once each slide has been displayed for the given duration.
+    ///
+    /// Slides can override this duration via the `duration` comment command.
+    pub fn with_auto_advance(mut self, duration: Duration) -> Self {
+        self.auto_advancer = Some(AutoAdvancer::new(duration, self.commands.sender()));
+        self
+    }
+
+    /// Play videos using the given command, followed by its arguments.
+    pub fn with_video_player(mut self, command: Vec<String>) -> Self {
+        self.video_player = command;
+        self
+    }
+
+    /// Don't draw presentations in terminals smaller than the given size.
+    pub fn with_minimum_size(mut self, minimum_size: MinimumSize) -> Self {
+        self.minimum_size = minimum_size;
+        self
+    }
+
+    /// Draw the given watermark on every slide of presentations that don't set their own.
+    pub fn with_default_watermark(mut self, watermark: Option<Watermark>) -> Self {
+        self.default_watermark = watermark;
+        self
+    }
+
+    /// Record everything that's drawn using the given recorder.
+    pub fn with_recorder(mut self, recorder: AsciicastRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Report the current slide to clients of the given control server.
+    pub fn with_control_server(mut self, server: HttpControlServer) -> Self {
+        self.control_server = Some(server);
+        self
     }
 
     /// Run a presentation.
-    pub fn present(mut self, path: &Path) -> Result<(), PresentationError> {
-        self.state = PresenterState::Presenting(self.load_presentation(path)?);
+    ///
+    /// Once the presentation is over, the timing statistics collected during it are returned.
+    pub fn present(self, path: &Path) -> Result<PresentationStats, PresentationError> {
+        self.present_all(&[path.into()])
+    }
 
-        let mut drawer = TerminalDrawer::new(io::stdout())?;
+    /// Run several presentations, one after the other.
+    ///
+    /// The presentations are navigated using the next/previous deck commands, starting with the
+    /// first one. Once the presenter exits, the timing statistics collected are returned.
+    pub fn present_all(mut self, paths: &[PathBuf]) -> Result<PresentationStats, PresentationError> {
+        let Some(path) = paths.first() else {
+            return Err(PresentationError::Fatal("no presentations provided".into()));
+        };
+        self.decks = paths.to_vec();
+        let resume_state = match &self.resume_file {
+            Some(file) => file.load().unwrap_or_else(|e| {
+                warn!("failed to load resume file {}: {e}", file.path().display());
+                None
+            }),
+            None => None,
+        };
+        let first_slide = resume_state.as_ref().map(|state| state.slide).unwrap_or(0);
+        let (mut presentation, pending_build) = self.load_presentation(path, Self::eager_slides(first_slide))?;
+        if let Some(state) = resume_state {
+            info!("resuming presentation at slide index={}", state.slide);
+            presentation.jump_slide(state.slide);
+        }
+        self.state = PresenterState::Presenting(presentation);
+        self.pending_build = pending_build;
+        self.report_warnings();
+        self.hook_runner.run_enter(&self.state.presentation()?.current_slide().hooks.borrow());
+        self.state.presentation()?.start_automatic_renders(&self.commands.sender());
+
+        let mut drawer = TerminalDrawer::new(RecordingWriter::new(io::stdout(), self.recorder.take()))?;
         loop {
             self.render(&mut drawer)?;
+            self.update_auto_advance();
 
             loop {
-                let command = match self.commands.next_command()? {
+                let command = self.next_command()?;
+                debug!("received command {command:?}");
+                let command = match command {
                     Command::User(command) => command,
                     Command::ReloadPresentation => {
-                        self.try_reload(path);
+                        self.try_reload();
                         break;
                     }
                     Command::Abort { error } => return Err(PresentationError::Fatal(error)),
+                    Command::Redraw => break,
+                    Command::AutoAdvance { generation } => match &self.auto_advancer {
+                        Some(advancer) if advancer.is_current(generation) => UserCommand::JumpNextSlide,
+                        _ => continue,
+                    },
+                    Command::HookFailed { error } => {
+                        warn!("{error}");
+                        self.status.error(error);
+                        break;
+                    }
                 };
                 match self.apply_user_command(command) {
-                    CommandSideEffect::Exit => return Ok(()),
+                    CommandSideEffect::Exit => {
+                        self.hook_runner.run_exit(&self.state.presentation()?.current_slide().hooks.borrow());
+                        self.save_resume_state();
+                        self.stats.finish();
+                        return Ok(self.stats);
+                    }
+                    CommandSideEffect::Suspend => {
+                        drawer.suspend()?;
+                        break;
+                    }
+                    CommandSideEffect::PlayVideos(videos) => {
+                        self.play_videos(&mut drawer, &videos);
+                        break;
+                    }
                     CommandSideEffect::Redraw => {
                         break;
                     }
+                    CommandSideEffect::Reload => {
+                        self.try_reload();
+                        break;
+                    }
                     CommandSideEffect::None => (),
                 };
             }
         }
     }
 
-    fn render(&mut self, drawer: &mut TerminalDrawer<Stdout>) -> RenderResult {
+    fn play_videos(&mut self, drawer: &mut TerminalDrawer<RecordingWriter<Stdout>>, videos: &[String]) {
+        let Some((program, args)) = self.video_player.split_first() else {
+            return;
+        };
+        info!("playing videos {videos:?}");
+        let mut command = process::Command::new(program);
+        command.args(args).args(videos).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+        match drawer.run_external(&mut command) {
+            Ok(status) if status.success() => (),
+            Ok(status) => self.status.error(format!("video player failed: {status}")),
+            Err(e) => self.status.error(format!("running {program}: {e}")),
+        };
+    }
+
+    fn update_auto_advance(&mut self) {
+        let (Some(advancer), PresenterState::Presenting(presentation)) = (&mut self.auto_advancer, &self.state) else {
+            return;
+        };
+        // Don't move on while the screen is blanked or the presenter is in the middle of something.
+        if self.blank_screen.is_some() || self.prompt.is_some() || self.exit_requested {
+            advancer.pause();
+            return;
+        }
+        let slide = presentation.current_slide();
+        advancer.slide_displayed(presentation.current_slide_index(), slide.visible_chunks(), slide.duration);
+    }
+
+    fn render(&mut self, drawer: &mut TerminalDrawer<RecordingWriter<Stdout>>) -> Result<(), PresentationError> {
+        if let Some(color) = self.blank_screen {
+            return Ok(drawer.render_blank(color)?);
+        }
+        let dimensions = WindowSize::current()?;
+        if dimensions.columns < self.minimum_size.columns || dimensions.rows < self.minimum_size.rows {
+            return Ok(drawer.render_terminal_too_small(&self.minimum_size)?);
+        }
         let result = match &self.state {
-            PresenterState::Presenting(presentation) => drawer.render_slide(presentation),
-            PresenterState::Failure { error, .. } => drawer.render_error(error),
-            PresenterState::Empty => panic!("cannot render without state"),
+            PresenterState::Presenting(presentation) => {
+                self.stats.enter_slide(presentation.current_slide_index());
+                if let Some(server) = &self.control_server {
+                    // The total isn't known until the presentation is done being built.
+                    let total_slides = self.pending_build.is_none().then(|| presentation.iter_slides().count());
+                    server.set_position(presentation.current_slide_index(), total_slides);
+                }
+                drawer.render_slide_with_panes(presentation, &self.panes)
+            }
+            PresenterState::Failure { error, source, .. } => drawer.render_error(error, source.as_ref()),
+            PresenterState::Empty => return Err(PresentationError::NoPresentation),
+        };
+        let result = match (result, self.reload_paused) {
+            (Ok(()), true) => drawer.render_status_indicator("⏸ reload paused"),
+            (result, _) => result,
         };
-        // If the screen is too small, simply ignore this. Eventually the user will resize the
+        let result = match (result, self.status.current()) {
+            (Ok(()), Some(message)) => drawer.render_status_message(message),
+            (result, _) => result,
+        };
+        let result = match (result, self.exit_requested) {
+            (Ok(()), true) => drawer.render_question("Exit the presentation? Press q or <ctrl>c again to confirm"),
+            (result, _) => result,
+        };
+        let result = match (result, &self.prompt) {
+            (Ok(()), Some(prompt)) => drawer.render_prompt(prompt),
+            (result, _) => result,
+        };
+        // Some slides need more room than the minimum size. Let the user know so they can resize the
         // screen.
-        if matches!(result, Err(RenderError::TerminalTooSmall)) { Ok(()) } else { result }
+        match result {
+            Err(RenderError::TerminalTooSmall) => drawer.render_terminal_too_small(&self.minimum_size)?,
+            result => result?,
+        };
+        Ok(())
+    }
+
+    fn next_command(&mut self) -> io::Result<Command> {
+        // Keep building the presentation in between commands if it's being built lazily.
+        while self.pending_build.is_some() {
+            if let Some(command) = self.commands.try_next_command()? {
+                return Ok(command);
+            }
+            if self.continue_build(LAZY_BUILD_STEP) {
+                return Ok(Command::Redraw);
+            }
+        }
+        self.commands.next_command()
+    }
+
+    // The number of slides to build right away when loading a presentation that starts at the given slide.
+    fn eager_slides(first_slide: usize) -> usize {
+        first_slide.saturating_add(EAGER_SLIDES)
+    }
+
+    // Build more slides of the presentation that's being built lazily, if there's one.
+    //
+    // Returns `true` if it's done being built, either because it's complete or because it failed.
+    fn continue_build(&mut self, slides: usize) -> bool {
+        let (Some(build), PresenterState::Presenting(presentation)) = (&mut self.pending_build, &mut self.state) else {
+            self.pending_build = None;
+            return false;
+        };
+        match build.build_into(presentation, slides) {
+            Ok(false) => false,
+            Ok(true) => {
+                debug!("finished building presentation slides={}", presentation.iter_slides().count());
+                self.pending_build = None;
+                self.report_warnings();
+                true
+            }
+            Err(e) => {
+                self.pending_build = None;
+                self.show_load_error(e.into());
+                true
+            }
+        }
+    }
+
+    fn finish_build(&mut self) {
+        self.continue_build(usize::MAX);
+    }
+
+    // Make sure any slide the command could jump to has been built.
+    fn build_jump_target(&mut self, command: &UserCommand) {
+        let PresenterState::Presenting(presentation) = &self.state else {
+            return;
+        };
+        match command {
+            UserCommand::JumpNextSlide if presentation.next_slide().is_none() => {
+                self.continue_build(LAZY_BUILD_STEP);
+            }
+            UserCommand::JumpLastSlide
+            | UserCommand::JumpSlide(_)
+            | UserCommand::GoTo(_)
+            | UserCommand::JumpNextFullSlide
+            | UserCommand::JumpNextPart
+            | UserCommand::JumpNextSection => self.finish_build(),
+            _ => (),
+        };
     }
 
     fn apply_user_command(&mut self, command: UserCommand) -> CommandSideEffect {
-        // This one always happens no matter our state.
-        if matches!(command, UserCommand::Exit) {
-            return CommandSideEffect::Exit;
+        if self.exit_requested && !matches!(command, UserCommand::Redraw) {
+            self.exit_requested = false;
+            // Any key other than the exit ones cancels exiting.
+            return match command {
+                UserCommand::Exit => CommandSideEffect::Exit,
+                _ => CommandSideEffect::Redraw,
+            };
+        }
+        if matches!(command, UserCommand::Exit) && self.needs_exit_confirmation() {
+            self.exit_requested = true;
+            return CommandSideEffect::Redraw;
+        }
+        // Any key other than the ones that affect the process itself brings the slide back.
+        if self.blank_screen.is_some()
+            && !matches!(command, UserCommand::Redraw | UserCommand::Exit | UserCommand::Suspend)
+        {
+            self.blank_screen = None;
+            return CommandSideEffect::Redraw;
+        }
+        // These always happen no matter our state.
+        match command {
+            UserCommand::Exit => return CommandSideEffect::Exit,
+            UserCommand::Suspend => return CommandSideEffect::Suspend,
+            UserCommand::ToggleNotes => {
+                self.panes.notes = !self.panes.notes;
+                return CommandSideEffect::Redraw;
+            }
+            UserCommand::ToggleNextSlidePreview => {
+                self.panes.next_slide_preview = !self.panes.next_slide_preview;
+                return CommandSideEffect::Redraw;
+            }
+            UserCommand::ToggleHotReload => {
+                if matches!(self.mode, PresentMode::Presentation) {
+                    return CommandSideEffect::None;
+                }
+                self.reload_paused = !self.reload_paused;
+                // Pick up any changes made while reloading was paused.
+                return if self.reload_paused { CommandSideEffect::Redraw } else { CommandSideEffect::Reload };
+            }
+            UserCommand::BlankScreen(color) => {
+                self.blank_screen = Some(color);
+                return CommandSideEffect::Redraw;
+            }
+            UserCommand::UpdatePrompt(prompt) => {
+                self.prompt = prompt;
+                return CommandSideEffect::Redraw;
+            }
+            UserCommand::NextDeck => return self.switch_deck(self.current_deck + 1),
+            UserCommand::PreviousDeck => match self.current_deck.checked_sub(1) {
+                Some(index) => return self.switch_deck(index),
+                None => {
+                    self.status.error("this is the first presentation");
+                    return CommandSideEffect::Redraw;
+                }
+            },
+            UserCommand::ToggleStopwatch => {
+                self.stopwatch.toggle(&self.commands.sender());
+                return CommandSideEffect::Redraw;
+            }
+            UserCommand::ResetStopwatch => {
+                self.stopwatch.reset();
+                return CommandSideEffect::Redraw;
+            }
+            UserCommand::StopwatchLap => {
+                match self.stopwatch.lap() {
+                    Some(lap) => self.status.info(format!(
+                        "lap {}: {} (total {})",
+                        lap.number,
+                        StopwatchTime(lap.time),
+                        StopwatchTime(lap.total)
+                    )),
+                    None => self.status.error("stopwatch is not running"),
+                };
+                return CommandSideEffect::Redraw;
+            }
+            UserCommand::GoTo(_) => {
+                self.prompt = None;
+                if !matches!(self.state, PresenterState::Presenting(_)) {
+                    return CommandSideEffect::Redraw;
+                }
+            }
+            _ => (),
+        };
+        if self.pending_build.is_some() {
+            self.build_jump_target(&command);
+            if !matches!(self.state, PresenterState::Presenting(_)) {
+                return CommandSideEffect::Redraw;
+            }
         }
         let PresenterState::Presenting(presentation) = &mut self.state else {
             return CommandSideEffect::None;
         };
+        let previous_hooks = presentation.current_slide().hooks.clone();
+        let previous_index = presentation.current_slide_index();
+        // Only jumps that go somewhere specific are remembered, not moving between slides one by one.
+        let is_long_jump = matches!(
+            command,
+            UserCommand::JumpFirstSlide
+                | UserCommand::JumpLastSlide
+                | UserCommand::JumpNextPart
+                | UserCommand::JumpPreviousPart
+                | UserCommand::JumpNextSection
+                | UserCommand::JumpPreviousSection
+                | UserCommand::JumpSlide(_)
+                | UserCommand::JumpToMark(_)
+                | UserCommand::GoTo(_)
+        );
         let needs_redraw = match command {
             UserCommand::Redraw => true,
             UserCommand::JumpNextSlide => presentation.jump_next_slide(),
             UserCommand::JumpPreviousSlide => presentation.jump_previous_slide(),
+            UserCommand::JumpNextFullSlide => presentation.jump_next_full_slide(),
+            UserCommand::JumpPreviousFullSlide => presentation.jump_previous_full_slide(),
             UserCommand::JumpFirstSlide => presentation.jump_first_slide(),
             UserCommand::JumpLastSlide => presentation.jump_last_slide(),
+            UserCommand::JumpNextPart => presentation.jump_next_part(),
+            UserCommand::JumpPreviousPart => presentation.jump_previous_part(),
+            UserCommand::JumpNextSection => presentation.jump_next_section(),
+            UserCommand::JumpPreviousSection => presentation.jump_previous_section(),
             UserCommand::JumpSlide(number) => presentation.jump_slide(number.saturating_sub(1) as usize),
+            UserCommand::SetMark(mark) => {
+                self.marks.set(mark, previous_index);
+                self.status.info(format!("slide {} marked as '{mark}'", previous_index + 1));
+                true
+            }
+            UserCommand::JumpToMark(mark) => {
+                match self.marks.get(mark) {
+                    Some(index) if index < presentation.iter_slides().count() => {
+                        presentation.jump_slide(index);
+                    }
+                    Some(_) => self.status.error(format!("mark '{mark}' points to a slide that no longer exists")),
+                    None => self.status.error(format!("mark '{mark}' is not set")),
+                };
+                true
+            }
+            UserCommand::ExecuteCode => presentation.start_on_demand_renders(&self.commands.sender()),
+            UserCommand::KillCode => {
+                if !presentation.stop_on_demand_renders() {
+                    self.status.error("no code is running in this slide");
+                }
+                true
+            }
+            UserCommand::PlayVideo => {
+                let videos = &presentation.current_slide().videos;
+                if videos.is_empty() {
+                    self.status.error("this slide has no videos");
+                    return CommandSideEffect::Redraw;
+                }
+                return CommandSideEffect::PlayVideos(videos.clone());
+            }
+            UserCommand::GoTo(target) => {
+                match presentation.find_slide(&target) {
+                    Some(index) => {
+                        presentation.jump_slide(index);
+                    }
+                    None => self.status.error(format!("no slide matches '{target}'")),
+                };
+                // Always redraw as the prompt needs to go away.
+                true
+            }
             UserCommand::Exit => return CommandSideEffect::Exit,
+            UserCommand::Suspend => return CommandSideEffect::Suspend,
+            // Everything else was already handled above.
+            _ => return CommandSideEffect::None,
         };
+        if is_long_jump && presentation.current_slide_index() != previous_index {
+            self.marks.record_jump(previous_index);
+        }
+        let current_hooks = &presentation.current_slide().hooks;
+        if !Rc::ptr_eq(&previous_hooks, current_hooks) {
+            self.hook_runner.run_exit(&previous_hooks.borrow());
+            self.hook_runner.run_enter(&current_hooks.borrow());
+        }
+        // Code that runs automatically can show up in any step of a slide, not only its first one.
+        let needs_redraw = presentation.start_automatic_renders(&self.commands.sender()) || needs_redraw;
         if needs_redraw { CommandSideEffect::Redraw } else { CommandSideEffect::None }
     }
 
-    fn try_reload(&mut self, path: &Path) {
-        if matches!(self.mode, PresentMode::Presentation) {
+    fn save_resume_state(&self) {
+        let (Some(file), Ok(presentation)) = (&self.resume_file, self.state.presentation()) else {
             return;
+        };
+        let state = ResumeState { slide: presentation.current_slide_index() };
+        if let Err(e) = file.save(&state) {
+            warn!("failed to save resume file {}: {e}", file.path().display());
         }
-        match self.load_presentation(path) {
-            Ok(mut presentation) => {
-                let current = self.state.presentation();
-                let target_slide = PresentationDiffer::first_modified_slide(current, &presentation)
-                    .unwrap_or(current.current_slide_index());
+    }
+
+    fn needs_exit_confirmation(&self) -> bool {
+        if !matches!(self.mode, PresentMode::Presentation) {
+            return false;
+        }
+        match &self.state {
+            PresenterState::Presenting(presentation) => presentation.options().confirm_exit.unwrap_or(true),
+            _ => false,
+        }
+    }
+
+    fn switch_deck(&mut self, index: usize) -> CommandSideEffect {
+        let Some(path) = self.decks.get(index).cloned() else {
+            self.status.error("this is the last presentation");
+            return CommandSideEffect::Redraw;
+        };
+        let previous_path = &self.decks[self.current_deck];
+        let previous_base_path = previous_path.parent().unwrap_or(Path::new("/")).to_path_buf();
+        self.resources.set_base_path(path.parent().unwrap_or(Path::new("/")));
+        let (presentation, pending_build) = match self.load_presentation(&path, Self::eager_slides(0)) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                // Stay where we are rather than presenting something broken.
+                warn!("failed to load presentation {}: {e}", path.display());
+                self.resources.set_base_path(previous_base_path);
+                self.status.error(format!("failed to load {}: {e}", path.display()));
+                return CommandSideEffect::Redraw;
+            }
+        };
+        info!("switching to presentation {}", path.display());
+        if let PresenterState::Presenting(current) = &self.state {
+            self.hook_runner.run_exit(&current.current_slide().hooks.borrow());
+        }
+        self.hook_runner.run_enter(&presentation.current_slide().hooks.borrow());
+        presentation.start_automatic_renders(&self.commands.sender());
+        self.state = PresenterState::Presenting(presentation);
+        self.pending_build = pending_build;
+        self.current_deck = index;
+        // Marks point to slides in the presentation they were set in.
+        self.marks.clear();
+        self.commands.watch_presentation(&path);
+        if let Some(advancer) = &mut self.auto_advancer {
+            advancer.pause();
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        self.status.info(format!("presentation {}/{}: {name}", index + 1, self.decks.len()));
+        self.report_warnings();
+        CommandSideEffect::Redraw
+    }
+
+    fn try_reload(&mut self) {
+        if matches!(self.mode, PresentMode::Presentation) || self.reload_paused {
+            return;
+        }
+        let path = self.decks[self.current_deck].clone();
+        let current_index = self.state.presentation().map(|current| current.current_slide_index()).unwrap_or(0);
+        match self.load_presentation(&path, Self::eager_slides(current_index)) {
+            Ok((mut presentation, pending_build)) => {
+                let (target_slide, visible_chunks) = match self.state.presentation() {
+                    Ok(current) => {
+                        let carried_over = PresentationDiffer::carry_over_state(current, &mut presentation);
+                        debug!("carried over the state of {carried_over} slides");
+                        // Slides that aren't built yet can't be compared, so changes past the ones built in
+                        // both presentations are only looked for when both are complete.
+                        let complete = self.pending_build.is_none() && pending_build.is_none();
+                        let built = current.iter_slides().count().min(presentation.iter_slides().count());
+                        let target = PresentationDiffer::first_modified_slide(current, &presentation)
+                            .filter(|index| complete || *index < built)
+                            .unwrap_or(current_index);
+                        // Stay on the same chunk if we're not moving to a different slide.
+                        let visible_chunks = match target == current_index {
+                            true => current.current_slide().visible_chunks(),
+                            false => 1,
+                        };
+                        (target, visible_chunks)
+                    }
+                    Err(_) => (0, 1),
+                };
+                info!("reloaded presentation, jumping to slide index={target_slide}");
                 presentation.jump_slide(target_slide);
-                self.state = PresenterState::Presenting(presentation)
+                presentation.show_chunks(visible_chunks);
+                self.state = PresenterState::Presenting(presentation);
+                self.pending_build = pending_build;
+                self.status.info("presentation reloaded");
+                self.report_warnings();
             }
             Err(e) => {
-                let presentation = mem::take(&mut self.state).into_presentation();
-                self.state = PresenterState::Failure { error: e.to_string(), presentation }
+                warn!("failed to reload presentation: {e}");
+                self.show_load_error(e);
             }
         };
     }
 
-    fn load_presentation(&mut self, path: &Path) -> Result<Presentation, LoadPresentationError> {
+    fn show_load_error(&mut self, error: LoadPresentationError) {
+        let source = error
+            .yaml_error()
+            .and_then(|error| error.snippet(2))
+            .map(|snippet| ErrorSource::from_yaml(&snippet, &self.default_highlighter));
+        // Errors are displayed on top of the presentation so there's nothing to display without one.
+        let Ok(presentation) = mem::take(&mut self.state).into_presentation() else {
+            warn!("no presentation to display error on: {error}");
+            return;
+        };
+        self.state = PresenterState::Failure { error: error.to_string(), source, presentation }
+    }
+
+    // Load a presentation, only building the given number of slides right away.
+    //
+    // If there's anything left to build, the build is returned so it can be completed later on.
+    fn load_presentation(
+        &mut self,
+        path: &Path,
+        eager_slides: usize,
+    ) -> Result<(Presentation, Option<LazyBuild<'a>>), LoadPresentationError> {
         let content = fs::read_to_string(path).map_err(LoadPresentationError::Reading)?;
         let elements = self.parser.parse(&content)?;
-        let presentation =
+        let mut builder =
             PresentationBuilder::new(self.default_highlighter.clone(), self.default_theme, &mut self.resources)
-                .build(elements)?;
-        Ok(presentation)
+                .with_slides_compatibility(self.slides_compatibility)
+                .with_nerd_fonts(TerminalCapabilities::current().nerd_fonts)
+                .with_default_watermark(self.default_watermark.clone())
+                .with_stopwatch(self.stopwatch.clone());
+        if let Ok(dimensions) = WindowSize::current() {
+            builder = builder.with_terminal_rows(dimensions.rows);
+        }
+        let (presentation, pending_build) = builder.build_lazily(elements, eager_slides)?;
+        Ok((presentation, pending_build))
+    }
+
+    // Warnings are only reported once the presentation is complete.
+    fn report_warnings(&mut self) {
+        let Ok(presentation) = self.state.presentation() else {
+            return;
+        };
+        if self.pending_build.is_some() || presentation.warnings().is_empty() {
+            return;
+        }
+        for warning in presentation.warnings() {
+            warn!("{warning}");
+        }
+        self.status.error(presentation.warnings().join("; "));
     }
 }
 
 enum CommandSideEffect {
     Exit,
+    Suspend,
+    PlayVideos(Vec<String>),
     Redraw,
+    Reload,
     None,
 }
 
@@ -148,24 +749,25 @@ enum PresenterState {
     Presenting(Presentation),
     Failure {
         error: String,
+        source: Option<ErrorSource>,
         presentation: Presentation,
     },
 }
 
 impl PresenterState {
-    fn presentation(&self) -> &Presentation {
+    fn presentation(&self) -> Result<&Presentation, PresentationError> {
         match self {
-            Self::Presenting(presentation) => presentation,
-            Self::Failure { presentation, .. } => presentation,
-            Self::Empty => panic!("state is empty"),
+            Self::Presenting(presentation) => Ok(presentation),
+            Self::Failure { presentation, .. } => Ok(presentation),
+            Self::Empty => Err(PresentationError::NoPresentation),
         }
     }
 
-    fn into_presentation(self) -> Presentation {
+    fn into_presentation(self) -> Result<Presentation, PresentationError> {
         match self {
-            Self::Presenting(presentation) => presentation,
-            Self::Failure { presentation, .. } => presentation,
-            Self::Empty => panic!("state is empty"),
+            Self::Presenting(presentation) => Ok(presentation),
+            Self::Failure { presentation, .. } => Ok(presentation),
+            Self::Empty => Err(PresentationError::NoPresentation),
         }
     }
 }
@@ -192,6 +794,15 @@ pub enum LoadPresentationError {
     Processing(#[from] BuildError),
 }
 
+impl LoadPresentationError {
+    fn yaml_error(&self) -> Option<&YamlError> {
+        match self {
+            Self::Processing(error) => error.yaml_error(),
+            _ => None,
+        }
+    }
+}
+
 /// An error during the presentation.
 #[derive(thiserror::Error, Debug)]
 pub enum PresentationError {
@@ -206,4 +817,7 @@ pub enum PresentationError {
 
     #[error("fatal error: {0}")]
     Fatal(String),
+
+    #[error("no presentation loaded")]
+    NoPresentation,
 }
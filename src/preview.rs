@@ -0,0 +1,35 @@
+const THEME_PREVIEW_BODY: &str = include_str!("../assets/theme-preview.md");
+
+/// Generate a presentation that showcases how every element looks like using the given theme.
+///
+/// The presentation doesn't reference any theme so it's displayed using the default one.
+pub fn theme_preview(theme_name: &str) -> String {
+    format!("---\ntitle: Theme preview\nsub_title: \"{theme_name}\"\nauthor: presenterm\n---\n\n{THEME_PREVIEW_BODY}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        builder::PresentationBuilder, markdown::parse::MarkdownParser, render::highlighting::CodeHighlighter,
+        resource::Resources, theme::PresentationTheme,
+    };
+    use comrak::Arena;
+
+    #[test]
+    fn builds_with_every_theme() {
+        for name in PresentationTheme::theme_names() {
+            let contents = theme_preview(name);
+            let arena = Arena::new();
+            let elements = MarkdownParser::new(&arena).parse(&contents).expect("parse failed");
+            let theme = PresentationTheme::from_name(name).expect("theme not found");
+            let highlighter = CodeHighlighter::new("base16-ocean.dark").unwrap();
+            let mut resources = Resources::new("/tmp");
+            let presentation = PresentationBuilder::new(highlighter, &theme, &mut resources)
+                .build(elements)
+                .unwrap_or_else(|e| panic!("build failed for theme {name}: {e}"));
+            // The intro slide plus the ones in the preview.
+            assert_eq!(presentation.iter_slides().count(), 6);
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use once_cell::sync::Lazy;
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+// This is global as the things we want to measure are spread all over the place and threading a
+// profiler through all of them would make every one of those APIs more complicated.
+static PROFILER: Lazy<Mutex<Option<Profile>>> = Lazy::new(Default::default);
+
+/// Enable profiling.
+///
+/// Until this is called, measuring anything is a no-op.
+pub fn enable() {
+    let mut profiler = PROFILER.lock().expect("poisoned lock");
+    if profiler.is_none() {
+        *profiler = Some(Profile::default());
+    }
+}
+
+/// Set the slide that's currently being drawn.
+///
+/// Any [Phase::Draw] and [Phase::ImageEncode] measurements will be attributed to this slide.
+pub fn enter_slide(index: usize) {
+    if let Some(profile) = PROFILER.lock().expect("poisoned lock").as_mut() {
+        profile.current_slide = Some(index);
+    }
+}
+
+/// Start measuring a phase.
+///
+/// The measurement stops when the returned guard is dropped.
+#[must_use]
+pub fn measure(phase: Phase) -> Measurement {
+    Measurement { phase, slide: None, start: Instant::now() }
+}
+
+/// Start measuring a phase that's tied to a specific slide.
+#[must_use]
+pub fn measure_slide(phase: Phase, slide: usize) -> Measurement {
+    Measurement { phase, slide: Some(slide), start: Instant::now() }
+}
+
+/// Get the profile collected so far, if profiling is enabled.
+pub fn report() -> Option<Profile> {
+    PROFILER.lock().expect("poisoned lock").clone()
+}
+
+/// A phase in the lifetime of a presentation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Phase {
+    Parse,
+    Build,
+    Highlight,
+    ImageEncode,
+    Draw,
+}
+
+impl Phase {
+    fn is_drawing(&self) -> bool {
+        matches!(self, Self::Draw | Self::ImageEncode)
+    }
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Parse => "parse",
+            Self::Build => "build",
+            Self::Highlight => "highlight",
+            Self::ImageEncode => "image",
+            Self::Draw => "draw",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// A measurement in progress.
+pub struct Measurement {
+    phase: Phase,
+    slide: Option<usize>,
+    start: Instant,
+}
+
+impl Drop for Measurement {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        if let Some(profile) = PROFILER.lock().expect("poisoned lock").as_mut() {
+            let slide = self.slide.or_else(|| profile.current_slide.filter(|_| self.phase.is_drawing()));
+            profile.record(self.phase, slide, elapsed);
+        }
+    }
+}
+
+/// The time spent on every phase.
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    current_slide: Option<usize>,
+    phases: BTreeMap<Phase, PhaseTimes>,
+    slides: BTreeMap<usize, BTreeMap<Phase, PhaseTimes>>,
+}
+
+impl Profile {
+    fn record(&mut self, phase: Phase, slide: Option<usize>, elapsed: Duration) {
+        self.phases.entry(phase).or_default().add(elapsed);
+        if let Some(slide) = slide {
+            self.slides.entry(slide).or_default().entry(phase).or_default().add(elapsed);
+        }
+    }
+
+    /// Get the times recorded for a phase.
+    pub fn phase(&self, phase: Phase) -> PhaseTimes {
+        self.phases.get(&phase).cloned().unwrap_or_default()
+    }
+
+    /// Get the times recorded for a phase in a specific slide.
+    pub fn slide_phase(&self, slide: usize, phase: Phase) -> PhaseTimes {
+        self.slides.get(&slide).and_then(|phases| phases.get(&phase)).cloned().unwrap_or_default()
+    }
+}
+
+impl Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<10} {:>6} {:>12} {:>12}", "Phase", "Count", "Total", "Max")?;
+        for (phase, times) in &self.phases {
+            writeln!(f, "{:<10} {:>6} {:>12.3?} {:>12.3?}", phase.to_string(), times.count, times.total, times.max)?;
+        }
+        writeln!(f)?;
+        let columns = [Phase::Highlight, Phase::ImageEncode, Phase::Draw];
+        write!(f, "{:<6}", "Slide")?;
+        for phase in columns {
+            write!(f, " {:>12}", phase.to_string())?;
+        }
+        writeln!(f)?;
+        for (slide, phases) in &self.slides {
+            write!(f, "{:<6}", slide + 1)?;
+            for phase in columns {
+                let total = phases.get(&phase).map(|times| times.total).unwrap_or_default();
+                write!(f, " {total:>12.3?}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// The times recorded for a phase.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PhaseTimes {
+    /// The number of times this phase was measured.
+    pub count: usize,
+
+    /// The total time spent on this phase.
+    pub total: Duration,
+
+    /// The longest time spent on a single measurement.
+    pub max: Duration,
+}
+
+impl PhaseTimes {
+    fn add(&mut self, elapsed: Duration) {
+        self.count += 1;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn record() {
+        let mut profile = Profile::default();
+        profile.record(Phase::Parse, None, Duration::from_millis(5));
+        profile.record(Phase::Draw, Some(0), Duration::from_millis(2));
+        profile.record(Phase::Draw, Some(0), Duration::from_millis(4));
+        profile.record(Phase::Draw, Some(1), Duration::from_millis(1));
+
+        let expected = PhaseTimes { count: 3, total: Duration::from_millis(7), max: Duration::from_millis(4) };
+        assert_eq!(profile.phase(Phase::Draw), expected);
+        assert_eq!(profile.phase(Phase::Parse).count, 1);
+        assert_eq!(profile.slide_phase(0, Phase::Draw).total, Duration::from_millis(6));
+        assert_eq!(profile.slide_phase(1, Phase::Draw).count, 1);
+        assert_eq!(profile.slide_phase(1, Phase::Parse).count, 0);
+    }
+}
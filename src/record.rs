@@ -0,0 +1,115 @@
+use crate::render::properties::WindowSize;
+use serde_json::json;
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Records everything drawn in the terminal into an [asciicast v2](https://docs.asciinema.org/manual/asciicast/v2/)
+/// file so a presentation can be replayed later on.
+pub struct AsciicastRecorder {
+    output: BufWriter<File>,
+    start: Instant,
+}
+
+impl AsciicastRecorder {
+    /// Create a recording in the given path for a terminal of the given size.
+    pub fn create(path: &Path, dimensions: &WindowSize) -> io::Result<Self> {
+        let mut output = BufWriter::new(File::create(path)?);
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let header = json!({
+            "version": 2,
+            "width": dimensions.columns,
+            "height": dimensions.rows,
+            "timestamp": timestamp,
+        });
+        writeln!(output, "{header}")?;
+        output.flush()?;
+        Ok(Self { output, start: Instant::now() })
+    }
+
+    /// Record a frame containing the given output.
+    pub fn record_frame(&mut self, data: &[u8]) -> io::Result<()> {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let event = json!([elapsed, "o", String::from_utf8_lossy(data)]);
+        writeln!(self.output, "{event}")?;
+        self.output.flush()
+    }
+}
+
+/// A writer that forwards everything to another one and optionally records it.
+///
+/// Every time this writer is flushed, everything written since the last flush is recorded as a
+/// single frame.
+pub struct RecordingWriter<W: Write> {
+    inner: W,
+    recorder: Option<AsciicastRecorder>,
+    frame: Vec<u8>,
+}
+
+impl<W: Write> RecordingWriter<W> {
+    /// Construct a new writer that forwards everything to the given one.
+    pub fn new(inner: W, recorder: Option<AsciicastRecorder>) -> Self {
+        Self { inner, recorder, frame: Vec::new() }
+    }
+}
+
+impl<W: Write> Write for RecordingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        if self.recorder.is_some() {
+            self.frame.extend_from_slice(&buf[..written]);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        if let Some(recorder) = &mut self.recorder {
+            if !self.frame.is_empty() {
+                recorder.record_frame(&self.frame)?;
+                self.frame.clear();
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn recording() {
+        let directory = tempfile::tempdir().expect("creating temp dir");
+        let path = directory.path().join("out.cast");
+        let dimensions = WindowSize { rows: 24, columns: 80, width: 0, height: 0 };
+        let recorder = AsciicastRecorder::create(&path, &dimensions).expect("creating recorder");
+
+        let mut output = Vec::new();
+        let mut writer = RecordingWriter::new(&mut output, Some(recorder));
+        writer.write_all(b"hello ").unwrap();
+        writer.write_all(b"\x1b[1mworld").unwrap();
+        writer.flush().unwrap();
+        // Flushing without writing anything doesn't produce empty frames.
+        writer.flush().unwrap();
+        writer.write_all(b"bye").unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+        assert_eq!(output, b"hello \x1b[1mworldbye");
+
+        let contents = fs::read_to_string(&path).expect("reading recording");
+        let lines: Vec<serde_json::Value> =
+            contents.lines().map(|line| serde_json::from_str(line).expect("invalid json")).collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["version"], 2);
+        assert_eq!(lines[0]["width"], 80);
+        assert_eq!(lines[0]["height"], 24);
+        assert_eq!(lines[1][1], "o");
+        assert_eq!(lines[1][2], "hello \x1b[1mworld");
+        assert_eq!(lines[2][2], "bye");
+    }
+}
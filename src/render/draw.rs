@@ -1,15 +1,21 @@
-use super::{operator::RenderOperator, terminal::Terminal};
+use super::{media::PlacedImage, operator::RenderOperator, terminal::Terminal};
 use crate::{
+    config::MinimumSize,
     markdown::{
-        elements::StyledText,
+        elements::{ProgrammingLanguage, StyledText},
         text::{WeightedLine, WeightedText},
+        width::{char_widths, DisplayWidth},
     },
-    presentation::{Presentation, RenderOperation},
-    render::properties::WindowSize,
+    presentation::{PreformattedLine, Presentation, RenderOperation, Slide, Watermark, WatermarkPosition},
+    profile::{self, Phase},
+    render::{highlighting::CodeHighlighter, properties::WindowSize},
+    status::{MessageKind, StatusMessage},
     style::{Color, Colors, TextStyle},
     theme::{Alignment, Margin},
+    yaml::YamlSnippet,
 };
-use std::io;
+use log::debug;
+use std::{io, mem, process, time::Instant};
 
 /// The result of a render operation.
 pub type RenderResult = Result<(), RenderError>;
@@ -29,18 +35,293 @@ where
         Ok(Self { terminal })
     }
 
+    /// Construct a drawer over a [std::io::Write] that doesn't touch the real terminal.
+    ///
+    /// Anything that needs the terminal, like images, is drawn as a placeholder.
+    pub fn headless(handle: W) -> Self {
+        Self { terminal: Terminal::headless(handle) }
+    }
+
+    /// Take the images that were left out since the last call to this function.
+    ///
+    /// Headless drawers draw placeholders instead of images. This allows drawing them somewhere
+    /// else, in the same place the placeholders are at.
+    pub fn take_placed_images(&mut self) -> Vec<PlacedImage> {
+        mem::take(&mut self.terminal.placed_images)
+    }
+
+    /// Render a slide in a screen of the given size.
+    pub fn render_slide_sized(&mut self, presentation: &Presentation, dimensions: WindowSize) -> RenderResult {
+        self.render_slide_in(presentation, dimensions)
+    }
+
     /// Render a slide.
     pub fn render_slide(&mut self, presentation: &Presentation) -> RenderResult {
-        let window_dimensions = WindowSize::current()?;
+        self.render_slide_with_panes(presentation, &SlidePanes::default())
+    }
+
+    /// Render a slide along with the given panes.
+    ///
+    /// The slide is rendered in the space left after making room for the panes.
+    pub fn render_slide_with_panes(&mut self, presentation: &Presentation, panes: &SlidePanes) -> RenderResult {
+        let dimensions = WindowSize::current()?;
+        self.terminal.begin_update()?;
+        let notes = presentation.current_slide().notes.borrow().clone();
+        let notes_rows = match panes.notes {
+            // Leave room for the separator and an empty line, and never take more than a third of
+            // the screen so there's still room for the slide itself.
+            true => (notes.len().max(1) as u16 + 2).min(dimensions.rows / 3),
+            false => 0,
+        };
+        let preview_columns = if panes.next_slide_preview { dimensions.columns / 3 } else { 0 };
+        self.render_slide_in(presentation, dimensions.shrink_rows(notes_rows).shrink_columns(preview_columns))?;
+        if panes.next_slide_preview {
+            let rows = dimensions.rows.saturating_sub(notes_rows);
+            self.render_next_slide_preview(presentation, dimensions.columns - preview_columns, rows)?;
+        }
+        if panes.notes {
+            self.render_notes(notes, notes_rows, &dimensions)?;
+        }
+        self.terminal.end_update()?;
+        self.terminal.flush()?;
+        Ok(())
+    }
+
+    fn render_notes(&mut self, notes: Vec<String>, pane_rows: u16, dimensions: &WindowSize) -> RenderResult {
+        let style =
+            TextStyle::default().colors(Colors { foreground: Some(Color::new(150, 150, 150)), background: None });
+        let alignment = Alignment::Left { margin: Margin::Fixed(1) };
+        let mut operations =
+            vec![RenderOperation::RenderSeparator(Default::default()), RenderOperation::RenderLineBreak];
+        let notes = match notes.is_empty() {
+            true => vec!["no speaker notes for this slide".to_string()],
+            false => notes,
+        };
+        for note in notes.into_iter().take(pane_rows.saturating_sub(1) as usize) {
+            let line = vec![WeightedText::from(StyledText::new(note, style.clone()))];
+            operations.extend([
+                RenderOperation::RenderTextLine { line: WeightedLine::from(line), alignment: alignment.clone() },
+                RenderOperation::RenderLineBreak,
+            ]);
+        }
+        self.terminal.move_to(0, dimensions.rows.saturating_sub(pane_rows))?;
+        let operator = RenderOperator::new(&mut self.terminal, dimensions.clone());
+        operator.render(&operations)
+    }
+
+    fn render_next_slide_preview(&mut self, presentation: &Presentation, start_column: u16, rows: u16) -> RenderResult {
+        let max_width = WindowSize::current()?.columns.saturating_sub(start_column + 3) as usize;
+        let lines = match presentation.next_slide() {
+            Some(slide) => Self::preview_lines(slide),
+            None => vec!["this is the last slide".to_string()],
+        };
+        let header = TextStyle::default().bold().apply("next slide");
+        let style =
+            TextStyle::default().colors(Colors { foreground: Some(Color::new(150, 150, 150)), background: None });
+        let mut lines = lines.into_iter();
+        for row in 0..rows {
+            self.terminal.move_to(start_column, row)?;
+            self.terminal.print_line("│ ")?;
+            match row {
+                1 => self.terminal.print_styled_line(header.clone())?,
+                0 | 2 => (),
+                _ => {
+                    if let Some(line) = lines.next() {
+                        self.terminal.print_styled_line(style.apply(Self::truncate(&line, max_width)))?;
+                    }
+                }
+            };
+        }
+        Ok(())
+    }
+
+    fn preview_lines(slide: &Slide) -> Vec<String> {
+        let mut lines = Vec::new();
+        for operation in slide.iter_operations() {
+            if let RenderOperation::RenderTextLine { line, .. } = operation {
+                let text: String = line.iter_texts().map(|text| text.text.text.as_str()).collect();
+                if !text.trim().is_empty() {
+                    lines.push(text);
+                }
+            }
+        }
+        lines
+    }
+
+    fn truncate(text: &str, max_width: usize) -> String {
+        let mut output = String::new();
+        let mut width = 0;
+        for (c, char_width) in char_widths(text) {
+            width += char_width;
+            if width > max_width {
+                break;
+            }
+            output.push(c);
+        }
+        output
+    }
+
+    fn render_slide_in(&mut self, presentation: &Presentation, window_dimensions: WindowSize) -> RenderResult {
+        profile::enter_slide(presentation.current_slide_index());
+        let _measurement = profile::measure_slide(Phase::Draw, presentation.current_slide_index());
+        let start = Instant::now();
         let slide = presentation.current_slide();
-        let operator = RenderOperator::new(&mut self.terminal, window_dimensions);
-        operator.render(&slide.render_operations)?;
+        debug!(
+            "rendering slide index={} chunks={}/{} rows={} columns={}",
+            presentation.current_slide_index(),
+            slide.visible_chunks(),
+            slide.chunks.len(),
+            window_dimensions.rows,
+            window_dimensions.columns
+        );
+        let operator = RenderOperator::new(&mut self.terminal, window_dimensions.clone());
+        operator.render(slide.iter_visible_operations())?;
+        if let Some(watermark) = &presentation.options().watermark {
+            self.render_watermark(watermark, &window_dimensions)?;
+        }
+        self.terminal.flush()?;
+        debug!("rendered slide index={} elapsed={:?}", presentation.current_slide_index(), start.elapsed());
+        Ok(())
+    }
+
+    fn render_watermark(&mut self, watermark: &Watermark, dimensions: &WindowSize) -> RenderResult {
+        let style = TextStyle::default().colors(Colors { foreground: Some(watermark.color()), background: None });
+        let WindowSize { rows, columns, .. } = *dimensions;
+        let text = Self::truncate(watermark.text.trim(), columns.saturating_sub(2) as usize);
+        let width = text.display_width() as u16;
+        let (column, row) = match watermark.position {
+            WatermarkPosition::Diagonal => return self.render_diagonal_watermark(&text, style, dimensions),
+            WatermarkPosition::TopLeft => (1, 0),
+            WatermarkPosition::TopRight => (columns.saturating_sub(width + 1), 0),
+            WatermarkPosition::BottomLeft => (1, rows.saturating_sub(1)),
+            WatermarkPosition::BottomRight => (columns.saturating_sub(width + 1), rows.saturating_sub(1)),
+        };
+        self.terminal.move_to(column, row)?;
+        self.terminal.print_styled_line(style.apply(text))?;
+        Ok(())
+    }
+
+    fn render_diagonal_watermark(&mut self, text: &str, style: TextStyle, dimensions: &WindowSize) -> RenderResult {
+        // Every character goes one row up and two columns to the right as cells are roughly twice
+        // as tall as they're wide, which makes the text go up at an angle close to 45 degrees.
+        let length = text.chars().count() as i32;
+        let start_row = (dimensions.rows as i32 + length) / 2 - 1;
+        let start_column = dimensions.columns as i32 / 2 - length + 1;
+        for (index, character) in text.chars().enumerate() {
+            let (row, column) = (start_row - index as i32, start_column + index as i32 * 2);
+            let visible =
+                (0..dimensions.rows as i32).contains(&row) && (0..dimensions.columns as i32).contains(&column);
+            // Spaces are skipped so they don't clear whatever is behind them.
+            if visible && !character.is_whitespace() {
+                self.terminal.move_to(column as u16, row as u16)?;
+                self.terminal.print_styled_line(style.apply(character.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Render a short status indicator on the top left corner of the screen.
+    ///
+    /// This is meant to be used on top of an already rendered slide.
+    pub fn render_status_indicator(&mut self, status: &str) -> RenderResult {
+        let style =
+            TextStyle::default().colors(Colors { foreground: Some(Color::new(150, 150, 150)), background: None });
+        self.terminal.move_to(1, 0)?;
+        self.terminal.print_styled_line(style.apply(status.to_string()))?;
+        self.terminal.flush()?;
+        Ok(())
+    }
+
+    /// Render a placeholder explaining the terminal is too small to draw anything in it.
+    ///
+    /// This is drawn line by line rather than through the usual layout so it fits even in tiny terminals.
+    pub fn render_terminal_too_small(&mut self, minimum: &MinimumSize) -> RenderResult {
+        let dimensions = WindowSize::current()?;
+        let WindowSize { columns, rows, .. } = dimensions;
+        let lines = match columns < minimum.columns || rows < minimum.rows {
+            true => vec![
+                "terminal too small".to_string(),
+                format!("need {}×{}, have {columns}×{rows}", minimum.columns, minimum.rows),
+            ],
+            false => vec!["terminal too small for this slide".to_string(), format!("have {columns}×{rows}")],
+        };
+        self.terminal.set_colors(Colors::default())?;
+        self.terminal.clear_screen()?;
+        let start_row = (rows / 2).saturating_sub(lines.len() as u16 / 2);
+        for (row, line) in (start_row..rows).zip(lines) {
+            let line = Self::truncate(&line, columns as usize);
+            let start_column = (columns as usize).saturating_sub(line.display_width()) / 2;
+            self.terminal.move_to(start_column as u16, row)?;
+            self.terminal.print_line(&line)?;
+        }
         self.terminal.flush()?;
         Ok(())
     }
 
-    /// Render an error.
-    pub fn render_error(&mut self, message: &str) -> RenderResult {
+    /// Render a blank screen using the given color.
+    pub fn render_blank(&mut self, color: Color) -> RenderResult {
+        let dimensions = WindowSize::current()?;
+        let operations = [
+            RenderOperation::SetColors(Colors { foreground: None, background: Some(color) }),
+            RenderOperation::ClearScreen,
+        ];
+        let operator = RenderOperator::new(&mut self.terminal, dimensions);
+        operator.render(&operations)?;
+        self.terminal.flush()?;
+        Ok(())
+    }
+
+    /// Render the go to prompt on the last row of the screen.
+    pub fn render_prompt(&mut self, text: &str) -> RenderResult {
+        let colors = Colors { foreground: Some(Color::new(255, 255, 255)), background: Some(Color::new(60, 60, 60)) };
+        self.render_bottom_line(&format!(":{text}"), colors)
+    }
+
+    /// Render a question for the user on the last row of the screen.
+    pub fn render_question(&mut self, question: &str) -> RenderResult {
+        let colors = Colors { foreground: Some(Color::new(255, 255, 255)), background: Some(Color::new(60, 60, 60)) };
+        self.render_bottom_line(&format!(" {question}"), colors)
+    }
+
+    /// Render a status line message on the last row of the screen.
+    pub fn render_status_message(&mut self, message: &StatusMessage) -> RenderResult {
+        let foreground = match message.kind {
+            MessageKind::Info => Color::new(255, 255, 255),
+            MessageKind::Error => Color::new(255, 0, 0),
+        };
+        let colors = Colors { foreground: Some(foreground), background: Some(Color::new(60, 60, 60)) };
+        self.render_bottom_line(&format!(" {}", message.text), colors)
+    }
+
+    fn render_bottom_line(&mut self, text: &str, colors: Colors) -> RenderResult {
+        let dimensions = WindowSize::current()?;
+        let padding = (dimensions.columns as usize).saturating_sub(text.display_width());
+        let style = TextStyle::default().colors(colors);
+        self.terminal.move_to(0, dimensions.rows.saturating_sub(1))?;
+        self.terminal.print_styled_line(style.apply(format!("{text}{}", " ".repeat(padding))))?;
+        self.terminal.flush()?;
+        Ok(())
+    }
+
+    /// Suspend the process until it's resumed by the shell.
+    ///
+    /// The terminal is restored while suspended and the caller is expected to redraw the screen
+    /// after this returns.
+    pub fn suspend(&mut self) -> RenderResult {
+        #[cfg(unix)]
+        self.terminal.suspend()?;
+        Ok(())
+    }
+
+    /// Run a command that takes over the terminal until it exits, like a video player.
+    ///
+    /// Just like with [TerminalDrawer::suspend], the caller is expected to redraw the screen after this returns.
+    pub fn run_external(&mut self, command: &mut process::Command) -> Result<process::ExitStatus, RenderError> {
+        Ok(self.terminal.run_external(command)?)
+    }
+
+    /// Render an error, optionally along with the source that caused it.
+    pub fn render_error(&mut self, message: &str, source: Option<&ErrorSource>) -> RenderResult {
         let dimensions = WindowSize::current()?;
         let heading = vec![
             WeightedText::from(StyledText::new("Error loading presentation", TextStyle::default().bold())),
@@ -48,7 +329,7 @@ where
         ];
         let error = vec![WeightedText::from(StyledText::from(message))];
         let alignment = Alignment::Center { minimum_size: 0, minimum_margin: Margin::Percent(8) };
-        let operations = [
+        let mut operations = vec![
             RenderOperation::ClearScreen,
             RenderOperation::SetColors(Colors {
                 foreground: Some(Color::new(255, 0, 0)),
@@ -60,6 +341,13 @@ where
             RenderOperation::RenderLineBreak,
             RenderOperation::RenderTextLine { line: WeightedLine::from(error), alignment: alignment.clone() },
         ];
+        if let Some(source) = source {
+            operations.extend([RenderOperation::RenderLineBreak, RenderOperation::RenderLineBreak]);
+            for line in &source.lines {
+                let line = PreformattedLine { alignment: alignment.clone(), ..line.clone() };
+                operations.extend([RenderOperation::RenderPreformattedLine(line), RenderOperation::RenderLineBreak]);
+            }
+        }
         let operator = RenderOperator::new(&mut self.terminal, dimensions);
         operator.render(&operations)?;
         self.terminal.flush()?;
@@ -67,6 +355,55 @@ where
     }
 }
 
+/// The piece of source code that caused an error, ready to be displayed.
+#[derive(Clone, Debug)]
+pub struct ErrorSource {
+    lines: Vec<PreformattedLine>,
+}
+
+impl ErrorSource {
+    /// Highlight a YAML snippet and mark the position of the error in it.
+    pub fn from_yaml(snippet: &YamlSnippet, highlighter: &CodeHighlighter) -> Self {
+        let last_line_number = snippet.first_line + snippet.lines.len() - 1;
+        let number_width = last_line_number.to_string().len();
+        let gutter_width = number_width + 3;
+        let code = snippet.lines.join("\n");
+        let code_lines = highlighter.highlight(&code, &ProgrammingLanguage::Yaml);
+        let block_length = snippet.lines.iter().map(|line| line.display_width()).max().unwrap_or(0) + gutter_width;
+
+        let mut lines = Vec::new();
+        for (index, code_line) in code_lines.into_iter().enumerate() {
+            let number = snippet.first_line + index;
+            let text = format!("{number:>number_width$} │ {}", code_line.formatted.trim_end());
+            let unformatted_length = gutter_width + code_line.original.trim_end().display_width();
+            let text = text.into();
+            lines.push(PreformattedLine { text, unformatted_length, block_length, alignment: Default::default() });
+            if index == snippet.error_line {
+                // Point at the offending column right below it.
+                let offset = gutter_width + snippet.column.saturating_sub(1);
+                let marker = TextStyle::default()
+                    .bold()
+                    .colors(Colors { foreground: Some(Color::new(255, 0, 0)), background: None });
+                let text = format!("{}{}", " ".repeat(offset), marker.apply("^".to_string()));
+                let unformatted_length = offset + 1;
+                let text = text.into();
+                lines.push(PreformattedLine { text, unformatted_length, block_length, alignment: Default::default() });
+            }
+        }
+        Self { lines }
+    }
+}
+
+/// The panes that can be displayed alongside a slide.
+#[derive(Clone, Debug, Default)]
+pub struct SlidePanes {
+    /// Display a pane at the bottom of the screen with the current slide's speaker notes.
+    pub notes: bool,
+
+    /// Display a pane on the right side of the screen with a preview of the next slide.
+    pub next_slide_preview: bool,
+}
+
 /// A rendering error.
 #[derive(thiserror::Error, Debug)]
 pub enum RenderError {
@@ -1,8 +1,11 @@
-use crate::markdown::elements::ProgrammingLanguage;
+use crate::{
+    markdown::elements::ProgrammingLanguage,
+    style::{Color, Colors},
+};
 use once_cell::sync::Lazy;
 use syntect::{
     easy::HighlightLines,
-    highlighting::{Style, Theme, ThemeSet},
+    highlighting::{FontStyle, Style, Theme, ThemeSet},
     parsing::SyntaxSet,
     util::{as_24_bit_terminal_escaped, LinesWithEndings},
 };
@@ -40,6 +43,40 @@ impl CodeHighlighter {
         lines
     }
 
+    /// Format a piece of code just like [CodeHighlighter::highlight] does but using a single dim color.
+    ///
+    /// This is meant for lines that aren't the focus of attention, so the highlighted ones stand out.
+    pub fn dim<'a>(&self, code: &'a str) -> Vec<CodeLine<'a>> {
+        let settings = &self.theme.settings;
+        let white = syntect::highlighting::Color::WHITE;
+        let background = settings.background.unwrap_or(syntect::highlighting::Color::BLACK);
+        let foreground = settings.foreground.unwrap_or(white);
+        // Move the foreground most of the way towards the background.
+        let blend = |foreground: u8, background: u8| ((foreground as u16 + background as u16 * 2) / 3) as u8;
+        let foreground = syntect::highlighting::Color {
+            r: blend(foreground.r, background.r),
+            g: blend(foreground.g, background.g),
+            b: blend(foreground.b, background.b),
+            a: 255,
+        };
+        let style = Style { foreground, background, font_style: FontStyle::empty() };
+        LinesWithEndings::from(code)
+            .map(|line| CodeLine { original: line, formatted: as_24_bit_terminal_escaped(&[(style, line)], true) })
+            .collect()
+    }
+
+    /// Get the colors this theme uses for line numbers.
+    ///
+    /// The background is the code's rather than the gutter's so line numbers look like part of the code block.
+    pub fn line_number_colors(&self) -> Colors {
+        let settings = &self.theme.settings;
+        let convert = |color: syntect::highlighting::Color| Color::new(color.r, color.g, color.b);
+        Colors {
+            foreground: settings.gutter_foreground.or(settings.foreground).map(convert),
+            background: settings.background.map(convert),
+        }
+    }
+
     fn language_extension(language: &ProgrammingLanguage) -> &'static str {
         use ProgrammingLanguage::*;
         match language {
@@ -0,0 +1,200 @@
+use super::{
+    media::PlacedImage,
+    properties::WindowSize,
+    virtual_terminal::{Cell, CellStyle, VirtualTerminal},
+};
+use crate::style::Color;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::{fmt::Write, io};
+
+// The size of a cell in pixels, only used to pick the size images are rendered at.
+const CELL_WIDTH: u16 = 10;
+const CELL_HEIGHT: u16 = 20;
+
+// Cells are laid out using `ch` units horizontally and this many `em` units vertically.
+const LINE_HEIGHT: f32 = 1.2;
+
+const SCRIPT: &str = r##"
+const slides = document.querySelectorAll("section");
+let current = 0;
+function show(index) {
+  current = Math.max(0, Math.min(index, slides.length - 1));
+  slides.forEach((slide, i) => slide.classList.toggle("current", i === current));
+  history.replaceState(null, "", "#" + (current + 1));
+}
+document.addEventListener("keydown", (event) => {
+  switch (event.key) {
+    case "ArrowRight": case "ArrowDown": case "PageDown": case " ": case "l": case "j": show(current + 1); break;
+    case "ArrowLeft": case "ArrowUp": case "PageUp": case "h": case "k": show(current - 1); break;
+    case "Home": show(0); break;
+    case "End": show(slides.length - 1); break;
+    default: return;
+  }
+  event.preventDefault();
+});
+show((parseInt(location.hash.substring(1)) || 1) - 1);
+"##;
+
+/// Turns the contents of [VirtualTerminal]s into the slides of a standalone HTML document.
+///
+/// Every slide is a `<section>` that contains the terminal's text, styled using inline colors, and
+/// its images embedded as data URLs. Only one slide is displayed at a time and the document
+/// contains a small script that moves between them using the keyboard.
+pub struct HtmlWriter {
+    foreground: Color,
+    background: Color,
+    slides: Vec<String>,
+}
+
+impl HtmlWriter {
+    /// Construct a new writer that uses the given colors for cells that use the default ones.
+    pub fn new(foreground: Color, background: Color) -> Self {
+        Self { foreground, background, slides: Vec::new() }
+    }
+
+    /// Get the size of a slide that fits the given number of rows and columns.
+    pub fn slide_size(rows: u16, columns: u16) -> WindowSize {
+        WindowSize { rows, columns, width: columns * CELL_WIDTH, height: rows * CELL_HEIGHT }
+    }
+
+    /// Add a slide that contains the contents of the given terminal along with the given images.
+    pub fn add_slide(&mut self, terminal: &VirtualTerminal, images: &[PlacedImage]) {
+        let mut slide = String::from("<section><pre>");
+        for row in terminal.rows() {
+            self.write_row(&mut slide, row);
+            slide.push('\n');
+        }
+        slide.push_str("</pre>");
+        for PlacedImage { image, placement } in images {
+            let Some(image) = image.decoded() else {
+                continue;
+            };
+            let mut png = Vec::new();
+            if image.write_to(&mut io::Cursor::new(&mut png), image::ImageOutputFormat::Png).is_err() {
+                continue;
+            }
+            let _ = write!(
+                slide,
+                r#"<img style="left:{}ch;top:{}em;width:{}ch;height:{}em" src="data:image/png;base64,{}">"#,
+                placement.column,
+                placement.row as f32 * LINE_HEIGHT,
+                placement.columns,
+                placement.rows as f32 * LINE_HEIGHT,
+                STANDARD.encode(png)
+            );
+        }
+        slide.push_str("</section>\n");
+        self.slides.push(slide);
+    }
+
+    /// Write the document, containing all the slides added so far.
+    pub fn finish(self) -> String {
+        let mut output = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        output.push_str("<title>presentation</title>\n<style>\n");
+        let _ = writeln!(
+            output,
+            "body {{ margin: 0; background: {}; color: {}; }}",
+            Self::color(self.background),
+            Self::color(self.foreground)
+        );
+        let _ = writeln!(output, "pre {{ margin: 0; font-family: monospace; line-height: {LINE_HEIGHT}em; }}");
+        output.push_str("section { display: none; position: relative; width: fit-content; margin: 1em auto; }\n");
+        output.push_str("section.current { display: block; }\n");
+        output.push_str("img { position: absolute; }\n");
+        output.push_str("</style>\n</head>\n<body>\n");
+        for slide in &self.slides {
+            output.push_str(slide);
+        }
+        let _ = write!(output, "<script>{SCRIPT}</script>\n</body>\n</html>\n");
+        output
+    }
+
+    fn write_row(&self, output: &mut String, row: &[Cell]) {
+        let mut run = String::new();
+        let mut run_style = CellStyle::default();
+        for cell in row {
+            let mut style = cell.style;
+            style.background = style.background.filter(|color| *color != self.background);
+            if style != run_style {
+                self.write_run(output, &run, run_style);
+                run.clear();
+                run_style = style;
+            }
+            match cell.character.unwrap_or(' ') {
+                '&' => run.push_str("&amp;"),
+                '<' => run.push_str("&lt;"),
+                '>' => run.push_str("&gt;"),
+                character => run.push(character),
+            };
+        }
+        // Trailing spaces are only kept if they're visible.
+        if run_style.background.is_none() && !run_style.underlined {
+            run.truncate(run.trim_end_matches(' ').len());
+        }
+        self.write_run(output, &run, run_style);
+    }
+
+    fn write_run(&self, output: &mut String, text: &str, style: CellStyle) {
+        if text.is_empty() {
+            return;
+        }
+        let mut css = Vec::new();
+        if let Some(color) = style.foreground.filter(|color| *color != self.foreground) {
+            css.push(format!("color:{}", Self::color(color)));
+        }
+        if let Some(color) = style.background {
+            css.push(format!("background:{}", Self::color(color)));
+        }
+        if style.bold {
+            css.push("font-weight:bold".into());
+        }
+        if style.underlined {
+            css.push("text-decoration:underline".into());
+        }
+        if css.is_empty() {
+            output.push_str(text);
+        } else {
+            let _ = write!(output, r#"<span style="{}">{text}</span>"#, css.join(";"));
+        }
+    }
+
+    fn color(color: Color) -> String {
+        let [r, g, b] = color.rgb();
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crossterm::{style, QueueableCommand};
+    use std::io::Write;
+
+    #[test]
+    fn slide_contents() {
+        let mut terminal = VirtualTerminal::new(2, 8);
+        terminal.queue(style::Print("a<b ")).unwrap();
+        terminal.queue(style::SetForegroundColor(Color::new(255, 0, 0).into())).unwrap();
+        terminal.queue(style::Print("x&y")).unwrap();
+        terminal.flush().unwrap();
+
+        let mut writer = HtmlWriter::new(Color::new(255, 255, 255), Color::new(0, 0, 0));
+        writer.add_slide(&terminal, &[]);
+        assert_eq!(
+            writer.slides,
+            &["<section><pre>a&lt;b <span style=\"color:#ff0000\">x&amp;y</span>\n\n</pre></section>\n"]
+        );
+    }
+
+    #[test]
+    fn document() {
+        let terminal = VirtualTerminal::new(2, 2);
+        let mut writer = HtmlWriter::new(Color::new(255, 255, 255), Color::new(0, 0, 0));
+        writer.add_slide(&terminal, &[]);
+        writer.add_slide(&terminal, &[]);
+        let output = writer.finish();
+        assert!(output.starts_with("<!DOCTYPE html>\n"));
+        assert!(output.contains("body { margin: 0; background: #000000; color: #ffffff; }"));
+        assert_eq!(output.matches("<section>").count(), 2);
+    }
+}
@@ -1,19 +1,49 @@
-use crate::render::properties::WindowSize;
-use image::{DynamicImage, ImageError};
-use std::{fmt::Debug, io, rc::Rc};
+use crate::{
+    capabilities::{ImageProtocol, TerminalCapabilities},
+    markdown::elements::ImageAlignment,
+    presentation::ImageProperties,
+    profile::{self, Phase},
+    render::properties::WindowSize,
+};
+use image::{io::Reader, DynamicImage, ImageError};
+use log::debug;
+use once_cell::sync::OnceCell;
+use std::{
+    fmt::Debug,
+    io::{self, Cursor},
+    sync::Arc,
+    thread,
+};
 use viuer::ViuError;
 
 use super::properties::CursorPosition;
 
 /// An image.
 ///
-/// This stores the image in an [std::rc::Rc] so it's cheap to clone.
-#[derive(Clone, PartialEq)]
-pub struct Image(Rc<DynamicImage>);
+/// This stores the image in an [std::sync::Arc] so it's cheap to clone. The image may still be
+/// decoding in the background, in which case only its dimensions are known.
+#[derive(Clone)]
+pub struct Image {
+    contents: Arc<OnceCell<Result<DynamicImage, String>>>,
+    width: u32,
+    height: u32,
+}
+
+impl PartialEq for Image {
+    fn eq(&self, other: &Self) -> bool {
+        if Arc::ptr_eq(&self.contents, &other.contents) {
+            return true;
+        }
+        match (self.contents.get(), other.contents.get()) {
+            (Some(Ok(ours)), Some(Ok(theirs))) => ours == theirs,
+            _ => false,
+        }
+    }
+}
 
 impl Debug for Image {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Image<{}x{}>", self.0.width(), self.0.height())
+        write!(f, "Image<{}x{}>", self.width, self.height)
     }
 }
 
@@ -21,8 +51,40 @@ impl Image {
     /// Construct a new image from a byte sequence.
     pub fn new(contents: &[u8]) -> Result<Self, InvalidImage> {
         let contents = image::load_from_memory(contents)?;
-        let contents = Rc::new(contents);
-        Ok(Self(contents))
+        let (width, height) = (contents.width(), contents.height());
+        let contents = Arc::new(OnceCell::with_value(Ok(contents)));
+        Ok(Self { contents, width, height })
+    }
+
+    /// Construct a new image from a byte sequence, decoding it in the background.
+    ///
+    /// Only the image header is processed before returning. Once the image is fully decoded, the
+    /// given callback is invoked from the background thread.
+    pub fn new_async<F>(contents: Vec<u8>, on_loaded: F) -> Result<Self, InvalidImage>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let reader = Reader::new(Cursor::new(&contents)).with_guessed_format().map_err(ImageError::from)?;
+        let (width, height) = reader.into_dimensions()?;
+        let cell = Arc::new(OnceCell::new());
+        let image = Self { contents: cell.clone(), width, height };
+        thread::spawn(move || {
+            debug!("decoding image in background width={width} height={height}");
+            let decoded = image::load_from_memory(&contents).map_err(|e| e.to_string());
+            let _ = cell.set(decoded);
+            on_loaded();
+        });
+        Ok(image)
+    }
+
+    /// Check whether this image has finished loading.
+    pub fn is_loaded(&self) -> bool {
+        self.contents.get().is_some()
+    }
+
+    /// Get the decoded image, if it's done loading and it was decoded successfully.
+    pub(crate) fn decoded(&self) -> Option<&DynamicImage> {
+        self.contents.get().and_then(|contents| contents.as_ref().ok())
     }
 }
 
@@ -43,17 +105,63 @@ impl MediaRender {
         image: &Image,
         position: CursorPosition,
         dimensions: &WindowSize,
+        properties: &ImageProperties,
     ) -> Result<(), RenderImageError> {
-        let image = &image.0;
+        let contents = match image.contents.get() {
+            Some(Ok(contents)) => contents,
+            Some(Err(e)) => return Err(RenderImageError::Decode(e.clone())),
+            None => return Err(RenderImageError::NotLoaded),
+        };
+        let placement = self.place_image(image, &position, dimensions, properties);
+        let capabilities = TerminalCapabilities::current();
+        let config = viuer::Config {
+            width: Some(placement.columns as u32),
+            x: placement.column,
+            y: placement.row as i16,
+            truecolor: capabilities.true_color,
+            use_kitty: capabilities.image_protocol == ImageProtocol::Kitty,
+            use_iterm: capabilities.image_protocol == ImageProtocol::Iterm2,
+            ..Default::default()
+        };
+        debug!(
+            "drawing image width={} height={} columns={} x={} y={}",
+            image.width, image.height, placement.columns, placement.column, placement.row
+        );
+        let (columns, rows) = {
+            let _measurement = profile::measure(Phase::ImageEncode);
+            viuer::print(contents, &config)?
+        };
+        debug!("drew image columns={columns} rows={rows}");
+        Ok(())
+    }
 
+    /// Compute where an image will be drawn.
+    ///
+    /// This can be used to draw a placeholder for an image that's still loading, taking up the
+    /// same space the image will eventually use.
+    pub fn place_image(
+        &self,
+        image: &Image,
+        position: &CursorPosition,
+        dimensions: &WindowSize,
+        properties: &ImageProperties,
+    ) -> ImagePlacement {
         // Compute the image's width in columns by translating pixels -> columns.
         let column_in_pixels = dimensions.pixels_per_column();
         let column_margin = (dimensions.columns as f64 * 0.95) as u32;
-        let mut width_in_columns = (image.width() as f64 / column_in_pixels) as u32;
+        let mut width_in_columns = (image.width as f64 / column_in_pixels) as u32;
 
         // Do the same for its height.
         let row_in_pixels = dimensions.pixels_per_row();
-        let height_in_rows = (image.height() as f64 / row_in_pixels) as u32;
+        let mut height_in_rows = (image.height as f64 / row_in_pixels) as u32;
+
+        // If an explicit width was requested, scale it to that, preserving the aspect ratio.
+        if let Some(width) = properties.width {
+            let target_width = (dimensions.columns as u32 * width as u32 / 100).max(1);
+            let scale_ratio = target_width as f64 / width_in_columns.max(1) as f64;
+            height_in_rows = (height_in_rows as f64 * scale_ratio) as u32;
+            width_in_columns = target_width;
+        }
 
         // If the image doesn't fit vertically, shrink it.
         let available_height = dimensions.rows.saturating_sub(position.row) as u32;
@@ -62,24 +170,46 @@ impl MediaRender {
             // need to shrink the height.
             let shrink_ratio = available_height as f64 / height_in_rows as f64;
             width_in_columns = (width_in_columns as f64 * shrink_ratio) as u32;
+            height_in_rows = available_height;
         }
         // Don't go too far wide.
-        let width_in_columns = width_in_columns.min(column_margin);
+        if width_in_columns > column_margin {
+            let shrink_ratio = column_margin as f64 / width_in_columns as f64;
+            height_in_rows = (height_in_rows as f64 * shrink_ratio) as u32;
+            width_in_columns = column_margin;
+        }
 
-        // Draw it in the middle
-        let start_column = dimensions.columns / 2 - (width_in_columns / 2) as u16;
-        let start_column = start_column + position.column;
-        let config = viuer::Config {
-            width: Some(width_in_columns),
-            x: start_column,
-            y: position.row as i16,
-            ..Default::default()
+        let start_column = match properties.alignment {
+            ImageAlignment::Left => 0,
+            ImageAlignment::Center => dimensions.columns / 2 - (width_in_columns / 2) as u16,
+            ImageAlignment::Right => dimensions.columns.saturating_sub(width_in_columns as u16),
         };
-        viuer::print(image, &config)?;
-        Ok(())
+        let start_column = start_column + position.column;
+        ImagePlacement {
+            column: start_column,
+            row: position.row,
+            columns: width_in_columns as u16,
+            rows: height_in_rows as u16,
+        }
     }
 }
 
+/// The position and size an image will be drawn at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImagePlacement {
+    pub column: u16,
+    pub row: u16,
+    pub columns: u16,
+    pub rows: u16,
+}
+
+/// An image that a headless terminal left a placeholder for, along with where it would have been drawn.
+#[derive(Clone, Debug)]
+pub struct PlacedImage {
+    pub image: Image,
+    pub placement: ImagePlacement,
+}
+
 /// An invalid image.
 #[derive(thiserror::Error, Debug)]
 #[error("invalid image: {0}")]
@@ -97,4 +227,10 @@ pub enum RenderImageError {
 
     #[error("invalid image: {0}")]
     InvalidImage(#[from] InvalidImage),
+
+    #[error("decoding image: {0}")]
+    Decode(String),
+
+    #[error("image is still loading")]
+    NotLoaded,
 }
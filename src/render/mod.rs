@@ -1,8 +1,12 @@
 pub mod draw;
 pub mod highlighting;
+pub mod html;
 pub(crate) mod layout;
 pub mod media;
 pub mod operator;
+pub mod pdf;
 pub mod properties;
+pub mod raster;
 pub(crate) mod terminal;
 pub(crate) mod text;
+pub mod virtual_terminal;
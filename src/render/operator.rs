@@ -1,19 +1,23 @@
 use super::{
     draw::{RenderError, RenderResult},
     layout::Layout,
-    media::{Image, MediaRender},
+    media::{Image, ImagePlacement, MediaRender, PlacedImage},
     properties::CursorPosition,
     terminal::Terminal,
     text::TextDrawer,
 };
 use crate::{
+    capabilities::{ImageProtocol, TerminalCapabilities},
     markdown::text::WeightedLine,
-    presentation::{AsRenderOperations, MarginProperties, PreformattedLine, RenderOperation},
+    presentation::{
+        AsRenderOperations, BlockLine, ImageProperties, MarginProperties, PreformattedLine, RenderOperation,
+    },
     render::{layout::Positioning, properties::WindowSize},
-    style::Colors,
-    theme::Alignment,
+    style::{Colors, TextStyle},
+    theme::{Alignment, LineSize, SeparatorStyle},
 };
 use std::{io, mem};
+use unicode_width::UnicodeWidthStr;
 
 pub(crate) struct RenderOperator<'a, W>
 where
@@ -24,6 +28,7 @@ where
     colors: Colors,
     max_modified_row: u16,
     layout: LayoutState,
+    line_size: LineSize,
 }
 
 impl<'a, W> RenderOperator<'a, W>
@@ -34,10 +39,20 @@ where
         let max_modified_row = terminal.cursor_row;
         let current_rect = WindowRect { dimensions: window_dimensions, start_column: 0 };
         let window_rects = vec![current_rect.clone()];
-        Self { terminal, window_rects, colors: Default::default(), max_modified_row, layout: Default::default() }
+        Self {
+            terminal,
+            window_rects,
+            colors: Default::default(),
+            max_modified_row,
+            layout: Default::default(),
+            line_size: Default::default(),
+        }
     }
 
-    pub(crate) fn render(mut self, operations: &[RenderOperation]) -> RenderResult {
+    pub(crate) fn render<'b, I>(mut self, operations: I) -> RenderResult
+    where
+        I: IntoIterator<Item = &'b RenderOperation>,
+    {
         for operation in operations {
             self.render_one(operation)?;
         }
@@ -50,14 +65,18 @@ where
             RenderOperation::ApplyMargin(properties) => self.apply_margin(properties),
             RenderOperation::PopMargin => self.pop_margin(),
             RenderOperation::SetColors(colors) => self.set_colors(colors),
+            RenderOperation::SetLineSize(size) => self.set_line_size(*size),
             RenderOperation::JumpToVerticalCenter => self.jump_to_vertical_center(),
             RenderOperation::JumpToBottom => self.jump_to_bottom(),
+            RenderOperation::JumpToBottomRow { index } => self.jump_to_bottom_row(*index),
             RenderOperation::RenderTextLine { line: texts, alignment } => self.render_text(texts, alignment),
-            RenderOperation::RenderSeparator => self.render_separator(),
+            RenderOperation::RenderSeparator(style) => self.render_separator(style),
             RenderOperation::RenderLineBreak => self.render_line_break(),
-            RenderOperation::RenderImage(image) => self.render_image(image),
+            RenderOperation::RenderImage(image, properties) => self.render_image(image, properties),
             RenderOperation::RenderPreformattedLine(operation) => self.render_preformatted_line(operation),
+            RenderOperation::RenderBlockLine(operation) => self.render_block_line(operation),
             RenderOperation::RenderDynamic(generator) => self.render_dynamic(generator.as_ref()),
+            RenderOperation::RenderOnDemand(generator) => self.render_dynamic(generator.as_ref()),
             RenderOperation::InitColumnLayout { columns } => self.init_column_layout(columns),
             RenderOperation::EnterColumn { column } => self.enter_column(*column),
             RenderOperation::ExitLayout => self.exit_layout(),
@@ -83,9 +102,14 @@ where
     }
 
     fn apply_margin(&mut self, properties: &MarginProperties) -> RenderResult {
-        let MarginProperties { horizontal_margin, bottom_slide_margin } = properties;
+        let MarginProperties { horizontal_margin, bottom_slide_margin, max_columns } = properties;
         let current = self.current_rect();
-        let margin = horizontal_margin.as_characters(current.dimensions.columns);
+        let mut margin = horizontal_margin.as_characters(current.dimensions.columns);
+        if let Some(max_columns) = max_columns {
+            // Grow the margin on both sides so the content is centered.
+            let excess = current.dimensions.columns.saturating_sub(*max_columns);
+            margin = margin.max(excess / 2);
+        }
         let new_rect = current.apply_margin(margin).shrink_rows(*bottom_slide_margin);
         self.window_rects.push(new_rect);
         Ok(())
@@ -109,6 +133,11 @@ where
         Ok(())
     }
 
+    fn set_line_size(&mut self, size: LineSize) -> RenderResult {
+        self.line_size = size;
+        Ok(())
+    }
+
     fn jump_to_vertical_center(&mut self) -> RenderResult {
         let center_row = self.current_dimensions().rows / 2;
         self.terminal.move_to_row(center_row)?;
@@ -120,16 +149,48 @@ where
         Ok(())
     }
 
+    fn jump_to_bottom_row(&mut self, index: u16) -> RenderResult {
+        self.terminal.move_to_row(self.current_dimensions().rows.saturating_sub(index))?;
+        Ok(())
+    }
+
     fn render_text(&mut self, text: &WeightedLine, alignment: &Alignment) -> RenderResult {
-        let layout = self.build_layout(alignment.clone());
-        let text_drawer = TextDrawer::new(&layout, text, self.current_dimensions(), &self.colors)?;
+        // Lines using a larger size address columns in units of their scaled width, so we need
+        // to scale down our rect accordingly.
+        let scale = self.line_size.column_scale();
+        let rect = self.current_rect();
+        let dimensions = rect.dimensions.shrink_columns(rect.dimensions.columns - rect.dimensions.columns / scale);
+        let layout = Layout::new(alignment.clone()).with_start_column(rect.start_column / scale);
+        let text_drawer = TextDrawer::new(&layout, text, &dimensions, &self.colors)?.with_line_size(self.line_size);
         text_drawer.draw(self.terminal)
     }
 
-    fn render_separator(&mut self) -> RenderResult {
-        let separator: String = "—".repeat(self.current_dimensions().columns as usize);
-        self.terminal.move_to_column(self.current_rect().start_column)?;
-        self.terminal.print_line(&separator)?;
+    fn render_separator(&mut self, style: &SeparatorStyle) -> RenderResult {
+        let columns = self.current_dimensions().columns;
+        let width = match style.width {
+            Some(width) => (columns as u32 * width.min(100) as u32 / 100) as u16,
+            None => columns,
+        };
+        let character = style.character.as_deref().unwrap_or("—");
+        let character_width = character.width().max(1);
+        let separator = match &style.ornament {
+            Some(ornament) => {
+                let ornament = format!(" {ornament} ");
+                let side_width = (width as usize).saturating_sub(ornament.width()) / 2;
+                let side = character.repeat(side_width / character_width);
+                format!("{side}{ornament}{side}")
+            }
+            None => character.repeat(width as usize / character_width),
+        };
+        let start_column = self.current_rect().start_column + columns.saturating_sub(separator.width() as u16) / 2;
+        self.terminal.move_to_column(start_column)?;
+        if style.colors == Colors::default() {
+            self.terminal.print_line(&separator)?;
+        } else {
+            let separator = TextStyle::default().colors(style.colors.clone()).apply(separator);
+            self.terminal.print_styled_line(separator)?;
+            self.terminal.set_colors(self.colors.clone())?;
+        }
         Ok(())
     }
 
@@ -138,16 +199,61 @@ where
         Ok(())
     }
 
-    fn render_image(&mut self, image: &Image) -> RenderResult {
+    fn render_image(&mut self, image: &Image, properties: &ImageProperties) -> RenderResult {
         let position = CursorPosition { row: self.terminal.cursor_row, column: self.current_rect().start_column };
+        let mut dimensions = self.current_dimensions().clone();
+        if let Some(slots) = properties.vertical_slots.filter(|slots| *slots > 1) {
+            let available_rows = dimensions.rows.saturating_sub(position.row);
+            dimensions = dimensions.shrink_rows(available_rows - available_rows / slots);
+        }
+        // Images are drawn straight into the terminal so they can't be drawn when headless.
+        let headless = self.terminal.is_headless();
+        if headless || !image.is_loaded() || TerminalCapabilities::current().image_protocol == ImageProtocol::None {
+            return self.render_image_placeholder(image, position, &dimensions, properties);
+        }
         MediaRender
-            .draw_image(image, position, self.current_dimensions())
+            .draw_image(image, position, &dimensions, properties)
             .map_err(|e| RenderError::Other(Box::new(e)))?;
         // TODO try to avoid
         self.terminal.sync_cursor_row()?;
         Ok(())
     }
 
+    fn render_image_placeholder(
+        &mut self,
+        image: &Image,
+        position: CursorPosition,
+        dimensions: &WindowSize,
+        properties: &ImageProperties,
+    ) -> RenderResult {
+        let placement = MediaRender.place_image(image, &position, dimensions, properties);
+        let ImagePlacement { column, row, columns, rows } = placement;
+        if columns < 2 || rows < 2 {
+            return Ok(());
+        }
+        if self.terminal.is_headless() {
+            self.terminal.placed_images.push(PlacedImage { image: image.clone(), placement });
+        }
+        let inner_width = columns as usize - 2;
+        let label = if self.terminal.is_headless() || image.is_loaded() { "image" } else { "loading image..." };
+        let label_row = row + rows / 2;
+        for current_row in row..row + rows {
+            let line = if current_row == row {
+                format!("┌{}┐", "─".repeat(inner_width))
+            } else if current_row == row + rows - 1 {
+                format!("└{}┘", "─".repeat(inner_width))
+            } else if current_row == label_row && label.len() <= inner_width {
+                format!("│{label:^inner_width$}│")
+            } else {
+                format!("│{}│", " ".repeat(inner_width))
+            };
+            self.terminal.move_to(column, current_row)?;
+            self.terminal.print_line(&line)?;
+        }
+        self.terminal.move_to_row(row + rows)?;
+        Ok(())
+    }
+
     fn render_preformatted_line(&mut self, operation: &PreformattedLine) -> RenderResult {
         let PreformattedLine { text, unformatted_length, block_length, alignment } = operation;
         let layout = self.build_layout(alignment.clone());
@@ -167,7 +273,20 @@ where
         Ok(())
     }
 
-    fn render_dynamic(&mut self, generator: &dyn AsRenderOperations) -> RenderResult {
+    fn render_block_line(&mut self, operation: &BlockLine) -> RenderResult {
+        let BlockLine { prefix, text, block_length, alignment } = operation;
+        let layout = self.build_layout(alignment.clone());
+        let text_drawer =
+            TextDrawer::new_block(&layout, text, *block_length as u16, self.current_dimensions(), &self.colors)?
+                .with_prefix(prefix);
+        text_drawer.draw(self.terminal)?;
+
+        // Restore colors
+        self.apply_colors()?;
+        Ok(())
+    }
+
+    fn render_dynamic<T: AsRenderOperations + ?Sized>(&mut self, generator: &T) -> RenderResult {
         let operations = generator.as_render_operations(self.current_dimensions());
         for operation in operations {
             self.render_one(&operation)?;
@@ -180,8 +299,8 @@ where
             self.exit_layout()?;
         }
         let columns = columns.iter().copied().map(u16::from).collect();
-        let current_position = CursorPosition::current()?;
-        self.layout = LayoutState::InitializedColumn { columns, start_row: current_position.row };
+        self.terminal.sync_cursor_row()?;
+        self.layout = LayoutState::InitializedColumn { columns, start_row: self.terminal.cursor_row };
         Ok(())
     }
 
@@ -285,3 +404,34 @@ impl WindowRect {
         Self { dimensions, start_column: self.start_column }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{render::virtual_terminal::VirtualTerminal, theme::Margin};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::fits(10, " hi   ")]
+    #[case::too_wide(2, "  hi  ")]
+    fn limits_max_columns(#[case] max_columns: u16, #[case] expected: &str) {
+        let mut virtual_terminal = VirtualTerminal::new(1, 6);
+        let mut terminal = Terminal::headless(&mut virtual_terminal);
+        let dimensions = WindowSize { rows: 1, columns: 6, width: 0, height: 0 };
+        let operations = [
+            RenderOperation::ApplyMargin(MarginProperties {
+                horizontal_margin: Margin::Fixed(1),
+                bottom_slide_margin: 0,
+                max_columns: Some(max_columns),
+            }),
+            RenderOperation::RenderTextLine {
+                line: WeightedLine::from(String::from("hi")),
+                alignment: Alignment::Left { margin: Margin::Fixed(0) },
+            },
+        ];
+        RenderOperator::new(&mut terminal, dimensions).render(&operations).unwrap();
+        terminal.flush().unwrap();
+        drop(terminal);
+        assert_eq!(virtual_terminal.text_rows(), &[expected]);
+    }
+}
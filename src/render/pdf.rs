@@ -0,0 +1,423 @@
+use super::{
+    media::PlacedImage,
+    properties::WindowSize,
+    raster::box_drawing_edges,
+    virtual_terminal::{Cell, CellStyle, VirtualTerminal},
+};
+use crate::style::Color;
+use flate2::{write::ZlibEncoder, Compression};
+use image::{DynamicImage, GenericImageView};
+use std::{fmt::Write as _, io::Write};
+
+// Courier glyphs are 0.6 times as wide as the font size.
+const FONT_SIZE: f32 = 10.0;
+const CELL_WIDTH: f32 = 6.0;
+const CELL_HEIGHT: f32 = 12.0;
+
+// How far above the bottom of a cell text is drawn, leaving room for descenders.
+const BASELINE_OFFSET: f32 = 3.0;
+
+// How many pixels every point is worth when placing images.
+const PIXELS_PER_POINT: u16 = 2;
+
+/// Turns the contents of [VirtualTerminal]s into the pages of a PDF document.
+///
+/// Text is drawn using the standard Courier fonts so it can be selected and searched for. These
+/// only support Latin-1 characters, so box drawing characters are drawn as lines and anything else
+/// that's not supported is drawn as a `?`.
+pub struct PdfWriter {
+    foreground: Color,
+    background: Color,
+    pages: Vec<Page>,
+}
+
+impl PdfWriter {
+    /// Construct a new writer that uses the given colors for cells that use the default ones.
+    pub fn new(foreground: Color, background: Color) -> Self {
+        Self { foreground, background, pages: Vec::new() }
+    }
+
+    /// Get the size of a page that fits the given number of rows and columns.
+    ///
+    /// The pixel size is meant to be used to place images, which end up being drawn at roughly
+    /// their actual size.
+    pub fn page_size(rows: u16, columns: u16) -> WindowSize {
+        WindowSize {
+            rows,
+            columns,
+            width: columns * CELL_WIDTH as u16 * PIXELS_PER_POINT,
+            height: rows * CELL_HEIGHT as u16 * PIXELS_PER_POINT,
+        }
+    }
+
+    /// Add a page that contains the contents of the given terminal along with the given images.
+    pub fn add_page(&mut self, terminal: &VirtualTerminal, images: &[PlacedImage]) {
+        let rows = terminal.rows();
+        let columns = rows.first().map(|row| row.len()).unwrap_or(0);
+        let width = columns as f32 * CELL_WIDTH;
+        let height = rows.len() as f32 * CELL_HEIGHT;
+        let mut page = PageBuilder { height, contents: String::new() };
+        page.fill_rect(self.background, 0.0, 0.0, width, height);
+        for (row_index, row) in rows.iter().enumerate() {
+            page.draw_backgrounds(row_index, row, self.background);
+        }
+        for (row_index, row) in rows.iter().enumerate() {
+            page.draw_text(row_index, row, self.foreground);
+        }
+        let mut page_images = Vec::new();
+        for PlacedImage { image, placement } in images {
+            let Some(image) = image.decoded() else {
+                continue;
+            };
+            let name = format!("Im{}", page_images.len());
+            let x = placement.column as f32 * CELL_WIDTH;
+            let y = placement.row as f32 * CELL_HEIGHT;
+            let image_width = placement.columns as f32 * CELL_WIDTH;
+            let image_height = placement.rows as f32 * CELL_HEIGHT;
+            page.draw_image(&name, x, y, image_width, image_height);
+            page_images.push(EncodedImage::new(name, image, self.background));
+        }
+        let contents = compress(page.contents.as_bytes());
+        self.pages.push(Page { width, height, contents, images: page_images });
+    }
+
+    /// Write the document, containing all the pages added so far.
+    pub fn finish(self) -> Vec<u8> {
+        let mut document = Document::default();
+        let pages_id = document.reserve_object();
+        let catalog = document.add_object(format!("<< /Type /Catalog /Pages {pages_id} 0 R >>").into_bytes());
+        let regular_font = document.add_object(Self::font("Courier"));
+        let bold_font = document.add_object(Self::font("Courier-Bold"));
+        let mut page_ids = Vec::new();
+        for page in self.pages {
+            let mut xobjects = String::new();
+            for image in page.images {
+                let dictionary = format!(
+                    "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB /BitsPerComponent 8",
+                    image.width, image.height
+                );
+                let id = document.add_stream(&dictionary, &image.contents);
+                let _ = write!(xobjects, "/{} {id} 0 R ", image.name);
+            }
+            let contents = document.add_stream("", &page.contents);
+            let page_object = format!(
+                "<< /Type /Page /Parent {pages_id} 0 R /MediaBox [0 0 {} {}] /Contents {contents} 0 R \
+                 /Resources << /Font << /F1 {regular_font} 0 R /F2 {bold_font} 0 R >> /XObject << {xobjects}>> >> >>",
+                page.width, page.height
+            );
+            page_ids.push(document.add_object(page_object.into_bytes()));
+        }
+        let kids: Vec<_> = page_ids.iter().map(|id| format!("{id} 0 R")).collect();
+        let pages = format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), page_ids.len());
+        document.set_object(pages_id, pages.into_bytes());
+        document.write(catalog)
+    }
+
+    fn font(name: &str) -> Vec<u8> {
+        format!("<< /Type /Font /Subtype /Type1 /BaseFont /{name} /Encoding /WinAnsiEncoding >>").into_bytes()
+    }
+}
+
+struct Page {
+    width: f32,
+    height: f32,
+    contents: Vec<u8>,
+    images: Vec<EncodedImage>,
+}
+
+struct EncodedImage {
+    name: String,
+    width: u32,
+    height: u32,
+    contents: Vec<u8>,
+}
+
+impl EncodedImage {
+    fn new(name: String, image: &DynamicImage, background: Color) -> Self {
+        // Transparent pixels are blended with the background as PDF images don't have an alpha
+        // channel unless a separate mask is used for it.
+        let [background_r, background_g, background_b] = background.rgb();
+        let mut pixels = Vec::new();
+        for (_, _, pixel) in image.pixels() {
+            let [r, g, b, a] = pixel.0;
+            let blend = |value: u8, background: u8| {
+                ((value as u32 * a as u32 + background as u32 * (255 - a as u32)) / 255) as u8
+            };
+            pixels.extend([blend(r, background_r), blend(g, background_g), blend(b, background_b)]);
+        }
+        let contents = compress(&pixels);
+        Self { name, width: image.width(), height: image.height(), contents }
+    }
+}
+
+// Builds the contents stream of a page.
+//
+// PDF coordinates start at the bottom left corner of the page so everything is flipped vertically
+// here, which lets callers use coordinates that start at the top left, like the terminal does.
+struct PageBuilder {
+    height: f32,
+    contents: String,
+}
+
+impl PageBuilder {
+    fn fill_rect(&mut self, color: Color, x: f32, y: f32, width: f32, height: f32) {
+        let y = self.height - y - height;
+        let _ = writeln!(self.contents, "{} rg {x} {y} {width} {height} re f", Self::color(color));
+    }
+
+    fn draw_line(&mut self, color: Color, from: (f32, f32), to: (f32, f32)) {
+        let (from_y, to_y) = (self.height - from.1, self.height - to.1);
+        let _ = writeln!(self.contents, "{} RG 1 w {} {from_y} m {} {to_y} l S", Self::color(color), from.0, to.0);
+    }
+
+    fn draw_image(&mut self, name: &str, x: f32, y: f32, width: f32, height: f32) {
+        let y = self.height - y - height;
+        let _ = writeln!(self.contents, "q {width} 0 0 {height} {x} {y} cm /{name} Do Q");
+    }
+
+    fn draw_backgrounds(&mut self, row_index: usize, row: &[Cell], default_background: Color) {
+        let y = row_index as f32 * CELL_HEIGHT;
+        let mut run_start = 0;
+        for column_index in 1..=row.len() {
+            let background = row[run_start].style.background;
+            if row.get(column_index).is_some_and(|cell| cell.style.background == background) {
+                continue;
+            }
+            if let Some(color) = background.filter(|color| *color != default_background) {
+                let x = run_start as f32 * CELL_WIDTH;
+                self.fill_rect(color, x, y, (column_index - run_start) as f32 * CELL_WIDTH, CELL_HEIGHT);
+            }
+            run_start = column_index;
+        }
+    }
+
+    fn draw_text(&mut self, row_index: usize, row: &[Cell], default_foreground: Color) {
+        let y = row_index as f32 * CELL_HEIGHT;
+        let mut text = String::new();
+        let mut text_start = 0;
+        let mut text_style = CellStyle::default();
+        for (column_index, cell) in row.iter().enumerate() {
+            let x = column_index as f32 * CELL_WIDTH;
+            let foreground = cell.style.foreground.unwrap_or(default_foreground);
+            let character = cell.character.unwrap_or(' ');
+            let drawing = Self::draw_symbol(character);
+            let style = CellStyle { background: None, ..cell.style };
+            if drawing.is_some() || style != text_style {
+                self.draw_text_run(&text, text_start, y, text_style, default_foreground);
+                text.clear();
+                text_start = column_index;
+                text_style = style;
+            }
+            match drawing {
+                Some(Symbol::Box(up, down, left, right)) => {
+                    let (center_x, center_y) = (x + CELL_WIDTH / 2.0, y + CELL_HEIGHT / 2.0);
+                    let start_x = if left { x } else { center_x };
+                    let end_x = if right { x + CELL_WIDTH } else { center_x };
+                    let start_y = if up { y } else { center_y };
+                    let end_y = if down { y + CELL_HEIGHT } else { center_y };
+                    if left || right {
+                        self.draw_line(foreground, (start_x, center_y), (end_x, center_y));
+                    }
+                    if up || down {
+                        self.draw_line(foreground, (center_x, start_y), (center_x, end_y));
+                    }
+                }
+                Some(Symbol::Block { top, bottom }) => {
+                    let start_y = if top { y } else { y + CELL_HEIGHT / 2.0 };
+                    let end_y = if bottom { y + CELL_HEIGHT } else { y + CELL_HEIGHT / 2.0 };
+                    self.fill_rect(foreground, x, start_y, CELL_WIDTH, end_y - start_y);
+                }
+                None => {
+                    if text.is_empty() {
+                        text_start = column_index;
+                    }
+                    text.push(character);
+                }
+            };
+        }
+        self.draw_text_run(&text, text_start, y, text_style, default_foreground);
+    }
+
+    fn draw_text_run(&mut self, text: &str, column: usize, y: f32, style: CellStyle, default_foreground: Color) {
+        if text.trim().is_empty() && !style.underlined {
+            return;
+        }
+        let x = column as f32 * CELL_WIDTH;
+        let foreground = style.foreground.unwrap_or(default_foreground);
+        let font = if style.bold { "F2" } else { "F1" };
+        let baseline = self.height - y - CELL_HEIGHT + BASELINE_OFFSET;
+        let _ = writeln!(
+            self.contents,
+            "BT /{font} {FONT_SIZE} Tf {} rg {x} {baseline} Td ({}) Tj ET",
+            Self::color(foreground),
+            Self::encode_text(text)
+        );
+        if style.underlined {
+            let underline_y = y + CELL_HEIGHT - BASELINE_OFFSET + 1.0;
+            let end_x = x + text.chars().count() as f32 * CELL_WIDTH;
+            self.draw_line(foreground, (x, underline_y), (end_x, underline_y));
+        }
+    }
+
+    fn draw_symbol(character: char) -> Option<Symbol> {
+        if let Some((up, down, left, right)) = box_drawing_edges(character) {
+            return Some(Symbol::Box(up, down, left, right));
+        }
+        match character {
+            '█' => Some(Symbol::Block { top: true, bottom: true }),
+            '▀' => Some(Symbol::Block { top: true, bottom: false }),
+            '▄' => Some(Symbol::Block { top: false, bottom: true }),
+            _ => None,
+        }
+    }
+
+    // Encode text as a PDF string in the WinAnsi encoding, which is mostly Latin-1.
+    fn encode_text(text: &str) -> String {
+        let mut output = String::new();
+        for character in text.chars() {
+            let byte = match character {
+                '(' | ')' | '\\' => {
+                    output.push('\\');
+                    character as u8
+                }
+                ' '..='~' => character as u8,
+                '•' | '●' => 0x95,
+                '–' => 0x96,
+                '—' => 0x97,
+                '‘' => 0x91,
+                '’' => 0x92,
+                '“' => 0x93,
+                '”' => 0x94,
+                '…' => 0x85,
+                '€' => 0x80,
+                '\u{a0}'..='\u{ff}' => character as u8,
+                _ => b'?',
+            };
+            if byte.is_ascii() {
+                output.push(byte as char);
+            } else {
+                let _ = write!(output, "\\{byte:03o}");
+            }
+        }
+        output
+    }
+
+    fn color(color: Color) -> String {
+        let [r, g, b] = color.rgb();
+        let component = |value: u8| value as f32 / 255.0;
+        format!("{:.3} {:.3} {:.3}", component(r), component(g), component(b))
+    }
+}
+
+enum Symbol {
+    Box(bool, bool, bool, bool),
+    Block { top: bool, bottom: bool },
+}
+
+// The objects in a PDF document, which are referenced by their 1 based index.
+#[derive(Default)]
+struct Document {
+    objects: Vec<Vec<u8>>,
+}
+
+impl Document {
+    fn add_object(&mut self, contents: Vec<u8>) -> usize {
+        self.objects.push(contents);
+        self.objects.len()
+    }
+
+    fn reserve_object(&mut self) -> usize {
+        self.add_object(Vec::new())
+    }
+
+    fn set_object(&mut self, id: usize, contents: Vec<u8>) {
+        self.objects[id - 1] = contents;
+    }
+
+    fn add_stream(&mut self, dictionary: &str, contents: &[u8]) -> usize {
+        let mut object =
+            format!("<< {dictionary} /Filter /FlateDecode /Length {} >>\nstream\n", contents.len()).into_bytes();
+        object.extend(contents);
+        object.extend(b"\nendstream");
+        self.add_object(object)
+    }
+
+    fn write(self, root: usize) -> Vec<u8> {
+        let mut output = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (index, object) in self.objects.iter().enumerate() {
+            offsets.push(output.len());
+            output.extend(format!("{} 0 obj\n", index + 1).as_bytes());
+            output.extend(object);
+            output.extend(b"\nendobj\n");
+        }
+        let xref_offset = output.len();
+        output.extend(format!("xref\n0 {}\n0000000000 65535 f \n", self.objects.len() + 1).as_bytes());
+        for offset in offsets {
+            output.extend(format!("{offset:010} 00000 n \n").as_bytes());
+        }
+        let trailer = format!(
+            "trailer\n<< /Size {} /Root {root} 0 R >>\nstartxref\n{xref_offset}\n%%EOF\n",
+            self.objects.len() + 1
+        );
+        output.extend(trailer.as_bytes());
+        output
+    }
+}
+
+fn compress(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).expect("compressing into memory failed");
+    encoder.finish().expect("compressing into memory failed")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crossterm::{style, QueueableCommand};
+    use rstest::rstest;
+    use std::io::Write;
+
+    #[rstest]
+    #[case::plain("hello", "hello")]
+    #[case::escaped("f(x) \\ 2", "f\\(x\\) \\\\ 2")]
+    #[case::latin1("café", "caf\\351")]
+    #[case::windows("• a", "\\225 a")]
+    #[case::unsupported("🚀", "?")]
+    fn encode_text(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(PageBuilder::encode_text(input), expected);
+    }
+
+    #[test]
+    fn page_contents() {
+        let mut terminal = VirtualTerminal::new(1, 4);
+        terminal.queue(style::SetForegroundColor(Color::new(255, 0, 0).into())).unwrap();
+        terminal.queue(style::Print("a─b")).unwrap();
+        terminal.flush().unwrap();
+
+        let mut page = PageBuilder { height: CELL_HEIGHT, contents: String::new() };
+        page.draw_text(0, &terminal.rows()[0], Color::new(255, 255, 255));
+        let lines: Vec<_> = page.contents.lines().collect();
+        assert_eq!(
+            lines,
+            &[
+                "BT /F1 10 Tf 1.000 0.000 0.000 rg 0 3 Td (a) Tj ET",
+                "1.000 0.000 0.000 RG 1 w 6 6 m 12 6 l S",
+                "BT /F1 10 Tf 1.000 0.000 0.000 rg 12 3 Td (b) Tj ET",
+            ]
+        );
+    }
+
+    #[test]
+    fn document() {
+        let terminal = VirtualTerminal::new(2, 2);
+        let mut writer = PdfWriter::new(Color::new(255, 255, 255), Color::new(0, 0, 0));
+        writer.add_page(&terminal, &[]);
+        writer.add_page(&terminal, &[]);
+        let output = String::from_utf8_lossy(&writer.finish()).to_string();
+        assert!(output.starts_with("%PDF-1.4\n"));
+        assert!(output.ends_with("%%EOF\n"));
+        assert!(output.contains("/Kids [6 0 R 8 0 R] /Count 2"));
+        assert!(output.contains("/MediaBox [0 0 12 24]"));
+    }
+}
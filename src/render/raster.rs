@@ -0,0 +1,338 @@
+use super::virtual_terminal::{Cell, VirtualTerminal};
+use crate::style::Color;
+use image::{Rgba, RgbaImage};
+
+const GLYPH_COLUMNS: u32 = 5;
+const GLYPH_ROWS: u32 = 7;
+
+// Every cell contains a glyph plus a column of spacing to its right and a row of spacing above and
+// below it.
+const CELL_COLUMNS: u32 = GLYPH_COLUMNS + 1;
+const CELL_ROWS: u32 = GLYPH_ROWS + 2;
+
+/// Turns the contents of a [VirtualTerminal] into an image.
+///
+/// Text is drawn using a built in bitmap font, which only supports ASCII and a few box drawing
+/// characters. Anything else is drawn as an empty box.
+#[derive(Clone, Debug)]
+pub struct Rasterizer {
+    foreground: Color,
+    background: Color,
+    scale: u32,
+}
+
+impl Rasterizer {
+    /// Construct a new rasterizer that uses the given colors for cells that use the default ones.
+    pub fn new(foreground: Color, background: Color) -> Self {
+        Self { foreground, background, scale: 2 }
+    }
+
+    /// The size in pixels of every cell, as `(width, height)`.
+    pub fn cell_size(&self) -> (u32, u32) {
+        (CELL_COLUMNS * self.scale, CELL_ROWS * self.scale)
+    }
+
+    /// Draw the contents of the given terminal into an image.
+    pub fn rasterize(&self, terminal: &VirtualTerminal) -> RgbaImage {
+        let rows = terminal.rows();
+        let columns = rows.first().map(|row| row.len()).unwrap_or(0) as u32;
+        let (cell_width, cell_height) = self.cell_size();
+        let mut image = RgbaImage::new(columns * cell_width, rows.len() as u32 * cell_height);
+        for (row_index, row) in rows.iter().enumerate() {
+            for (column_index, cell) in row.iter().enumerate() {
+                self.draw_cell(&mut image, cell, column_index as u32 * cell_width, row_index as u32 * cell_height);
+            }
+        }
+        image
+    }
+
+    fn draw_cell(&self, image: &mut RgbaImage, cell: &Cell, x: u32, y: u32) {
+        let foreground = Self::pixel(cell.style.foreground.unwrap_or(self.foreground));
+        let background = Self::pixel(cell.style.background.unwrap_or(self.background));
+        let (cell_width, cell_height) = self.cell_size();
+        for pixel_y in 0..cell_height {
+            for pixel_x in 0..cell_width {
+                image.put_pixel(x + pixel_x, y + pixel_y, background);
+            }
+        }
+        let Some(character) = cell.character else {
+            return;
+        };
+        let mut bitmap = Self::cell_bitmap(character);
+        if cell.style.bold {
+            for row in &mut bitmap {
+                *row |= *row >> 1;
+            }
+        }
+        if cell.style.underlined {
+            bitmap[CELL_ROWS as usize - 1] = (1 << CELL_COLUMNS) - 1;
+        }
+        for (row_index, row) in bitmap.iter().enumerate() {
+            for column_index in 0..CELL_COLUMNS {
+                if row & (1 << (CELL_COLUMNS - 1 - column_index)) == 0 {
+                    continue;
+                }
+                for offset_y in 0..self.scale {
+                    for offset_x in 0..self.scale {
+                        let pixel_x = x + column_index * self.scale + offset_x;
+                        let pixel_y = y + row_index as u32 * self.scale + offset_y;
+                        image.put_pixel(pixel_x, pixel_y, foreground);
+                    }
+                }
+            }
+        }
+    }
+
+    // Get the bitmap for a character over the entire cell. Every row is a bitmask where the most
+    // significant of the lowest `CELL_COLUMNS` bits is the leftmost pixel.
+    fn cell_bitmap(character: char) -> [u8; CELL_ROWS as usize] {
+        let mut bitmap = [0; CELL_ROWS as usize];
+        if let Some(glyph) = Self::ascii_glyph(character) {
+            for (row, bits) in glyph.iter().enumerate() {
+                // Leave the row above the glyph and the column to its right empty.
+                bitmap[row + 1] = bits << 1;
+            }
+            return bitmap;
+        }
+        let full_row = (1 << CELL_COLUMNS) - 1;
+        let middle_row = CELL_ROWS as usize / 2;
+        let middle_column = 1 << (CELL_COLUMNS - 1 - GLYPH_COLUMNS / 2);
+        let left_half = full_row & !(middle_column - 1);
+        let right_half = middle_column | (middle_column - 1);
+        let Some((up, down, left, right)) = box_drawing_edges(character) else {
+            match character {
+                '█' => bitmap.fill(full_row),
+                '▀' => bitmap[..middle_row].fill(full_row),
+                '▄' => bitmap[middle_row..].fill(full_row),
+                // Dashes, like the ones separators are drawn with by default, are drawn just like
+                // horizontal lines so separators look continuous.
+                '—' | '–' | '―' => bitmap[middle_row] = full_row,
+                '•' | '·' | '●' | '▪' => {
+                    let dot = middle_column | middle_column << 1 | middle_column >> 1;
+                    bitmap[middle_row - 1..=middle_row + 1].fill(dot);
+                }
+                '◦' => {
+                    let dot = middle_column | middle_column << 1 | middle_column >> 1;
+                    bitmap[middle_row - 1] = dot;
+                    bitmap[middle_row] = middle_column << 1 | middle_column >> 1;
+                    bitmap[middle_row + 1] = dot;
+                }
+                '…' => bitmap[GLYPH_ROWS as usize] = 0b101010,
+                _ => {
+                    // Draw an empty box for anything we don't know how to draw.
+                    let edges = 1 << (GLYPH_COLUMNS - 1) | 1;
+                    bitmap[1] = full_row >> 1 << 1;
+                    bitmap[2..GLYPH_ROWS as usize].fill(edges << 1);
+                    bitmap[GLYPH_ROWS as usize] = full_row >> 1 << 1;
+                }
+            };
+            return bitmap;
+        };
+        if up {
+            bitmap[..middle_row].fill(middle_column);
+        }
+        if down {
+            for row in &mut bitmap[middle_row + 1..] {
+                *row |= middle_column;
+            }
+        }
+        match (left, right) {
+            (true, true) => bitmap[middle_row] |= full_row,
+            (true, false) => bitmap[middle_row] |= left_half,
+            (false, true) => bitmap[middle_row] |= right_half,
+            (false, false) => bitmap[middle_row] |= middle_column,
+        };
+        bitmap
+    }
+
+    fn ascii_glyph(character: char) -> Option<&'static [u8; GLYPH_ROWS as usize]> {
+        let index = (character as u32).checked_sub(' ' as u32)?;
+        GLYPHS.get(index as usize)
+    }
+
+    fn pixel(color: Color) -> Rgba<u8> {
+        let [r, g, b] = color.rgb();
+        Rgba([r, g, b, 255])
+    }
+}
+
+/// Get the edges of a cell, as `(up, down, left, right)`, that a box drawing character connects.
+pub(crate) fn box_drawing_edges(character: char) -> Option<(bool, bool, bool, bool)> {
+    let edges = match character {
+        '─' | '━' | '═' => (false, false, true, true),
+        '│' | '┃' | '║' => (true, true, false, false),
+        '┌' | '╭' => (false, true, false, true),
+        '┐' | '╮' => (false, true, true, false),
+        '└' | '╰' => (true, false, false, true),
+        '┘' | '╯' => (true, false, true, false),
+        '├' => (true, true, false, true),
+        '┤' => (true, true, true, false),
+        '┬' => (false, true, true, true),
+        '┴' => (true, false, true, true),
+        '┼' => (true, true, true, true),
+        _ => return None,
+    };
+    Some(edges)
+}
+
+// A 5x7 bitmap font for every printable ASCII character, starting at the space character. Every
+// row is a bitmask where the most significant of the lowest 5 bits is the leftmost pixel.
+#[rustfmt::skip]
+const GLYPHS: [[u8; GLYPH_ROWS as usize]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04], // '!'
+    [0x0a, 0x0a, 0x0a, 0x00, 0x00, 0x00, 0x00], // '"'
+    [0x0a, 0x0a, 0x1f, 0x0a, 0x1f, 0x0a, 0x0a], // '#'
+    [0x04, 0x0f, 0x14, 0x0e, 0x05, 0x1e, 0x04], // '$'
+    [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03], // '%'
+    [0x0c, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0d], // '&'
+    [0x04, 0x04, 0x04, 0x00, 0x00, 0x00, 0x00], // '\''
+    [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02], // '('
+    [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08], // ')'
+    [0x00, 0x04, 0x15, 0x0e, 0x15, 0x04, 0x00], // '*'
+    [0x00, 0x04, 0x04, 0x1f, 0x04, 0x04, 0x00], // '+'
+    [0x00, 0x00, 0x00, 0x00, 0x0c, 0x04, 0x08], // ','
+    [0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00], // '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x0c], // '.'
+    [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00], // '/'
+    [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e], // '0'
+    [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e], // '1'
+    [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f], // '2'
+    [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e], // '3'
+    [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02], // '4'
+    [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e], // '5'
+    [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e], // '6'
+    [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08], // '7'
+    [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e], // '8'
+    [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c], // '9'
+    [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x0c, 0x00], // ':'
+    [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x04, 0x08], // ';'
+    [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02], // '<'
+    [0x00, 0x00, 0x1f, 0x00, 0x1f, 0x00, 0x00], // '='
+    [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08], // '>'
+    [0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04], // '?'
+    [0x0e, 0x11, 0x01, 0x0d, 0x15, 0x15, 0x0e], // '@'
+    [0x0e, 0x11, 0x11, 0x11, 0x1f, 0x11, 0x11], // 'A'
+    [0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e], // 'B'
+    [0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e], // 'C'
+    [0x1c, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1c], // 'D'
+    [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f], // 'E'
+    [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10], // 'F'
+    [0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f], // 'G'
+    [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11], // 'H'
+    [0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e], // 'I'
+    [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c], // 'J'
+    [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11], // 'K'
+    [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f], // 'L'
+    [0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11], // 'M'
+    [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11], // 'N'
+    [0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e], // 'O'
+    [0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10], // 'P'
+    [0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d], // 'Q'
+    [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11], // 'R'
+    [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e], // 'S'
+    [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // 'T'
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e], // 'U'
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04], // 'V'
+    [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a], // 'W'
+    [0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11], // 'X'
+    [0x11, 0x11, 0x11, 0x0a, 0x04, 0x04, 0x04], // 'Y'
+    [0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f], // 'Z'
+    [0x0e, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0e], // '['
+    [0x00, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00], // '\'
+    [0x0e, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0e], // ']'
+    [0x04, 0x0a, 0x11, 0x00, 0x00, 0x00, 0x00], // '^'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1f], // '_'
+    [0x08, 0x04, 0x02, 0x00, 0x00, 0x00, 0x00], // '`'
+    [0x00, 0x00, 0x0e, 0x01, 0x0f, 0x11, 0x0f], // 'a'
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1e], // 'b'
+    [0x00, 0x00, 0x0e, 0x10, 0x10, 0x11, 0x0e], // 'c'
+    [0x01, 0x01, 0x0d, 0x13, 0x11, 0x11, 0x0f], // 'd'
+    [0x00, 0x00, 0x0e, 0x11, 0x1f, 0x10, 0x0e], // 'e'
+    [0x06, 0x09, 0x08, 0x1c, 0x08, 0x08, 0x08], // 'f'
+    [0x00, 0x0f, 0x11, 0x11, 0x0f, 0x01, 0x0e], // 'g'
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x11], // 'h'
+    [0x04, 0x00, 0x0c, 0x04, 0x04, 0x04, 0x0e], // 'i'
+    [0x02, 0x00, 0x06, 0x02, 0x02, 0x12, 0x0c], // 'j'
+    [0x10, 0x10, 0x12, 0x14, 0x18, 0x14, 0x12], // 'k'
+    [0x0c, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e], // 'l'
+    [0x00, 0x00, 0x1a, 0x15, 0x15, 0x11, 0x11], // 'm'
+    [0x00, 0x00, 0x16, 0x19, 0x11, 0x11, 0x11], // 'n'
+    [0x00, 0x00, 0x0e, 0x11, 0x11, 0x11, 0x0e], // 'o'
+    [0x00, 0x00, 0x1e, 0x11, 0x1e, 0x10, 0x10], // 'p'
+    [0x00, 0x00, 0x0d, 0x13, 0x0f, 0x01, 0x01], // 'q'
+    [0x00, 0x00, 0x16, 0x19, 0x10, 0x10, 0x10], // 'r'
+    [0x00, 0x00, 0x0e, 0x10, 0x0e, 0x01, 0x1e], // 's'
+    [0x08, 0x08, 0x1c, 0x08, 0x08, 0x09, 0x06], // 't'
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x13, 0x0d], // 'u'
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x0a, 0x04], // 'v'
+    [0x00, 0x00, 0x11, 0x11, 0x15, 0x15, 0x0a], // 'w'
+    [0x00, 0x00, 0x11, 0x0a, 0x04, 0x0a, 0x11], // 'x'
+    [0x00, 0x00, 0x11, 0x11, 0x0f, 0x01, 0x0e], // 'y'
+    [0x00, 0x00, 0x1f, 0x02, 0x04, 0x08, 0x1f], // 'z'
+    [0x02, 0x04, 0x04, 0x08, 0x04, 0x04, 0x02], // '{'
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // '|'
+    [0x08, 0x04, 0x04, 0x02, 0x04, 0x04, 0x08], // '}'
+    [0x00, 0x00, 0x08, 0x15, 0x02, 0x00, 0x00], // '~'
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crossterm::{style, QueueableCommand};
+    use rstest::rstest;
+    use std::io::Write;
+
+    fn bitmap_rows(character: char) -> Vec<String> {
+        Rasterizer::cell_bitmap(character)
+            .iter()
+            .map(|row| {
+                (0..CELL_COLUMNS)
+                    .map(|column| if row & (1 << (CELL_COLUMNS - 1 - column)) != 0 { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ascii_bitmap() {
+        let expected = &["......", ".###..", "#...#.", "#...#.", "#...#.", "#####.", "#...#.", "#...#.", "......"];
+        assert_eq!(bitmap_rows('A'), expected);
+    }
+
+    #[test]
+    fn box_drawing_bitmap() {
+        let expected = &["......", "......", "......", "......", "..####", "..#...", "..#...", "..#...", "..#..."];
+        assert_eq!(bitmap_rows('┌'), expected);
+    }
+
+    #[rstest]
+    #[case::em_dash('—')]
+    #[case::en_dash('–')]
+    #[case::horizontal_bar('―')]
+    fn dash_bitmap(#[case] character: char) {
+        assert_eq!(bitmap_rows(character), bitmap_rows('─'));
+    }
+
+    #[test]
+    fn hollow_bullet_bitmap() {
+        let expected = &["......", "......", "......", ".###..", ".#.#..", ".###..", "......", "......", "......"];
+        assert_eq!(bitmap_rows('◦'), expected);
+    }
+
+    #[test]
+    fn rasterize() {
+        let mut terminal = VirtualTerminal::new(2, 3);
+        terminal.queue(style::SetBackgroundColor(Color::new(0, 0, 255).into())).unwrap();
+        terminal.queue(style::Print("█")).unwrap();
+        terminal.flush().unwrap();
+
+        let rasterizer = Rasterizer::new(Color::new(255, 255, 255), Color::new(0, 0, 0));
+        let image = rasterizer.rasterize(&terminal);
+        let (cell_width, cell_height) = rasterizer.cell_size();
+        assert_eq!(image.dimensions(), (3 * cell_width, 2 * cell_height));
+        // The full block uses the default foreground color while the next cell has no contents.
+        assert_eq!(image.get_pixel(0, 0), &Rgba([255, 255, 255, 255]));
+        assert_eq!(image.get_pixel(cell_width, 0), &Rgba([0, 0, 0, 255]));
+    }
+}
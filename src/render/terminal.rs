@@ -1,12 +1,26 @@
-use super::properties::CursorPosition;
-use crate::style::Colors;
+use super::{media::PlacedImage, properties::CursorPosition};
+use crate::{
+    capabilities::TerminalCapabilities,
+    style::{Color, Colors},
+};
 use crossterm::{
     cursor,
+    event::{self, KeyboardEnhancementFlags},
     style::{self, StyledContent},
     terminal::{self},
     QueueableCommand,
 };
-use std::io;
+use std::{
+    borrow::Cow,
+    io, panic, process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Once,
+    },
+};
+
+static INSTALL_RESTORE_HOOKS: Once = Once::new();
+static KEYBOARD_ENHANCED: AtomicBool = AtomicBool::new(false);
 
 /// A wrapper over the terminal write handle.
 pub(crate) struct Terminal<W>
@@ -15,15 +29,39 @@ where
 {
     writer: W,
     pub cursor_row: u16,
+    headless: bool,
+    true_color: bool,
+    box_drawing: bool,
+    // Headless terminals can't draw images so they keep track of where they would have gone instead.
+    pub placed_images: Vec<PlacedImage>,
 }
 
 impl<W: io::Write> Terminal<W> {
     pub(crate) fn new(mut writer: W) -> io::Result<Self> {
-        terminal::enable_raw_mode()?;
-        writer.queue(cursor::Hide)?;
-        writer.queue(terminal::EnterAlternateScreen)?;
+        INSTALL_RESTORE_HOOKS.call_once(install_restore_hooks);
+        setup_terminal(&mut writer)?;
 
-        Ok(Self { writer, cursor_row: 0 })
+        let capabilities = TerminalCapabilities::current();
+        Ok(Self {
+            writer,
+            cursor_row: 0,
+            headless: false,
+            true_color: capabilities.true_color,
+            box_drawing: capabilities.box_drawing,
+            placed_images: Vec::new(),
+        })
+    }
+
+    /// Construct a terminal that doesn't touch the real one.
+    ///
+    /// This is meant to be used to render into something other than the terminal, like a
+    /// [VirtualTerminal](super::virtual_terminal::VirtualTerminal).
+    pub(crate) fn headless(writer: W) -> Self {
+        Self { writer, cursor_row: 0, headless: true, true_color: true, box_drawing: true, placed_images: Vec::new() }
+    }
+
+    pub(crate) fn is_headless(&self) -> bool {
+        self.headless
     }
 
     pub(crate) fn move_to(&mut self, column: u16, row: u16) -> io::Result<()> {
@@ -56,12 +94,18 @@ impl<W: io::Write> Terminal<W> {
     }
 
     pub(crate) fn print_line(&mut self, text: &str) -> io::Result<()> {
+        let text = self.translate_text(text);
         self.writer.queue(style::Print(text))?;
         Ok(())
     }
 
     pub(crate) fn print_styled_line(&mut self, content: StyledContent<String>) -> io::Result<()> {
-        self.writer.queue(style::PrintStyledContent(content))?;
+        let mut style = *content.style();
+        style.foreground_color = style.foreground_color.map(|color| self.translate_color(color));
+        style.background_color = style.background_color.map(|color| self.translate_color(color));
+        style.underline_color = style.underline_color.map(|color| self.translate_color(color));
+        let text = self.translate_text(content.content()).into_owned();
+        self.writer.queue(style::PrintStyledContent(StyledContent::new(style, text)))?;
         Ok(())
     }
 
@@ -72,17 +116,82 @@ impl<W: io::Write> Terminal<W> {
     }
 
     pub(crate) fn set_colors(&mut self, colors: Colors) -> io::Result<()> {
-        self.writer.queue(style::SetColors(colors.into()))?;
+        let colors: style::Colors = colors.into();
+        let colors = style::Colors {
+            foreground: colors.foreground.map(|color| self.translate_color(color)),
+            background: colors.background.map(|color| self.translate_color(color)),
+        };
+        self.writer.queue(style::SetColors(colors))?;
         Ok(())
     }
 
+    /// Start a frame, which the terminal displays all at once when [Terminal::end_update] is called.
+    ///
+    /// This does nothing if the terminal doesn't support synchronized output.
+    pub(crate) fn begin_update(&mut self) -> io::Result<()> {
+        if !self.headless && TerminalCapabilities::current().synchronized_output {
+            self.writer.queue(terminal::BeginSynchronizedUpdate)?;
+        }
+        Ok(())
+    }
+
+    /// End a frame started via [Terminal::begin_update].
+    pub(crate) fn end_update(&mut self) -> io::Result<()> {
+        if !self.headless && TerminalCapabilities::current().synchronized_output {
+            self.writer.queue(terminal::EndSynchronizedUpdate)?;
+        }
+        Ok(())
+    }
+
+    // Use the closest color in the 256 color palette if the terminal can't display 24 bit ones.
+    fn translate_color(&self, color: style::Color) -> style::Color {
+        match color {
+            style::Color::Rgb { r, g, b } if !self.true_color => {
+                style::Color::AnsiValue(Color::new(r, g, b).ansi_256())
+            }
+            color => color,
+        }
+    }
+
+    // Replace box drawing characters with ASCII ones if the terminal can't display them.
+    fn translate_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.box_drawing || text.is_ascii() {
+            return Cow::Borrowed(text);
+        }
+        Cow::Owned(text.chars().map(ascii_box_drawing).collect())
+    }
+
     pub(crate) fn flush(&mut self) -> io::Result<()> {
         self.writer.flush()?;
         Ok(())
     }
 
+    /// Suspend this process, giving the terminal back to the shell until we're resumed.
+    #[cfg(unix)]
+    pub(crate) fn suspend(&mut self) -> io::Result<()> {
+        teardown_terminal(&mut self.writer)?;
+
+        // SIGTSTP is handled by us so we stop using SIGSTOP, which can't be caught. This returns
+        // once we get a SIGCONT.
+        signal_hook::low_level::raise(signal_hook::consts::SIGSTOP)?;
+
+        setup_terminal(&mut self.writer)?;
+        Ok(())
+    }
+
+    /// Run a command that takes over the terminal, like a video player, until it exits.
+    pub(crate) fn run_external(&mut self, command: &mut process::Command) -> io::Result<process::ExitStatus> {
+        teardown_terminal(&mut self.writer)?;
+        let status = command.status();
+        setup_terminal(&mut self.writer)?;
+        status
+    }
+
     pub(crate) fn sync_cursor_row(&mut self) -> io::Result<()> {
-        self.cursor_row = CursorPosition::current()?.row;
+        // There's no real cursor to query when headless so the row we keep track of is all we have.
+        if !self.headless {
+            self.cursor_row = CursorPosition::current()?.row;
+        }
         Ok(())
     }
 }
@@ -92,8 +201,90 @@ where
     W: io::Write,
 {
     fn drop(&mut self) {
-        let _ = self.writer.queue(terminal::LeaveAlternateScreen);
-        let _ = self.writer.queue(cursor::Show);
-        let _ = terminal::disable_raw_mode();
+        if self.headless {
+            return;
+        }
+        let _ = teardown_terminal(&mut self.writer);
+    }
+}
+
+fn setup_terminal<W: io::Write>(writer: &mut W) -> io::Result<()> {
+    terminal::enable_raw_mode()?;
+    writer.queue(cursor::Hide)?;
+    writer.queue(terminal::EnterAlternateScreen)?;
+    // Pasted text should not be interpreted as key presses.
+    writer.queue(event::EnableBracketedPaste)?;
+    // Terminals that support the kitty keyboard protocol report key releases as separate events,
+    // which lets us tell them apart from presses rather than guessing.
+    if TerminalCapabilities::current().kitty_keyboard {
+        let flags = KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES | KeyboardEnhancementFlags::REPORT_EVENT_TYPES;
+        writer.queue(event::PushKeyboardEnhancementFlags(flags))?;
+        KEYBOARD_ENHANCED.store(true, Ordering::Relaxed);
+    }
+    writer.flush()
+}
+
+fn teardown_terminal<W: io::Write>(writer: &mut W) -> io::Result<()> {
+    if KEYBOARD_ENHANCED.swap(false, Ordering::Relaxed) {
+        writer.queue(event::PopKeyboardEnhancementFlags)?;
+    }
+    writer.queue(event::DisableBracketedPaste)?;
+    writer.queue(terminal::LeaveAlternateScreen)?;
+    writer.queue(cursor::Show)?;
+    writer.flush()?;
+    terminal::disable_raw_mode()
+}
+
+/// Restore the terminal to the state it was in before we started presenting.
+///
+/// This leaves the alternate screen, shows the cursor again, and disables raw mode. Doing any of
+/// these more than once is harmless so this can be called at any point.
+pub(crate) fn restore_terminal() {
+    let _ = teardown_terminal(&mut io::stdout());
+}
+
+fn ascii_box_drawing(c: char) -> char {
+    match c {
+        '─' | '━' | '═' | '—' => '-',
+        '│' | '┃' | '║' => '|',
+        '┼' | '┬' | '┴' | '├' | '┤' | '┌' | '┐' | '└' | '┘' | '╭' | '╮' | '╰' | '╯' => '+',
+        c => c,
     }
 }
+
+/// Install hooks that restore the terminal if we crash or get killed.
+///
+/// Destructors aren't run on panics when using `panic = "abort"` nor when a signal kills the
+/// process, which would otherwise leave the user's terminal in raw mode inside the alternate screen.
+fn install_restore_hooks() {
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        // Restore first so the panic message is printed on the user's screen.
+        restore_terminal();
+        previous_hook(info);
+    }));
+
+    #[cfg(unix)]
+    install_signal_handlers();
+}
+
+#[cfg(unix)]
+fn install_signal_handlers() {
+    use signal_hook::{
+        consts::{SIGHUP, SIGINT, SIGQUIT, SIGTERM},
+        iterator::Signals,
+    };
+    use std::{process, thread};
+
+    let Ok(mut signals) = Signals::new([SIGINT, SIGTERM, SIGHUP, SIGQUIT]) else {
+        return;
+    };
+    thread::spawn(move || {
+        if let Some(signal) = signals.forever().next() {
+            restore_terminal();
+            // Exiting skips destructors so anything that relies on them needs to be cleaned up here.
+            crate::input::socket::remove_bound_sockets();
+            process::exit(128 + signal);
+        }
+    });
+}
@@ -1,12 +1,13 @@
 use super::terminal::Terminal;
 use crate::{
-    markdown::text::WeightedLine,
+    markdown::{text::WeightedLine, width::DisplayWidth},
     render::{
         draw::{RenderError, RenderResult},
         layout::{Layout, Positioning},
         properties::WindowSize,
     },
     style::{Colors, TextStyle},
+    theme::LineSize,
 };
 use std::io;
 
@@ -19,6 +20,9 @@ pub(crate) struct TextDrawer<'a> {
     line: &'a WeightedLine,
     positioning: Positioning,
     default_colors: &'a Colors,
+    line_size: LineSize,
+    prefix: &'a str,
+    fill_rows: bool,
 }
 
 impl<'a> TextDrawer<'a> {
@@ -28,16 +32,54 @@ impl<'a> TextDrawer<'a> {
         dimensions: &WindowSize,
         default_colors: &'a Colors,
     ) -> Result<Self, RenderError> {
-        let text_length = line.width() as u16;
+        Self::sized(layout, line, line.width() as u16, dimensions, default_colors)
+    }
+
+    /// Construct a drawer for a line that's part of a block of the given length.
+    ///
+    /// Every row this line is wrapped into is padded with spaces up to the block's width.
+    pub(crate) fn new_block(
+        layout: &Layout,
+        line: &'a WeightedLine,
+        block_length: u16,
+        dimensions: &WindowSize,
+        default_colors: &'a Colors,
+    ) -> Result<Self, RenderError> {
+        let mut drawer = Self::sized(layout, line, block_length, dimensions, default_colors)?;
+        drawer.fill_rows = true;
+        Ok(drawer)
+    }
+
+    fn sized(
+        layout: &Layout,
+        line: &'a WeightedLine,
+        text_length: u16,
+        dimensions: &WindowSize,
+        default_colors: &'a Colors,
+    ) -> Result<Self, RenderError> {
         let positioning = layout.compute(dimensions, text_length);
         // If our line doesn't fit and it's just too small then abort
         if text_length > positioning.max_line_length && positioning.max_line_length <= MINIMUM_LINE_LENGTH {
             Err(RenderError::TerminalTooSmall)
         } else {
-            Ok(Self { line, positioning, default_colors })
+            Ok(Self { line, positioning, default_colors, line_size: LineSize::Normal, prefix: "", fill_rows: false })
         }
     }
 
+    /// Draw the given prefix at the beginning of every row.
+    pub(crate) fn with_prefix(mut self, prefix: &'a str) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    /// Draw text using the given line size.
+    ///
+    /// The positioning used to construct this drawer is expected to already be scaled down.
+    pub(crate) fn with_line_size(mut self, line_size: LineSize) -> Self {
+        self.line_size = line_size;
+        self
+    }
+
     /// Draw text on the given handle.
     ///
     /// This performs word splitting and word wrapping.
@@ -46,24 +88,76 @@ impl<'a> TextDrawer<'a> {
         W: io::Write,
     {
         let Positioning { max_line_length, start_column } = self.positioning;
+        let prefix_width = self.prefix.display_width();
+        let max_text_length = (max_line_length as usize).saturating_sub(prefix_width).max(1);
 
-        for (line_index, line) in self.line.split(max_line_length as usize).enumerate() {
-            terminal.move_to_column(start_column)?;
-            if line_index > 0 {
-                terminal.move_down(1)?;
-            }
-            for chunk in line {
-                let (text, style) = chunk.into_parts();
-                let text = style.apply(text);
-                terminal.print_styled_line(text)?;
+        let row_escape_codes = self.line_size.row_escape_codes();
+        let mut lines: Vec<_> = self.line.split(max_text_length).collect();
+        // Empty lines still take up a row in a block.
+        if lines.is_empty() && self.fill_rows {
+            lines.push(Vec::new());
+        }
+        for (line_index, line) in lines.into_iter().enumerate() {
+            // Double height lines are drawn twice: once for the top half and once for the bottom.
+            let start_column = match line_index {
+                0 => start_column,
+                _ => start_column + self.line.hanging_indent() as u16,
+            };
+            for (row_index, escape_code) in row_escape_codes.iter().enumerate() {
+                terminal.move_to_column(start_column)?;
+                if line_index > 0 || row_index > 0 {
+                    terminal.move_down(1)?;
+                }
+                if !escape_code.is_empty() {
+                    terminal.print_line(escape_code)?;
+                }
+                terminal.print_line(self.prefix)?;
+                let mut line_length = prefix_width;
+                for chunk in line.clone() {
+                    line_length += chunk.width();
+                    let (text, style) = chunk.into_parts();
+                    let text = style.apply(text);
+                    terminal.print_styled_line(text)?;
 
-                // Crossterm resets colors if any attributes are set so let's just re-apply colors
-                // if the format has anything on it at all.
-                if style != TextStyle::default() {
-                    terminal.set_colors(self.default_colors.clone())?;
+                    // Crossterm resets colors if any attributes are set so let's just re-apply
+                    // colors if the format has anything on it at all.
+                    if style != TextStyle::default() {
+                        terminal.set_colors(self.default_colors.clone())?;
+                    }
+                }
+                if self.fill_rows {
+                    let padding = (max_line_length as usize).saturating_sub(line_length);
+                    terminal.print_line(&" ".repeat(padding))?;
                 }
             }
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        render::virtual_terminal::VirtualTerminal,
+        theme::{Alignment, Margin},
+    };
+
+    #[test]
+    fn wrapped_block() {
+        let mut virtual_terminal = VirtualTerminal::new(3, 12);
+        let mut terminal = Terminal::headless(&mut virtual_terminal);
+        let layout = Layout::new(Alignment::Center { minimum_margin: Margin::Fixed(0), minimum_size: 0 });
+        let line = WeightedLine::from(String::from("hello there world"));
+        let dimensions = WindowSize { rows: 3, columns: 12, width: 0, height: 0 };
+        let colors = Colors::default();
+        let drawer = TextDrawer::new_block(&layout, &line, 19, &dimensions, &colors).unwrap().with_prefix("> ");
+        drawer.draw(&mut terminal).unwrap();
+        terminal.flush().unwrap();
+        drop(terminal);
+
+        // Every row starts with the prefix and is padded up to the available width.
+        let rows = virtual_terminal.text_rows();
+        assert_eq!(rows, &["> hello     ", "> there     ", "> world     "]);
+    }
+}
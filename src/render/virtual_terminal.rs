@@ -0,0 +1,287 @@
+use crate::style::Color;
+use std::{io, mem};
+use unicode_width::UnicodeWidthChar;
+
+/// A terminal that lives in memory.
+///
+/// This interprets the escape sequences used when rendering presentations and keeps track of
+/// what would be displayed in every cell of a real terminal, which allows rendering presentations
+/// without one. Anything it doesn't understand is ignored.
+#[derive(Clone, Debug)]
+pub struct VirtualTerminal {
+    rows: Vec<Vec<Cell>>,
+    columns: u16,
+    cursor_row: u16,
+    cursor_column: u16,
+    style: CellStyle,
+    pending: Vec<u8>,
+}
+
+/// A cell in a [VirtualTerminal].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cell {
+    /// The character in this cell.
+    ///
+    /// Cells that are covered by a wide character that starts in the cell before them contain `None`.
+    pub character: Option<char>,
+
+    /// The style of this cell.
+    pub style: CellStyle,
+}
+
+/// The style of a cell.
+///
+/// Colors set to `None` use the terminal's default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CellStyle {
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+    pub bold: bool,
+    pub underlined: bool,
+}
+
+impl VirtualTerminal {
+    /// Construct a new terminal of the given size.
+    pub fn new(rows: u16, columns: u16) -> Self {
+        let empty_row = vec![Cell { character: Some(' '), style: Default::default() }; columns as usize];
+        Self {
+            rows: vec![empty_row; rows as usize],
+            columns,
+            cursor_row: 0,
+            cursor_column: 0,
+            style: Default::default(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Get the rows in this terminal.
+    pub fn rows(&self) -> &[Vec<Cell>] {
+        &self.rows
+    }
+
+    /// Get the text in every row in this terminal.
+    pub fn text_rows(&self) -> Vec<String> {
+        self.rows.iter().map(|row| row.iter().filter_map(|cell| cell.character).collect()).collect()
+    }
+
+    fn process(&mut self, input: &str) {
+        let mut chars = input.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\x1b' => {
+                    if chars.next_if_eq(&'[').is_none() {
+                        // Not a control sequence, skip the byte after the escape.
+                        chars.next();
+                        continue;
+                    }
+                    let mut parameters = String::new();
+                    while let Some(c) = chars.next_if(|c| !('@'..='~').contains(c)) {
+                        parameters.push(c);
+                    }
+                    if let Some(command) = chars.next() {
+                        self.apply_control_sequence(&parameters, command);
+                    }
+                }
+                '\r' => self.cursor_column = 0,
+                '\n' => self.cursor_row = self.cursor_row.saturating_add(1),
+                c if c.is_control() => (),
+                c => self.print(c),
+            }
+        }
+    }
+
+    fn apply_control_sequence(&mut self, parameters: &str, command: char) {
+        // Private sequences, like the ones used to hide the cursor, don't affect what's displayed.
+        if parameters.starts_with(['?', '<', '>', '=']) {
+            return;
+        }
+        let values: Vec<u16> = parameters.split(';').map(|value| value.parse().unwrap_or(0)).collect();
+        let first = values.first().copied().unwrap_or(0);
+        let amount = first.max(1);
+        match command {
+            'H' | 'f' => {
+                self.cursor_row = first.saturating_sub(1);
+                self.cursor_column = values.get(1).copied().unwrap_or(1).saturating_sub(1);
+            }
+            'd' => self.cursor_row = first.saturating_sub(1),
+            'G' => self.cursor_column = first.saturating_sub(1),
+            'A' => self.cursor_row = self.cursor_row.saturating_sub(amount),
+            'B' => self.cursor_row = self.cursor_row.saturating_add(amount),
+            'C' => self.cursor_column = self.cursor_column.saturating_add(amount),
+            'D' => self.cursor_column = self.cursor_column.saturating_sub(amount),
+            'E' => {
+                self.cursor_row = self.cursor_row.saturating_add(amount);
+                self.cursor_column = 0;
+            }
+            'J' if first == 2 => self.clear(),
+            'm' => self.apply_graphics(&values),
+            _ => (),
+        };
+    }
+
+    fn apply_graphics(&mut self, values: &[u16]) {
+        let mut values = values.iter().copied();
+        while let Some(value) = values.next() {
+            match value {
+                0 => self.style = CellStyle::default(),
+                1 => self.style.bold = true,
+                4 => self.style.underlined = true,
+                22 => self.style.bold = false,
+                24 => self.style.underlined = false,
+                30..=37 => self.style.foreground = Some(Self::ansi_color(value - 30)),
+                90..=97 => self.style.foreground = Some(Self::ansi_color(value - 90 + 8)),
+                40..=47 => self.style.background = Some(Self::ansi_color(value - 40)),
+                100..=107 => self.style.background = Some(Self::ansi_color(value - 100 + 8)),
+                38 => self.style.foreground = Self::extended_color(&mut values),
+                48 => self.style.background = Self::extended_color(&mut values),
+                39 => self.style.foreground = None,
+                49 => self.style.background = None,
+                _ => (),
+            }
+        }
+    }
+
+    fn extended_color<I: Iterator<Item = u16>>(values: &mut I) -> Option<Color> {
+        match values.next()? {
+            2 => {
+                let mut component = || values.next().map(|value| value as u8);
+                Some(Color::new(component()?, component()?, component()?))
+            }
+            5 => Some(Self::ansi_color(values.next()?)),
+            _ => None,
+        }
+    }
+
+    fn ansi_color(index: u16) -> Color {
+        const BASIC: [(u8, u8, u8); 16] = [
+            (0, 0, 0),
+            (205, 0, 0),
+            (0, 205, 0),
+            (205, 205, 0),
+            (0, 0, 238),
+            (205, 0, 205),
+            (0, 205, 205),
+            (229, 229, 229),
+            (127, 127, 127),
+            (255, 0, 0),
+            (0, 255, 0),
+            (255, 255, 0),
+            (92, 92, 255),
+            (255, 0, 255),
+            (0, 255, 255),
+            (255, 255, 255),
+        ];
+        match index {
+            0..=15 => {
+                let (r, g, b) = BASIC[index as usize];
+                Color::new(r, g, b)
+            }
+            16..=231 => {
+                let index = index - 16;
+                let level = |value: u16| if value == 0 { 0 } else { (55 + value * 40) as u8 };
+                Color::new(level(index / 36), level(index / 6 % 6), level(index % 6))
+            }
+            _ => {
+                let level = (8 + (index.min(255) - 232) * 10) as u8;
+                Color::new(level, level, level)
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        let style = CellStyle { background: self.style.background, ..Default::default() };
+        for row in &mut self.rows {
+            row.fill(Cell { character: Some(' '), style });
+        }
+    }
+
+    fn print(&mut self, c: char) {
+        let width = c.width().unwrap_or(0) as u16;
+        if width == 0 {
+            return;
+        }
+        let (row, column) = (self.cursor_row as usize, self.cursor_column);
+        if let Some(cells) = self.rows.get_mut(row) {
+            if column + width <= self.columns {
+                cells[column as usize] = Cell { character: Some(c), style: self.style };
+                for offset in 1..width {
+                    cells[(column + offset) as usize] = Cell { character: None, style: self.style };
+                }
+            }
+        }
+        self.cursor_column = self.cursor_column.saturating_add(width);
+    }
+}
+
+impl io::Write for VirtualTerminal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Escape sequences and characters can be split across writes so only process them on flush.
+        self.pending.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let input = mem::take(&mut self.pending);
+        self.process(&String::from_utf8_lossy(&input));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crossterm::{
+        cursor,
+        style::{self, Stylize},
+        terminal, QueueableCommand,
+    };
+    use io::Write;
+
+    #[test]
+    fn print_and_move() {
+        let mut terminal = VirtualTerminal::new(3, 6);
+        terminal.queue(cursor::MoveTo(1, 1)).unwrap();
+        terminal.queue(style::Print("hi")).unwrap();
+        terminal.queue(cursor::MoveToNextLine(1)).unwrap();
+        terminal.queue(style::Print("界!")).unwrap();
+        terminal.queue(cursor::MoveToRow(0)).unwrap();
+        terminal.queue(cursor::MoveToColumn(4)).unwrap();
+        terminal.queue(style::Print("longer")).unwrap();
+        terminal.flush().unwrap();
+        assert_eq!(terminal.text_rows(), &["    lo", " hi   ", "界!   "]);
+    }
+
+    #[test]
+    fn styles() {
+        let mut terminal = VirtualTerminal::new(1, 4);
+        let background = Color::new(1, 2, 3);
+        let colors = style::Colors { foreground: None, background: Some(background.into()) };
+        terminal.queue(style::SetColors(colors)).unwrap();
+        terminal.queue(terminal::Clear(terminal::ClearType::All)).unwrap();
+        terminal.queue(style::PrintStyledContent("a".with(style::Color::Rgb { r: 4, g: 5, b: 6 }).bold())).unwrap();
+        terminal.queue(style::Print("b")).unwrap();
+        terminal.flush().unwrap();
+
+        let row = &terminal.rows()[0];
+        let expected = CellStyle {
+            foreground: Some(Color::new(4, 5, 6)),
+            background: Some(background),
+            bold: true,
+            underlined: false,
+        };
+        assert_eq!(row[0].style, expected);
+        // Resetting the attributes resets everything.
+        assert_eq!(row[1].style, CellStyle::default());
+        assert_eq!(row[3].style.background, Some(background));
+    }
+
+    #[test]
+    fn split_characters() {
+        let mut terminal = VirtualTerminal::new(1, 4);
+        let bytes = "é".as_bytes();
+        terminal.write_all(&bytes[..1]).unwrap();
+        terminal.write_all(&bytes[1..]).unwrap();
+        terminal.flush().unwrap();
+        assert_eq!(terminal.text_rows(), &["é   "]);
+    }
+}
@@ -0,0 +1,312 @@
+use crate::render::terminal::Terminal;
+use crossterm::event::{poll, read, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use serde::Deserialize;
+use std::{
+    fs, io,
+    ops::Range,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+const SEEK_STEP: Duration = Duration::from_secs(5);
+const MAX_POLL_TIMEOUT: Duration = Duration::from_millis(250);
+
+// Sequences that change the terminal's mode rather than drawing anything. Recordings contain the
+// ones used when the presentation started and finished but the player sets up the terminal itself.
+const MODE_SEQUENCES: &[&str] =
+    &["\x1b[?1049h", "\x1b[?1049l", "\x1b[?2004h", "\x1b[?2004l", "\x1b[?25h", "\x1b[?25l", "\x1b[<1u"];
+
+/// A presentation recorded in an asciicast v2 file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recording {
+    /// The output events in this recording, sorted by time.
+    pub events: Vec<RecordedOutput>,
+}
+
+impl Recording {
+    /// Load a recording from a file.
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        let contents = fs::read_to_string(path)?;
+        Self::parse(&contents)
+    }
+
+    /// Parse the contents of an asciicast v2 file.
+    pub fn parse(contents: &str) -> Result<Self, ReplayError> {
+        #[derive(Deserialize)]
+        struct Header {
+            version: u32,
+        }
+
+        let mut lines = contents.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+        let (_, header) = lines.next().ok_or(ReplayError::MissingHeader)?;
+        let header: Header = serde_json::from_str(header).map_err(|e| ReplayError::InvalidLine(1, e))?;
+        if header.version != 2 {
+            return Err(ReplayError::UnsupportedVersion(header.version));
+        }
+        let mut events = Vec::new();
+        for (index, line) in lines {
+            let (time, kind, data): (f64, String, String) =
+                serde_json::from_str(line).map_err(|e| ReplayError::InvalidLine(index + 1, e))?;
+            // Only output is replayed, any other event like input or resizes is ignored.
+            if kind == "o" {
+                let time = Duration::try_from_secs_f64(time).unwrap_or_default();
+                events.push(RecordedOutput { time, data: strip_mode_sequences(&data) });
+            }
+        }
+        events.sort_by_key(|event| event.time);
+        Ok(Self { events })
+    }
+
+    /// The time at which the last event in this recording happens.
+    pub fn duration(&self) -> Duration {
+        self.events.last().map(|event| event.time).unwrap_or_default()
+    }
+}
+
+/// A piece of output in a recording.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedOutput {
+    /// The time since the beginning of the recording at which this was written.
+    pub time: Duration,
+
+    /// The data that was written.
+    pub data: String,
+}
+
+fn strip_mode_sequences(data: &str) -> String {
+    let mut output = data.to_string();
+    for sequence in MODE_SEQUENCES {
+        output = output.replace(sequence, "");
+    }
+    // Keyboard enhancement flags are pushed as `ESC [ > <flags> u`.
+    while let Some(start) = output.find("\x1b[>") {
+        let Some(length) = output[start + 3..].find(|c: char| !c.is_ascii_digit()) else {
+            break;
+        };
+        let end = start + 3 + length;
+        if !output[end..].starts_with('u') {
+            break;
+        }
+        output.replace_range(start..=end, "");
+    }
+    output
+}
+
+/// Keeps track of the position within a recording.
+#[derive(Debug, Default)]
+struct Playback {
+    position: Duration,
+    next_event: usize,
+}
+
+impl Playback {
+    /// Move to the given position, returning the events that need to be written to get there.
+    ///
+    /// If the position is before the current one, playback starts over from the beginning, which
+    /// requires clearing the screen first.
+    fn seek(&mut self, recording: &Recording, position: Duration) -> Seek {
+        let rewind = position < self.position;
+        if rewind {
+            self.next_event = 0;
+        }
+        let start = self.next_event;
+        let end = start + recording.events[start..].iter().take_while(|event| event.time <= position).count();
+        self.next_event = end;
+        self.position = position;
+        Seek { rewind, events: start..end }
+    }
+
+    /// The time until the next event in the recording, if any.
+    fn time_until_next_event(&self, recording: &Recording) -> Option<Duration> {
+        recording.events.get(self.next_event).map(|event| event.time.saturating_sub(self.position))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Seek {
+    rewind: bool,
+    events: Range<usize>,
+}
+
+/// A command while replaying.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ReplayCommand {
+    TogglePause,
+    SeekForward,
+    SeekBackward,
+    Restart,
+    Exit,
+}
+
+impl ReplayCommand {
+    fn from_key_event(event: KeyEvent) -> Option<Self> {
+        if event.kind == KeyEventKind::Release {
+            return None;
+        }
+        let command = match event.code {
+            KeyCode::Char(' ') | KeyCode::Char('p') => Self::TogglePause,
+            KeyCode::Right | KeyCode::Char('l') => Self::SeekForward,
+            KeyCode::Left | KeyCode::Char('h') => Self::SeekBackward,
+            KeyCode::Char('g') => Self::Restart,
+            KeyCode::Char('q') | KeyCode::Esc => Self::Exit,
+            KeyCode::Char('c') if event.modifiers == KeyModifiers::CONTROL => Self::Exit,
+            _ => return None,
+        };
+        Some(command)
+    }
+}
+
+/// Replays a recorded presentation in the terminal, respecting the time at which everything was
+/// originally drawn.
+///
+/// Replaying can be paused with `space`, moved back and forth using the arrow keys, and stopped
+/// using `q`.
+pub struct Player {
+    recording: Recording,
+    playback: Playback,
+    // The position playback was at the last time it was resumed or moved, and when that happened.
+    base_position: Duration,
+    resumed_at: Option<Instant>,
+}
+
+impl Player {
+    /// Construct a new player for the given recording.
+    pub fn new(recording: Recording) -> Self {
+        Self { recording, playback: Playback::default(), base_position: Duration::ZERO, resumed_at: None }
+    }
+
+    /// Replay the recording until the user exits.
+    pub fn play(mut self) -> Result<(), ReplayError> {
+        let mut terminal = Terminal::new(io::stdout())?;
+        terminal.clear_screen()?;
+        terminal.flush()?;
+        self.resumed_at = Some(Instant::now());
+        loop {
+            let position = self.current_position();
+            self.seek(&mut terminal, position)?;
+
+            let timeout = match (self.resumed_at, self.playback.time_until_next_event(&self.recording)) {
+                (Some(_), Some(time)) => time.min(MAX_POLL_TIMEOUT),
+                _ => MAX_POLL_TIMEOUT,
+            };
+            if !poll(timeout)? {
+                continue;
+            }
+            let Event::Key(event) = read()? else {
+                continue;
+            };
+            match ReplayCommand::from_key_event(event) {
+                Some(ReplayCommand::TogglePause) => {
+                    self.base_position = self.current_position();
+                    self.resumed_at = match self.resumed_at {
+                        Some(_) => None,
+                        None => Some(Instant::now()),
+                    };
+                }
+                Some(ReplayCommand::SeekForward) => self.move_to(self.current_position() + SEEK_STEP),
+                Some(ReplayCommand::SeekBackward) => self.move_to(self.current_position().saturating_sub(SEEK_STEP)),
+                Some(ReplayCommand::Restart) => self.move_to(Duration::ZERO),
+                Some(ReplayCommand::Exit) => return Ok(()),
+                None => (),
+            };
+        }
+    }
+
+    fn current_position(&self) -> Duration {
+        let elapsed = self.resumed_at.map(|instant| instant.elapsed()).unwrap_or_default();
+        (self.base_position + elapsed).min(self.recording.duration())
+    }
+
+    fn move_to(&mut self, position: Duration) {
+        self.base_position = position.min(self.recording.duration());
+        if self.resumed_at.is_some() {
+            self.resumed_at = Some(Instant::now());
+        }
+    }
+
+    fn seek<W: io::Write>(&mut self, terminal: &mut Terminal<W>, position: Duration) -> io::Result<()> {
+        let seek = self.playback.seek(&self.recording, position);
+        if seek.events.is_empty() && !seek.rewind {
+            return Ok(());
+        }
+        if seek.rewind {
+            terminal.clear_screen()?;
+        }
+        for event in &self.recording.events[seek.events] {
+            terminal.print_line(&event.data)?;
+        }
+        terminal.flush()
+    }
+}
+
+/// An error replaying a recording.
+#[derive(thiserror::Error, Debug)]
+pub enum ReplayError {
+    #[error("io: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("recording has no header")]
+    MissingHeader,
+
+    #[error("unsupported asciicast version {0}, only version 2 is supported")]
+    UnsupportedVersion(u32),
+
+    #[error("invalid recording at line {0}: {1}")]
+    InvalidLine(usize, serde_json::Error),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    fn event(millis: u64, data: &str) -> RecordedOutput {
+        RecordedOutput { time: Duration::from_millis(millis), data: data.into() }
+    }
+
+    #[test]
+    fn parse() {
+        let contents = r#"{"version": 2, "width": 80, "height": 24}
+[0.5, "o", "\u001b[?1049hhello"]
+[1.0, "i", "q"]
+
+[1.5, "o", "bye"]
+"#;
+        let recording = Recording::parse(contents).expect("parse failed");
+        assert_eq!(recording.events, &[event(500, "hello"), event(1500, "bye")]);
+        assert_eq!(recording.duration(), Duration::from_millis(1500));
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::version(r#"{"version": 1}"#)]
+    #[case::invalid_event("{\"version\": 2}\n[1.0, \"o\"]")]
+    fn invalid_recording(#[case] contents: &str) {
+        assert!(Recording::parse(contents).is_err());
+    }
+
+    #[rstest]
+    #[case::alternate_screen("\x1b[?1049h\x1b[?25lhi", "hi")]
+    #[case::keyboard_flags("\x1b[>3uhi\x1b[<1u", "hi")]
+    #[case::other_sequences("\x1b[1mhi\x1b[2J", "\x1b[1mhi\x1b[2J")]
+    fn mode_sequences(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(strip_mode_sequences(input), expected);
+    }
+
+    #[test]
+    fn seek() {
+        let recording = Recording { events: vec![event(0, "a"), event(1000, "b"), event(2000, "c")] };
+        let mut playback = Playback::default();
+
+        let seek = playback.seek(&recording, Duration::from_millis(500));
+        assert_eq!(seek, Seek { rewind: false, events: 0..1 });
+        assert_eq!(playback.time_until_next_event(&recording), Some(Duration::from_millis(500)));
+
+        let seek = playback.seek(&recording, Duration::from_millis(2500));
+        assert_eq!(seek, Seek { rewind: false, events: 1..3 });
+        assert_eq!(playback.time_until_next_event(&recording), None);
+
+        let seek = playback.seek(&recording, Duration::from_millis(1000));
+        assert_eq!(seek, Seek { rewind: true, events: 0..2 });
+    }
+}
@@ -1,21 +1,46 @@
 use crate::{
+    input::source::Command,
     render::media::{Image, InvalidImage},
     theme::{LoadThemeError, PresentationTheme},
 };
+use base64::{engine::general_purpose::STANDARD, Engine};
 use std::{
+    cell::RefCell,
     collections::HashMap,
     fs, io,
     path::{Path, PathBuf},
+    process::{self, Stdio},
+    rc::Rc,
+    sync::mpsc::Sender,
 };
 
+// Images larger than this many bytes are decoded in the background.
+const ASYNC_IMAGE_THRESHOLD: usize = 256 * 1024;
+
+const DATA_URI_PREFIX: &str = "data:";
+
+const REMOTE_URI_PREFIXES: &[&str] = &["http://", "https://"];
+
 /// Manages resources pulled from the filesystem such as images.
 ///
 /// All resources are cached so once a specific resource is loaded, looking it up with the same
 /// path will involve an in-memory lookup.
+///
+/// Relative paths are looked up in the base path first and then in every search path, in order.
+///
+/// Clones share the same state, including their caches, so a presentation that's being built in steps can hold on to
+/// its own copy.
+#[derive(Clone)]
 pub struct Resources {
+    state: Rc<RefCell<ResourcesState>>,
+}
+
+struct ResourcesState {
     base_path: PathBuf,
+    search_paths: Vec<PathBuf>,
     images: HashMap<PathBuf, Image>,
     themes: HashMap<PathBuf, PresentationTheme>,
+    image_loaded_sender: Option<Sender<Command>>,
 }
 
 impl Resources {
@@ -23,35 +48,174 @@ impl Resources {
     ///
     /// Any relative paths will be assumed to be relative to the given base.
     pub fn new<P: Into<PathBuf>>(base_path: P) -> Self {
-        Self { base_path: base_path.into(), images: Default::default(), themes: Default::default() }
+        let state = ResourcesState {
+            base_path: base_path.into(),
+            search_paths: Vec::new(),
+            images: Default::default(),
+            themes: Default::default(),
+            image_loaded_sender: None,
+        };
+        Self { state: Rc::new(RefCell::new(state)) }
+    }
+
+    /// Set the base path that relative paths are relative to.
+    pub fn set_base_path<P: Into<PathBuf>>(&mut self, path: P) {
+        self.state.borrow_mut().base_path = path.into();
+    }
+
+    /// Decode large images in the background.
+    ///
+    /// Every time an image finishes loading, a [Command::Redraw] will be sent via the given sender
+    /// so the slide can be drawn again.
+    pub fn with_async_images(self, sender: Sender<Command>) -> Self {
+        self.state.borrow_mut().image_loaded_sender = Some(sender);
+        self
+    }
+
+    /// Set the additional directories to look for resources in.
+    ///
+    /// Relative search paths are assumed to be relative to the base path.
+    pub fn set_search_paths(&mut self, paths: Vec<PathBuf>) {
+        self.state.borrow_mut().search_paths = paths;
+    }
+
+    /// Resolve a path into the first location where it exists.
+    ///
+    /// If the path can't be found anywhere, it's assumed to be relative to the base path.
+    pub fn resolve_path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        let path = path.as_ref();
+        let state = self.state.borrow();
+        let default = state.base_path.join(path);
+        if path.is_absolute() || default.exists() {
+            return default;
+        }
+        state
+            .search_paths
+            .iter()
+            .map(|search_path| state.base_path.join(search_path).join(path))
+            .find(|candidate| candidate.exists())
+            .unwrap_or(default)
     }
 
     /// Get the image at the given path.
+    ///
+    /// Besides paths, this accepts `data:` URIs containing base64 encoded images and pages within PDF files, in the
+    /// form `<path>.pdf#page=<number>`. Remote images, referenced via `http://` or `https://` URLs, aren't supported.
     pub fn image<P: AsRef<Path>>(&mut self, path: P) -> Result<Image, LoadImageError> {
-        let path = self.base_path.join(path);
-        if let Some(image) = self.images.get(&path) {
+        let path = path.as_ref();
+        if is_remote_uri(path) {
+            return Err(LoadImageError::RemoteImage(path.display().to_string()));
+        }
+        let path = if is_data_uri(path) {
+            path.to_path_buf()
+        } else if let Some(page) = PdfPage::parse(path) {
+            PdfPage { path: self.resolve_path(&page.path), ..page }.to_path()
+        } else {
+            self.resolve_path(path)
+        };
+        if let Some(image) = self.state.borrow().images.get(&path) {
             return Ok(image.clone());
         }
 
-        let contents = fs::read(&path).map_err(|e| LoadImageError::Io(path.clone(), e))?;
-        let image = Image::new(&contents)?;
-        self.images.insert(path, image.clone());
+        let contents = match (path.to_str(), PdfPage::parse(&path)) {
+            (Some(uri), _) if is_data_uri(&path) => decode_data_uri(uri)?,
+            (_, Some(page)) => page.rasterize()?,
+            _ => fs::read(&path).map_err(|e| LoadImageError::Io(path.clone(), e))?,
+        };
+        let image_loaded_sender = self.state.borrow().image_loaded_sender.clone();
+        let image = match image_loaded_sender {
+            Some(sender) if contents.len() > ASYNC_IMAGE_THRESHOLD => Image::new_async(contents, move || {
+                let _ = sender.send(Command::Redraw);
+            })?,
+            _ => Image::new(&contents)?,
+        };
+        self.state.borrow_mut().images.insert(path, image.clone());
         Ok(image)
     }
 
     /// Get the theme at the given path.
     pub fn theme<P: AsRef<Path>>(&mut self, path: P) -> Result<PresentationTheme, LoadThemeError> {
-        let path = self.base_path.join(path);
-        if let Some(theme) = self.themes.get(&path) {
+        let path = self.resolve_path(path);
+        if let Some(theme) = self.state.borrow().themes.get(&path) {
             return Ok(theme.clone());
         }
 
         let theme = PresentationTheme::from_path(&path)?;
-        self.themes.insert(path, theme.clone());
+        self.state.borrow_mut().themes.insert(path, theme.clone());
         Ok(theme)
     }
 }
 
+/// Check whether a path is actually a `data:` URI that contains the resource itself.
+pub fn is_data_uri(path: &Path) -> bool {
+    path.to_str().is_some_and(|path| path.starts_with(DATA_URI_PREFIX))
+}
+
+fn is_remote_uri(path: &Path) -> bool {
+    path.to_str().is_some_and(|path| REMOTE_URI_PREFIXES.iter().any(|prefix| path.starts_with(prefix)))
+}
+
+// Decodes URIs in the form `data:image/<format>;base64,<data>`.
+fn decode_data_uri(uri: &str) -> Result<Vec<u8>, LoadImageError> {
+    let uri = uri.strip_prefix(DATA_URI_PREFIX).unwrap_or(uri);
+    let (media_type, data) = uri.split_once(',').ok_or(LoadImageError::InvalidDataUri("no data"))?;
+    let media_type = media_type.strip_suffix(";base64").ok_or(LoadImageError::InvalidDataUri("not base64 encoded"))?;
+    if !media_type.starts_with("image/") {
+        return Err(LoadImageError::InvalidDataUri("not an image"));
+    }
+    let data: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+    STANDARD.decode(data).map_err(|_| LoadImageError::InvalidDataUri("invalid base64 data"))
+}
+
+/// A page within a PDF file, referenced as `<path>.pdf#page=<number>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PdfPage {
+    /// The path to the PDF file.
+    pub path: PathBuf,
+
+    /// The page number, starting at 1.
+    pub page: u32,
+}
+
+impl PdfPage {
+    // The resolution pages are rasterized at.
+    const DPI: u32 = 150;
+
+    /// Parse a path that references a page within a PDF file.
+    pub fn parse(path: &Path) -> Option<Self> {
+        let (path, page) = path.to_str()?.rsplit_once("#page=")?;
+        let path = PathBuf::from(path);
+        if !path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("pdf")) {
+            return None;
+        }
+        let page = page.parse().ok().filter(|page| *page > 0)?;
+        Some(Self { path, page })
+    }
+
+    fn to_path(&self) -> PathBuf {
+        format!("{}#page={}", self.path.display(), self.page).into()
+    }
+
+    // This uses poppler's `pdftoppm` which writes the page as a PNG to stdout.
+    fn rasterize(&self) -> Result<Vec<u8>, LoadImageError> {
+        let page = self.page.to_string();
+        let output = process::Command::new("pdftoppm")
+            .args(["-png", "-singlefile", "-r", &Self::DPI.to_string(), "-f", &page, "-l", &page])
+            .arg(&self.path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .map_err(|e| LoadImageError::Pdf(self.path.clone(), format!("running pdftoppm: {e}")))?;
+        if output.status.success() {
+            return Ok(output.stdout);
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let error = stderr.lines().last().map(String::from).unwrap_or_else(|| output.status.to_string());
+        Err(LoadImageError::Pdf(self.path.clone(), error))
+    }
+}
+
 /// An error loading an image.
 #[derive(thiserror::Error, Debug)]
 pub enum LoadImageError {
@@ -60,4 +224,100 @@ pub enum LoadImageError {
 
     #[error("processing image: {0}")]
     InvalidImage(#[from] InvalidImage),
+
+    #[error("invalid data URI: {0}")]
+    InvalidDataUri(&'static str),
+
+    #[error("rendering PDF page from {0}: {1}")]
+    Pdf(PathBuf, String),
+
+    #[error("remote images are not supported: {0}")]
+    RemoteImage(String),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn search_paths() {
+        let root = tempdir().expect("no temp dir");
+        for directory in ["deck", "deck/assets", "shared"] {
+            fs::create_dir(root.path().join(directory)).expect("creating dir failed");
+        }
+        fs::write(root.path().join("deck/local.png"), "").unwrap();
+        fs::write(root.path().join("deck/assets/local.png"), "").unwrap();
+        fs::write(root.path().join("deck/assets/asset.png"), "").unwrap();
+        fs::write(root.path().join("shared/asset.png"), "").unwrap();
+        fs::write(root.path().join("shared/shared.png"), "").unwrap();
+
+        let base = root.path().join("deck");
+        let mut resources = Resources::new(&base);
+        resources.set_search_paths(vec!["assets".into(), "../shared".into()]);
+        assert_eq!(resources.resolve_path("local.png"), base.join("local.png"));
+        assert_eq!(resources.resolve_path("asset.png"), base.join("assets/asset.png"));
+        assert_eq!(resources.resolve_path("shared.png"), base.join("../shared/shared.png"));
+        assert_eq!(resources.resolve_path("missing.png"), base.join("missing.png"));
+    }
+
+    #[test]
+    fn change_base_path() {
+        let mut resources = Resources::new("/first");
+        resources.set_base_path("/second");
+        assert_eq!(resources.resolve_path("image.png"), PathBuf::from("/second/image.png"));
+    }
+
+    #[test]
+    fn clones_share_state() {
+        let mut resources = Resources::new("/first");
+        let clone = resources.clone();
+        resources.set_base_path("/second");
+        assert_eq!(clone.resolve_path("image.png"), PathBuf::from("/second/image.png"));
+    }
+
+    #[test]
+    fn data_uri_image() {
+        let mut png = Vec::new();
+        image::DynamicImage::new_rgb8(2, 3)
+            .write_to(&mut io::Cursor::new(&mut png), image::ImageOutputFormat::Png)
+            .expect("encoding image");
+        let uri = format!("data:image/png;base64,{}", STANDARD.encode(&png));
+
+        let mut resources = Resources::new("/tmp");
+        let image = resources.image(&uri).expect("loading image failed");
+        assert_eq!(image, Image::new(&png).unwrap());
+    }
+
+    #[rstest]
+    #[case::no_data("data:image/png;base64")]
+    #[case::not_base64("data:image/png,hello")]
+    #[case::not_image("data:text/plain;base64,aGVsbG8=")]
+    #[case::invalid_base64("data:image/png;base64,!!!")]
+    fn invalid_data_uri(#[case] uri: &str) {
+        let mut resources = Resources::new("/tmp");
+        let result = resources.image(uri);
+        assert!(matches!(result, Err(LoadImageError::InvalidDataUri(_))), "{uri}");
+    }
+
+    #[rstest]
+    #[case::http("http://example.com/image.png")]
+    #[case::https("https://example.com/image.png")]
+    fn remote_image(#[case] url: &str) {
+        let mut resources = Resources::new("/tmp");
+        let result = resources.image(url);
+        assert!(matches!(result, Err(LoadImageError::RemoteImage(_))), "{url}");
+    }
+
+    #[rstest]
+    #[case::page("paper.pdf#page=3", Some(PdfPage { path: "paper.pdf".into(), page: 3 }))]
+    #[case::nested("docs/Paper.PDF#page=12", Some(PdfPage { path: "docs/Paper.PDF".into(), page: 12 }))]
+    #[case::no_page("paper.pdf", None)]
+    #[case::zero_page("paper.pdf#page=0", None)]
+    #[case::not_pdf("image.png#page=1", None)]
+    fn pdf_page(#[case] path: &str, #[case] expected: Option<PdfPage>) {
+        assert_eq!(PdfPage::parse(Path::new(path)), expected);
+    }
 }
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// A file that keeps track of where a presentation was left at so it can be resumed later.
+///
+/// This lives right next to the presentation, as a hidden file named after it.
+#[derive(Clone, Debug)]
+pub struct ResumeFile {
+    path: PathBuf,
+}
+
+impl ResumeFile {
+    /// Construct the resume file for the presentation in the given path.
+    pub fn for_presentation(presentation_path: &Path) -> Self {
+        let file_name = presentation_path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        let path = presentation_path.with_file_name(format!(".{file_name}.presenterm-state"));
+        Self { path }
+    }
+
+    /// The path to this file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load the state in this file.
+    ///
+    /// Returns `None` if the file doesn't exist yet.
+    pub fn load(&self) -> Result<Option<ResumeState>, ResumeError> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let state = serde_yaml::from_str(&contents)?;
+        Ok(Some(state))
+    }
+
+    /// Save the given state into this file.
+    pub fn save(&self, state: &ResumeState) -> Result<(), ResumeError> {
+        let contents = serde_yaml::to_string(state)?;
+        fs::write(&self.path, contents)?;
+        Ok(())
+    }
+}
+
+/// The state of a presentation that allows resuming it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResumeState {
+    /// The index of the slide the presentation was at.
+    pub slide: usize,
+}
+
+/// An error loading or saving a resume file.
+#[derive(thiserror::Error, Debug)]
+pub enum ResumeError {
+    #[error("io: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("invalid state: {0}")]
+    Serde(#[from] serde_yaml::Error),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn path() {
+        let file = ResumeFile::for_presentation(Path::new("/tmp/talk/presentation.md"));
+        assert_eq!(file.path(), Path::new("/tmp/talk/.presentation.md.presenterm-state"));
+    }
+
+    #[test]
+    fn save_and_load() {
+        let directory = tempfile::tempdir().expect("creating temp dir");
+        let file = ResumeFile::for_presentation(&directory.path().join("presentation.md"));
+        assert_eq!(file.load().expect("load failed"), None);
+
+        let state = ResumeState { slide: 42 };
+        file.save(&state).expect("save failed");
+        assert_eq!(file.load().expect("load failed"), Some(state));
+    }
+}
@@ -0,0 +1,224 @@
+use crate::{config::NewPresentationConfig, theme::PresentationTheme};
+use serde_yaml::{Mapping, Value};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+const DEFAULT_THEME: &str = "dark";
+
+const TEMPLATE_BODY: &str = r#"Getting started
+---
+
+Every slide is separated from the next one by an `end_slide` comment command.
+
+<!-- pause -->
+
+Use `pause` comment commands to reveal a slide's content one step at a time.
+
+<!-- end_slide -->
+
+Code
+---
+
+```rust
+fn main() {
+    println!("hello world");
+}
+```
+
+<!-- end_slide -->
+
+Thank you!
+---
+
+Questions?
+"#;
+
+/// Creates new presentations out of a template so users don't need to start from scratch.
+pub struct PresentationScaffold<'a> {
+    config: &'a NewPresentationConfig,
+    theme_stub: bool,
+}
+
+impl<'a> PresentationScaffold<'a> {
+    /// Construct a new scaffold that uses the given defaults.
+    pub fn new(config: &'a NewPresentationConfig) -> Self {
+        Self { config, theme_stub: false }
+    }
+
+    /// Whether to also create a custom theme for the presentation, based on the configured one.
+    pub fn with_theme_stub(mut self, value: bool) -> Self {
+        self.theme_stub = value;
+        self
+    }
+
+    /// Create a presentation in the given path.
+    ///
+    /// Existing files are never overwritten. Returns the paths of every file created.
+    pub fn create(&self, path: &Path) -> Result<Vec<PathBuf>, ScaffoldError> {
+        let theme_name = self.config.theme.as_deref().unwrap_or(DEFAULT_THEME);
+        let raw_theme =
+            PresentationTheme::raw_theme(theme_name).ok_or_else(|| ScaffoldError::UnknownTheme(theme_name.into()))?;
+        let stem = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        let theme_path = path.with_file_name(format!("{stem}-theme.yaml"));
+        let mut files = vec![path.to_path_buf()];
+        if self.theme_stub {
+            files.push(theme_path.clone());
+        }
+        if let Some(existing) = files.iter().find(|path| path.exists()) {
+            return Err(ScaffoldError::AlreadyExists(existing.clone()));
+        }
+
+        let theme = match self.theme_stub {
+            true => ThemeReference::Path(theme_path.file_name().unwrap_or_default().to_string_lossy().into()),
+            false => ThemeReference::Name(theme_name.into()),
+        };
+        let contents = self.presentation(&title_from_file_stem(&stem), theme)?;
+        fs::write(path, contents)?;
+        if self.theme_stub {
+            let header = format!("# A custom theme based on the '{theme_name}' theme. Tweak it as you see fit.\n\n");
+            fs::write(&theme_path, [header.as_bytes(), raw_theme].concat())?;
+        }
+        Ok(files)
+    }
+
+    fn presentation(&self, title: &str, theme: ThemeReference) -> Result<String, ScaffoldError> {
+        let mut front_matter = Mapping::new();
+        front_matter.insert("title".into(), title.into());
+        if let Some(author) = &self.config.author {
+            front_matter.insert("author".into(), author.as_str().into());
+        }
+        let mut theme_metadata = Mapping::new();
+        match theme {
+            ThemeReference::Name(name) => theme_metadata.insert("name".into(), name.into()),
+            ThemeReference::Path(path) => theme_metadata.insert("path".into(), path.into()),
+        };
+        if let Some(footer) = &self.config.footer {
+            let footer = without_nulls(serde_yaml::to_value(footer)?);
+            theme_metadata.insert("override".into(), Value::Mapping(Mapping::from_iter([("footer".into(), footer)])));
+        }
+        front_matter.insert("theme".into(), theme_metadata.into());
+        let front_matter = serde_yaml::to_string(&front_matter)?;
+        Ok(format!("---\n{front_matter}---\n\n{TEMPLATE_BODY}"))
+    }
+}
+
+enum ThemeReference {
+    Name(String),
+    Path(String),
+}
+
+// Turns `my-talk` into `My talk`.
+fn title_from_file_stem(stem: &str) -> String {
+    let title = stem.replace(['-', '_'], " ");
+    let mut chars = title.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => "My presentation".into(),
+    }
+}
+
+// Serializing styles produces a `null` for every unset property, which is just noise.
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Mapping(mapping) => {
+            let mapping = mapping.into_iter().filter(|(_, value)| !value.is_null());
+            Value::Mapping(mapping.map(|(key, value)| (key, without_nulls(value))).collect())
+        }
+        value => value,
+    }
+}
+
+/// An error creating a presentation.
+#[derive(thiserror::Error, Debug)]
+pub enum ScaffoldError {
+    #[error("io: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("{0} already exists")]
+    AlreadyExists(PathBuf),
+
+    #[error("unknown theme '{0}'")]
+    UnknownTheme(String),
+
+    #[error("serializing front matter: {0}")]
+    Serialize(#[from] serde_yaml::Error),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{compat::parse_front_matter, presentation::PresentationAuthor, theme::FooterStyle};
+    use rstest::rstest;
+    use tempfile::tempdir;
+
+    fn front_matter(contents: &str) -> &str {
+        let contents = contents.strip_prefix("---\n").expect("no front matter");
+        &contents[..contents.find("---\n").expect("front matter not terminated")]
+    }
+
+    #[test]
+    fn create() {
+        let directory = tempdir().expect("creating temp dir");
+        let path = directory.path().join("my-talk.md");
+        let config = NewPresentationConfig {
+            author: Some("Jane".into()),
+            theme: Some("dark".into()),
+            footer: Some(FooterStyle::Template {
+                left: None,
+                center: Some("{title}".into()),
+                right: None,
+                colors: Default::default(),
+            }),
+        };
+        let files = PresentationScaffold::new(&config).create(&path).expect("creating presentation");
+        assert_eq!(files, vec![path.clone()]);
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let front_matter = front_matter(&contents);
+        assert!(!front_matter.contains("null"));
+        let (metadata, _) = parse_front_matter(front_matter).expect("invalid front matter");
+        assert_eq!(metadata.title.as_deref(), Some("My talk"));
+        assert_eq!(metadata.author.as_ref().map(PresentationAuthor::name), Some("Jane"));
+        assert_eq!(metadata.theme.name.as_deref(), Some("dark"));
+        let footer = metadata.theme.overrides.expect("no overrides").footer;
+        assert!(matches!(footer, FooterStyle::Template { center: Some(center), .. } if center == "{title}"));
+
+        // Existing presentations are left alone.
+        let result = PresentationScaffold::new(&config).create(&path);
+        assert!(matches!(result, Err(ScaffoldError::AlreadyExists(_))));
+    }
+
+    #[test]
+    fn theme_stub() {
+        let directory = tempdir().expect("creating temp dir");
+        let path = directory.path().join("talk.md");
+        let config = NewPresentationConfig::default();
+        let scaffold = PresentationScaffold::new(&config).with_theme_stub(true);
+        let files = scaffold.create(&path).expect("creating presentation");
+        let theme_path = directory.path().join("talk-theme.yaml");
+        assert_eq!(files, &[path.clone(), theme_path.clone()]);
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let (metadata, _) = parse_front_matter(front_matter(&contents)).expect("invalid front matter");
+        assert_eq!(metadata.theme.path.as_deref(), Some("talk-theme.yaml"));
+        PresentationTheme::from_path(theme_path).expect("invalid theme");
+    }
+
+    #[test]
+    fn unknown_theme() {
+        let directory = tempdir().expect("creating temp dir");
+        let config = NewPresentationConfig { theme: Some("potato".into()), ..Default::default() };
+        let result = PresentationScaffold::new(&config).create(&directory.path().join("talk.md"));
+        assert!(matches!(result, Err(ScaffoldError::UnknownTheme(_))));
+    }
+
+    #[rstest]
+    #[case::dashes("my-first-talk", "My first talk")]
+    #[case::underscores("rust_intro", "Rust intro")]
+    #[case::empty("", "My presentation")]
+    fn titles(#[case] stem: &str, #[case] expected: &str) {
+        assert_eq!(title_from_file_stem(stem), expected);
+    }
+}
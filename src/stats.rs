@@ -0,0 +1,147 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+    time::{Duration, Instant},
+};
+
+/// Timing statistics collected while presenting.
+///
+/// This keeps track of how long was spent on each slide and how many times the presenter went
+/// back to a previous slide.
+#[derive(Debug)]
+pub struct PresentationStats {
+    start: Instant,
+    current: Option<(usize, Instant)>,
+    slide_durations: BTreeMap<usize, Duration>,
+    back_jumps: usize,
+    total: Option<Duration>,
+}
+
+impl PresentationStats {
+    /// Start collecting statistics.
+    pub fn new() -> Self {
+        Self::new_at(Instant::now())
+    }
+
+    fn new_at(start: Instant) -> Self {
+        Self { start, current: None, slide_durations: BTreeMap::new(), back_jumps: 0, total: None }
+    }
+
+    /// Record that the slide at the given index was entered.
+    pub fn enter_slide(&mut self, index: usize) {
+        self.enter_slide_at(index, Instant::now());
+    }
+
+    fn enter_slide_at(&mut self, index: usize, now: Instant) {
+        if let Some((previous, _)) = self.current {
+            if previous == index {
+                return;
+            }
+            if index < previous {
+                self.back_jumps += 1;
+            }
+        }
+        self.close_current(now);
+        self.current = Some((index, now));
+    }
+
+    /// Stop collecting statistics.
+    pub fn finish(&mut self) {
+        self.finish_at(Instant::now());
+    }
+
+    fn finish_at(&mut self, now: Instant) {
+        self.close_current(now);
+        self.total = Some(now.duration_since(self.start));
+    }
+
+    /// The total time spent presenting.
+    pub fn total_time(&self) -> Duration {
+        self.total.unwrap_or_else(|| self.start.elapsed())
+    }
+
+    /// The time spent on the slide at the given index.
+    pub fn slide_time(&self, index: usize) -> Duration {
+        self.slide_durations.get(&index).copied().unwrap_or_default()
+    }
+
+    /// The number of times we jumped back to a previous slide.
+    pub fn back_jumps(&self) -> usize {
+        self.back_jumps
+    }
+
+    fn close_current(&mut self, now: Instant) {
+        if let Some((index, entered)) = self.current.take() {
+            *self.slide_durations.entry(index).or_default() += now.duration_since(entered);
+        }
+    }
+}
+
+impl Default for PresentationStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for PresentationStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Total time: {}", FormattedDuration(self.total_time()))?;
+        writeln!(f, "Back jumps: {}", self.back_jumps)?;
+        writeln!(f)?;
+        writeln!(f, "Slide  Time")?;
+        for (index, duration) in &self.slide_durations {
+            writeln!(f, "{:<5}  {}", index + 1, FormattedDuration(*duration))?;
+        }
+        Ok(())
+    }
+}
+
+struct FormattedDuration(Duration);
+
+impl Display for FormattedDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.0.as_secs();
+        let (hours, minutes, seconds) = (seconds / 3600, (seconds / 60) % 60, seconds % 60);
+        if hours > 0 {
+            write!(f, "{hours}h {minutes:02}m {seconds:02}s")
+        } else if minutes > 0 {
+            write!(f, "{minutes}m {seconds:02}s")
+        } else {
+            write!(f, "{seconds}s")
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn slide_times() {
+        let start = Instant::now();
+        let at = |seconds| start + Duration::from_secs(seconds);
+        let mut stats = PresentationStats::new_at(start);
+        stats.enter_slide_at(0, at(0));
+        stats.enter_slide_at(1, at(10));
+        stats.enter_slide_at(0, at(15));
+        stats.enter_slide_at(0, at(16));
+        stats.enter_slide_at(2, at(20));
+        stats.finish_at(at(30));
+
+        assert_eq!(stats.slide_time(0), Duration::from_secs(15));
+        assert_eq!(stats.slide_time(1), Duration::from_secs(5));
+        assert_eq!(stats.slide_time(2), Duration::from_secs(10));
+        assert_eq!(stats.back_jumps(), 1);
+        assert_eq!(stats.total_time(), Duration::from_secs(30));
+    }
+
+    #[rstest]
+    #[case::seconds(5, "5s")]
+    #[case::minutes(65, "1m 05s")]
+    #[case::hours(3725, "1h 02m 05s")]
+    fn duration_formatting(#[case] seconds: u64, #[case] expected: &str) {
+        let formatted = FormattedDuration(Duration::from_secs(seconds)).to_string();
+        assert_eq!(formatted, expected);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::input::source::Command;
+use std::{
+    sync::mpsc::Sender,
+    thread,
+    time::{Duration, Instant},
+};
+
+const DEFAULT_MESSAGE_DURATION: Duration = Duration::from_secs(3);
+
+/// A line at the bottom of the screen used to give the user feedback.
+///
+/// Messages posted here go away on their own after a while. Every time a message is posted a
+/// [Command::Redraw] is scheduled for when it expires so it's removed from the screen.
+pub struct StatusLine {
+    message: Option<StatusMessage>,
+    sender: Sender<Command>,
+    duration: Duration,
+}
+
+impl StatusLine {
+    /// Construct a new status line that uses the given sender to schedule redraws.
+    pub fn new(sender: Sender<Command>) -> Self {
+        Self { message: None, sender, duration: DEFAULT_MESSAGE_DURATION }
+    }
+
+    /// Post an informational message.
+    pub fn info<S: Into<String>>(&mut self, text: S) {
+        self.post(text.into(), MessageKind::Info, Instant::now());
+    }
+
+    /// Post an error message.
+    pub fn error<S: Into<String>>(&mut self, text: S) {
+        self.post(text.into(), MessageKind::Error, Instant::now());
+    }
+
+    /// Get the message that's currently being displayed, if any.
+    pub fn current(&self) -> Option<&StatusMessage> {
+        self.current_at(Instant::now())
+    }
+
+    fn current_at(&self, now: Instant) -> Option<&StatusMessage> {
+        self.message.as_ref().filter(|message| message.expires_at > now)
+    }
+
+    fn post(&mut self, text: String, kind: MessageKind, now: Instant) {
+        self.message = Some(StatusMessage { text, kind, expires_at: now + self.duration });
+
+        let sender = self.sender.clone();
+        let duration = self.duration;
+        thread::spawn(move || {
+            thread::sleep(duration);
+            let _ = sender.send(Command::Redraw);
+        });
+    }
+}
+
+/// A message in the status line.
+#[derive(Clone, Debug)]
+pub struct StatusMessage {
+    /// The message's text.
+    pub text: String,
+
+    /// The kind of message this is.
+    pub kind: MessageKind,
+
+    expires_at: Instant,
+}
+
+/// The kind of a status message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageKind {
+    Info,
+    Error,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn message_expires() {
+        let (sender, receiver) = channel();
+        let mut status = StatusLine::new(sender);
+        status.duration = Duration::from_millis(10);
+
+        let now = Instant::now();
+        status.post("reloaded".into(), MessageKind::Info, now);
+        let message = status.current_at(now).expect("no message");
+        assert_eq!(message.text, "reloaded");
+        assert_eq!(message.kind, MessageKind::Info);
+        assert!(status.current_at(now + Duration::from_millis(10)).is_none());
+
+        let command = receiver.recv_timeout(Duration::from_secs(5)).expect("no redraw");
+        assert!(matches!(command, Command::Redraw));
+    }
+
+    #[test]
+    fn newer_message_replaces_older() {
+        let (sender, _receiver) = channel();
+        let mut status = StatusLine::new(sender);
+        let now = Instant::now();
+        status.post("first".into(), MessageKind::Info, now);
+        status.post("second".into(), MessageKind::Error, now);
+        let message = status.current_at(now).expect("no message");
+        assert_eq!(message.text, "second");
+        assert_eq!(message.kind, MessageKind::Error);
+    }
+}
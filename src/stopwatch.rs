@@ -0,0 +1,179 @@
+use crate::input::source::Command;
+use std::{
+    fmt,
+    sync::{mpsc::Sender, Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A stopwatch that the presenter controls.
+///
+/// This is independent of how long the presentation has been running for, and can be started,
+/// stopped, and reset at will, e.g. to time exercises during a workshop. Clones of a stopwatch
+/// share the same state so it can be displayed in the footer while being driven by the presenter.
+#[derive(Clone, Debug, Default)]
+pub struct Stopwatch {
+    state: Arc<Mutex<StopwatchState>>,
+}
+
+#[derive(Debug, Default)]
+struct StopwatchState {
+    running_since: Option<Instant>,
+    accumulated: Duration,
+    last_lap: Duration,
+    laps: usize,
+    // Bumped every time the stopwatch starts or stops so stale tickers know they need to stop.
+    generation: u64,
+}
+
+impl StopwatchState {
+    fn elapsed_at(&self, now: Instant) -> Duration {
+        let running = self.running_since.map(|since| now.saturating_duration_since(since)).unwrap_or_default();
+        self.accumulated + running
+    }
+}
+
+impl Stopwatch {
+    /// Start the stopwatch if it's stopped, or stop it otherwise.
+    ///
+    /// While the stopwatch is running, a [Command::Redraw] is sent via the given sender every second.
+    pub fn toggle(&self, sender: &Sender<Command>) {
+        self.toggle_at(Instant::now(), sender);
+    }
+
+    /// Stop the stopwatch and set it back to zero.
+    pub fn reset(&self) {
+        let mut state = self.state.lock().expect("poisoned lock");
+        *state = StopwatchState { generation: state.generation + 1, ..Default::default() };
+    }
+
+    /// Record a lap.
+    ///
+    /// Returns `None` if the stopwatch isn't running.
+    pub fn lap(&self) -> Option<Lap> {
+        self.lap_at(Instant::now())
+    }
+
+    /// Check whether the stopwatch is running.
+    pub fn is_running(&self) -> bool {
+        self.state.lock().expect("poisoned lock").running_since.is_some()
+    }
+
+    /// Get the time measured so far.
+    pub fn elapsed(&self) -> Duration {
+        self.state.lock().expect("poisoned lock").elapsed_at(Instant::now())
+    }
+
+    fn toggle_at(&self, now: Instant, sender: &Sender<Command>) {
+        let mut state = self.state.lock().expect("poisoned lock");
+        state.generation += 1;
+        match state.running_since.take() {
+            Some(since) => state.accumulated += now.saturating_duration_since(since),
+            None => {
+                state.running_since = Some(now);
+                self.spawn_ticker(state.generation, sender.clone());
+            }
+        };
+    }
+
+    fn lap_at(&self, now: Instant) -> Option<Lap> {
+        let mut state = self.state.lock().expect("poisoned lock");
+        state.running_since?;
+        let total = state.elapsed_at(now);
+        let time = total.saturating_sub(state.last_lap);
+        state.last_lap = total;
+        state.laps += 1;
+        Some(Lap { number: state.laps, time, total })
+    }
+
+    fn spawn_ticker(&self, generation: u64, sender: Sender<Command>) {
+        let state = self.state.clone();
+        let is_current = move || state.lock().expect("poisoned lock").generation == generation;
+        thread::spawn(move || {
+            thread::sleep(TICK_INTERVAL);
+            while is_current() && sender.send(Command::Redraw).is_ok() {
+                thread::sleep(TICK_INTERVAL);
+            }
+        });
+    }
+}
+
+/// A lap recorded in a stopwatch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lap {
+    /// The 1-based number of this lap.
+    pub number: usize,
+
+    /// The time since the previous lap.
+    pub time: Duration,
+
+    /// The time since the stopwatch was started.
+    pub total: Duration,
+}
+
+/// Formats a duration the way a stopwatch displays it: `MM:SS`, or `H:MM:SS` past the first hour.
+pub struct StopwatchTime(pub Duration);
+
+impl fmt::Display for StopwatchTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.0.as_secs();
+        let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+        if hours > 0 { write!(f, "{hours}:{minutes:02}:{seconds:02}") } else { write!(f, "{minutes:02}:{seconds:02}") }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn start_and_stop() {
+        let (sender, _receiver) = channel();
+        let stopwatch = Stopwatch::default();
+        let start = Instant::now();
+        stopwatch.toggle_at(start, &sender);
+        assert!(stopwatch.is_running());
+        stopwatch.toggle_at(start + Duration::from_secs(5), &sender);
+        assert!(!stopwatch.is_running());
+        assert_eq!(stopwatch.elapsed(), Duration::from_secs(5));
+
+        stopwatch.reset();
+        assert_eq!(stopwatch.elapsed(), Duration::ZERO);
+    }
+
+    #[test]
+    fn laps() {
+        let (sender, _receiver) = channel();
+        let stopwatch = Stopwatch::default();
+        let start = Instant::now();
+        assert_eq!(stopwatch.lap_at(start), None);
+
+        stopwatch.toggle_at(start, &sender);
+        let lap = stopwatch.lap_at(start + Duration::from_secs(10)).expect("no lap");
+        assert_eq!(lap, Lap { number: 1, time: Duration::from_secs(10), total: Duration::from_secs(10) });
+        let lap = stopwatch.lap_at(start + Duration::from_secs(25)).expect("no lap");
+        assert_eq!(lap, Lap { number: 2, time: Duration::from_secs(15), total: Duration::from_secs(25) });
+    }
+
+    #[test]
+    fn ticks_while_running() {
+        let (sender, receiver) = channel();
+        let stopwatch = Stopwatch::default();
+        stopwatch.toggle(&sender);
+        let command = receiver.recv_timeout(Duration::from_secs(5)).expect("no redraw");
+        assert!(matches!(command, Command::Redraw));
+        stopwatch.reset();
+    }
+
+    #[rstest]
+    #[case::seconds(42, "00:42")]
+    #[case::minutes(754, "12:34")]
+    #[case::hours(3723, "1:02:03")]
+    fn format(#[case] seconds: u64, #[case] expected: &str) {
+        assert_eq!(StopwatchTime(Duration::from_secs(seconds)).to_string(), expected);
+    }
+}
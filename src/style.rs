@@ -125,6 +125,57 @@ impl Color {
     pub fn new(r: u8, g: u8, b: u8) -> Self {
         Self(crossterm::style::Color::Rgb { r, g, b })
     }
+
+    /// Get the red, green, and blue components of this color.
+    pub fn rgb(&self) -> [u8; 3] {
+        match self.0 {
+            crossterm::style::Color::Rgb { r, g, b } => [r, g, b],
+            _ => panic!("not rgb"),
+        }
+    }
+
+    /// Get the color at a position within a gradient that goes through the given colors.
+    ///
+    /// The position goes from 0, the first color, to 1, the last one. Returns `None` if there are no colors.
+    pub fn gradient(colors: &[Color], position: f64) -> Option<Color> {
+        let segments = colors.len().checked_sub(1)?;
+        if segments == 0 {
+            return colors.first().copied();
+        }
+        let position = position.clamp(0.0, 1.0) * segments as f64;
+        let index = (position.floor() as usize).min(segments - 1);
+        let ratio = position - index as f64;
+        let [start, end] = [colors[index].rgb(), colors[index + 1].rgb()];
+        let blend = |component: usize| {
+            let (start, end) = (start[component] as f64, end[component] as f64);
+            (start + (end - start) * ratio).round() as u8
+        };
+        Some(Color::new(blend(0), blend(1), blend(2)))
+    }
+
+    /// Get the closest color in the 256 color palette, for terminals that can't display 24 bit colors.
+    pub fn ansi_256(&self) -> u8 {
+        const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+        let rgb = self.rgb();
+        let distance = |other: [u8; 3]| -> i32 {
+            rgb.iter().zip(other).map(|(ours, theirs)| (*ours as i32 - theirs as i32).pow(2)).sum()
+        };
+        let closest_level = |component: u8| {
+            let offset = |index: &usize| (CUBE_LEVELS[*index] as i32 - component as i32).abs();
+            (0..CUBE_LEVELS.len()).min_by_key(offset).unwrap_or(0)
+        };
+        let [r, g, b] = rgb.map(closest_level);
+        let cube = (16 + 36 * r + 6 * g + b) as u8;
+
+        // The grayscale ramp goes from 8 to 238 in steps of 10.
+        let average = rgb.iter().map(|component| *component as u32).sum::<u32>() / 3;
+        let step = ((average + 5).saturating_sub(8) / 10).min(23) as u8;
+        let gray = 232 + step;
+        match distance([8 + step * 10; 3]) < distance([CUBE_LEVELS[r], CUBE_LEVELS[g], CUBE_LEVELS[b]]) {
+            true => gray,
+            false => cube,
+        }
+    }
 }
 
 impl FromStr for Color {
@@ -138,11 +189,7 @@ impl FromStr for Color {
 
 impl Display for Color {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let rgb = match self.0 {
-            crossterm::style::Color::Rgb { r, g, b } => [r, g, b],
-            _ => panic!("not rgb"),
-        };
-        write!(f, "{}", hex::encode(rgb))
+        write!(f, "{}", hex::encode(self.rgb()))
     }
 }
 
@@ -177,10 +224,33 @@ pub struct ParseColorError(#[from] FromHexError);
 #[cfg(test)]
 mod test {
     use super::*;
+    use rstest::rstest;
 
     #[test]
     fn color_serde() {
         let color: Color = "beef42".parse().unwrap();
         assert_eq!(color.to_string(), "beef42");
     }
+
+    #[rstest]
+    #[case::black(Color::new(0, 0, 0), 16)]
+    #[case::white(Color::new(255, 255, 255), 231)]
+    #[case::red(Color::new(250, 10, 5), 196)]
+    #[case::cube(Color::new(95, 135, 175), 67)]
+    #[case::gray(Color::new(128, 128, 128), 244)]
+    #[case::dark_gray(Color::new(30, 31, 30), 234)]
+    fn ansi_256(#[case] color: Color, #[case] expected: u8) {
+        assert_eq!(color.ansi_256(), expected);
+    }
+
+    #[test]
+    fn gradient() {
+        let colors = [Color::new(0, 0, 0), Color::new(100, 200, 0), Color::new(100, 0, 0)];
+        assert_eq!(Color::gradient(&colors, 0.0), Some(Color::new(0, 0, 0)));
+        assert_eq!(Color::gradient(&colors, 0.25), Some(Color::new(50, 100, 0)));
+        assert_eq!(Color::gradient(&colors, 0.5), Some(Color::new(100, 200, 0)));
+        assert_eq!(Color::gradient(&colors, 1.0), Some(Color::new(100, 0, 0)));
+        assert_eq!(Color::gradient(&colors[..1], 0.5), Some(Color::new(0, 0, 0)));
+        assert_eq!(Color::gradient(&[], 0.5), None);
+    }
 }
@@ -1,6 +1,14 @@
-use crate::style::Colors;
+use crate::{
+    presentation::SlideKind,
+    style::{Color, Colors},
+};
 use serde::{Deserialize, Serialize};
-use std::{fs, io, path::Path};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 include!(concat!(env!("OUT_DIR"), "/themes.rs"));
 
@@ -19,14 +27,26 @@ pub struct PresentationTheme {
     #[serde(default)]
     pub inline_code: InlineCodeStyle,
 
+    /// The style for the line displayed once executed code finishes.
+    #[serde(default)]
+    pub execution_status: ExecutionStatusStyle,
+
     /// The style for a table.
     #[serde(default)]
-    pub table: Option<Alignment>,
+    pub table: TableStyle,
 
     /// The style for a block quote.
     #[serde(default)]
     pub block_quote: BlockQuoteStyle,
 
+    /// The style for separators.
+    #[serde(default)]
+    pub separator: SeparatorStyle,
+
+    /// The style for image captions.
+    #[serde(default)]
+    pub caption: CaptionStyle,
+
     /// The default style.
     #[serde(rename = "default", default)]
     pub default_style: DefaultStyle,
@@ -42,6 +62,14 @@ pub struct PresentationTheme {
     /// The style of the presentation footer.
     #[serde(default)]
     pub footer: FooterStyle,
+
+    /// Overrides for every kind of slide.
+    #[serde(default)]
+    pub slide_kinds: SlideKindStyles,
+
+    /// The named colors that can be referenced within presentations.
+    #[serde(default)]
+    pub palette: ColorPalette,
 }
 
 impl PresentationTheme {
@@ -59,6 +87,11 @@ impl PresentationTheme {
         THEMES.keys().copied()
     }
 
+    /// Get the raw YAML definition of a built-in theme.
+    pub fn raw_theme(name: &str) -> Option<&'static [u8]> {
+        THEMES.get(name).copied()
+    }
+
     /// Construct a presentation from a path.
     pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, LoadThemeError> {
         let contents = fs::read_to_string(path)?;
@@ -85,8 +118,9 @@ impl PresentationTheme {
             PresentationTitle => &self.intro_slide.title.alignment,
             PresentationSubTitle => &self.intro_slide.subtitle.alignment,
             PresentationAuthor => &self.intro_slide.author.alignment,
-            Table => &self.table,
+            Table => &self.table.alignment,
             BlockQuote => &self.block_quote.alignment,
+            Caption => &self.caption.alignment,
         };
         alignment.clone().unwrap_or_default()
     }
@@ -114,6 +148,10 @@ pub struct SlideTitleStyle {
     /// The colors to be used.
     #[serde(default)]
     pub colors: Colors,
+
+    /// The size of the line the title is drawn in.
+    #[serde(default)]
+    pub line_size: Option<LineSize>,
 }
 
 /// The style for all headings.
@@ -157,6 +195,122 @@ pub struct HeadingStyle {
     #[serde(default)]
     pub prefix: Option<String>,
 
+    /// The colors to be used for the prefix.
+    ///
+    /// Any color not set here is inherited from the heading's colors.
+    #[serde(default)]
+    pub prefix_colors: Colors,
+
+    /// The colors to be used.
+    #[serde(default)]
+    pub colors: Colors,
+}
+
+/// The size of a line of text.
+///
+/// Anything other than the normal size relies on the DEC double width/height line attributes, which
+/// are not supported by every terminal.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LineSize {
+    /// Use the regular size.
+    #[default]
+    Normal,
+
+    /// Every character is twice as wide.
+    DoubleWidth,
+
+    /// Every character is twice as wide and twice as tall.
+    DoubleHeight,
+}
+
+impl LineSize {
+    /// The number of columns each character takes up.
+    pub fn column_scale(&self) -> u16 {
+        match self {
+            Self::Normal => 1,
+            Self::DoubleWidth | Self::DoubleHeight => 2,
+        }
+    }
+
+    /// The escape codes that need to be emitted for each of the rows that make up a line.
+    pub(crate) fn row_escape_codes(&self) -> &'static [&'static str] {
+        match self {
+            Self::Normal => &[""],
+            Self::DoubleWidth => &["\x1b#6"],
+            Self::DoubleHeight => &["\x1b#3", "\x1b#4"],
+        }
+    }
+}
+
+/// The style of a table.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct TableStyle {
+    /// The alignment.
+    #[serde(flatten, default)]
+    pub alignment: Option<Alignment>,
+
+    /// The style of the header row.
+    #[serde(default)]
+    pub header: TableRowStyle,
+
+    /// The style of every row other than the header.
+    #[serde(default)]
+    pub rows: TableRowStyle,
+
+    /// The background color to use in every other row.
+    ///
+    /// This allows having "zebra striped" tables which are easier to follow.
+    #[serde(default)]
+    pub alternate_row_background: Option<Color>,
+}
+
+/// The style of a table row.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct TableRowStyle {
+    /// Whether the text should be bold.
+    #[serde(default)]
+    pub bold: bool,
+
+    /// The colors to be used.
+    #[serde(default)]
+    pub colors: Colors,
+}
+
+/// The style of an image caption.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct CaptionStyle {
+    /// The alignment.
+    #[serde(flatten, default)]
+    pub alignment: Option<Alignment>,
+
+    /// The colors to be used.
+    #[serde(default)]
+    pub colors: Colors,
+
+    /// Whether to prefix every caption with "Figure N:", where N is the number of the image in the
+    /// presentation.
+    #[serde(default)]
+    pub numbering: bool,
+}
+
+/// The style of a separator line.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct SeparatorStyle {
+    /// The character to draw the line with.
+    #[serde(default)]
+    pub character: Option<String>,
+
+    /// The width of the separator, as a percentage of the available width.
+    ///
+    /// Separators that don't take up the entire width are centered.
+    #[serde(default)]
+    pub width: Option<u8>,
+
+    /// A piece of text to draw in the middle of the separator, like "✦".
+    #[serde(default)]
+    pub ornament: Option<String>,
+
     /// The colors to be used.
     #[serde(default)]
     pub colors: Colors,
@@ -175,6 +329,12 @@ pub struct BlockQuoteStyle {
     #[serde(default)]
     pub prefix: Option<String>,
 
+    /// The number of columns each tab is expanded to.
+    ///
+    /// If unset, the tab width used for code blocks is used.
+    #[serde(default)]
+    pub tab_width: Option<u8>,
+
     /// The colors to be used.
     #[serde(default)]
     pub colors: Colors,
@@ -194,6 +354,82 @@ pub struct IntroSlideStyle {
     /// The style of the author line.
     #[serde(default)]
     pub author: AuthorStyle,
+
+    /// The style of the line containing the author's social handles.
+    #[serde(default)]
+    pub socials: SocialsStyle,
+
+    /// The way the title, subtitle, and author are laid out.
+    #[serde(default)]
+    pub layout: IntroSlideLayout,
+}
+
+/// The layout of the introduction slide.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(tag = "style", rename_all = "snake_case")]
+pub enum IntroSlideLayout {
+    /// Every line is vertically centered and aligned as configured in its own style.
+    #[default]
+    Centered,
+
+    /// Every line is left aligned, with a horizontal rule below the title.
+    LeftRule,
+
+    /// The text is placed on the left half of the slide and an image on the right one.
+    Split {
+        /// The path to the image, relative to the presentation.
+        image: PathBuf,
+    },
+}
+
+/// The styles for every kind of slide.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SlideKindStyles {
+    /// The style of the introduction slide.
+    #[serde(default)]
+    pub title: SlideKindStyle,
+
+    /// The style of slides that begin a section.
+    #[serde(default)]
+    pub section: SlideKindStyle,
+
+    /// The style of regular slides.
+    #[serde(default)]
+    pub content: SlideKindStyle,
+
+    /// The style of closing slides.
+    #[serde(default)]
+    pub closing: SlideKindStyle,
+}
+
+impl SlideKindStyles {
+    /// Get the style for a kind of slide.
+    pub fn get(&self, kind: SlideKind) -> &SlideKindStyle {
+        match kind {
+            SlideKind::Title => &self.title,
+            SlideKind::Section => &self.section,
+            SlideKind::Content => &self.content,
+            SlideKind::Closing => &self.closing,
+        }
+    }
+}
+
+/// Overrides on the theme for a kind of slide.
+///
+/// Anything that isn't set falls back to the theme's own style.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SlideKindStyle {
+    /// The colors to be used.
+    #[serde(default)]
+    pub colors: Colors,
+
+    /// The style of the footer.
+    #[serde(default)]
+    pub footer: Option<FooterStyle>,
+
+    /// The alignment to use for every element.
+    #[serde(flatten, default)]
+    pub alignment: Option<Alignment>,
 }
 
 /// A simple style.
@@ -203,6 +439,12 @@ pub struct DefaultStyle {
     #[serde(default, with = "serde_yaml::with::singleton_map")]
     pub margin: Option<Margin>,
 
+    /// The maximum number of columns the content in a slide can take.
+    ///
+    /// The content is centered when the terminal is wider than this.
+    #[serde(default)]
+    pub max_columns: Option<u16>,
+
     /// The colors to be used.
     #[serde(default)]
     pub colors: Colors,
@@ -272,6 +514,57 @@ pub struct AuthorStyle {
     /// The positioning of the author's name.
     #[serde(default)]
     pub positioning: AuthorPositioning,
+
+    /// The style of the author's title, when using a structured author.
+    #[serde(default)]
+    pub title: AuthorDetailStyle,
+
+    /// The style of the author's company, when using a structured author.
+    #[serde(default)]
+    pub company: AuthorDetailStyle,
+
+    /// The style of the author's contact, when using a structured author.
+    #[serde(default)]
+    pub contact: AuthorDetailStyle,
+}
+
+/// The style for a line in the author block in the presentation intro slide.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct AuthorDetailStyle {
+    /// The colors to be used.
+    #[serde(default)]
+    pub colors: Colors,
+
+    /// Whether to use italics.
+    #[serde(default)]
+    pub italics: bool,
+}
+
+/// The style for the line containing the author's social handles in the presentation intro slide.
+///
+/// This line uses the same alignment as the author's name.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SocialsStyle {
+    /// The colors to be used.
+    #[serde(default)]
+    pub colors: Colors,
+
+    /// The text placed in between every handle.
+    #[serde(default)]
+    pub separator: Option<String>,
+}
+
+impl SocialsStyle {
+    /// The separator used when none is set.
+    pub const DEFAULT_SEPARATOR: &'static str = "   ";
+}
+
+/// A set of named colors.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ColorPalette {
+    /// The colors in this palette, by name.
+    #[serde(default)]
+    pub colors: BTreeMap<String, Color>,
 }
 
 /// The style of the footer that's shown in every slide.
@@ -302,6 +595,12 @@ pub enum FooterStyle {
         /// The colors to be used.
         #[serde(default)]
         colors: Colors,
+
+        /// The colors the progress bar transitions through from the left to the right of the screen.
+        ///
+        /// These take precedence over the foreground color.
+        #[serde(default)]
+        gradient: Vec<Color>,
     },
 
     /// No footer.
@@ -333,6 +632,34 @@ pub struct CodeBlockStyle {
     /// The syntect theme name to use.
     #[serde(default)]
     pub theme_name: Option<String>,
+
+    /// The number of columns a tab takes up.
+    #[serde(default)]
+    pub tab_width: Option<u8>,
+
+    /// The style for line numbers.
+    #[serde(default)]
+    pub line_numbers: LineNumbersStyle,
+}
+
+/// The style for the line numbers displayed next to code.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct LineNumbersStyle {
+    /// The colors to be used, which default to the ones the code's syntax highlighting theme uses.
+    #[serde(default)]
+    pub colors: Colors,
+}
+
+/// The style for the line displayed once executed code finishes.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ExecutionStatusStyle {
+    /// The colors used when the code finishes successfully.
+    #[serde(default)]
+    pub success: Colors,
+
+    /// The colors used when the code fails.
+    #[serde(default)]
+    pub failure: Colors,
 }
 
 /// The style for inline code.
@@ -341,6 +668,26 @@ pub struct InlineCodeStyle {
     /// The colors to be used.
     #[serde(default)]
     pub colors: Colors,
+
+    /// The number of spaces to add before and after the code.
+    #[serde(default)]
+    pub padding: Option<u8>,
+
+    /// The delimiters to surround the code with.
+    #[serde(default)]
+    pub delimiters: Option<InlineCodeDelimiters>,
+}
+
+/// The delimiters to surround inline code with.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct InlineCodeDelimiters {
+    /// The delimiter placed before the code.
+    #[serde(default)]
+    pub start: String,
+
+    /// The delimiter placed after the code.
+    #[serde(default)]
+    pub end: String,
 }
 
 /// Vertical/horizontal padding.
@@ -356,8 +703,11 @@ pub struct PaddingRect {
 }
 
 /// A margin.
+///
+/// Besides the `fixed: <columns>` and `percent: <percent>` forms, this can be written as either a number of columns
+/// (e.g. `5`) or a percent of the screen size (e.g. `10%`).
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
-#[serde(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case", try_from = "RawMargin")]
 pub enum Margin {
     /// A fixed number of characters.
     Fixed(u16),
@@ -367,6 +717,16 @@ pub enum Margin {
 }
 
 impl Margin {
+    // Margins are applied on both sides so anything this large wouldn't leave any room for the contents.
+    const MAX_PERCENT: u16 = 49;
+
+    fn percent(percent: u16) -> Result<Self, &'static str> {
+        match percent {
+            0..=Self::MAX_PERCENT => Ok(Self::Percent(percent)),
+            _ => Err("margin percent must be smaller than 50, as it's applied on both sides"),
+        }
+    }
+
     pub fn as_characters(&self, screen_size: u16) -> u16 {
         match *self {
             Self::Fixed(value) => value,
@@ -384,6 +744,50 @@ impl Default for Margin {
     }
 }
 
+impl FromStr for Margin {
+    type Err = &'static str;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim();
+        match input.strip_suffix('%') {
+            Some(percent) => {
+                let percent: u16 = percent.trim().parse().map_err(|_| "invalid margin percent")?;
+                Self::percent(percent)
+            }
+            None => Ok(Self::Fixed(input.parse().map_err(|_| "margin must be a number of columns or a percent")?)),
+        }
+    }
+}
+
+impl TryFrom<RawMargin> for Margin {
+    type Error = &'static str;
+
+    fn try_from(margin: RawMargin) -> Result<Self, Self::Error> {
+        match margin {
+            RawMargin::Columns(columns) => Ok(Self::Fixed(columns)),
+            RawMargin::Text(text) => text.parse(),
+            RawMargin::Explicit { fixed: Some(columns), percent: None } => Ok(Self::Fixed(columns)),
+            RawMargin::Explicit { fixed: None, percent: Some(percent) } => Self::percent(percent),
+            RawMargin::Explicit { .. } => Err("margin must have exactly one of 'fixed' or 'percent'"),
+        }
+    }
+}
+
+// The forms a margin can be written in.
+#[derive(Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+enum RawMargin {
+    Columns(u16),
+    Text(String),
+    Explicit {
+        #[serde(default)]
+        fixed: Option<u16>,
+
+        #[serde(default)]
+        percent: Option<u16>,
+    },
+}
+
 /// An element type.
 #[derive(Clone, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 #[serde(rename_all = "snake_case")]
@@ -403,6 +807,7 @@ pub enum ElementType {
     PresentationAuthor,
     Table,
     BlockQuote,
+    Caption,
 }
 
 /// Where to position the author's name in the intro slide.
@@ -430,6 +835,7 @@ pub enum LoadThemeError {
 #[cfg(test)]
 mod test {
     use super::*;
+    use rstest::rstest;
 
     #[test]
     fn validate_themes() {
@@ -442,4 +848,35 @@ mod test {
             assert!(merged.is_ok(), "theme '{theme_name}' can't be merged: {}", merged.unwrap_err());
         }
     }
+
+    #[rstest]
+    #[case::columns("5", Margin::Fixed(5))]
+    #[case::columns_text("\"5\"", Margin::Fixed(5))]
+    #[case::percent("10%", Margin::Percent(10))]
+    #[case::fixed("fixed: 5", Margin::Fixed(5))]
+    #[case::explicit_percent("percent: 8", Margin::Percent(8))]
+    #[case::largest_percent("49%", Margin::Percent(49))]
+    fn margin(#[case] input: &str, #[case] expected: Margin) {
+        let margin: Margin = serde_yaml::from_str(input).expect("invalid margin");
+        assert_eq!(margin, expected);
+    }
+
+    #[rstest]
+    #[case::too_large("150%")]
+    #[case::half("50%")]
+    #[case::explicit_half("percent: 50")]
+    #[case::not_a_number("potato")]
+    #[case::both("{fixed: 5, percent: 8}")]
+    fn invalid_margin(#[case] input: &str) {
+        assert!(serde_yaml::from_str::<Margin>(input).is_err());
+    }
+
+    #[test]
+    fn default_style_margin() {
+        let style: DefaultStyle = serde_yaml::from_str("margin: 10%").expect("invalid style");
+        assert_eq!(style.margin, Some(Margin::Percent(10)));
+
+        let style: DefaultStyle = serde_yaml::from_str("margin:\n  fixed: 3").expect("invalid style");
+        assert_eq!(style.margin, Some(Margin::Fixed(3)));
+    }
 }
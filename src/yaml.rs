@@ -0,0 +1,121 @@
+use std::fmt;
+
+/// An error parsing a YAML document.
+///
+/// Unlike a plain [serde_yaml::Error], this keeps the document around so the part of it that
+/// caused the error can be displayed.
+#[derive(thiserror::Error, Debug)]
+pub struct YamlError {
+    #[source]
+    error: serde_yaml::Error,
+    document: String,
+    line_offset: usize,
+}
+
+impl YamlError {
+    /// Construct a new error for the given document.
+    pub fn new<S: Into<String>>(error: serde_yaml::Error, document: S) -> Self {
+        Self { error, document: document.into(), line_offset: 0 }
+    }
+
+    /// Set the number of lines that come before the document in the file it was taken from.
+    ///
+    /// This is used so the line numbers in snippets match the ones in that file.
+    pub fn with_line_offset(mut self, line_offset: usize) -> Self {
+        self.line_offset = line_offset;
+        self
+    }
+
+    /// Get the snippet of the document where the error happened.
+    ///
+    /// The snippet contains the offending line along with up to `context` lines around it. This
+    /// returns `None` if the error can't be tied to a specific position in the document.
+    pub fn snippet(&self, context: usize) -> Option<YamlSnippet> {
+        let location = self.error.location()?;
+        let error_line = location.line().checked_sub(1)?;
+        let lines: Vec<_> = self.document.lines().collect();
+        if error_line >= lines.len() {
+            return None;
+        }
+        let first_line = error_line.saturating_sub(context);
+        let last_line = (error_line + context).min(lines.len() - 1);
+        let lines = lines[first_line..=last_line].iter().map(|line| line.to_string()).collect();
+        let column = location.column();
+        let error_line = error_line - first_line;
+        Some(YamlSnippet { lines, first_line: self.line_offset + first_line + 1, error_line, column })
+    }
+}
+
+impl fmt::Display for YamlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+/// A piece of a YAML document that contains an error.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct YamlSnippet {
+    /// The lines in this snippet.
+    pub lines: Vec<String>,
+
+    /// The 1-based line number of the first line in this snippet within the file the document is in.
+    pub first_line: usize,
+
+    /// The index within `lines` of the line that contains the error.
+    pub error_line: usize,
+
+    /// The 1-based column where the error is within the offending line.
+    pub column: usize,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Document {
+        title: String,
+        count: u32,
+    }
+
+    fn parse(document: &str) -> YamlError {
+        let error = serde_yaml::from_str::<Document>(document).expect_err("parse succeeded");
+        YamlError::new(error, document)
+    }
+
+    #[test]
+    fn snippet() {
+        let error = parse("a: 1\nb: 2\ntitle: hi\ncount: potato\nc: 3\nd: 4\ne: 5");
+        let snippet = error.snippet(1).expect("no snippet");
+        let expected = YamlSnippet {
+            lines: vec!["title: hi".into(), "count: potato".into(), "c: 3".into()],
+            first_line: 3,
+            error_line: 1,
+            column: 8,
+        };
+        assert_eq!(snippet, expected);
+    }
+
+    #[test]
+    fn snippet_at_start() {
+        let error = parse("count: potato\ntitle: hi");
+        let snippet = error.snippet(2).expect("no snippet");
+        assert_eq!(snippet.first_line, 1);
+        assert_eq!(snippet.error_line, 0);
+        assert_eq!(snippet.lines.len(), 2);
+    }
+
+    #[test]
+    fn snippet_with_line_offset() {
+        let error = parse(
+            "title: hi
+count: potato",
+        )
+        .with_line_offset(10);
+        let snippet = error.snippet(0).expect("no snippet");
+        assert_eq!(snippet.first_line, 12);
+        assert_eq!(snippet.error_line, 0);
+    }
+}